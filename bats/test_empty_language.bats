@@ -0,0 +1,35 @@
+#!/usr/bin/env bats
+
+setup() {
+    PROJECT_ROOT="${BATS_TEST_DIRNAME}/.."
+    EXAMPLE_DIR="${PROJECT_ROOT}/examples"
+    # An automaton without reachable final states
+    cat > "${BATS_TMPDIR}/empty_language.json" <<JSON
+{
+  "dimensions": 1,
+  "states": [
+    { "id": 0, "is_initial": true, "is_final": false },
+    { "id": 1, "is_initial": false, "is_final": true }
+  ],
+  "transitions": [
+    { "from": 0, "to": 0, "label": ["a", 0] }
+  ]
+}
+JSON
+}
+
+@test "Warn about an automaton with the empty language" {
+    cd "$PROJECT_ROOT"
+    run cargo run -- -f "${BATS_TMPDIR}/empty_language.json" -i "${EXAMPLE_DIR}/small1.txt" -o "${BATS_TMPDIR}/empty-language.txt"
+    [ "$status" -eq 0 ]
+
+    echo "$output" | grep -q 'no matches can ever be produced'
+}
+
+@test "Do not warn about an automaton with a non-empty language" {
+    cd "$PROJECT_ROOT"
+    run cargo run -- -f "${EXAMPLE_DIR}/small.json" -i "${EXAMPLE_DIR}/small1.txt" -i "${EXAMPLE_DIR}/small2.txt" -o "${BATS_TMPDIR}/small-nonempty.txt"
+    [ "$status" -eq 0 ]
+
+    ! echo "$output" | grep -q 'no matches can ever be produced'
+}
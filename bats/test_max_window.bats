@@ -0,0 +1,25 @@
+#!/usr/bin/env bats
+
+setup() {
+    PROJECT_ROOT="${BATS_TEST_DIRNAME}/.."
+    EXAMPLE_DIR="${PROJECT_ROOT}/examples"
+}
+
+@test "Drop the matches longer than the window" {
+    cd "$PROJECT_ROOT"
+    for mode in naive online; do
+        cargo run -- -f "${EXAMPLE_DIR}/small.json" -i "${EXAMPLE_DIR}/small1.txt" -i "${EXAMPLE_DIR}/small2.txt" -m "$mode" --max-window 1 -o "${BATS_TMPDIR}/small-window-${mode}.txt"
+
+        # The matches spanning (0, 2) or (1, 2) on the first log are absent
+        [ -s "${BATS_TMPDIR}/small-window-${mode}.txt" ]
+        [ "$(grep -c '0: ([01], 2)' "${BATS_TMPDIR}/small-window-${mode}.txt")" -eq 0 ]
+    done
+}
+
+@test "Warn that the window is ignored in the modes not supporting it" {
+    cd "$PROJECT_ROOT"
+    run cargo run -- -f "${EXAMPLE_DIR}/small.json" -i "${EXAMPLE_DIR}/small1.txt" -i "${EXAMPLE_DIR}/small2.txt" -m fjs --max-window 1 -o "${BATS_TMPDIR}/small-window-fjs.txt"
+    [ "$status" -eq 0 ]
+
+    echo "$output" | grep -q 'max-window is not supported'
+}
@@ -0,0 +1,39 @@
+#!/usr/bin/env bats
+
+setup() {
+    PROJECT_ROOT="${BATS_TEST_DIRNAME}/.."
+    EXAMPLE_DIR="${PROJECT_ROOT}/examples"
+    # An automaton requiring exactly one "c" on the first variable
+    cat > "${BATS_TMPDIR}/one_c.json" <<JSON
+{
+  "dimensions": 2,
+  "states": [
+    { "id": 0, "is_initial": true, "is_final": false },
+    { "id": 1, "is_initial": false, "is_final": true }
+  ],
+  "transitions": [
+    { "from": 0, "to": 0, "label": ["a", 0] },
+    { "from": 0, "to": 0, "label": ["b", 1] },
+    { "from": 0, "to": 1, "label": ["c", 0] },
+    { "from": 1, "to": 1, "label": ["d", 1] }
+  ]
+}
+JSON
+}
+
+@test "Product in DOT format" {
+    cd "$PROJECT_ROOT"
+    run cargo run -- -f "${EXAMPLE_DIR}/small.json" --product "${BATS_TMPDIR}/one_c.json" --graphviz -o "${BATS_TMPDIR}/product.dot"
+    [ "$status" -eq 0 ]
+
+    [ "$(grep -c 'label="State' "${BATS_TMPDIR}/product.dot")" -eq 5 ]
+    [ "$(grep -c 'doublecircle' "${BATS_TMPDIR}/product.dot")" -eq 1 ]
+}
+
+@test "Product in JSON format" {
+    cd "$PROJECT_ROOT"
+    run cargo run -- -f "${EXAMPLE_DIR}/small.json" --product "${BATS_TMPDIR}/one_c.json" -o "${BATS_TMPDIR}/product.json"
+    [ "$status" -eq 0 ]
+
+    [ "$(grep -c '"is_initial"' "${BATS_TMPDIR}/product.json")" -eq 5 ]
+}
@@ -0,0 +1,26 @@
+#!/usr/bin/env bats
+
+setup() {
+    PROJECT_ROOT="${BATS_TEST_DIRNAME}/.."
+    EXAMPLE_DIR="${PROJECT_ROOT}/examples"
+    printf 'a\nc\nd\nd\n' > "${BATS_TMPDIR}/self.txt"
+}
+
+@test "Sharing one input between two tracks is the same as duplicating it" {
+    cd "$PROJECT_ROOT"
+    for mode in naive online fjs naive-filtered online-filtered fjs-filtered; do
+        cargo run -- -f "${EXAMPLE_DIR}/small.json" -i "${BATS_TMPDIR}/self.txt" --share-stream 1=0 -m "$mode" -o "${BATS_TMPDIR}/self-shared.txt"
+        cargo run -- -f "${EXAMPLE_DIR}/small.json" -i "${BATS_TMPDIR}/self.txt" -i "${BATS_TMPDIR}/self.txt" -m "$mode" -o "${BATS_TMPDIR}/self-duplicated.txt"
+
+        [ -s "${BATS_TMPDIR}/self-shared.txt" ]
+        diff <(sort -u "${BATS_TMPDIR}/self-shared.txt") <(sort -u "${BATS_TMPDIR}/self-duplicated.txt")
+    done
+}
+
+@test "Reject sharing a stream that is not an input file" {
+    cd "$PROJECT_ROOT"
+    run cargo run -- -f "${EXAMPLE_DIR}/small.json" -i "${EXAMPLE_DIR}/small1.txt" --share-stream 1=2
+    [ "$status" -eq 2 ]
+
+    echo "$output" | grep -q 'Invalid --share-stream'
+}
@@ -0,0 +1,202 @@
+use crate::automata::NFAH;
+use crate::hyper_pattern_matching::HyperPatternMatching;
+use crate::symbol_matcher::SharedSymbolMatcher;
+use log::warn;
+use std::collections::HashSet;
+
+/// A [`HyperPatternMatching`] checking that the fed actions appear in the automaton.
+///
+/// An action the automaton never mentions is likely a typo or a misconfigured input. Since any
+/// track can be assigned to any variable, an action is unknown if it is not in
+/// [`NFAH::letters_for_variable`] for any variable. Each unknown action is warned once per track.
+/// In the strict mode, nothing is warned, but the unknown action and the actions fed after it are
+/// ignored, and the caller should report [`AlphabetCheckingMatching::first_unknown`] as an error.
+pub struct AlphabetCheckingMatching<Matching: HyperPatternMatching> {
+    matching: Matching,
+    /// The actions in the automaton for each variable
+    letters: Vec<HashSet<String>>,
+    /// The matcher of the actions and the letters if they are not compared exactly
+    symbol_matcher: Option<SharedSymbolMatcher>,
+    strict: bool,
+    /// The pairs of a track and an unknown action already reported
+    reported: HashSet<(usize, String)>,
+    /// The first unknown action and its track
+    first_unknown: Option<(usize, String)>,
+}
+
+impl<Matching: HyperPatternMatching> AlphabetCheckingMatching<Matching> {
+    /// Wraps `matching` to check the fed actions against the alphabet of `automaton`.
+    pub fn new<'a>(matching: Matching, automaton: &'a NFAH<'a>, strict: bool) -> Self {
+        let letters = (0..automaton.dimensions)
+            .map(|var| automaton.letters_for_variable(var))
+            .collect();
+        Self {
+            matching,
+            letters,
+            symbol_matcher: None,
+            strict,
+            reported: HashSet::new(),
+            first_unknown: None,
+        }
+    }
+
+    /// Compares the fed actions and the letters of the automaton by `symbol_matcher`, as the
+    /// wrapped matching does.
+    pub fn with_symbol_matcher(mut self, symbol_matcher: SharedSymbolMatcher) -> Self {
+        self.symbol_matcher = Some(symbol_matcher);
+        self
+    }
+
+    /// Returns the first unknown action and its track, if any.
+    pub fn first_unknown(&self) -> Option<&(usize, String)> {
+        self.first_unknown.as_ref()
+    }
+
+    fn is_known(&self, action: &str) -> bool {
+        match &self.symbol_matcher {
+            Some(symbol_matcher) => self
+                .letters
+                .iter()
+                .flatten()
+                .any(|letter| symbol_matcher.matches(letter, action)),
+            None => self.letters.iter().any(|letters| letters.contains(action)),
+        }
+    }
+}
+
+impl<Matching: HyperPatternMatching> HyperPatternMatching for AlphabetCheckingMatching<Matching> {
+    fn feed(&mut self, action: &str, track: usize) {
+        if self.strict && self.first_unknown.is_some() {
+            return;
+        }
+        if !self.is_known(action) {
+            // In the strict mode, the caller reports the first unknown action as an error
+            if !self.strict && self.reported.insert((track, action.to_string())) {
+                warn!(
+                    "The action {:?} in the track {} is not in the automaton",
+                    action, track
+                );
+            }
+            if self.first_unknown.is_none() {
+                self.first_unknown = Some((track, action.to_string()));
+            }
+            if self.strict {
+                return;
+            }
+        }
+        self.matching.feed(action, track);
+    }
+
+    fn dimensions(&self) -> usize {
+        self.matching.dimensions()
+    }
+
+    fn flush(&mut self) {
+        self.matching.flush();
+    }
+
+    fn consume_remaining(&mut self) {
+        self.matching.consume_remaining();
+    }
+
+    fn set_eof(&mut self, track: usize) {
+        self.matching.set_eof(track);
+    }
+
+    fn matches_found(&self) -> usize {
+        self.matching.matches_found()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyper_pattern_matching::HyperPatternMatchingAdapter;
+    use crate::result_notifier::SharedBufferResultNotifier;
+    use crate::shared_buffer::SharedBuffer;
+    use crate::single_hyper_pattern_matching::NaiveSingleHyperPatternMatching;
+    use crate::tests::utils::{create_small_automaton, install_recording_logger};
+    use typed_arena::Arena;
+
+    type Matching<'a> = HyperPatternMatchingAdapter<
+        'a,
+        NaiveSingleHyperPatternMatching<'a, SharedBufferResultNotifier>,
+        SharedBufferResultNotifier,
+    >;
+
+    /// Feeds "c" to the track 0 and `actions` to the track 1 until their end
+    fn feed_all(matching: &mut AlphabetCheckingMatching<Matching>, actions: &[&str]) {
+        matching.feed("c", 0);
+        for action in actions {
+            matching.feed(action, 1);
+        }
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+    }
+
+    #[test]
+    fn test_warn_unknown_action() {
+        let logger = install_recording_logger();
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &trans_arena);
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut matching = AlphabetCheckingMatching::new(
+            Matching::new(&automaton, notifier, 2),
+            &automaton,
+            false,
+        );
+
+        // Use an action unlikely to be logged by other tests concurrently
+        feed_all(&mut matching, &["typo_4242", "typo_4242", "d"]);
+        assert_eq!(
+            matching.first_unknown(),
+            Some(&(1, "typo_4242".to_string()))
+        );
+        let warnings = logger
+            .records()
+            .into_iter()
+            .filter(|record| record.starts_with("WARN") && record.contains("typo_4242"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                "WARN hyppau::alphabet_check: The action \"typo_4242\" in the track 1 is not in the automaton"
+            ]
+        );
+
+        // The unknown actions are still fed in the non-strict mode
+        let mut result_sink = result_buffer.make_sink();
+        assert!(result_sink.pop().is_some());
+    }
+
+    #[test]
+    fn test_strict_unknown_action() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &trans_arena);
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut matching =
+            AlphabetCheckingMatching::new(Matching::new(&automaton, notifier, 2), &automaton, true);
+
+        feed_all(&mut matching, &["x", "d"]);
+        assert_eq!(matching.first_unknown(), Some(&(1, "x".to_string())));
+
+        // The actions after the unknown one are ignored, so "d" cannot complete a match
+        let mut result_sink = result_buffer.make_sink();
+        assert!(result_sink.pop().is_none());
+
+        // The known actions are accepted
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut matching =
+            AlphabetCheckingMatching::new(Matching::new(&automaton, notifier, 2), &automaton, true);
+        feed_all(&mut matching, &["d"]);
+        assert!(matching.first_unknown().is_none());
+        let mut result_sink = result_buffer.make_sink();
+        assert!(result_sink.pop().is_some());
+    }
+}
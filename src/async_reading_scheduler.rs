@@ -0,0 +1,143 @@
+use crate::hyper_pattern_matching::HyperPatternMatching;
+use futures::{Stream, StreamExt};
+use log::{debug, warn};
+
+/// A scheduler that pulls actions from an asynchronous stream and feeds them into a
+/// [`HyperPatternMatching`] implementation.
+///
+/// Unlike [`crate::reading_scheduler::ReadingScheduler`], the actions of all the tracks come from a
+/// single stream of `(track, action)` pairs, e.g., decoded from a TCP connection. The matching
+/// itself stays synchronous. Since the stream does not tell the end of each track, all the tracks
+/// are closed when the stream ends.
+///
+/// # Type Parameters
+///
+/// * `Matching` - A type that implements the [`HyperPatternMatching`] trait.
+/// * `Source` - The asynchronous stream of `(track, action)` pairs.
+pub struct AsyncReadingScheduler<Matching: HyperPatternMatching, Source> {
+    matching: Matching,
+    source: Source,
+    num_tracks: usize,
+}
+
+impl<Matching, Source> AsyncReadingScheduler<Matching, Source>
+where
+    Matching: HyperPatternMatching,
+    Source: Stream<Item = (usize, String)> + Unpin,
+{
+    /// Creates a new `AsyncReadingScheduler` from the given matching engine and stream.
+    ///
+    /// # Parameters
+    ///
+    /// * `matching` - An implementation of [`HyperPatternMatching`].
+    /// * `source` - A stream of `(track, action)` pairs.
+    /// * `num_tracks` - The number of tracks, i.e., the valid track indices are `0..num_tracks`.
+    pub fn new(matching: Matching, source: Source, num_tracks: usize) -> Self {
+        Self {
+            matching,
+            source,
+            num_tracks,
+        }
+    }
+
+    /// Runs the scheduler until the end of the stream.
+    ///
+    /// Each action is passed to [`HyperPatternMatching::feed`] as soon as it arrives. Actions for
+    /// an unknown track are skipped with a warning. When the stream ends, all the tracks are closed
+    /// and the remaining configurations are consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let matching = MyPatternMatcher::new(...);
+    /// let source = futures::stream::iter(vec![(0, "a".to_string()), (1, "b".to_string())]);
+    /// let mut scheduler = AsyncReadingScheduler::new(matching, source, 2);
+    /// scheduler.run().await;
+    /// ```
+    pub async fn run(&mut self) {
+        while let Some((track, action)) = self.source.next().await {
+            if track >= self.num_tracks {
+                warn!(
+                    "Skip the action {:?} for the unknown track {} (the number of tracks: {})",
+                    action, track, self.num_tracks
+                );
+                continue;
+            }
+            self.matching.feed(&action, track);
+        }
+
+        debug!("The stream is closed");
+        for track in 0..self.num_tracks {
+            self.matching.set_eof(track);
+        }
+        self.matching.consume_remaining();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata_runner::AppendOnlySequence;
+    use crate::naive_hyper_pattern_matching::NaiveHyperPatternMatching;
+    use crate::result_notifier::{MatchingInterval, MatchingResult, SharedBufferResultNotifier};
+    use crate::shared_buffer::SharedBuffer;
+    use crate::tests::utils::create_small_automaton;
+    use std::collections::HashSet;
+    use typed_arena::Arena;
+
+    #[test]
+    fn test_run() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+
+        let matching = NaiveHyperPatternMatching::<SharedBufferResultNotifier>::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+
+        // The same input as the test of the synchronous ReadingScheduler, interleaved.
+        // The action for the unknown track 2 is skipped.
+        let source = futures::stream::iter(
+            [
+                (0, "a"),
+                (1, "a"),
+                (0, "a"),
+                (2, "c"),
+                (1, "d"),
+                (0, "c"),
+                (1, "d"),
+            ]
+            .map(|(track, action)| (track, action.to_string())),
+        );
+        let mut scheduler = AsyncReadingScheduler::new(matching, source, 2);
+        futures::executor::block_on(scheduler.run());
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+
+        let expected: HashSet<MatchingResult> = [
+            (0, 2, 1, 1),
+            (1, 2, 1, 1),
+            (2, 2, 1, 1),
+            (0, 2, 2, 2),
+            (1, 2, 2, 2),
+            (2, 2, 2, 2),
+        ]
+        .into_iter()
+        .map(|(s0, e0, s1, e1)| MatchingResult {
+            intervals: vec![MatchingInterval::new(s0, e0), MatchingInterval::new(s1, e1)],
+            ids: vec![0, 1],
+        })
+        .collect();
+        assert_eq!(results, expected);
+    }
+}
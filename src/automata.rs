@@ -1,14 +1,32 @@
-use std::cell::RefCell;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 use typed_arena::Arena;
 
+use crate::symbol_matcher::SymbolMatcher;
+
+/// Arbitrary data attached to a transition, e.g., the name of the rule it comes from.
+pub type TransitionMeta = Rc<dyn Any>;
+
 /// Represents a transition for an NFA.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Transition<'a, L> {
     pub label: L,
     pub next_state: &'a State<'a, L>,
+    /// The metadata of the transition, if any.
+    ///
+    /// The metadata is ignored by the matching and the comparison of transitions, but it is kept
+    /// on the copied transitions, e.g., by [`Automata::star`].
+    pub meta: Option<TransitionMeta>,
+}
+
+impl<L: PartialEq> PartialEq for Transition<'_, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.label == other.label && self.next_state == other.next_state
+    }
 }
 
 impl<L: Hash> Hash for Transition<'_, L> {
@@ -24,6 +42,44 @@ impl<L: Hash> Hash for Transition<'_, L> {
 /// is from the alphabet Σ and 'var' identifies the variable.
 pub type NFAHTransition<'a> = Transition<'a, (String, usize)>;
 
+impl NFAHTransition<'_> {
+    /// Returns the letter of the label.
+    pub fn letter(&self) -> &str {
+        &self.label.0
+    }
+
+    /// Returns the variable of the label.
+    pub fn variable(&self) -> usize {
+        self.label.1
+    }
+}
+
+/// A letter matcher used in the labels of [`ClassNFAH`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LabelMatch {
+    /// Matches exactly the given letter.
+    Exact(String),
+    /// Matches any letter except the given ones, i.e., a negated character class.
+    /// This is a `BTreeSet` rather than a `HashSet` so that the label is hashable.
+    NotIn(BTreeSet<String>),
+    /// Matches any letter, i.e., a wildcard consuming one letter of whatever it is.
+    /// Unlike a literal `"*"` letter, it does not require the action to be `"*"`.
+    Any,
+}
+
+impl LabelMatch {
+    /// Returns `true` if `action` is matched, where the letters are compared by `symbol_matcher`.
+    pub fn matches_with(&self, action: &str, symbol_matcher: &dyn SymbolMatcher) -> bool {
+        match self {
+            LabelMatch::Exact(letter) => symbol_matcher.matches(letter, action),
+            LabelMatch::NotIn(letters) => !letters
+                .iter()
+                .any(|letter| symbol_matcher.matches(letter, action)),
+            LabelMatch::Any => true,
+        }
+    }
+}
+
 /// Represents a state in an NFA.
 ///
 /// Stores whether it is final (accepting) and its outgoing transitions.
@@ -31,7 +87,9 @@ pub struct State<'a, L> {
     /// Outgoing transitions.
     pub transitions: RefCell<Vec<&'a Transition<'a, L>>>,
     /// Whether this state is an accepting state.
-    pub is_final: bool,
+    ///
+    /// This is a `Cell` so that the finality can be toggled after construction (see [`State::set_final`]).
+    is_final: Cell<bool>,
 }
 
 impl<'a, L> State<'a, L> {
@@ -44,6 +102,27 @@ impl<'a, L> State<'a, L> {
     pub fn add_transition(&self, transition: &'a Transition<'a, L>) {
         self.transitions.borrow_mut().push(transition);
     }
+
+    /// Returns `true` if this state is an accepting state.
+    pub fn is_final(&self) -> bool {
+        self.is_final.get()
+    }
+
+    /// Sets whether this state is an accepting state.
+    pub fn set_final(&self, is_final: bool) {
+        self.is_final.set(is_final);
+    }
+
+    /// Removes the given outgoing transition from this state.
+    ///
+    /// The transition is compared by its address, so only the given transition is removed even if
+    /// another transition has the same label and target. Returns `true` if the transition was found.
+    pub fn remove_transition(&self, transition: &Transition<'a, L>) -> bool {
+        let mut transitions = self.transitions.borrow_mut();
+        let len = transitions.len();
+        transitions.retain(|&t| !std::ptr::eq(t, transition));
+        transitions.len() != len
+    }
 }
 
 impl<L> PartialEq for State<'_, L> {
@@ -57,7 +136,7 @@ impl<L> Eq for State<'_, L> {}
 
 impl<L> Debug for State<'_, L> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "State({:p}, is_final: {})", self, self.is_final)
+        write!(f, "State({:p}, is_final: {})", self, self.is_final())
     }
 }
 
@@ -77,38 +156,107 @@ pub type NFAH<'a> = Automata<'a, (String, usize)>;
 pub type NFA<'a> = Automata<'a, String>;
 /// An epsilon-NFA (where transitions are labeled by `Some(symbol)` or `None` for ε).
 pub type EpsilonNFA<'a> = Automata<'a, Option<String>>;
+/// An NFAH whose transitions may read a class of letters, e.g., any letter except "a".
+pub type ClassNFAH<'a> = Automata<'a, (LabelMatch, usize)>;
+
+/// The finality of the two components of each state of a product, see
+/// [`Automata::product_tagged`].
+pub type FinalityTags<'b, L> = HashMap<*const State<'b, L>, (bool, bool)>;
 
 pub trait ValidLabel {
     /// Checks that the label is valid given an optional dimension.
     /// For automata over Σ×Vars, the dimension is required.
     /// For automata over Σ, the dimension is ignored.
-    fn validate(&self, dimensions: usize);
+    ///
+    /// Returns an error message if the label is invalid.
+    fn validate(&self, dimensions: usize) -> Result<(), String>;
+}
+
+/// Checks that the variable index `var` is within the given dimensions.
+fn validate_variable(var: usize, dimensions: usize) -> Result<(), String> {
+    if var >= dimensions {
+        return Err(format!(
+            "Variable index {} out of bounds for {} dimensions",
+            var, dimensions
+        ));
+    }
+    Ok(())
 }
 
 impl ValidLabel for (String, usize) {
-    fn validate(&self, dimensions: usize) {
-        let (_, var) = self;
-        if *var >= dimensions {
-            panic!("Variable index out of bounds");
+    fn validate(&self, dimensions: usize) -> Result<(), String> {
+        validate_variable(self.1, dimensions)
+    }
+}
+
+impl ValidLabel for (LabelMatch, usize) {
+    fn validate(&self, dimensions: usize) -> Result<(), String> {
+        validate_variable(self.1, dimensions)
+    }
+}
+
+/// A label reading a letter on a variable, used to run automata over Σ×Vars on inputs.
+pub trait VariableLabel {
+    /// Returns the variable to read.
+    fn variable(&self) -> usize;
+
+    /// Returns `true` if the label matches the letter `action` on its variable, where the letters
+    /// are compared by `symbol_matcher`.
+    fn matches_with(&self, action: &str, symbol_matcher: &dyn SymbolMatcher) -> bool;
+
+    /// Returns the letter if the label matches only this letter, or `None` for a class of letters.
+    fn letter(&self) -> Option<&str>;
+}
+
+impl VariableLabel for (String, usize) {
+    fn variable(&self) -> usize {
+        self.1
+    }
+
+    fn matches_with(&self, action: &str, symbol_matcher: &dyn SymbolMatcher) -> bool {
+        symbol_matcher.matches(&self.0, action)
+    }
+
+    fn letter(&self) -> Option<&str> {
+        Some(&self.0)
+    }
+}
+
+impl VariableLabel for (LabelMatch, usize) {
+    fn variable(&self) -> usize {
+        self.1
+    }
+
+    fn matches_with(&self, action: &str, symbol_matcher: &dyn SymbolMatcher) -> bool {
+        self.0.matches_with(action, symbol_matcher)
+    }
+
+    fn letter(&self) -> Option<&str> {
+        match &self.0 {
+            LabelMatch::Exact(letter) => Some(letter),
+            LabelMatch::NotIn(_) | LabelMatch::Any => None,
         }
     }
 }
 
 impl ValidLabel for String {
-    fn validate(&self, _dimensions: usize) {
+    fn validate(&self, _dimensions: usize) -> Result<(), String> {
         // No validity check is necessary for simple letter labels.
+        Ok(())
     }
 }
 
 impl ValidLabel for Option<String> {
-    fn validate(&self, _dimensions: usize) {
+    fn validate(&self, _dimensions: usize) -> Result<(), String> {
         // No validity check is necessary for simple letter labels.
+        Ok(())
     }
 }
 
 impl ValidLabel for char {
-    fn validate(&self, _dimensions: usize) {
+    fn validate(&self, _dimensions: usize) -> Result<(), String> {
         // No validity check is necessary for simple letter labels.
+        Ok(())
     }
 }
 
@@ -146,7 +294,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
     pub fn add_state(&mut self, is_initial: bool, is_final: bool) -> &'a State<'a, L> {
         let state = self.states.alloc(State {
             transitions: RefCell::new(Vec::new()),
-            is_final,
+            is_final: Cell::new(is_final),
         });
         if is_initial {
             self.initial_states.push(state);
@@ -155,21 +303,56 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
     }
 
     /// Adds a transition (action, var) from `from` to `to`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the label is invalid, e.g., its variable is out of bounds.
     pub fn add_transition(
         &self,
         from: &'a State<'a, L>,
         label: L,
         to: &'a State<'a, L>,
     ) -> &'a Transition<'a, L> {
-        label.validate(self.dimensions);
+        self.add_transition_with_meta(from, label, to, None)
+    }
+
+    /// Adds a transition as [`Automata::add_transition`] with the metadata `meta`.
+    pub fn add_transition_with_meta(
+        &self,
+        from: &'a State<'a, L>,
+        label: L,
+        to: &'a State<'a, L>,
+        meta: Option<TransitionMeta>,
+    ) -> &'a Transition<'a, L> {
+        if let Err(message) = label.validate(self.dimensions) {
+            panic!("{}", message);
+        }
         let transition = self.transitions.alloc(Transition {
             label,
             next_state: to,
+            meta,
         });
         from.add_transition(transition);
         transition
     }
 
+    /// Removes `transition` from the outgoing transitions of `from`.
+    ///
+    /// The transition stays in the arena but is no longer reachable.
+    /// Returns `true` if `transition` was an outgoing transition of `from`.
+    pub fn remove_transition(
+        &self,
+        from: &'a State<'a, L>,
+        transition: &'a Transition<'a, L>,
+    ) -> bool {
+        from.remove_transition(transition)
+    }
+
+    /// Sets whether `state` is an accepting state.
+    pub fn set_final(&self, state: &'a State<'a, L>, is_final: bool) {
+        state.set_final(is_final);
+    }
+
     /// Returns the length of the shortest accepted word in the automaton using BFS.
     pub fn shortest_accepted_word_length(&self) -> usize {
         // (state, current_length) is the BFS node;
@@ -186,7 +369,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
 
         while let Some((current_state, length)) = queue.pop_front() {
             // If current_state is final, update the shortest_length length
-            if current_state.is_final {
+            if current_state.is_final() {
                 // Update the shortest length
                 match shortest_length {
                     None => shortest_length = Some(length),
@@ -217,6 +400,49 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         shortest_length.unwrap()
     }
 
+    /// Returns a shortest word leading from an initial state to `target`, using BFS.
+    ///
+    /// Returns `None` if `target` is unreachable. This is mainly for debugging, e.g., to find out
+    /// why a state of a determinized or product automaton is reached.
+    pub fn shortest_word_to(&self, target: &State<'a, L>) -> Option<Vec<L>> {
+        // The predecessor and the label used to reach each visited state for the first time
+        let mut parents: HashMap<*const State<'a, L>, Option<(&'a State<'a, L>, &'a L)>> =
+            HashMap::with_capacity(self.states.len());
+        let mut queue = VecDeque::with_capacity(self.initial_states.len());
+
+        for &initial_state in &self.initial_states {
+            if parents.insert(initial_state as *const _, None).is_none() {
+                queue.push_back(initial_state);
+            }
+        }
+
+        while let Some(current_state) = queue.pop_front() {
+            if std::ptr::eq(current_state, target) {
+                // Reconstruct the word by following the predecessors
+                let mut word = Vec::new();
+                let mut state = current_state;
+                while let Some((previous, label)) = parents[&(state as *const _)] {
+                    word.push(label.clone());
+                    state = previous;
+                }
+                word.reverse();
+                return Some(word);
+            }
+
+            for &transition in current_state.get_transitions().iter() {
+                let next_state = transition.next_state;
+                if let std::collections::hash_map::Entry::Vacant(e) =
+                    parents.entry(next_state as *const _)
+                {
+                    e.insert(Some((current_state, &transition.label)));
+                    queue.push_back(next_state);
+                }
+            }
+        }
+
+        None
+    }
+
     /// Returns all prefixes of length `n` that can appear along a path from any initial state.
     ///
     /// We store the entire label `(action, var)` in the prefix.
@@ -263,12 +489,51 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         prefixes
     }
 
+    /// Returns the number of transitions carrying each label, e.g., to analyze the alphabet.
+    ///
+    /// Only the transitions from the states reachable from an initial state are counted.
+    pub fn transition_count_by_label(&self) -> HashMap<L, usize> {
+        let mut counts = HashMap::new();
+        let mut visited = HashSet::with_capacity(self.states.len());
+        let mut queue = VecDeque::with_capacity(self.initial_states.len());
+        for &init in &self.initial_states {
+            if visited.insert(init as *const State<'a, L>) {
+                queue.push_back(init);
+            }
+        }
+
+        while let Some(current_state) = queue.pop_front() {
+            for &transition in current_state.get_transitions().iter() {
+                *counts.entry(transition.label.clone()).or_insert(0) += 1;
+                if visited.insert(transition.next_state as *const State<'a, L>) {
+                    queue.push_back(transition.next_state);
+                }
+            }
+        }
+
+        counts
+    }
+
     /// Removes transitions to states that cannot lead to a final state.
     /// A standard "reverse" reachability: keep only states from which
     /// a final state is reachable, removing transitions that lead to
     /// states outside that set.
     pub fn remove_unreachable_transitions(&self) {
         // 1) Collect all states reachable from an initial state
+        let reachable = self.reachable_states();
+
+        // 2) Among these, keep only states from which some final state is reachable.
+        let can_reach_final = self.co_reachable_states(&reachable);
+
+        // 3) Remove transitions that lead to states not in can_reach_final.
+        for &current_state in &reachable {
+            let mut trans_out = current_state.transitions.borrow_mut();
+            trans_out.retain(|t| can_reach_final.contains(&t.next_state));
+        }
+    }
+
+    /// Returns the states reachable from an initial state.
+    fn reachable_states(&self) -> HashSet<&'a State<'a, L>> {
         let mut reachable = HashSet::with_capacity(self.states.len());
         let mut worklist = VecDeque::with_capacity(self.initial_states.len());
 
@@ -287,14 +552,22 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             }
         }
 
-        // 2) Among these, keep only states from which some final state is reachable.
-        //    We do a backward search from final states among the "reachable" set.
+        reachable
+    }
+
+    /// Returns the states in `reachable` from which some final state is reachable, i.e., the
+    /// co-reachable states.
+    fn co_reachable_states(
+        &self,
+        reachable: &HashSet<&'a State<'a, L>>,
+    ) -> HashSet<&'a State<'a, L>> {
+        // We do a backward search from final states among the "reachable" set.
         let mut can_reach_final = HashSet::with_capacity(reachable.len());
         let mut final_queue = VecDeque::with_capacity(reachable.len() / 2);
 
         // Start from final states (that are in `reachable`)
-        for &current_state in &reachable {
-            if current_state.is_final {
+        for &current_state in reachable {
+            if current_state.is_final() {
                 can_reach_final.insert(current_state);
                 final_queue.push_back(current_state);
             }
@@ -305,7 +578,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         loop {
             let before = can_reach_final.len();
             let mut newly_added = Vec::with_capacity(reachable.len() - can_reach_final.len());
-            for &current_state in &reachable {
+            for &current_state in reachable {
                 if can_reach_final.contains(current_state) {
                     // skip
                     continue;
@@ -328,15 +601,16 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             }
         }
 
-        // 3) Remove transitions that lead to states not in can_reach_final.
-        for &current_state in &reachable {
-            let mut trans_out = current_state.transitions.borrow_mut();
-            trans_out.retain(|t| can_reach_final.contains(&t.next_state));
-        }
+        can_reach_final
     }
 }
 
 impl<L> Automata<'_, L> {
+    /// Returns `true` if this automaton accepts the empty word, i.e., if any initial state is final.
+    pub fn accepts_empty(&self) -> bool {
+        self.initial_states.iter().any(|s| s.is_final())
+    }
+
     /// Returns `true` if this automaton's language is empty
     /// (i.e., if no final state can be reached from any initial state).
     /// Otherwise, returns `false`.
@@ -349,12 +623,13 @@ impl<L> Automata<'_, L> {
         let mut visited = HashSet::with_capacity(self.states.len());
         let mut queue = VecDeque::with_capacity(self.initial_states.len());
 
+        // If an initial state is already final, language is non-empty
+        if self.accepts_empty() {
+            return false;
+        }
+
         // Initialize queue with all initial states
         for &init in &self.initial_states {
-            // If an initial state is already final, language is non-empty
-            if init.is_final {
-                return false;
-            }
             queue.push_back(init);
             visited.insert(init as *const _);
         }
@@ -363,7 +638,7 @@ impl<L> Automata<'_, L> {
         while let Some(state) = queue.pop_front() {
             for &transition in state.get_transitions().iter() {
                 let next = transition.next_state;
-                if next.is_final {
+                if next.is_final() {
                     // Found a final state reachable => language is non-empty
                     return false;
                 }
@@ -380,6 +655,129 @@ impl<L> Automata<'_, L> {
     }
 }
 
+impl<L: VariableLabel> Automata<'_, L> {
+    /// Returns the minimum number of letters on each variable over all the accepted words.
+    ///
+    /// The minimum for each variable is computed independently, i.e., the minimums may come from
+    /// different accepted words. The entries are `None` if the automaton accepts no word.
+    pub fn shortest_accepted_word_length_per_variable(&self) -> Vec<Option<usize>> {
+        (0..self.dimensions)
+            .map(|variable| {
+                // 0-1 BFS, where only the transitions on `variable` cost one letter
+                let mut distances = HashMap::with_capacity(self.states.len());
+                let mut queue = VecDeque::with_capacity(self.initial_states.len());
+                for &init in &self.initial_states {
+                    distances.insert(init as *const _, 0);
+                    queue.push_back((init, 0));
+                }
+                while let Some((state, distance)) = queue.pop_front() {
+                    if distances[&(state as *const _)] < distance {
+                        // Already reached with fewer letters
+                        continue;
+                    }
+                    if state.is_final() {
+                        // The first final state popped has the minimum distance
+                        return Some(distance);
+                    }
+                    for &transition in state.get_transitions().iter() {
+                        let cost = usize::from(transition.label.variable() == variable);
+                        let next = transition.next_state;
+                        let next_distance = distance + cost;
+                        let next_ptr = next as *const _;
+                        if distances
+                            .get(&next_ptr)
+                            .is_none_or(|&known| next_distance < known)
+                        {
+                            distances.insert(next_ptr, next_distance);
+                            if cost == 0 {
+                                queue.push_front((next, next_distance));
+                            } else {
+                                queue.push_back((next, next_distance));
+                            }
+                        }
+                    }
+                }
+                None
+            })
+            .collect()
+    }
+
+    /// Returns the actions that an accepted word can start with on each variable.
+    ///
+    /// The `v`-th element is `None` if an accepted word may read nothing on the variable `v`, i.e.,
+    /// a final state is reachable without reading `v`, or if it may start with a class of letters
+    /// on `v`. Otherwise, a matching trial starting at a position whose action on `v` is not in the
+    /// set never succeeds.
+    pub fn first_symbols_per_variable(&self) -> Vec<Option<HashSet<String>>> {
+        (0..self.dimensions)
+            .map(|var| {
+                let mut first_symbols = HashSet::new();
+                let mut visited: HashSet<*const State<'_, L>> = HashSet::new();
+                let mut queue: VecDeque<&State<'_, L>> =
+                    self.initial_states.iter().copied().collect();
+                while let Some(state) = queue.pop_front() {
+                    if !visited.insert(state as *const _) {
+                        continue;
+                    }
+                    if state.is_final() {
+                        return None;
+                    }
+                    for transition in state.get_transitions().iter() {
+                        if transition.label.variable() == var {
+                            first_symbols.insert(transition.label.letter()?.to_string());
+                        } else {
+                            queue.push_back(transition.next_state);
+                        }
+                    }
+                }
+                Some(first_symbols)
+            })
+            .collect()
+    }
+}
+
+impl<'a, L: Eq + Hash + Clone + ValidLabel + VariableLabel> Automata<'a, L> {
+    /// Returns the prefixes of length `n` of the accepted words projected to `variable`.
+    ///
+    /// Only the labels on `variable` are in the prefixes. With `n` at most the minimum by
+    /// [`Automata::shortest_accepted_word_length_per_variable`], every accepted word has such a
+    /// prefix, e.g., to learn the skip values from.
+    pub fn accepted_prefixes_on_variable(&self, variable: usize, n: usize) -> HashSet<Vec<L>> {
+        let co_reachable = self.co_reachable_states(&self.reachable_states());
+        let mut prefixes = HashSet::new();
+        let mut visited = HashSet::with_capacity(self.states.len());
+        let mut queue = VecDeque::with_capacity(self.initial_states.len());
+        for &init in &self.initial_states {
+            if co_reachable.contains(init) && visited.insert((init as *const _, vec![])) {
+                queue.push_back((init, vec![]));
+            }
+        }
+
+        while let Some((current_state, prefix)) = queue.pop_front() {
+            if prefix.len() == n {
+                prefixes.insert(prefix);
+                continue;
+            }
+            for &transition in current_state.get_transitions().iter() {
+                let next = transition.next_state;
+                // Only the paths to a final state make the accepted words
+                if !co_reachable.contains(next) {
+                    continue;
+                }
+                let mut next_prefix = prefix.clone();
+                if transition.label.variable() == variable {
+                    next_prefix.push(transition.label.clone());
+                }
+                if visited.insert((next as *const _, next_prefix.clone())) {
+                    queue.push_back((next, next_prefix));
+                }
+            }
+        }
+
+        prefixes
+    }
+}
+
 impl<'a, L> Automata<'a, L>
 where
     L: Clone,
@@ -491,6 +889,24 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         new_states_arena: &'b Arena<State<'b, L>>,
         new_trans_arena: &'b Arena<Transition<'b, L>>,
     ) -> Automata<'b, L> {
+        self.product_tagged(other, new_states_arena, new_trans_arena)
+            .0
+    }
+
+    /// Builds the product automaton of `self` and `other` as [`Automata::product`], and tags each
+    /// product state with the finality of its components in `self` and `other`.
+    ///
+    /// The tags tell which operand accepts a word reaching the state, so the acceptance of the
+    /// intersection (`a && b`), the union (`a || b`), or the difference (`a && !b`) is obtained from
+    /// the same construction. The product follows only the words both automata can read, so the
+    /// union and the difference are exact only if both automata are complete, e.g., with a sink
+    /// state. The product states themselves are final as in [`Automata::product`].
+    pub fn product_tagged<'b>(
+        &self,
+        other: &Automata<'a, L>,
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> (Automata<'b, L>, FinalityTags<'b, L>) {
         if self.dimensions != other.dimensions {
             panic!(
                 "The two automata must have the same dimensions: expected {}, got {}",
@@ -503,13 +919,15 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
 
         // We'll map (s1_ptr, s2_ptr) -> newly created product state.
         let mut pair_to_state = HashMap::new();
+        let mut tags = HashMap::new();
         let mut queue = VecDeque::new();
 
         // 1) Create product initial states from all pairs of (init1, init2)
         for &init1 in &self.initial_states {
             for &init2 in &other.initial_states {
-                let is_final = init1.is_final && init2.is_final;
+                let is_final = init1.is_final() && init2.is_final();
                 let prod_init = product_automata.add_state(true, is_final);
+                tags.insert(prod_init as *const _, (init1.is_final(), init2.is_final()));
                 pair_to_state.insert((init1 as *const _, init2 as *const _), prod_init);
                 queue.push_back((init1, init2));
             }
@@ -546,8 +964,9 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
                             let new_next = match pair_to_state.get(&key) {
                                 Some(&existing) => existing,
                                 None => {
-                                    let is_fin = n1.is_final && n2.is_final;
+                                    let is_fin = n1.is_final() && n2.is_final();
                                     let st_new = product_automata.add_state(false, is_fin);
+                                    tags.insert(st_new as *const _, (n1.is_final(), n2.is_final()));
                                     pair_to_state.insert(key, st_new);
                                     queue.push_back((n1, n2));
                                     st_new
@@ -561,7 +980,218 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             }
         }
 
-        product_automata
+        (product_automata, tags)
+    }
+
+    /// Returns `true` if no word is accepted by both `self` and `other`.
+    ///
+    /// This is the emptiness of [`Automata::product`], but the pairs of states are explored
+    /// without building the product, and the search stops at the first pair of final states.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimensions of the two automata differ.
+    pub fn languages_disjoint(&self, other: &Automata<'_, L>) -> bool {
+        !self.search_shared_final_pair(other).0
+    }
+
+    /// Returns `true` if some word is accepted by both `self` and `other`, i.e., the product is
+    /// nonempty.
+    ///
+    /// The pairs of states of the product are explored in the BFS order without building the
+    /// product, and `true` is returned as soon as a pair of final states is created, i.e., one BFS
+    /// layer earlier than [`Automata::languages_disjoint`], whose negation this is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimensions of the two automata differ.
+    pub fn product_nonempty(&self, other: &Automata<'_, L>) -> bool {
+        self.search_created_final_pair(other).0
+    }
+
+    /// Searches the pairs of states of the product of `self` and `other` in the BFS order, and
+    /// stops as soon as a pair of final states is created.
+    ///
+    /// Returns whether such a pair is created, and the number of the pairs visited until then.
+    fn search_created_final_pair(&self, other: &Automata<'_, L>) -> (bool, usize) {
+        if self.dimensions != other.dimensions {
+            panic!(
+                "The two automata must have the same dimensions: expected {}, got {}",
+                self.dimensions, other.dimensions
+            );
+        }
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        for &init1 in &self.initial_states {
+            for &init2 in &other.initial_states {
+                if visited.insert((init1 as *const State<L>, init2 as *const State<L>)) {
+                    if init1.is_final() && init2.is_final() {
+                        return (true, visited.len());
+                    }
+                    queue.push_back((init1, init2));
+                }
+            }
+        }
+
+        // BFS in the space of (s1, s2) pairs
+        while let Some((s1, s2)) = queue.pop_front() {
+            let transitions_2 = s2.get_transitions();
+            for &t1 in s1.get_transitions().iter() {
+                for &t2 in transitions_2.iter().filter(|t2| t2.label == t1.label) {
+                    let key = (
+                        t1.next_state as *const State<L>,
+                        t2.next_state as *const State<L>,
+                    );
+                    if visited.insert(key) {
+                        if t1.next_state.is_final() && t2.next_state.is_final() {
+                            return (true, visited.len());
+                        }
+                        queue.push_back((t1.next_state, t2.next_state));
+                    }
+                }
+            }
+        }
+        (false, visited.len())
+    }
+
+    /// Searches the pairs of states of the product of `self` and `other` for a pair of final
+    /// states in the BFS order.
+    ///
+    /// Returns whether such a pair is reached, and the number of the pairs visited until then.
+    fn search_shared_final_pair(&self, other: &Automata<'_, L>) -> (bool, usize) {
+        if self.dimensions != other.dimensions {
+            panic!(
+                "The two automata must have the same dimensions: expected {}, got {}",
+                self.dimensions, other.dimensions
+            );
+        }
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        for &init1 in &self.initial_states {
+            for &init2 in &other.initial_states {
+                if visited.insert((init1 as *const State<L>, init2 as *const State<L>)) {
+                    queue.push_back((init1, init2));
+                }
+            }
+        }
+
+        // BFS in the space of (s1, s2) pairs
+        while let Some((s1, s2)) = queue.pop_front() {
+            if s1.is_final() && s2.is_final() {
+                return (true, visited.len());
+            }
+            let transitions_2 = s2.get_transitions();
+            for &t1 in s1.get_transitions().iter() {
+                for &t2 in transitions_2.iter().filter(|t2| t2.label == t1.label) {
+                    let key = (
+                        t1.next_state as *const State<L>,
+                        t2.next_state as *const State<L>,
+                    );
+                    if visited.insert(key) {
+                        queue.push_back((t1.next_state, t2.next_state));
+                    }
+                }
+            }
+        }
+        (false, visited.len())
+    }
+
+    /// Builds the intersection of all the automata in `automata`.
+    ///
+    /// The intersection is built by folding [`Automata::product`] over `automata` from the left.
+    /// Each intermediate product is built in fresh arenas, and only the final result is copied to
+    /// the given arenas.
+    ///
+    /// If any of the automata or any of the intermediate products has an empty language, the
+    /// remaining products are skipped and the result is an empty automaton without any state.
+    ///
+    /// # Arguments
+    ///
+    /// * `automata` - The automata to intersect. They must all have the same dimensions.
+    /// * `new_states_arena` - The arena for the states of the resulting automaton.
+    /// * `new_trans_arena` - The arena for the transitions of the resulting automaton.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `automata` is empty or if the dimensions of the automata differ.
+    pub fn intersect_many<'b>(
+        automata: &[&Automata<'a, L>],
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> Automata<'b, L> {
+        let dimensions = match automata.first() {
+            Some(first) => first.dimensions,
+            None => panic!("At least one automaton is required for the intersection"),
+        };
+        for automaton in automata {
+            if automaton.dimensions != dimensions {
+                panic!(
+                    "All the automata must have the same dimensions: expected {}, got {}",
+                    dimensions, automaton.dimensions
+                );
+            }
+        }
+        // The intersection is empty if any of the components is empty.
+        if automata.iter().any(|automaton| automaton.is_empty()) {
+            return Automata::new(new_states_arena, new_trans_arena, dimensions);
+        }
+
+        // Since `product` takes the two automata of the same lifetime, the operands after the
+        // first two are copied to the arenas allocated here.
+        let arenas = Arena::new();
+        let fresh_arenas = || -> &(Arena<State<'_, L>>, Arena<Transition<'_, L>>) {
+            arenas.alloc((Arena::new(), Arena::new()))
+        };
+        let (first, rest) = automata.split_first().unwrap();
+        let Some((second, rest)) = rest.split_first() else {
+            return first.copy_reachable(new_states_arena, new_trans_arena);
+        };
+        let (states, transitions) = fresh_arenas();
+        let mut intersection = first.product(second, states, transitions);
+        for automaton in rest {
+            if intersection.is_empty() {
+                break;
+            }
+            let (states, transitions) = fresh_arenas();
+            let operand = automaton.copy_reachable(states, transitions);
+            let (states, transitions) = fresh_arenas();
+            intersection = intersection.product(&operand, states, transitions);
+        }
+        if intersection.is_empty() {
+            return Automata::new(new_states_arena, new_trans_arena, dimensions);
+        }
+        intersection.copy_reachable(new_states_arena, new_trans_arena)
+    }
+
+    /// Copies the part of `self` reachable from the initial states to the given arenas.
+    fn copy_reachable<'b>(
+        &self,
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> Automata<'b, L> {
+        let mut copy = Automata::new(new_states_arena, new_trans_arena, self.dimensions);
+        let mut state_map: HashMap<*const State<'a, L>, &State<'b, L>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &initial in &self.initial_states {
+            if let std::collections::hash_map::Entry::Vacant(e) =
+                state_map.entry(initial as *const _)
+            {
+                e.insert(copy.add_state(true, initial.is_final()));
+                queue.push_back(initial);
+            }
+        }
+        while let Some(current) = queue.pop_front() {
+            let new_current = state_map[&(current as *const _)];
+            for &t in current.transitions.borrow().iter() {
+                let next = t.next_state;
+                let new_next = *state_map.entry(next as *const _).or_insert_with(|| {
+                    queue.push_back(next);
+                    copy.add_state(false, next.is_final())
+                });
+                copy.add_transition(new_current, t.label.clone(), new_next);
+            }
+        }
+        copy
     }
 
     /// Concatenates `self` with `other`, building the new automata in the provided arenas.
@@ -587,9 +1217,9 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         // Determine if the second automata accepts the empty word.
         // (A run in self may “jump” into other without consuming input only if
         // an initial state of `other` is final.)
-        let other_accepts_empty = other.initial_states.iter().any(|s| s.is_final);
+        let other_accepts_empty = other.accepts_empty();
         // Also, if self accepts ε then concatenation should include words from other alone.
-        let self_accepts_empty = self.initial_states.iter().any(|s| s.is_final);
+        let self_accepts_empty = self.accepts_empty();
 
         // We will copy the states from self and other into the new automata.
         // The mapping is from the pointer of an original state to its copy.
@@ -600,14 +1230,14 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         // A state from self will be final in the new automata only if it was final and
         // the second automata accepts ε (i.e. the run can finish in self).
         for state in self.iter_states() {
-            let new_is_final = state.is_final && other_accepts_empty;
+            let new_is_final = state.is_final() && other_accepts_empty;
             let new_state = new_aut.add_state(false, new_is_final);
             map_self.insert(state as *const _, new_state);
         }
 
         // Copy all states from other.
         for state in other.iter_states() {
-            let new_state = new_aut.add_state(false, state.is_final);
+            let new_state = new_aut.add_state(false, state.is_final());
             map_other.insert(state as *const _, new_state);
         }
 
@@ -628,7 +1258,12 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             let new_from = map_self[&(state as *const _)];
             for &trans in state.get_transitions().iter() {
                 let new_to = map_self[&(trans.next_state as *const _)];
-                new_aut.add_transition(new_from, trans.label.clone(), new_to);
+                new_aut.add_transition_with_meta(
+                    new_from,
+                    trans.label.clone(),
+                    new_to,
+                    trans.meta.clone(),
+                );
             }
         }
 
@@ -637,7 +1272,12 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             let new_from = map_other[&(state as *const _)];
             for &trans in state.get_transitions().iter() {
                 let new_to = map_other[&(trans.next_state as *const _)];
-                new_aut.add_transition(new_from, trans.label.clone(), new_to);
+                new_aut.add_transition_with_meta(
+                    new_from,
+                    trans.label.clone(),
+                    new_to,
+                    trans.meta.clone(),
+                );
             }
         }
 
@@ -647,12 +1287,17 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         // These transitions “simulate” the ε-move in the usual construction,
         // by consuming the same letter that would be read from other.
         for state in self.iter_states() {
-            if state.is_final {
+            if state.is_final() {
                 let new_from = map_self[&(state as *const _)];
                 for &init_other in &other.initial_states {
                     for &trans in init_other.get_transitions().iter() {
                         let new_to = map_other[&(trans.next_state as *const _)];
-                        new_aut.add_transition(new_from, trans.label.clone(), new_to);
+                        new_aut.add_transition_with_meta(
+                            new_from,
+                            trans.label.clone(),
+                            new_to,
+                            trans.meta.clone(),
+                        );
                     }
                 }
             }
@@ -680,8 +1325,150 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         // Create a mapping from each state in self to its copy in new_aut.
         let mut map_self: HashMap<*const State<'a, L>, &State<'b, L>> = HashMap::new();
         for state in self.iter_states() {
-            // In the copied automaton, the finality is kept as in self.
-            let new_state = new_aut.add_state(false, state.is_final);
+            // In the copied automaton, the finality is kept as in self.
+            let new_state = new_aut.add_state(false, state.is_final());
+            map_self.insert(state as *const _, new_state);
+        }
+
+        // Copy transitions from self.
+        for state in self.iter_states() {
+            let new_from = map_self[&(state as *const _)];
+            for &trans in state.get_transitions().iter() {
+                let new_to = map_self[&(trans.next_state as *const _)];
+                new_aut.add_transition_with_meta(
+                    new_from,
+                    trans.label.clone(),
+                    new_to,
+                    trans.meta.clone(),
+                );
+            }
+        }
+
+        // Add jump transitions.
+        // For every state in self that is final, add (non-deterministic) transitions that
+        // simulate a jump into self by “injecting” each outgoing transition from every initial state.
+        for state in self.iter_states() {
+            if state.is_final() {
+                let new_from = map_self[&(state as *const _)];
+                for &init in &self.initial_states {
+                    for &trans in init.get_transitions().iter() {
+                        let new_to = map_self[&(trans.next_state as *const _)];
+                        new_aut.add_transition_with_meta(
+                            new_from,
+                            trans.label.clone(),
+                            new_to,
+                            trans.meta.clone(),
+                        );
+                    }
+                }
+            }
+        }
+
+        // Add a new fresh state which will be the sole initial state.
+        // This state is marked final (so that the empty word is accepted).
+        let new_init = new_aut.add_state(true, true);
+        // From the new initial state, add jump transitions based on the outgoing transitions
+        // of each initial state of the original automaton.
+        for &init in &self.initial_states {
+            for &trans in init.get_transitions().iter() {
+                let new_to = map_self[&(trans.next_state as *const _)];
+                new_aut.add_transition_with_meta(
+                    new_init,
+                    trans.label.clone(),
+                    new_to,
+                    trans.meta.clone(),
+                );
+            }
+        }
+        // In star, the only initial state is the new one.
+        new_aut.initial_states = vec![new_init];
+
+        new_aut
+    }
+
+    /// Returns a new automaton recognizing the Kleene plus (A⁺) of the language of `self`.
+    ///
+    /// The new automaton is built in the given arenas. Its construction is similar to `star`
+    /// except that we do not add a fresh initial state; rather, we keep the copy of self’s initial
+    /// states as initial. In addition, jump transitions are added from every final state to the
+    /// outgoing transitions of each initial state, allowing repeated occurrences.
+    pub fn plus<'b>(
+        &'a self,
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> Automata<'b, L> {
+        // Create the new automaton.
+        let mut new_aut = Automata::new(new_states_arena, new_trans_arena, self.dimensions);
+
+        // Create a mapping from each state in self to its copy in new_aut.
+        let mut map_self: HashMap<*const State<'a, L>, &State<'b, L>> = HashMap::new();
+        for state in self.iter_states() {
+            let new_state = new_aut.add_state(false, state.is_final());
+            map_self.insert(state as *const _, new_state);
+        }
+
+        // Copy transitions from self.
+        for state in self.iter_states() {
+            let new_from = map_self[&(state as *const _)];
+            for &trans in state.get_transitions().iter() {
+                let new_to = map_self[&(trans.next_state as *const _)];
+                new_aut.add_transition_with_meta(
+                    new_from,
+                    trans.label.clone(),
+                    new_to,
+                    trans.meta.clone(),
+                );
+            }
+        }
+
+        // Add jump transitions.
+        // For every state in self that is final, add transitions to simulate restarting self:
+        // for each initial state in self, for each outgoing transition from that initial state.
+        for state in self.iter_states() {
+            if state.is_final() {
+                let new_from = map_self[&(state as *const _)];
+                for &init in &self.initial_states {
+                    for &trans in init.get_transitions().iter() {
+                        let new_to = map_self[&(trans.next_state as *const _)];
+                        new_aut.add_transition_with_meta(
+                            new_from,
+                            trans.label.clone(),
+                            new_to,
+                            trans.meta.clone(),
+                        );
+                    }
+                }
+            }
+        }
+
+        // Set the new automaton's initial states to be the copies of self's initial states.
+        for &init in &self.initial_states {
+            new_aut.initial_states.push(map_self[&(init as *const _)]);
+        }
+
+        new_aut
+    }
+
+    /// Returns a new automaton recognizing the prefix closure of the language of `self`, i.e.,
+    /// the set of all the prefixes of the words accepted by `self`.
+    ///
+    /// The new automaton is a copy of `self` built in the given arenas, where exactly the
+    /// co-reachable states, i.e., the states from which a final state is reachable, are final.
+    pub fn prefix_closure<'b>(
+        &'a self,
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> Automata<'b, L> {
+        let reachable: HashSet<_> = self.iter_states().collect();
+        let co_reachable = self.co_reachable_states(&reachable);
+
+        // Create the new automaton.
+        let mut new_aut = Automata::new(new_states_arena, new_trans_arena, self.dimensions);
+
+        // Create a mapping from each state in self to its copy in new_aut.
+        let mut map_self: HashMap<*const State<'a, L>, &State<'b, L>> = HashMap::new();
+        for state in self.iter_states() {
+            let new_state = new_aut.add_state(false, co_reachable.contains(state));
             map_self.insert(state as *const _, new_state);
         }
 
@@ -690,93 +1477,58 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             let new_from = map_self[&(state as *const _)];
             for &trans in state.get_transitions().iter() {
                 let new_to = map_self[&(trans.next_state as *const _)];
-                new_aut.add_transition(new_from, trans.label.clone(), new_to);
-            }
-        }
-
-        // Add jump transitions.
-        // For every state in self that is final, add (non-deterministic) transitions that
-        // simulate a jump into self by “injecting” each outgoing transition from every initial state.
-        for state in self.iter_states() {
-            if state.is_final {
-                let new_from = map_self[&(state as *const _)];
-                for &init in &self.initial_states {
-                    for &trans in init.get_transitions().iter() {
-                        let new_to = map_self[&(trans.next_state as *const _)];
-                        new_aut.add_transition(new_from, trans.label.clone(), new_to);
-                    }
-                }
+                new_aut.add_transition_with_meta(
+                    new_from,
+                    trans.label.clone(),
+                    new_to,
+                    trans.meta.clone(),
+                );
             }
         }
 
-        // Add a new fresh state which will be the sole initial state.
-        // This state is marked final (so that the empty word is accepted).
-        let new_init = new_aut.add_state(true, true);
-        // From the new initial state, add jump transitions based on the outgoing transitions
-        // of each initial state of the original automaton.
         for &init in &self.initial_states {
-            for &trans in init.get_transitions().iter() {
-                let new_to = map_self[&(trans.next_state as *const _)];
-                new_aut.add_transition(new_init, trans.label.clone(), new_to);
-            }
+            new_aut.initial_states.push(map_self[&(init as *const _)]);
         }
-        // In star, the only initial state is the new one.
-        new_aut.initial_states = vec![new_init];
 
         new_aut
     }
 
-    /// Returns a new automaton recognizing the Kleene plus (A⁺) of the language of `self`.
+    /// Returns a copy of `self` built in the given arenas, where the label of each transition is
+    /// transformed by `f`, e.g., to collapse synonymous letters.
     ///
-    /// The new automaton is built in the given arenas. Its construction is similar to `star`
-    /// except that we do not add a fresh initial state; rather, we keep the copy of self’s initial
-    /// states as initial. In addition, jump transitions are added from every final state to the
-    /// outgoing transitions of each initial state, allowing repeated occurrences.
-    pub fn plus<'b>(
+    /// Returns an error message if a transformed label is invalid, e.g., its variable is out of
+    /// bounds.
+    pub fn map_labels<'b, F: Fn(&L) -> L>(
         &'a self,
+        f: F,
         new_states_arena: &'b Arena<State<'b, L>>,
         new_trans_arena: &'b Arena<Transition<'b, L>>,
-    ) -> Automata<'b, L> {
-        // Create the new automaton.
+    ) -> Result<Automata<'b, L>, String> {
         let mut new_aut = Automata::new(new_states_arena, new_trans_arena, self.dimensions);
 
         // Create a mapping from each state in self to its copy in new_aut.
         let mut map_self: HashMap<*const State<'a, L>, &State<'b, L>> = HashMap::new();
         for state in self.iter_states() {
-            let new_state = new_aut.add_state(false, state.is_final);
+            let new_state = new_aut.add_state(false, state.is_final());
             map_self.insert(state as *const _, new_state);
         }
 
-        // Copy transitions from self.
+        // Copy transitions from self with the transformed labels.
         for state in self.iter_states() {
             let new_from = map_self[&(state as *const _)];
             for &trans in state.get_transitions().iter() {
+                let label = f(&trans.label);
+                label.validate(self.dimensions)?;
                 let new_to = map_self[&(trans.next_state as *const _)];
-                new_aut.add_transition(new_from, trans.label.clone(), new_to);
-            }
-        }
-
-        // Add jump transitions.
-        // For every state in self that is final, add transitions to simulate restarting self:
-        // for each initial state in self, for each outgoing transition from that initial state.
-        for state in self.iter_states() {
-            if state.is_final {
-                let new_from = map_self[&(state as *const _)];
-                for &init in &self.initial_states {
-                    for &trans in init.get_transitions().iter() {
-                        let new_to = map_self[&(trans.next_state as *const _)];
-                        new_aut.add_transition(new_from, trans.label.clone(), new_to);
-                    }
-                }
+                new_aut.add_transition_with_meta(new_from, label, new_to, trans.meta.clone());
             }
         }
 
-        // Set the new automaton's initial states to be the copies of self's initial states.
         for &init in &self.initial_states {
             new_aut.initial_states.push(map_self[&(init as *const _)]);
         }
 
-        new_aut
+        Ok(new_aut)
     }
 }
 
@@ -818,7 +1570,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
             for &t in &automata_b.initial_states {
                 let key = (Some(s), Some(t));
                 if let std::collections::hash_map::Entry::Vacant(e) = state_map.entry(key) {
-                    let is_final = s.is_final || t.is_final;
+                    let is_final = s.is_final() || t.is_final();
                     let new_state = new_aut.add_state(true, is_final);
                     e.insert(new_state);
                     worklist.push_back(key);
@@ -869,7 +1621,8 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
                             if let std::collections::hash_map::Entry::Vacant(e) =
                                 state_map.entry(new_key)
                             {
-                                let is_final = tr_a.next_state.is_final || tr_b.next_state.is_final;
+                                let is_final =
+                                    tr_a.next_state.is_final() || tr_b.next_state.is_final();
                                 let ns = new_aut.add_state(false, is_final);
                                 e.insert(ns);
                                 worklist.push_back(new_key);
@@ -885,7 +1638,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
                         if let std::collections::hash_map::Entry::Vacant(e) =
                             state_map.entry(new_key)
                         {
-                            let is_final = tr_a.next_state.is_final;
+                            let is_final = tr_a.next_state.is_final();
                             let ns = new_aut.add_state(false, is_final);
                             e.insert(ns);
                             worklist.push_back(new_key);
@@ -900,7 +1653,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
                         if let std::collections::hash_map::Entry::Vacant(e) =
                             state_map.entry(new_key)
                         {
-                            let is_final = tr_b.next_state.is_final;
+                            let is_final = tr_b.next_state.is_final();
                             let ns = new_aut.add_state(false, is_final);
                             e.insert(ns);
                             worklist.push_back(new_key);
@@ -920,7 +1673,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
                         if let std::collections::hash_map::Entry::Vacant(e) =
                             state_map.entry(new_key)
                         {
-                            let is_final = tr.next_state.is_final;
+                            let is_final = tr.next_state.is_final();
                             let ns = new_aut.add_state(false, is_final);
                             e.insert(ns);
                             worklist.push_back(new_key);
@@ -936,7 +1689,7 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
                         if let std::collections::hash_map::Entry::Vacant(e) =
                             state_map.entry(new_key)
                         {
-                            let is_final = tr.next_state.is_final;
+                            let is_final = tr.next_state.is_final();
                             let ns = new_aut.add_state(false, is_final);
                             e.insert(ns);
                             worklist.push_back(new_key);
@@ -950,6 +1703,58 @@ impl<'a, L: Eq + Hash + Clone + ValidLabel> Automata<'a, L> {
         }
         new_aut
     }
+
+    /// Builds an automaton accepting the union of the languages of `automata` by repeated
+    /// pairwise [`Automata::union`].
+    ///
+    /// Each intermediate union is built in fresh arenas, and only the final result is copied to
+    /// the given arenas. The automata with an empty language are ignored, so the result is an
+    /// empty automaton without any state if all of them are empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `automata` is empty or if the dimensions of the automata differ.
+    pub fn union_many<'b>(
+        automata: &[&Automata<'a, L>],
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> Automata<'b, L> {
+        let dimensions = match automata.first() {
+            Some(first) => first.dimensions,
+            None => panic!("At least one automaton is required for the union"),
+        };
+        for automaton in automata {
+            if automaton.dimensions != dimensions {
+                panic!(
+                    "All the automata must have the same dimensions: expected {}, got {}",
+                    dimensions, automaton.dimensions
+                );
+            }
+        }
+        // Since `union` takes the two automata of the same lifetime, all the operands are copied
+        // to the arenas allocated here.
+        let arenas = Arena::new();
+        let fresh_arenas = || -> &(Arena<State<'_, L>>, Arena<Transition<'_, L>>) {
+            arenas.alloc((Arena::new(), Arena::new()))
+        };
+        let mut operands = automata
+            .iter()
+            .filter(|automaton| !automaton.is_empty())
+            .map(|automaton| {
+                let (states, transitions) = fresh_arenas();
+                automaton.copy_reachable(states, transitions)
+            })
+            .collect::<Vec<_>>()
+            .into_iter();
+        let Some(mut result) = operands.next() else {
+            return Automata::new(new_states_arena, new_trans_arena, dimensions);
+        };
+        for operand in operands {
+            let (states, transitions) = fresh_arenas();
+            result = Automata::union(&result, &operand, states, transitions);
+        }
+        result.copy_reachable(new_states_arena, new_trans_arena)
+    }
 }
 
 #[cfg(test)]
@@ -958,6 +1763,7 @@ mod tests {
     use crate::{
         automata::NFAH,
         automata_runner::{AppendOnlySequence, NFAHRunner, SimpleAutomataRunner},
+        tests::utils::create_small_automaton,
     };
     use itertools::Itertools;
     use typed_arena::Arena;
@@ -1000,7 +1806,7 @@ mod tests {
         runner
             .current_configurations
             .iter()
-            .any(|c| c.current_state.is_final && c.input_sequence.iter().all(|s| s.is_empty()))
+            .any(|c| c.current_state.is_final() && c.input_sequence.iter().all(|s| s.is_empty()))
     }
 
     #[test]
@@ -1012,7 +1818,7 @@ mod tests {
         let current_state = automaton.add_state(true, false);
         assert_eq!(automaton.initial_states.len(), 1);
         assert!(automaton.initial_states.contains(&current_state));
-        assert!(!current_state.is_final);
+        assert!(!current_state.is_final());
         assert_eq!(current_state.transitions.borrow().len(), 0);
     }
 
@@ -1037,6 +1843,20 @@ mod tests {
         assert_eq!(all_outgoing[0].next_state, s2);
     }
 
+    #[test]
+    fn test_nfah_transition_accessors() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 2);
+
+        let s1 = automaton.add_state(true, false);
+        let s2 = automaton.add_state(false, true);
+
+        let t = automaton.add_nfah_transition(s1, "a".to_string(), 1, s2);
+        assert_eq!(t.letter(), "a");
+        assert_eq!(t.variable(), 1);
+    }
+
     #[test]
     fn test_iter() {
         let state_arena = Arena::new();
@@ -1064,6 +1884,46 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_shortest_word_to() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 2);
+
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+
+        automaton.add_nfah_transition(s0, "c".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "c".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "a".to_string(), 0, s3);
+        automaton.add_nfah_transition(s3, "b".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "c".to_string(), 0, sf);
+
+        assert_eq!(automaton.shortest_word_to(s0), Some(vec![]));
+        let word = automaton.shortest_word_to(s3).unwrap();
+        assert_eq!(word.len(), 3);
+        assert_eq!(
+            word,
+            vec![
+                ("c".to_string(), 0),
+                ("c".to_string(), 1),
+                ("a".to_string(), 0)
+            ]
+        );
+        assert_eq!(
+            automaton.shortest_word_to(sf).unwrap().len(),
+            automaton.shortest_accepted_word_length()
+        );
+
+        // A state not reachable from the initial states
+        let unreachable = automaton.add_state(false, true);
+        automaton.add_nfah_transition(unreachable, "a".to_string(), 0, s0);
+        assert_eq!(automaton.shortest_word_to(unreachable), None);
+    }
+
     #[test]
     fn test_shortest_accepted_word_length() {
         let state_arena = Arena::new();
@@ -1156,6 +2016,42 @@ mod tests {
         assert_eq!(s4.transitions.borrow().len(), 0);
     }
 
+    #[test]
+    fn test_transition_count_by_label() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let counts = automaton.transition_count_by_label();
+        let expected: HashMap<(String, usize), usize> = [
+            (("a".to_string(), 0), 2),
+            (("b".to_string(), 1), 2),
+            (("c".to_string(), 0), 1),
+            (("d".to_string(), 1), 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(counts, expected);
+        assert_eq!(counts.values().sum::<usize>(), 6);
+    }
+
+    #[test]
+    fn test_accepts_empty() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automata = Automata::<String>::new(&state_arena, &transition_arena, 0);
+        let s0 = automata.add_state(true, false);
+        let s1 = automata.add_state(false, true);
+        automata.add_transition(s0, "a".to_string(), s1);
+
+        // Only "a" is accepted
+        assert!(!automata.accepts_empty());
+
+        // Now ε is also accepted
+        automata.set_final(s0, true);
+        assert!(automata.accepts_empty());
+    }
+
     #[test]
     fn test_emptiness_check() {
         use typed_arena::Arena;
@@ -1291,7 +2187,7 @@ mod tests {
             }
             let mut count = 0;
             while let Some(st) = queue.pop_front() {
-                if st.is_final {
+                if st.is_final() {
                     count += 1;
                 }
                 for &tr in st.transitions.borrow().iter() {
@@ -1309,6 +2205,387 @@ mod tests {
             final_count_inter, 1,
             "Intersection product should have exactly one final state."
         );
+
+        // The reachable pairs are (s0, p0), (s0, p1), (s1, p0), (s1, p1), (s2, p2), and (s2, p3)
+        let tagged_s_arena = Arena::new();
+        let tagged_t_arena = Arena::new();
+        let (tagged, tags) = nfah1.product_tagged(&nfah2, &tagged_s_arena, &tagged_t_arena);
+        assert_eq!(tags.len(), tagged.states.len());
+        let mut reachable_tags = tagged
+            .iter_states()
+            .map(|state| {
+                let (final1, final2) = tags[&(state as *const _)];
+                assert_eq!(state.is_final(), final1 && final2);
+                (final1, final2)
+            })
+            .collect::<Vec<_>>();
+        reachable_tags.sort();
+        assert_eq!(
+            reachable_tags,
+            vec![
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (true, false),
+                (true, true),
+            ]
+        );
+        // Only (s2, p3) is final for the difference of nfah1 and nfah2
+        assert_eq!(
+            reachable_tags
+                .iter()
+                .filter(|&&(final1, final2)| final1 && !final2)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_languages_disjoint() {
+        // A: "a" on the variable 0 followed by any number of "b" on the variable 0
+        let s_arena_a = Arena::new();
+        let t_arena_a = Arena::new();
+        let mut nfah_a = NFAH::new(&s_arena_a, &t_arena_a, 1);
+        let a0 = nfah_a.add_state(true, false);
+        let a1 = nfah_a.add_state(false, true);
+        nfah_a.add_nfah_transition(a0, "a".to_string(), 0, a1);
+        nfah_a.add_nfah_transition(a1, "b".to_string(), 0, a1);
+
+        // B: words of the form "a b b" (overlapping with A)
+        let s_arena_b = Arena::new();
+        let t_arena_b = Arena::new();
+        let mut nfah_b = NFAH::new(&s_arena_b, &t_arena_b, 1);
+        let b0 = nfah_b.add_state(true, false);
+        let b1 = nfah_b.add_state(false, false);
+        let b2 = nfah_b.add_state(false, false);
+        let b3 = nfah_b.add_state(false, true);
+        nfah_b.add_nfah_transition(b0, "a".to_string(), 0, b1);
+        nfah_b.add_nfah_transition(b1, "b".to_string(), 0, b2);
+        nfah_b.add_nfah_transition(b2, "b".to_string(), 0, b3);
+        assert!(!nfah_a.languages_disjoint(&nfah_b));
+        assert!(!nfah_b.languages_disjoint(&nfah_a));
+
+        // C: "c" only
+        let s_arena_c = Arena::new();
+        let t_arena_c = Arena::new();
+        let mut nfah_c = NFAH::new(&s_arena_c, &t_arena_c, 1);
+        let c0 = nfah_c.add_state(true, false);
+        let c1 = nfah_c.add_state(false, true);
+        nfah_c.add_nfah_transition(c0, "c".to_string(), 0, c1);
+        assert!(nfah_a.languages_disjoint(&nfah_c));
+
+        // "a" on the variable 0 and "a" on the variable 1 are disjoint
+        let s_arena_d = Arena::new();
+        let t_arena_d = Arena::new();
+        let mut nfah_d = NFAH::new(&s_arena_d, &t_arena_d, 2);
+        let d0 = nfah_d.add_state(true, false);
+        let d1 = nfah_d.add_state(false, true);
+        nfah_d.add_nfah_transition(d0, "a".to_string(), 0, d1);
+        let s_arena_e = Arena::new();
+        let t_arena_e = Arena::new();
+        let mut nfah_e = NFAH::new(&s_arena_e, &t_arena_e, 2);
+        let e0 = nfah_e.add_state(true, false);
+        let e1 = nfah_e.add_state(false, true);
+        nfah_e.add_nfah_transition(e0, "a".to_string(), 1, e1);
+        assert!(nfah_d.languages_disjoint(&nfah_e));
+
+        // Consistent with the emptiness of the product
+        let s_arena = Arena::new();
+        let t_arena = Arena::new();
+        assert!(nfah_d.product(&nfah_e, &s_arena, &t_arena).is_empty());
+    }
+
+    #[test]
+    fn test_product_nonempty() {
+        // "a" followed by a long chain of "b" in both automata, where "a" is accepted by both
+        let s_arena_a = Arena::new();
+        let t_arena_a = Arena::new();
+        let mut nfah_a = NFAH::new(&s_arena_a, &t_arena_a, 1);
+        let s_arena_b = Arena::new();
+        let t_arena_b = Arena::new();
+        let mut nfah_b = NFAH::new(&s_arena_b, &t_arena_b, 1);
+        for nfah in [&mut nfah_a, &mut nfah_b] {
+            let mut previous = nfah.add_state(true, false);
+            let accepting = nfah.add_state(false, true);
+            nfah.add_nfah_transition(previous, "a".to_string(), 0, accepting);
+            for _ in 0..100 {
+                let next = nfah.add_state(false, false);
+                nfah.add_nfah_transition(previous, "b".to_string(), 0, next);
+                nfah.add_nfah_transition(next, "b".to_string(), 0, previous);
+                previous = next;
+            }
+        }
+
+        assert!(nfah_a.product_nonempty(&nfah_b));
+        assert!(!nfah_a.languages_disjoint(&nfah_b));
+        // The shared word "a" is found when the pair of the accepting states is created, i.e.,
+        // after visiting only the initial pair and that pair
+        assert_eq!(nfah_a.search_created_final_pair(&nfah_b), (true, 2));
+        // Checking the finality when a pair is popped explores one more BFS layer
+        assert_eq!(nfah_a.search_shared_final_pair(&nfah_b), (true, 3));
+        let s_arena = Arena::new();
+        let t_arena = Arena::new();
+        assert!(nfah_a.product(&nfah_b, &s_arena, &t_arena).states.len() > 100);
+
+        // Without any shared word, the whole product is explored
+        let s_arena_c = Arena::new();
+        let t_arena_c = Arena::new();
+        let mut nfah_c = NFAH::new(&s_arena_c, &t_arena_c, 1);
+        let c0 = nfah_c.add_state(true, false);
+        let c1 = nfah_c.add_state(false, true);
+        nfah_c.add_nfah_transition(c0, "c".to_string(), 0, c1);
+        assert!(!nfah_a.product_nonempty(&nfah_c));
+        assert_eq!(nfah_a.search_created_final_pair(&nfah_c), (false, 1));
+
+        // A pair of final initial states is found without any transition
+        let s_arena_d = Arena::new();
+        let t_arena_d = Arena::new();
+        let mut nfah_d = NFAH::new(&s_arena_d, &t_arena_d, 1);
+        nfah_d.add_state(true, true);
+        assert_eq!(nfah_d.search_created_final_pair(&nfah_d), (true, 1));
+    }
+
+    #[test]
+    fn test_shortest_accepted_word_length_per_variable() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &trans_arena);
+        // "c" on the variable 0 and "d" on the variable 1
+        assert_eq!(
+            automaton.shortest_accepted_word_length_per_variable(),
+            vec![Some(1), Some(1)]
+        );
+
+        // Accepts "a a" on the variable 0 and "b b b" on the variable 1 followed by "c" on the
+        // variable 0, so the minimums come from different words.
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 3);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let s4 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, sf);
+        automaton.add_nfah_transition(s0, "b".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "b".to_string(), 1, s3);
+        automaton.add_nfah_transition(s3, "b".to_string(), 1, s4);
+        automaton.add_nfah_transition(s4, "c".to_string(), 0, sf);
+        assert_eq!(
+            automaton.shortest_accepted_word_length_per_variable(),
+            vec![Some(1), Some(0), Some(0)]
+        );
+
+        // Without any final state, there is no accepted word
+        sf.set_final(false);
+        assert_eq!(
+            automaton.shortest_accepted_word_length_per_variable(),
+            vec![None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_accepted_prefixes_on_variable() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 2);
+
+        // Accepts "c" and then ("a" "b")* "c" on the variable 0 interleaved with "c" b* on the
+        // variable 1
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        // A dead end not making any accepted word
+        let dead = automaton.add_state(false, false);
+        automaton.add_nfah_transition(s0, "c".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "c".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "a".to_string(), 0, s3);
+        automaton.add_nfah_transition(s3, "b".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "c".to_string(), 0, sf);
+        automaton.add_nfah_transition(s0, "x".to_string(), 0, dead);
+        automaton.add_nfah_transition(dead, "y".to_string(), 0, dead);
+
+        let projected = |variable: usize, n: usize| -> HashSet<Vec<String>> {
+            automaton
+                .accepted_prefixes_on_variable(variable, n)
+                .into_iter()
+                .map(|prefix| {
+                    prefix
+                        .into_iter()
+                        .map(|(letter, var)| {
+                            assert_eq!(var, variable);
+                            letter
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        let words = |words: &[&[&str]]| -> HashSet<Vec<String>> {
+            words
+                .iter()
+                .map(|word| word.iter().map(|letter| letter.to_string()).collect())
+                .collect()
+        };
+        assert_eq!(
+            automaton.shortest_accepted_word_length_per_variable(),
+            vec![Some(2), Some(1)]
+        );
+        assert_eq!(projected(0, 2), words(&[&["c", "a"], &["c", "c"]]));
+        assert_eq!(projected(1, 1), words(&[&["c"]]));
+        assert_eq!(projected(1, 0), words(&[&[]]));
+
+        // Without any final state, there is no accepted word
+        sf.set_final(false);
+        assert!(projected(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_set_final_and_remove_transition() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 1);
+
+        // Accepts "ab" and "ac"
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        let to_remove = automaton.add_nfah_transition(s1, "b".to_string(), 0, s2);
+        automaton.add_nfah_transition(s1, "c".to_string(), 0, s2);
+
+        let word = |w: &[&str]| vec![w.iter().map(|c| c.to_string()).collect_vec()];
+        assert!(!accepts(&automaton, &word(&["a"])));
+        assert!(accepts(&automaton, &word(&["a", "b"])));
+        assert!(accepts(&automaton, &word(&["a", "c"])));
+
+        // Make "a" accepted too
+        automaton.set_final(s1, true);
+        assert!(s1.is_final());
+        assert!(accepts(&automaton, &word(&["a"])));
+
+        // Remove the transition for "ab"
+        assert!(automaton.remove_transition(s1, to_remove));
+        assert!(!automaton.remove_transition(s1, to_remove));
+        assert_eq!(s1.get_transitions().len(), 1);
+        assert!(accepts(&automaton, &word(&["a"])));
+        assert!(!accepts(&automaton, &word(&["a", "b"])));
+        assert!(accepts(&automaton, &word(&["a", "c"])));
+
+        // Make "a" rejected again
+        automaton.set_final(s1, false);
+        assert!(!accepts(&automaton, &word(&["a"])));
+        assert!(accepts(&automaton, &word(&["a", "c"])));
+    }
+
+    #[test]
+    fn test_intersect_many() {
+        // A: words containing "a"
+        let s_arena_a = Arena::new();
+        let t_arena_a = Arena::new();
+        let mut nfah_a = NFAH::new(&s_arena_a, &t_arena_a, 1);
+        let a0 = nfah_a.add_state(true, false);
+        let a1 = nfah_a.add_state(false, true);
+        nfah_a.add_nfah_transition(a0, "a".to_string(), 0, a1);
+        nfah_a.add_nfah_transition(a0, "b".to_string(), 0, a0);
+        nfah_a.add_nfah_transition(a1, "a".to_string(), 0, a1);
+        nfah_a.add_nfah_transition(a1, "b".to_string(), 0, a1);
+
+        // B: words of even length
+        let s_arena_b = Arena::new();
+        let t_arena_b = Arena::new();
+        let mut nfah_b = NFAH::new(&s_arena_b, &t_arena_b, 1);
+        let b0 = nfah_b.add_state(true, true);
+        let b1 = nfah_b.add_state(false, false);
+        for c in ["a", "b"] {
+            nfah_b.add_nfah_transition(b0, c.to_string(), 0, b1);
+            nfah_b.add_nfah_transition(b1, c.to_string(), 0, b0);
+        }
+
+        // C: words ending with "b"
+        let s_arena_c = Arena::new();
+        let t_arena_c = Arena::new();
+        let mut nfah_c = NFAH::new(&s_arena_c, &t_arena_c, 1);
+        let c0 = nfah_c.add_state(true, false);
+        let c1 = nfah_c.add_state(false, true);
+        nfah_c.add_nfah_transition(c0, "a".to_string(), 0, c0);
+        nfah_c.add_nfah_transition(c0, "b".to_string(), 0, c0);
+        nfah_c.add_nfah_transition(c0, "b".to_string(), 0, c1);
+
+        let s_arena = Arena::new();
+        let t_arena = Arena::new();
+        let intersection = NFAH::intersect_many(&[&nfah_a, &nfah_b, &nfah_c], &s_arena, &t_arena);
+        assert_eq!(intersection.dimensions, 1);
+
+        // Compare the languages on all the words of length at most 4.
+        for length in 0..=4 {
+            for word in (0..length)
+                .map(|_| ["a", "b"].into_iter())
+                .multi_cartesian_product()
+            {
+                let words = vec![word.iter().map(|c| c.to_string()).collect_vec()];
+                let expected = accepts(&nfah_a, &words)
+                    && accepts(&nfah_b, &words)
+                    && accepts(&nfah_c, &words);
+                assert_eq!(
+                    accepts(&intersection, &words),
+                    expected,
+                    "Mismatch on {:?}",
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_intersect_many_empty() {
+        let s_arena_a = Arena::new();
+        let t_arena_a = Arena::new();
+        let mut nfah_a = NFAH::new(&s_arena_a, &t_arena_a, 1);
+        let a0 = nfah_a.add_state(true, false);
+        let a1 = nfah_a.add_state(false, true);
+        nfah_a.add_nfah_transition(a0, "a".to_string(), 0, a1);
+
+        // B has no final state
+        let s_arena_b = Arena::new();
+        let t_arena_b = Arena::new();
+        let mut nfah_b = NFAH::new(&s_arena_b, &t_arena_b, 1);
+        let b0 = nfah_b.add_state(true, false);
+        nfah_b.add_nfah_transition(b0, "a".to_string(), 0, b0);
+
+        let s_arena = Arena::new();
+        let t_arena = Arena::new();
+        let intersection = NFAH::intersect_many(&[&nfah_a, &nfah_b], &s_arena, &t_arena);
+        assert!(intersection.is_empty());
+        assert!(intersection.initial_states.is_empty());
+    }
+
+    #[test]
+    fn test_intersect_many_empty_product() {
+        // A accepts "a" and B accepts "b", so their product is empty though neither of them is.
+        let s_arena_a = Arena::new();
+        let t_arena_a = Arena::new();
+        let mut nfah_a = NFAH::new(&s_arena_a, &t_arena_a, 1);
+        let a0 = nfah_a.add_state(true, false);
+        let a1 = nfah_a.add_state(false, true);
+        nfah_a.add_nfah_transition(a0, "a".to_string(), 0, a1);
+
+        let s_arena_b = Arena::new();
+        let t_arena_b = Arena::new();
+        let mut nfah_b = NFAH::new(&s_arena_b, &t_arena_b, 1);
+        let b0 = nfah_b.add_state(true, false);
+        let b1 = nfah_b.add_state(false, true);
+        nfah_b.add_nfah_transition(b0, "b".to_string(), 0, b1);
+
+        let s_arena = Arena::new();
+        let t_arena = Arena::new();
+        let intersection = NFAH::intersect_many(&[&nfah_a, &nfah_b, &nfah_a], &s_arena, &t_arena);
+        assert!(intersection.is_empty());
+        assert!(intersection.initial_states.is_empty());
     }
 
     #[cfg(test)]
@@ -1479,6 +2756,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prefix_closure() {
+        // Build an automaton accepting only "abc"
+        let arena_states = Arena::new();
+        let arena_trans = Arena::new();
+        let mut automata = NFAH::new(&arena_states, &arena_trans, 1);
+        let s0 = automata.add_state(true, false);
+        let s1 = automata.add_state(false, false);
+        let s2 = automata.add_state(false, false);
+        let s3 = automata.add_state(false, true);
+        // A dead branch, which must not become final
+        let dead = automata.add_state(false, false);
+        automata.add_transition(s0, ("a".to_string(), 0), s1);
+        automata.add_transition(s1, ("b".to_string(), 0), s2);
+        automata.add_transition(s2, ("c".to_string(), 0), s3);
+        automata.add_transition(s1, ("d".to_string(), 0), dead);
+
+        let word = |w: &str| vec![w.chars().map(|c| c.to_string()).collect_vec()];
+        assert!(!accepts(&automata, &word("a")));
+        assert!(accepts(&automata, &word("abc")));
+
+        let closure_states = Arena::new();
+        let closure_trans = Arena::new();
+        let closure = automata.prefix_closure(&closure_states, &closure_trans);
+        for w in ["", "a", "ab", "abc"] {
+            assert!(accepts(&closure, &word(w)), "{:?} should be accepted", w);
+        }
+        for w in ["b", "ad", "abcc"] {
+            assert!(!accepts(&closure, &word(w)), "{:?} should be rejected", w);
+        }
+    }
+
+    #[test]
+    fn test_map_labels() {
+        // Build an automaton accepting "ab" on the variable 0 and "c" on the variable 1
+        let arena_states = Arena::new();
+        let arena_trans = Arena::new();
+        let mut automata = NFAH::new(&arena_states, &arena_trans, 2);
+        let s0 = automata.add_state(true, false);
+        let s1 = automata.add_state(false, false);
+        let s2 = automata.add_state(false, false);
+        let s3 = automata.add_state(false, true);
+        automata.add_transition(s0, ("a".to_string(), 0), s1);
+        automata.add_transition(s1, ("b".to_string(), 0), s2);
+        automata.add_transition(s2, ("c".to_string(), 1), s3);
+
+        let word = |w0: &str, w1: &str| {
+            [w0, w1]
+                .iter()
+                .map(|w| w.chars().map(|c| c.to_string()).collect_vec())
+                .collect_vec()
+        };
+
+        let upper_states = Arena::new();
+        let upper_trans = Arena::new();
+        let upper = automata
+            .map_labels(
+                |(letter, var)| (letter.to_uppercase(), *var),
+                &upper_states,
+                &upper_trans,
+            )
+            .expect("The uppercased labels are valid");
+        assert_eq!(upper.dimensions, 2);
+        assert!(accepts(&upper, &word("AB", "C")));
+        assert!(!accepts(&upper, &word("ab", "c")));
+        assert!(accepts(&automata, &word("ab", "c")));
+
+        // Moving the letters to a variable out of bounds is invalid
+        let invalid_states = Arena::new();
+        let invalid_trans = Arena::new();
+        let result = automata.map_labels(
+            |(letter, var)| (letter.clone(), var + 2),
+            &invalid_states,
+            &invalid_trans,
+        );
+        assert!(result.is_err());
+    }
+
     #[cfg(test)]
     mod star_plus_tests {
         use super::*;
@@ -1506,6 +2861,7 @@ mod tests {
                 0,
                 "Kleene star should accept the empty word"
             );
+            assert!(star_aut.accepts_empty());
 
             // Additionally, check that non-empty words are accepted.
             // Accepted prefixes of length 1 should include "a".
@@ -1540,6 +2896,53 @@ mod tests {
             );
         }
 
+        /// Test that the metadata of the transitions survives on the copied edges of the star.
+        #[test]
+        fn test_star_keeps_metadata() {
+            // Build an automaton that accepts "ab", where the transition for "a" has a rule name
+            let arena_states = Arena::new();
+            let arena_trans = Arena::new();
+            let mut automata = Automata::<String>::new(&arena_states, &arena_trans, 0);
+            let s0 = automata.add_state(true, false);
+            let s1 = automata.add_state(false, false);
+            let s2 = automata.add_state(false, true);
+            let meta: TransitionMeta = Rc::new("rule-a".to_string());
+            automata.add_transition_with_meta(s0, "a".to_string(), s1, Some(meta));
+            automata.add_transition(s1, "b".to_string(), s2);
+
+            let arena_star_states = Arena::new();
+            let arena_star_trans = Arena::new();
+            let star_aut = automata.star(&arena_star_states, &arena_star_trans);
+
+            let transitions = arena_star_trans.len();
+            let mut rule_names = Vec::new();
+            for state in star_aut.iter_states() {
+                for transition in state.get_transitions().iter() {
+                    let rule_name = transition
+                        .meta
+                        .as_ref()
+                        .and_then(|meta| meta.downcast_ref::<String>());
+                    match transition.label.as_str() {
+                        "a" => rule_names.push(rule_name.cloned()),
+                        _ => assert_eq!(rule_name, None),
+                    }
+                }
+            }
+            // Every copy of the transition for "a" has the rule name
+            assert!(rule_names.len() > 1 && rule_names.len() < transitions);
+            assert!(rule_names
+                .iter()
+                .all(|name| name.as_deref() == Some("rule-a")));
+
+            // The metadata is ignored by the comparison of the transitions
+            let plain = Transition {
+                label: "a".to_string(),
+                next_state: s1,
+                meta: None,
+            };
+            assert_eq!(&plain, s0.get_transitions()[0]);
+        }
+
         /// Test that the Kleene plus of an automaton accepting "a" does not accept ε.
         #[test]
         fn test_plus_no_epsilon() {
@@ -1923,5 +3326,44 @@ mod tests {
             // and no transitions lead to (None, None).
             assert_eq!(union_aut.shortest_accepted_word_length(), 1);
         }
+
+        #[test]
+        fn test_union_many() {
+            let state_arena = Arena::new();
+            let trans_arena = Arena::new();
+            let aut_a = build_single_letter_automata(&state_arena, &trans_arena, "a", true);
+            let aut_b = build_single_letter_automata(&state_arena, &trans_arena, "b", true);
+            let aut_c = build_single_letter_automata(&state_arena, &trans_arena, "c", true);
+            // The empty language does not change the union
+            let aut_d = build_single_letter_automata(&state_arena, &trans_arena, "d", false);
+
+            let union_state_arena = Arena::new();
+            let union_trans_arena = Arena::new();
+            let union_aut = Automata::union_many(
+                &[&aut_a, &aut_b, &aut_c, &aut_d],
+                &union_state_arena,
+                &union_trans_arena,
+            );
+            assert_eq!(union_aut.dimensions, 0);
+            assert_eq!(union_aut.shortest_accepted_word_length(), 1);
+            let expected: HashSet<Vec<String>> = ["a", "b", "c"]
+                .into_iter()
+                .map(|letter| vec![letter.to_string()])
+                .collect();
+            assert_eq!(union_aut.accepted_prefixes(1), expected);
+        }
+
+        #[test]
+        #[should_panic(expected = "All the automata must have the same dimensions")]
+        fn test_union_many_different_dimensions() {
+            let state_arena = Arena::new();
+            let trans_arena = Arena::new();
+            let aut_a = build_single_letter_automata(&state_arena, &trans_arena, "a", true);
+            let aut_b = Automata::<String>::new(&state_arena, &trans_arena, 1);
+
+            let union_state_arena = Arena::new();
+            let union_trans_arena = Arena::new();
+            Automata::union_many(&[&aut_a, &aut_b], &union_state_arena, &union_trans_arena);
+        }
     }
 }
@@ -1,6 +1,8 @@
 use log::trace;
 
-use crate::automata::{NFAHState, NFAHTransition, ValidLabel, NFAH};
+use crate::automata::{Automata, State, Transition, VariableLabel};
+use crate::result_notifier::MatchingInterval;
+use crate::symbol_matcher::{ExactSymbolMatcher, SymbolMatcher};
 use std::cell::{Ref, RefCell};
 use std::collections::hash_set::Iter;
 use std::collections::HashSet;
@@ -59,6 +61,31 @@ pub trait NFAHRunner<'a, C: NFAHConfiguration<'a> + std::cmp::Eq + std::hash::Ha
         ids: Vec<usize>,
     );
 
+    /// Returns `true` if the configuration discovered by [`NFAHRunner::consume`] is kept.
+    ///
+    /// The configurations not kept are dropped together with their successors. By default,
+    /// all the configurations are kept.
+    fn keeps(&self, _configuration: &C) -> bool {
+        true
+    }
+
+    /// Returns the matcher deciding if the head of an input sequence matches a letter in
+    /// [`NFAHRunner::consume`].
+    ///
+    /// By default, the letters are compared exactly.
+    fn symbol_matcher(&self) -> &dyn SymbolMatcher {
+        &ExactSymbolMatcher
+    }
+
+    /// Returns the lengths of the input sequences of each variable taken at the beginning of the
+    /// current step, if any, e.g., by [`ReadableView::snapshot_len`].
+    ///
+    /// [`NFAHRunner::consume`] reads the heads of the input sequences against the snapshot. By
+    /// default, there is no snapshot and the current heads are read.
+    fn input_snapshot(&self) -> Option<&[usize]> {
+        None
+    }
+
     /// Consumes the input sequence and move to the successors.
     ///
     /// Returns `true` if the configuration set has updated.
@@ -72,8 +99,12 @@ pub trait NFAHRunner<'a, C: NFAHConfiguration<'a> + std::cmp::Eq + std::hash::Ha
 
             // Collect successors from every configuration we currently have.
             for current_configuration in configurations_to_examine.iter() {
-                new_configurations.append(&mut current_configuration.successors());
+                new_configurations.append(
+                    &mut current_configuration
+                        .successors_at(self.symbol_matcher(), self.input_snapshot()),
+                );
             }
+            new_configurations.retain(|c| self.keeps(c));
 
             // Insert all newly discovered configurations back into our set.
             self.extend(new_configurations.clone());
@@ -91,14 +122,17 @@ pub trait NFAHRunner<'a, C: NFAHConfiguration<'a> + std::cmp::Eq + std::hash::Ha
 ///
 /// This runner supports inserting configurations, iterating over them, and
 /// performing saturation expansions with `consume`.
-pub struct SimpleAutomataRunner<'a> {
-    automaton: &'a NFAH<'a>,
+/// * `L` - The label of the transitions, `(String, usize)` for NFAH by default.
+pub struct SimpleAutomataRunner<'a, L = (String, usize)> {
+    automaton: &'a Automata<'a, L>,
     /// The current set of configurations of type `SimpleAutomataConfiguration`.
     /// Each configuration is unique in the set (thanks to `Hash`/`Eq`).
-    pub current_configurations: HashSet<SimpleAutomataConfiguration<'a>>,
+    pub current_configurations: HashSet<SimpleAutomataConfiguration<'a, L>>,
+    /// Whether the configurations track the positions where the matching began.
+    track_matching_begin: bool,
 }
 
-impl<'a> SimpleAutomataRunner<'a> {
+impl<'a, L: VariableLabel + Eq + Hash + Clone + Debug> SimpleAutomataRunner<'a, L> {
     /// Constructs a new `SimpleAutomataRunner` by inserting configurations for
     /// each initial state of the given `automaton`.
     ///
@@ -111,7 +145,7 @@ impl<'a> SimpleAutomataRunner<'a> {
     /// # Returns
     ///
     /// A new `SimpleAutomataRunner` with initial configurations set up.
-    pub fn new(automaton: &'a NFAH<'a>, input_sequence: Vec<ReadableView<String>>) -> Self {
+    pub fn new(automaton: &'a Automata<'a, L>, input_sequence: Vec<ReadableView<String>>) -> Self {
         let mut current_configurations = HashSet::new();
         for initial_state in automaton.initial_states.iter() {
             let config = SimpleAutomataConfiguration::new(initial_state, input_sequence.clone());
@@ -120,18 +154,59 @@ impl<'a> SimpleAutomataRunner<'a> {
         Self {
             automaton,
             current_configurations,
+            track_matching_begin: false,
         }
     }
+
+    /// Constructs a new `SimpleAutomataRunner` whose configurations track the
+    /// positions where the matching began, so that the matched intervals can be
+    /// obtained by [`SimpleAutomataRunner::matched_intervals`].
+    ///
+    /// # Arguments
+    ///
+    /// * `automaton` - The automaton containing states and transitions.
+    /// * `input_sequence` - A vector of input views to be associated with each
+    ///   newly created configuration. The current positions of the views are
+    ///   the beginning of the matching.
+    pub fn with_matching_begin(
+        automaton: &'a Automata<'a, L>,
+        input_sequence: Vec<ReadableView<String>>,
+    ) -> Self {
+        let mut runner = Self {
+            automaton,
+            current_configurations: HashSet::new(),
+            track_matching_begin: true,
+        };
+        runner.insert_from_initial_states(input_sequence, vec![]);
+        runner
+    }
+
+    /// Returns the intervals matched by the current final configurations, sorted
+    /// and without duplicates.
+    ///
+    /// The result is always empty if the runner does not track the beginning of the matching.
+    pub fn matched_intervals(&self) -> Vec<Vec<MatchingInterval>> {
+        let mut intervals: Vec<Vec<MatchingInterval>> = self
+            .current_configurations
+            .iter()
+            .filter_map(|c| c.matched_intervals())
+            .collect();
+        intervals.sort();
+        intervals.dedup();
+        intervals
+    }
 }
 
-impl<'a> NFAHRunner<'a, SimpleAutomataConfiguration<'a>> for SimpleAutomataRunner<'a> {
+impl<'a, L: VariableLabel + Eq + Hash + Clone + Debug>
+    NFAHRunner<'a, SimpleAutomataConfiguration<'a, L>> for SimpleAutomataRunner<'a, L>
+{
     /// Inserts a new configuration into the `HashSet`. Duplicate configurations
     /// (i.e., those that are `Eq`) will be automatically skipped.
-    fn insert(&mut self, configuration: SimpleAutomataConfiguration<'a>) {
+    fn insert(&mut self, configuration: SimpleAutomataConfiguration<'a, L>) {
         self.current_configurations.insert(configuration);
     }
 
-    fn extend(&mut self, configurations: Vec<SimpleAutomataConfiguration<'a>>) {
+    fn extend(&mut self, configurations: Vec<SimpleAutomataConfiguration<'a, L>>) {
         self.current_configurations.extend(configurations);
     }
 
@@ -141,7 +216,7 @@ impl<'a> NFAHRunner<'a, SimpleAutomataConfiguration<'a>> for SimpleAutomataRunne
     }
 
     /// Returns an iterator over the current configurations in the `HashSet`.
-    fn iter(&mut self) -> Iter<SimpleAutomataConfiguration<'a>> {
+    fn iter(&mut self) -> Iter<SimpleAutomataConfiguration<'a, L>> {
         self.current_configurations.iter()
     }
 
@@ -153,7 +228,14 @@ impl<'a> NFAHRunner<'a, SimpleAutomataConfiguration<'a>> for SimpleAutomataRunne
         _ids: Vec<usize>,
     ) {
         for initial_state in self.automaton.initial_states.iter() {
-            let config = SimpleAutomataConfiguration::new(initial_state, input_sequence.clone());
+            let config = if self.track_matching_begin {
+                SimpleAutomataConfiguration::with_matching_begin(
+                    initial_state,
+                    input_sequence.clone(),
+                )
+            } else {
+                SimpleAutomataConfiguration::new(initial_state, input_sequence.clone())
+            };
             self.current_configurations.insert(config);
         }
     }
@@ -167,17 +249,20 @@ impl<'a> NFAHRunner<'a, SimpleAutomataConfiguration<'a>> for SimpleAutomataRunne
 /// # Lifetime Parameters
 /// * `'a`: lifetime that ties this configuration to the automaton’s states and transitions.
 pub trait NFAHConfiguration<'a> {
+    /// The label of the transitions, e.g., `(String, usize)` for NFAH.
+    type Label: VariableLabel + 'a;
+
     /// Returns the number of variables in the automaton.
     fn dimensions(&self) -> usize;
 
     /// Returns a shared reference to the list of outgoing transitions from
     /// the current state.
-    fn transitions(&self) -> Ref<Vec<&NFAHTransition<'a>>>;
+    fn transitions(&self) -> Ref<Vec<&'a Transition<'a, Self::Label>>>;
 
     /// Creates a new configuration that is identical to `self` except its
     /// current state is replaced with `current_state`. Typically used before
     /// checking or applying transitions.
-    fn duplicate(&self, current_state: &'a NFAHState<'a>) -> Self;
+    fn duplicate(&self, current_state: &'a State<'a, Self::Label>) -> Self;
 
     /// Returns the current "head" element of the `i`-th input sequence, if it exists.
     /// If the sequence is empty at that index, returns `None`.
@@ -187,6 +272,14 @@ pub trait NFAHConfiguration<'a> {
     /// * `i` - The index of the input sequence to examine.
     fn input_head(&self, i: usize) -> Option<String>;
 
+    /// Returns the head of the `i`-th input sequence as of the length `snapshot` of the sequence,
+    /// i.e., ignoring the elements appended after the snapshot.
+    ///
+    /// By default, the snapshot is ignored and the current head is returned.
+    fn input_head_at(&self, i: usize, _snapshot: usize) -> Option<String> {
+        self.input_head(i)
+    }
+
     /// Advances the `i`-th input sequence by `count` elements. If the sequence
     /// is shorter than `count`, is clamps the new start index to the sequence length.
     ///
@@ -202,26 +295,57 @@ pub trait NFAHConfiguration<'a> {
     /// Returns a list of all valid successor configurations. A successor is
     /// considered valid if for every dimension of the transition’s action:
     /// - If the transition’s action is non-empty, it must match the head of
-    ///   the corresponding input sequence,
+    ///   the corresponding input sequence (see [`VariableLabel::matches_with`]), e.g., a
+    ///   wildcard [`LabelMatch::Any`](crate::automata::LabelMatch::Any) matches any head,
     /// - Then that matching symbol is consumed (the input is advanced).
     fn successors(&self) -> Vec<Self>
+    where
+        Self: Sized,
+        Self: Eq + Hash,
+    {
+        self.successors_with(&ExactSymbolMatcher)
+    }
+
+    /// Computes the successor configurations as [`NFAHConfiguration::successors`], where the
+    /// heads of the input sequences and the letters are compared by `symbol_matcher`.
+    fn successors_with(&self, symbol_matcher: &dyn SymbolMatcher) -> Vec<Self>
+    where
+        Self: Sized,
+        Self: Eq + Hash,
+    {
+        self.successors_at(symbol_matcher, None)
+    }
+
+    /// Computes the successor configurations as [`NFAHConfiguration::successors_with`], where the
+    /// heads of the input sequences are read against `snapshot` if any (see
+    /// [`NFAHConfiguration::input_head_at`]).
+    fn successors_at(
+        &self,
+        symbol_matcher: &dyn SymbolMatcher,
+        snapshot: Option<&[usize]>,
+    ) -> Vec<Self>
     where
         Self: Sized,
         Self: Eq + Hash,
     {
         let mut successors = Vec::with_capacity(self.transitions().len());
         for transition in self.transitions().iter() {
-            // Ensure transition.var is within bounds.
-            transition.label.validate(self.dimensions());
+            let variable = transition.label.variable();
+            // The variables are validated when the automaton is constructed.
+            debug_assert!(variable < self.dimensions());
             // Check if the transition is applicable.
-            let head = self.input_head(transition.label.1);
-            if head.is_none() || transition.label.0 != head.unwrap() {
-                continue;
+            let head = match snapshot {
+                Some(snapshot) => self.input_head_at(variable, snapshot[variable]),
+                None => self.input_head(variable),
+            };
+            match head {
+                Some(head) if transition.label.matches_with(&head, symbol_matcher) => {}
+                _ => continue,
             }
             // Create a tentative successor configuration.
             let mut successor = self.duplicate(transition.next_state);
             // Consume one symbol on the input for the given dimension.
-            successor.input_advance(transition.label.1, 1);
+            successor.input_advance(variable, 1);
             successors.push(successor);
         }
         successors
@@ -233,43 +357,80 @@ pub trait NFAHConfiguration<'a> {
 /// - A vector of input sequences (as `ReadableView<String>`), indicating how
 ///   far each dimension of input has been read.
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
-pub struct SimpleAutomataConfiguration<'a> {
+pub struct SimpleAutomataConfiguration<'a, L = (String, usize)> {
     /// The current state of the automaton.
-    pub current_state: &'a NFAHState<'a>,
+    pub current_state: &'a State<'a, L>,
 
     /// A vector of readable views over the input(s) that the automaton consumes.
     /// Each `ReadableView<String>` tracks how far the automaton has read.
     /// For example, if this vector has length 2, we are dealing with a 2D input.
     pub input_sequence: Vec<ReadableView<String>>,
+
+    /// The positions where the matching began for each dimension, if tracked.
+    pub matching_begin: Option<Vec<usize>>,
 }
 
-impl<'a> SimpleAutomataConfiguration<'a> {
+impl<'a, L> SimpleAutomataConfiguration<'a, L> {
     /// Creates a new `SimpleAutomataConfiguration` from the given state and
     /// list of `ReadableView`s for each input dimension.
-    pub fn new(
-        current_state: &'a NFAHState<'a>,
+    pub fn new(current_state: &'a State<'a, L>, input_sequence: Vec<ReadableView<String>>) -> Self {
+        Self {
+            current_state,
+            input_sequence,
+            matching_begin: None,
+        }
+    }
+
+    /// Creates a new `SimpleAutomataConfiguration` tracking the beginning of the
+    /// matching, i.e., the current positions of `input_sequence`.
+    pub fn with_matching_begin(
+        current_state: &'a State<'a, L>,
         input_sequence: Vec<ReadableView<String>>,
     ) -> Self {
+        let matching_begin = input_sequence.iter().map(|s| s.start).collect();
         Self {
             current_state,
             input_sequence,
+            matching_begin: Some(matching_begin),
+        }
+    }
+
+    /// Returns the matched interval of each dimension if the current state is
+    /// final and the beginning of the matching is tracked.
+    ///
+    /// The interval of a dimension is `(begin, end)` where `end` is the position
+    /// of the last consumed element, similarly to the hyper pattern matching.
+    pub fn matched_intervals(&self) -> Option<Vec<MatchingInterval>> {
+        if !self.current_state.is_final() {
+            return None;
         }
+        let matching_begin = self.matching_begin.as_ref()?;
+        Some(
+            matching_begin
+                .iter()
+                .zip(self.input_sequence.iter())
+                .map(|(&begin, s)| MatchingInterval::new(begin, s.start.wrapping_sub(1)))
+                .collect(),
+        )
     }
 }
 
-impl<'a> NFAHConfiguration<'a> for SimpleAutomataConfiguration<'a> {
+impl<'a, L: VariableLabel + 'a> NFAHConfiguration<'a> for SimpleAutomataConfiguration<'a, L> {
+    type Label = L;
+
     fn dimensions(&self) -> usize {
         self.input_sequence.len()
     }
 
-    fn transitions(&self) -> Ref<Vec<&NFAHTransition<'a>>> {
+    fn transitions(&self) -> Ref<Vec<&'a Transition<'a, L>>> {
         self.current_state.transitions.borrow()
     }
 
-    fn duplicate(&self, current_state: &'a NFAHState<'a>) -> Self {
+    fn duplicate(&self, current_state: &'a State<'a, L>) -> Self {
         Self {
             current_state,
             input_sequence: self.input_sequence.clone(),
+            matching_begin: self.matching_begin.clone(),
         }
     }
 
@@ -332,6 +493,17 @@ impl<T> AppendOnlySequence<T> {
         self.data.borrow_mut().push(value);
     }
 
+    /// Appends all the values of `iter` to the end of the sequence, in order.
+    ///
+    /// This is the same as calling [`AppendOnlySequence::append`] for each value, but borrows the
+    /// underlying storage only once.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if *self.closed.borrow() {
+            panic!("Cannot append to a closed sequence.");
+        }
+        self.data.borrow_mut().extend(iter);
+    }
+
     /// Clears all elements in the sequence, removing them permanently.
     /// Any existing `ReadableView`s will now see an empty slice.
     /// Moreover, the sequence is re-opened for appending.
@@ -350,6 +522,26 @@ impl<T> AppendOnlySequence<T> {
         ReadableView::new(Rc::clone(&self.data), Rc::clone(&self.closed))
     }
 
+    /// Creates a readable view starting from the index `start` of the sequence.
+    ///
+    /// As with [`ReadableView::advance_readable`], `start` is clamped to the current length.
+    pub fn readable_view_from(&self, start: usize) -> ReadableView<T> {
+        let mut view = self.readable_view();
+        view.advance_readable(start);
+        view
+    }
+
+    /// Returns another handle to this sequence sharing the same storage.
+    ///
+    /// Appending to or closing either handle is visible from the other, e.g., when one input
+    /// stream backs several tracks.
+    pub fn share(&self) -> Self {
+        Self {
+            data: Rc::clone(&self.data),
+            closed: Rc::clone(&self.closed),
+        }
+    }
+
     /// Returns the current length of the sequence.
     pub fn len(&self) -> usize {
         self.data.borrow().len()
@@ -447,18 +639,79 @@ impl<T> ReadableView<T> {
         self.len() == 0
     }
 
+    /// Returns the current length of the underlying sequence, regardless of `start`, as a snapshot
+    /// for the reads within one step.
+    ///
+    /// [`ReadableView::len_at`] and [`ReadableView::readable_slice_at`] read against the snapshot,
+    /// so all the views of the sequence agree on the available elements even if an element is
+    /// appended in the middle of the step.
+    pub fn snapshot_len(&self) -> usize {
+        self.data.borrow().len()
+    }
+
+    /// Returns the length of the readable slice as of the length `snapshot` of the sequence, e.g.,
+    /// taken by [`ReadableView::snapshot_len`].
+    ///
+    /// The elements appended after the snapshot are not counted.
+    pub fn len_at(&self, snapshot: usize) -> usize {
+        usize::min(snapshot, self.data.borrow().len()).saturating_sub(self.start)
+    }
+
+    /// Returns a borrow of the readable slice as of the length `snapshot` of the sequence, i.e.,
+    /// without the elements appended after the snapshot.
+    pub fn readable_slice_at(&self, snapshot: usize) -> Ref<'_, [T]> {
+        Ref::map(self.data.borrow(), |vec| {
+            let end = usize::min(snapshot, vec.len());
+            &vec[usize::min(self.start, end)..end]
+        })
+    }
+
     /// Returns `true` if this view shares the same underlying data as another
     /// ReadableView
     pub fn same_data(&self, other: &ReadableView<T>) -> bool {
         std::ptr::eq(self.data.as_ptr(), other.data.as_ptr())
     }
 
-    /// Returns `true` if the sequence is closed.
+    /// Returns `true` if the underlying sequence is closed, i.e., no more elements
+    /// will be appended. This is shared by all the views of the same sequence,
+    /// regardless of the element type.
     pub fn is_closed(&self) -> bool {
         *self.closed.borrow()
     }
 }
 
+impl<T: Clone> ReadableView<T> {
+    /// Returns an owned copy of the readable slice.
+    ///
+    /// Unlike [`ReadableView::readable_slice`], the result does not borrow the underlying
+    /// sequence, so the sequence can be appended while the copy is alive.
+    pub fn readable_slice_to_vec(&self) -> Vec<T> {
+        self.readable_slice().to_vec()
+    }
+
+    /// Returns an owned copy of the `index`-th readable element, or `None` if it is not
+    /// available yet.
+    pub fn readable_get(&self, index: usize) -> Option<T> {
+        self.readable_slice().get(index).cloned()
+    }
+}
+
+impl<T: PartialEq> ReadableView<T> {
+    /// Returns `true` if the readable slices of `self` and `other` have the same contents.
+    ///
+    /// Unlike `==`, which compares the underlying sequence by pointer and the `start` index, this
+    /// compares the remaining elements, so views of distinct sequences or at different positions
+    /// can be content-equal.
+    pub fn content_eq(&self, other: &Self) -> bool {
+        *self.readable_slice() == *other.readable_slice()
+    }
+
+    /// Returns `true` if the readable slice is `expected`, e.g., for assertions in tests.
+    pub fn remaining_eq(&self, expected: &[T]) -> bool {
+        *self.readable_slice() == *expected
+    }
+}
+
 impl<T> Clone for ReadableView<T> {
     /// Cloning a `ReadableView` shares the same underlying data and the same
     /// `start` index. Both views will move independently if advanced later.
@@ -496,7 +749,7 @@ impl<T: Eq> Eq for ReadableView<T> {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::automata::NFAH;
+    use crate::automata::{ClassNFAH, LabelMatch, NFAH};
     use typed_arena::Arena;
 
     #[test]
@@ -507,15 +760,63 @@ mod tests {
         seq.append(3);
 
         let view1 = seq.readable_view();
-        assert_eq!(&*view1.readable_slice(), &[1, 2, 3]);
+        assert!(view1.remaining_eq(&[1, 2, 3]));
 
         seq.append(4);
         let view2 = seq.readable_view();
-        assert_eq!(&*view2.readable_slice(), &[1, 2, 3, 4]);
+        assert!(view2.remaining_eq(&[1, 2, 3, 4]));
 
         seq.clear();
         let view3 = seq.readable_view();
-        assert_eq!(&*view3.readable_slice(), &[] as &[i32]);
+        assert!(view3.remaining_eq(&[]));
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut seq = AppendOnlySequence::new();
+        seq.append(1);
+        let view = seq.readable_view();
+        assert_eq!(seq.len(), 1);
+
+        seq.extend(vec![2, 3, 4]);
+        assert_eq!(seq.len(), 4);
+        // The existing views observe the new elements
+        assert!(view.remaining_eq(&[1, 2, 3, 4]));
+
+        seq.extend(std::iter::empty());
+        assert_eq!(seq.len(), 4);
+        seq.extend(5..7);
+        assert!(view.remaining_eq(&[1, 2, 3, 4, 5, 6]));
+        assert_eq!(seq.get(5), Some(6));
+    }
+
+    #[test]
+    fn test_is_closed() {
+        let mut seq: AppendOnlySequence<String> = AppendOnlySequence::new();
+        seq.append("a".to_string());
+        let view = seq.readable_view();
+        assert!(!seq.is_closed());
+        assert!(!view.is_closed());
+        seq.close();
+        assert!(seq.is_closed());
+        // Views created before and after closing share the flag
+        assert!(view.is_closed());
+        assert!(seq.readable_view().is_closed());
+
+        let mut seq: AppendOnlySequence<Option<String>> = AppendOnlySequence::new();
+        seq.append(Some("a".to_string()));
+        seq.append(None);
+        let view = seq.readable_view();
+        assert!(!seq.is_closed());
+        assert!(!view.is_closed());
+        seq.close();
+        assert!(seq.is_closed());
+        assert!(view.is_closed());
+        assert!(seq.readable_view().is_closed());
+
+        // Clearing re-opens the sequence
+        seq.clear();
+        assert!(!view.is_closed());
     }
 
     #[test]
@@ -534,6 +835,129 @@ mod tests {
         assert_eq!(&*view.readable_slice(), Vec::<String>::new());
     }
 
+    #[test]
+    fn test_remaining_eq() {
+        let mut seq = AppendOnlySequence::new();
+        seq.extend([1, 2, 3]);
+        let mut view = seq.readable_view();
+        assert!(view.remaining_eq(&[1, 2, 3]));
+        assert!(!view.remaining_eq(&[1, 2]));
+
+        view.advance_readable(2);
+        assert!(view.remaining_eq(&[3]));
+        assert!(!view.remaining_eq(&[1, 2, 3]));
+
+        // Closing the sequence does not change the remaining elements
+        seq.close();
+        assert!(view.is_closed());
+        assert!(view.remaining_eq(&[3]));
+        view.advance_readable(1);
+        assert!(view.remaining_eq(&[]));
+    }
+
+    #[test]
+    fn test_readable_view_from() {
+        let mut seq = AppendOnlySequence::new();
+        seq.append("a".to_string());
+        seq.append("b".to_string());
+        seq.append("c".to_string());
+        let view = seq.readable_view_from(1);
+        assert_eq!(view.start, 1);
+        assert_eq!(&*view.readable_slice(), &["b", "c"]);
+
+        // The view sees the later appended elements
+        seq.append("d".to_string());
+        assert_eq!(&*view.readable_slice(), &["b", "c", "d"]);
+
+        let view = seq.readable_view_from(10);
+        assert_eq!(view.start, 4);
+        assert_eq!(&*view.readable_slice(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_snapshot_len() {
+        let mut seq = AppendOnlySequence::new();
+        seq.append("a".to_string());
+        seq.append("b".to_string());
+        let view0 = seq.readable_view();
+        let view1 = seq.readable_view_from(1);
+
+        // An element is appended between the reads of the two views within a step
+        let snapshot = view0.snapshot_len();
+        assert_eq!(view1.snapshot_len(), snapshot);
+        assert_eq!(view0.len_at(snapshot), 2);
+        seq.append("c".to_string());
+        assert_eq!(view1.len_at(snapshot), 1);
+        assert_eq!(&*view1.readable_slice_at(snapshot), &["b"]);
+        assert_eq!(&*view0.readable_slice_at(snapshot), &["a", "b"]);
+        // Without the snapshot, the second view sees the appended element
+        assert_eq!(view1.len(), 2);
+
+        // The next step sees the appended element
+        let snapshot = view1.snapshot_len();
+        assert_eq!(view0.len_at(snapshot), 3);
+        assert_eq!(&*view1.readable_slice_at(snapshot), &["b", "c"]);
+
+        // A snapshot before the start of a view, or after clearing the sequence, is empty
+        let view3 = seq.readable_view_from(3);
+        assert_eq!(view3.len_at(1), 0);
+        assert!(view3.readable_slice_at(1).is_empty());
+        seq.clear();
+        assert_eq!(view0.len_at(snapshot), 0);
+        assert!(view1.readable_slice_at(snapshot).is_empty());
+    }
+
+    #[test]
+    fn test_readable_slice_to_vec() {
+        let mut seq = AppendOnlySequence::new();
+        seq.append("a".to_string());
+        seq.append("b".to_string());
+        seq.append("c".to_string());
+        let mut view = seq.readable_view();
+        view.advance_readable(1);
+        assert_eq!(view.readable_slice_to_vec(), &*view.readable_slice());
+        assert_eq!(view.readable_get(1), Some("c".to_string()));
+        assert_eq!(view.readable_get(2), None);
+
+        // The owned copy does not block appending
+        let owned = view.readable_slice_to_vec();
+        seq.append("d".to_string());
+        assert_eq!(owned, vec!["b", "c"]);
+        assert_eq!(view.readable_slice_to_vec(), &*view.readable_slice());
+        assert_eq!(view.readable_get(2), Some("d".to_string()));
+    }
+
+    #[test]
+    fn test_readable_view_content_eq() {
+        let mut seq1 = AppendOnlySequence::new();
+        let mut seq2 = AppendOnlySequence::new();
+        seq1.extend(["a", "b", "c"].map(String::from));
+        seq2.extend(["b", "c"].map(String::from));
+
+        let mut view1 = seq1.readable_view();
+        let view2 = seq2.readable_view();
+        assert!(view1 != view2);
+        assert!(!view1.content_eq(&view2));
+
+        // Views of distinct sequences with the same remaining contents
+        view1.advance_readable(1);
+        assert!(view1 != view2);
+        assert!(view1.content_eq(&view2));
+
+        // Views of the same sequence at different positions
+        let mut view3 = seq1.readable_view();
+        assert!(view1 != view3);
+        assert!(!view1.content_eq(&view3));
+        view3.advance_readable(1);
+        assert!(view1 == view3);
+        assert!(view1.content_eq(&view3));
+
+        // Appending to one sequence changes only the content equality
+        seq2.append("d".to_string());
+        assert!(!view1.content_eq(&view2));
+        assert!(view1 == view3);
+    }
+
     #[test]
     fn test_automata_configuration_successors() {
         let state_arena = Arena::new();
@@ -591,6 +1015,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_negated_class_successors() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = ClassNFAH::new(&state_arena, &transition_arena, 1);
+
+        // Reads any letter except "a"
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, true);
+        let not_a = LabelMatch::NotIn(["a".to_string()].into_iter().collect());
+        automaton.add_transition(s0, (not_a, 0), s1);
+        automaton.add_transition(s1, (LabelMatch::Exact("a".to_string()), 0), s1);
+
+        let mut sequence = AppendOnlySequence::new();
+        for action in ["b", "a", "c"] {
+            sequence.append(action.to_string());
+        }
+        let successors_at = |position: usize| {
+            let mut view = sequence.readable_view();
+            view.advance_readable(position);
+            SimpleAutomataConfiguration::new(s0, vec![view]).successors()
+        };
+
+        // "b" and "c" are matched, but "a" is not
+        for position in [0, 2] {
+            let mut view = sequence.readable_view();
+            view.advance_readable(position + 1);
+            assert_eq!(
+                successors_at(position),
+                vec![SimpleAutomataConfiguration::new(s1, vec![view])]
+            );
+        }
+        assert!(successors_at(1).is_empty());
+
+        // The exact label on s1 still requires "a"
+        let mut view = sequence.readable_view();
+        view.advance_readable(1);
+        assert_eq!(
+            SimpleAutomataConfiguration::new(s1, vec![view])
+                .successors()
+                .len(),
+            1
+        );
+
+        // The runner accepts "ba" but not "ab"
+        let mut runner = SimpleAutomataRunner::new(&automaton, vec![sequence.readable_view()]);
+        runner.consume();
+        assert!(runner
+            .current_configurations
+            .iter()
+            .any(|c| c.current_state.is_final() && c.input_sequence[0].start == 2));
+    }
+
+    #[test]
+    fn test_wildcard_successors() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = ClassNFAH::new(&state_arena, &transition_arena, 1);
+
+        // Reads any letter by the wildcard, or the literal "*"
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, true);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_transition(s0, (LabelMatch::Any, 0), s1);
+        automaton.add_transition(s0, (LabelMatch::Exact("*".to_string()), 0), s2);
+
+        let mut sequence = AppendOnlySequence::new();
+        for action in ["b", "*"] {
+            sequence.append(action.to_string());
+        }
+
+        // The wildcard matches "b" and consumes it, but the literal "*" does not
+        let mut view = sequence.readable_view();
+        view.advance_readable(1);
+        assert_eq!(
+            SimpleAutomataConfiguration::new(s0, vec![sequence.readable_view()]).successors(),
+            vec![SimpleAutomataConfiguration::new(s1, vec![view])]
+        );
+
+        // Both match "*"
+        let mut view = sequence.readable_view();
+        view.advance_readable(1);
+        let successors = SimpleAutomataConfiguration::new(s0, vec![view]).successors();
+        assert_eq!(successors.len(), 2);
+        assert!(successors.iter().all(|c| c.input_sequence[0].start == 2));
+    }
+
     #[test]
     fn test_automata_runner() {
         let state_arena = Arena::new();
@@ -705,4 +1216,48 @@ mod tests {
             assert!(successors.contains(&SimpleAutomataConfiguration::new(s3, view)));
         }
     }
+
+    #[test]
+    fn test_automata_runner_matched_intervals() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 1);
+
+        // Accepts "ab" and "abb"
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "b".to_string(), 0, s3);
+
+        let mut sequence = AppendOnlySequence::new();
+        for action in ["c", "a", "b", "b", "a"] {
+            sequence.append(action.to_string());
+        }
+        sequence.close();
+
+        let mut runner =
+            SimpleAutomataRunner::with_matching_begin(&automaton, vec![sequence.readable_view()]);
+        for i in 1..sequence.len() {
+            let mut view = sequence.readable_view();
+            view.advance_readable(i);
+            runner.insert_from_initial_states(vec![view], vec![0]);
+        }
+        runner.consume();
+
+        assert_eq!(
+            runner.matched_intervals(),
+            vec![
+                vec![MatchingInterval::new(1, 2)],
+                vec![MatchingInterval::new(1, 3)]
+            ]
+        );
+
+        // The untracked runner does not report any intervals
+        let mut runner = SimpleAutomataRunner::new(&automaton, vec![sequence.readable_view()]);
+        runner.consume();
+        assert!(runner.matched_intervals().is_empty());
+    }
 }
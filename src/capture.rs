@@ -0,0 +1,315 @@
+use crate::hyper_pattern_matching::HyperPatternMatching;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+/// An event fed to a [`HyperPatternMatching`], captured by [`CapturingMatching`] and replayed by
+/// [`replay`].
+///
+/// Each event is a line of JSON, e.g., `{"event":"feed","track":0,"action":"a"}` and
+/// `{"event":"eof","track":0}`, after the header line of the number of the streams, e.g.,
+/// `{"streams":2}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum CapturedEvent {
+    /// The action fed to the track
+    Feed { track: usize, action: String },
+    /// The end of the track
+    Eof { track: usize },
+}
+
+/// The first line of a capture.
+#[derive(Serialize, Deserialize)]
+struct CaptureHeader {
+    /// The number of the streams the events are fed from
+    streams: usize,
+}
+
+/// A run captured by [`CapturingMatching`] and read by [`read_capture`].
+///
+/// The tracks of all the events are less than [`Capture::num_streams`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    num_streams: usize,
+    events: Vec<CapturedEvent>,
+}
+
+impl Capture {
+    /// Returns the number of the streams the events are fed from.
+    pub fn num_streams(&self) -> usize {
+        self.num_streams
+    }
+
+    /// Returns the captured events in the order they are fed.
+    pub fn events(&self) -> &[CapturedEvent] {
+        &self.events
+    }
+}
+
+/// A [`HyperPatternMatching`] writing the actions and the ends of the tracks fed to it to
+/// `output`, e.g., to reproduce a run deterministically by [`replay`] without the original inputs.
+///
+/// The number of the streams is written first, and the events are written in the order they are
+/// fed. Writing stops at the first error, which is
+/// reported by [`CapturingMatching::finish`] so that the matching itself is not interrupted.
+/// Without `output`, the events are fed through as they are.
+pub struct CapturingMatching<Matching: HyperPatternMatching> {
+    matching: Matching,
+    output: Option<Box<dyn Write>>,
+    /// The first error in writing the events
+    error: Option<io::Error>,
+}
+
+impl<Matching: HyperPatternMatching> CapturingMatching<Matching> {
+    /// Wraps `matching` to capture the events fed to it from `num_streams` streams to `output`, if
+    /// any.
+    pub fn new(matching: Matching, output: Option<Box<dyn Write>>, num_streams: usize) -> Self {
+        let mut capturing = Self {
+            matching,
+            output,
+            error: None,
+        };
+        capturing.write_line(&CaptureHeader {
+            streams: num_streams,
+        });
+        capturing
+    }
+
+    /// Flushes the captured events and returns the wrapped matching, or the first error in
+    /// writing the events.
+    pub fn finish(mut self) -> io::Result<Matching> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        if let Some(output) = &mut self.output {
+            output.flush()?;
+        }
+        Ok(self.matching)
+    }
+
+    fn write_line(&mut self, line: &impl Serialize) {
+        let Some(output) = &mut self.output else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *output, line)
+            .map_err(io::Error::from)
+            .and_then(|()| output.write_all(b"\n"));
+        if let Err(e) = written {
+            self.error = Some(e);
+            self.output = None;
+        }
+    }
+}
+
+impl<Matching: HyperPatternMatching> HyperPatternMatching for CapturingMatching<Matching> {
+    fn feed(&mut self, action: &str, track: usize) {
+        self.write_line(&CapturedEvent::Feed {
+            track,
+            action: action.to_string(),
+        });
+        self.matching.feed(action, track);
+    }
+
+    fn dimensions(&self) -> usize {
+        self.matching.dimensions()
+    }
+
+    fn flush(&mut self) {
+        self.matching.flush();
+    }
+
+    fn consume_remaining(&mut self) {
+        self.matching.consume_remaining();
+    }
+
+    fn set_eof(&mut self, track: usize) {
+        self.write_line(&CapturedEvent::Eof { track });
+        self.matching.set_eof(track);
+    }
+
+    fn matches_found(&self) -> usize {
+        self.matching.matches_found()
+    }
+}
+
+/// Reads the run captured by [`CapturingMatching`] from `reader`.
+///
+/// The blank lines are skipped. A malformed line, a missing header, and a track not less than the
+/// number of the streams in the header are errors of [`io::ErrorKind::InvalidData`].
+pub fn read_capture(reader: impl BufRead) -> io::Result<Capture> {
+    let invalid = |line_number: usize, message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid capture at line {}: {}", line_number, message),
+        )
+    };
+    let mut num_streams = None;
+    let mut events = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(num_streams) = num_streams else {
+            let header: CaptureHeader = serde_json::from_str(&line)
+                .map_err(|e| invalid(i + 1, format!("not a header: {}", e)))?;
+            num_streams = Some(header.streams);
+            continue;
+        };
+        let event: CapturedEvent =
+            serde_json::from_str(&line).map_err(|e| invalid(i + 1, e.to_string()))?;
+        let (CapturedEvent::Feed { track, .. } | CapturedEvent::Eof { track }) = event;
+        if track >= num_streams {
+            return Err(invalid(
+                i + 1,
+                format!(
+                    "the track {} is not less than {} streams",
+                    track, num_streams
+                ),
+            ));
+        }
+        events.push(event);
+    }
+    let num_streams = num_streams.ok_or_else(|| invalid(0, "no header".to_string()))?;
+    Ok(Capture {
+        num_streams,
+        events,
+    })
+}
+
+/// Feeds the events of `capture` to `matching` in order.
+///
+/// As [`ReadingScheduler::feed_until_eof`](crate::reading_scheduler::ReadingScheduler::feed_until_eof),
+/// the remaining input is not consumed, so the caller should call
+/// [`HyperPatternMatching::consume_remaining`] afterwards. The matching must read
+/// [`Capture::num_streams`] streams.
+pub fn replay<Matching: HyperPatternMatching>(capture: &Capture, matching: &mut Matching) {
+    for event in &capture.events {
+        match event {
+            CapturedEvent::Feed { track, action } => matching.feed(action, *track),
+            CapturedEvent::Eof { track } => matching.set_eof(*track),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata_runner::AppendOnlySequence;
+    use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
+    use crate::naive_hyper_pattern_matching::NaiveHyperPatternMatching;
+    use crate::reading_scheduler::ReadingScheduler;
+    use crate::result_notifier::{MatchingResult, SharedBufferResultNotifier};
+    use crate::shared_buffer::SharedBuffer;
+    use crate::tests::utils::create_small_automaton;
+    use std::collections::BTreeSet;
+    use std::fs::File;
+    use std::io::{BufReader, Cursor};
+    use typed_arena::Arena;
+
+    #[test]
+    fn test_capture_and_replay() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+        let new_matching = |result_buffer: &SharedBuffer<MatchingResult>| {
+            NaiveHyperPatternMatching::new(
+                &automaton,
+                SharedBufferResultNotifier::new(result_buffer.make_source()),
+                vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+                false,
+            )
+        };
+        let results = |result_buffer: &SharedBuffer<MatchingResult>| {
+            let mut result_sink = result_buffer.make_sink();
+            let mut results = BTreeSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            results
+        };
+
+        // Capture the run on the streams
+        let capture = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let result_buffer = SharedBuffer::new();
+        let matching = CapturingMatching::new(
+            new_matching(&result_buffer),
+            Some(Box::new(capture.reopen().unwrap())),
+            2,
+        );
+        let sources: Vec<Box<dyn StreamSource>> = vec![
+            Box::new(Cursor::new("a\na\nc\n")),
+            Box::new(Cursor::new("a\nd\nd\n")),
+        ];
+        let mut reading_scheduler =
+            ReadingScheduler::new(matching, MultiStreamReader::new(sources));
+        reading_scheduler.run();
+        reading_scheduler
+            .into_matching()
+            .finish()
+            .expect("Failed to capture");
+        let expected = results(&result_buffer);
+        assert!(!expected.is_empty());
+
+        let captured = read_capture(BufReader::new(File::open(capture.path()).unwrap())).unwrap();
+        assert_eq!(captured.num_streams(), 2);
+        let events = captured.events();
+        assert_eq!(events.len(), 8);
+        assert_eq!(
+            events[0],
+            CapturedEvent::Feed {
+                track: 0,
+                action: "a".to_string()
+            }
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, CapturedEvent::Eof { .. }))
+                .count(),
+            2
+        );
+
+        // Replay the captured events without the streams
+        let result_buffer = SharedBuffer::new();
+        let mut matching = new_matching(&result_buffer);
+        replay(&captured, &mut matching);
+        matching.consume_remaining();
+        assert_eq!(results(&result_buffer), expected);
+    }
+
+    #[test]
+    fn test_read_capture() {
+        let capture = read_capture(Cursor::new(concat!(
+            "{\"streams\":2}\n",
+            "{\"event\":\"feed\",\"track\":1,\"action\":\"x y\"}\n",
+            "\n",
+            "{\"event\":\"eof\",\"track\":1}\n"
+        )))
+        .unwrap();
+        assert_eq!(capture.num_streams(), 2);
+        assert_eq!(
+            capture.events(),
+            [
+                CapturedEvent::Feed {
+                    track: 1,
+                    action: "x y".to_string()
+                },
+                CapturedEvent::Eof { track: 1 }
+            ]
+        );
+
+        let invalid = [
+            // No header
+            "",
+            "{\"event\":\"eof\",\"track\":0}\n",
+            // No track
+            "{\"streams\":1}\n{\"event\":\"eof\"}\n",
+            // The track out of the streams
+            "{\"streams\":2}\n{\"event\":\"feed\",\"track\":7,\"action\":\"a\"}\n",
+        ];
+        for capture in invalid {
+            let error = read_capture(Cursor::new(capture)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{:?}", capture);
+        }
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::hash::{DefaultHasher, Hash, Hasher};
 
 use typed_arena::Arena;
@@ -98,6 +99,76 @@ where
         // After consuming the entire word, check if we're in a final state
         self.finals.contains(&current_state)
     }
+
+    /// Tests whether the DFA accepts the given input word, and returns the states visited.
+    ///
+    /// The trace starts with the initial state and has a state for each consumed symbol. If a
+    /// transition is undefined, the trace stops at the state where the rejection occurs.
+    pub fn accepts_with_trace(&self, input: &[A]) -> (bool, Vec<S>) {
+        let mut trace = Vec::with_capacity(input.len() + 1);
+        trace.push(self.initial.clone());
+
+        for sym in input {
+            let current_state = trace.last().unwrap();
+            match self.transitions.get(&(current_state.clone(), sym.clone())) {
+                Some(next_st) => trace.push(next_st.clone()),
+                None => return (false, trace),
+            }
+        }
+
+        let accepted = self.finals.contains(trace.last().unwrap());
+        (accepted, trace)
+    }
+
+    /// Removes the states unreachable from the initial state together with their transitions.
+    ///
+    /// The accepted language does not change.
+    pub fn trim(&mut self) {
+        let mut reachable = HashSet::new();
+        let mut stack = vec![self.initial.clone()];
+        reachable.insert(self.initial.clone());
+        while let Some(state) = stack.pop() {
+            for sym in &self.alphabet {
+                if let Some(next) = self.transitions.get(&(state.clone(), sym.clone())) {
+                    if reachable.insert(next.clone()) {
+                        stack.push(next.clone());
+                    }
+                }
+            }
+        }
+        self.retain_states(&reachable);
+    }
+
+    /// Removes the states from which no final state is reachable together with their
+    /// transitions. The initial state is always kept.
+    ///
+    /// The accepted language does not change, but a complete DFA may become incomplete.
+    pub fn trim_dead(&mut self) {
+        // Compute the states reaching a final state by a backward search
+        let mut predecessors: HashMap<&S, Vec<&S>> = HashMap::new();
+        for ((from, _), to) in &self.transitions {
+            predecessors.entry(to).or_default().push(from);
+        }
+        let mut alive: HashSet<S> = self.finals.iter().cloned().collect();
+        let mut stack: Vec<&S> = self.finals.iter().collect();
+        while let Some(state) = stack.pop() {
+            for &previous in predecessors.get(state).into_iter().flatten() {
+                if alive.insert(previous.clone()) {
+                    stack.push(previous);
+                }
+            }
+        }
+        alive.insert(self.initial.clone());
+        self.retain_states(&alive);
+    }
+
+    /// Keeps only the given states and the transitions between them.
+    fn retain_states(&mut self, kept: &HashSet<S>) {
+        self.states.retain(|s| kept.contains(s));
+        self.finals.retain(|s| kept.contains(s));
+        self.transitions
+            .retain(|(from, _), to| kept.contains(from) && kept.contains(to));
+    }
 }
 
 /// A simple wrapper around `HashSet<S>` that implements `Hash` in a canonical way.
@@ -159,12 +230,49 @@ where
 
         nfa
     }
+
+    /// Converts this DFA to an automaton with `dimensions` variables built in the given arenas.
+    ///
+    /// The automaton has one state for each state of this DFA and the same transitions, so it is
+    /// deterministic with the unique initial state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a label is invalid for `dimensions`.
+    pub fn to_automata<'a>(
+        &self,
+        states: &'a Arena<State<'a, A>>,
+        transitions: &'a Arena<Transition<'a, A>>,
+        dimensions: usize,
+    ) -> Automata<'a, A> {
+        let mut automaton = Automata::new(states, transitions, dimensions);
+        let mut old_to_new = HashMap::new();
+        for state in &self.states {
+            let new_state =
+                automaton.add_state(*state == self.initial, self.finals.contains(state));
+            old_to_new.insert(state, new_state);
+        }
+        for ((from, label), to) in &self.transitions {
+            automaton.add_transition(old_to_new[from], label.clone(), old_to_new[to]);
+        }
+        automaton
+    }
 }
 
 impl<'a, L> Automata<'a, L>
 where
     L: Eq + Hash + Clone + ValidLabel + Debug,
 {
+    /// Determinizes this automaton by the subset construction.
+    ///
+    /// The states of the result are numbered from the initial state `0`, and the alphabet consists
+    /// of the labels reachable from the initial states. Missing transitions reject, so the result
+    /// may be incomplete; use [`DFA::make_complete`] before [`DFA::negate`].
+    ///
+    /// If there are no initial states, the initial state `0` stands for the empty set of states:
+    /// it is not final and has no transitions, so the result accepts nothing. Without
+    /// transitions, the result accepts only the empty word if an initial state is final, and
+    /// nothing otherwise.
     pub fn determinize(&self) -> DFA<usize, L> {
         let mut alphabet = HashSet::new();
         // BFS over all states to find transitions
@@ -193,7 +301,7 @@ where
         let mut dfa = DFA::new(0, alphabet.clone().into_iter().collect());
 
         // check finals
-        if init_subset.0.iter().any(|state| state.is_final) {
+        if init_subset.0.iter().any(|state| state.is_final()) {
             dfa.set_final(*states.get(&init_subset.clone()).unwrap());
         }
 
@@ -221,7 +329,7 @@ where
                 if !visited.contains(&next_subset) {
                     visited.insert(next_subset.clone());
                     states.insert(next_subset.clone(), states.len());
-                    if next_subset.0.iter().any(|state| state.is_final) {
+                    if next_subset.0.iter().any(|state| state.is_final()) {
                         dfa.set_final(*states.get(&next_subset).unwrap());
                     }
                     queue.push_back(next_subset.clone());
@@ -236,6 +344,131 @@ where
 
         dfa
     }
+
+    /// Determinizes this automaton into a DFA complete over `alphabet`, e.g., for [`DFA::negate`].
+    ///
+    /// The alphabet of [`Automata::determinize`] consists of the labels in the transitions, so the
+    /// complement would miss the words using the other letters. Here, the alphabet is `alphabet`,
+    /// and the transitions with a label outside `alphabet` are dropped. The missing transitions
+    /// go to a non-final sink state.
+    pub fn complete_over(&self, alphabet: &HashSet<L>) -> DFA<usize, L> {
+        let mut dfa = self.determinize();
+        dfa.transitions.retain(|(_, sym), _| alphabet.contains(sym));
+        dfa.alphabet = alphabet.clone();
+        // The states of the determinized automaton are numbered from 0
+        let sink = dfa.states.len();
+        dfa.make_complete(sink);
+        dfa
+    }
+
+    /// Returns `true` if this automaton accepts all the words over `alphabet`.
+    ///
+    /// The automaton does not carry its alphabet, so it must be given explicitly. The transitions
+    /// with a label outside `alphabet` are ignored. See [`Automata::non_universal_witness`].
+    pub fn is_universal(&self, alphabet: &HashSet<L>) -> bool {
+        self.non_universal_witness(alphabet).is_none()
+    }
+
+    /// Returns a shortest word over `alphabet` rejected by this automaton, or `None` if this
+    /// automaton accepts all the words over `alphabet`.
+    ///
+    /// This automaton is determinized, completed over `alphabet`, and complemented, and the
+    /// witness is a shortest word accepted by the complement.
+    pub fn non_universal_witness(&self, alphabet: &HashSet<L>) -> Option<Vec<L>> {
+        let complement = self.complete_over(alphabet).negate();
+
+        // BFS for a shortest word reaching a final state of the complement
+        let mut previous: HashMap<usize, (usize, &L)> = HashMap::new();
+        let mut visited = HashSet::from([complement.initial]);
+        let mut queue = VecDeque::from([complement.initial]);
+        while let Some(state) = queue.pop_front() {
+            if complement.finals.contains(&state) {
+                let mut word = Vec::new();
+                let mut current = state;
+                while let Some(&(from, sym)) = previous.get(&current) {
+                    word.push(sym.clone());
+                    current = from;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for sym in alphabet {
+                let next = complement.transitions[&(state, sym.clone())];
+                if visited.insert(next) {
+                    previous.insert(next, (state, sym));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Builds the (intersection) product of this automaton and the deterministic `dfa`.
+    ///
+    /// This is the same as [`Automata::product`] with the automaton of `dfa`, but the `dfa` side
+    /// has at most one successor for each label, so the successors of each transition of this
+    /// automaton are looked up instead of cross-producted. Only the reachable pairs of states are
+    /// built.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a letter in the alphabet of `dfa` is invalid for the dimensions of this automaton.
+    pub fn product_with_dfa<'b, S: Eq + Hash + Clone>(
+        &self,
+        dfa: &DFA<S, L>,
+        new_states_arena: &'b Arena<State<'b, L>>,
+        new_trans_arena: &'b Arena<Transition<'b, L>>,
+    ) -> Automata<'b, L> {
+        for label in &dfa.alphabet {
+            if let Err(message) = label.validate(self.dimensions) {
+                panic!("The DFA does not fit the automaton: {}", message);
+            }
+        }
+        let mut product_automata =
+            Automata::<L>::new(new_states_arena, new_trans_arena, self.dimensions);
+
+        // We'll map (state_ptr, dfa_state) -> newly created product state.
+        let mut pair_to_state = HashMap::new();
+        let mut queue = VecDeque::new();
+        let dfa_initial_is_final = dfa.finals.contains(&dfa.initial);
+        for &init in &self.initial_states {
+            if let Entry::Vacant(entry) =
+                pair_to_state.entry((init as *const _, dfa.initial.clone()))
+            {
+                let is_final = init.is_final() && dfa_initial_is_final;
+                entry.insert(product_automata.add_state(true, is_final));
+                queue.push_back((init, dfa.initial.clone()));
+            }
+        }
+
+        while let Some((state, dfa_state)) = queue.pop_front() {
+            let new_current = pair_to_state[&(state as *const _, dfa_state.clone())];
+            for &transition in state.transitions.borrow().iter() {
+                // The unique successor in the DFA, if any
+                let Some(dfa_next) = dfa
+                    .transitions
+                    .get(&(dfa_state.clone(), transition.label.clone()))
+                else {
+                    continue;
+                };
+                let next = transition.next_state;
+                let key = (next as *const _, dfa_next.clone());
+                let new_next = match pair_to_state.get(&key) {
+                    Some(&existing) => existing,
+                    None => {
+                        let is_final = next.is_final() && dfa.finals.contains(dfa_next);
+                        let new_state = product_automata.add_state(false, is_final);
+                        pair_to_state.insert(key, new_state);
+                        queue.push_back((next, dfa_next.clone()));
+                        new_state
+                    }
+                };
+                product_automata.add_transition(new_current, transition.label.clone(), new_next);
+            }
+        }
+
+        product_automata
+    }
 }
 
 impl<S, A> DFA<S, A>
@@ -267,7 +500,355 @@ where
     }
 }
 
-// Remove this entire block
+impl<S, A> DFA<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash + Clone + Debug,
+{
+    /// Minimizes this DFA using Hopcroft's partition refinement.
+    ///
+    /// The missing transitions are regarded as the transitions to an implicit sink state. The
+    /// states unreachable from the initial state and the states from which no final state is
+    /// reachable are not in the result, so the result is the minimal trim DFA, possibly incomplete.
+    /// The states of the result are numbered from the initial state `0`.
+    pub fn minimize_hopcroft(&self) -> DFA<usize, A> {
+        let alphabet: Vec<&A> = self.alphabet.iter().collect();
+
+        // 1) Index the reachable states. The index `sink` is the implicit sink state.
+        let mut states = vec![&self.initial];
+        let mut index_of: HashMap<&S, usize> = HashMap::from([(&self.initial, 0)]);
+        let mut next = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            let mut row = Vec::with_capacity(alphabet.len());
+            for &sym in &alphabet {
+                row.push(
+                    self.transitions
+                        .get(&(states[i].clone(), sym.clone()))
+                        .map(|to| {
+                            *index_of.entry(to).or_insert_with(|| {
+                                states.push(to);
+                                states.len() - 1
+                            })
+                        }),
+                );
+            }
+            next.push(row);
+            i += 1;
+        }
+        let sink = states.len();
+        let next: Vec<Vec<usize>> = next
+            .into_iter()
+            .map(|row| row.into_iter().map(|to| to.unwrap_or(sink)).collect())
+            .chain(std::iter::once(vec![sink; alphabet.len()]))
+            .collect();
+        let mut predecessors = vec![vec![Vec::new(); sink + 1]; alphabet.len()];
+        for (from, row) in next.iter().enumerate() {
+            for (sym, &to) in row.iter().enumerate() {
+                predecessors[sym][to].push(from);
+            }
+        }
+
+        // 2) Refine the partition {final, non-final} until the blocks are consistent
+        let (finals, others): (Vec<usize>, Vec<usize>) =
+            (0..=sink).partition(|&q| q < sink && self.finals.contains(states[q]));
+        let mut blocks: Vec<Vec<usize>> = [finals, others]
+            .into_iter()
+            .filter(|block| !block.is_empty())
+            .collect();
+        let mut block_of = vec![0; sink + 1];
+        for (b, block) in blocks.iter().enumerate() {
+            for &q in block {
+                block_of[q] = b;
+            }
+        }
+        let mut worklist: HashSet<usize> = (0..blocks.len()).collect();
+        while let Some(&splitter) = worklist.iter().next() {
+            worklist.remove(&splitter);
+            let splitter_states = blocks[splitter].clone();
+            for predecessors in &predecessors {
+                // The states moving into the splitter with a symbol, grouped by their blocks
+                let mut hits: HashMap<usize, HashSet<usize>> = HashMap::new();
+                for &q in &splitter_states {
+                    for &p in &predecessors[q] {
+                        hits.entry(block_of[p]).or_default().insert(p);
+                    }
+                }
+                for (b, hit) in hits {
+                    if hit.len() == blocks[b].len() {
+                        continue;
+                    }
+                    let (inside, outside): (Vec<usize>, Vec<usize>) =
+                        blocks[b].iter().partition(|q| hit.contains(q));
+                    let new_block = blocks.len();
+                    for &q in &outside {
+                        block_of[q] = new_block;
+                    }
+                    let smaller = if inside.len() <= outside.len() {
+                        b
+                    } else {
+                        new_block
+                    };
+                    blocks[b] = inside;
+                    blocks.push(outside);
+                    if worklist.contains(&b) {
+                        worklist.insert(new_block);
+                    } else {
+                        worklist.insert(smaller);
+                    }
+                }
+            }
+        }
+
+        // 3) Build the quotient DFA. The states that cannot reach a final state are equivalent to
+        //    the sink state, so we drop the transitions to the block of the sink state.
+        let dead = block_of[sink];
+        let mut quotient = DFA::new(block_of[0], self.alphabet.clone());
+        for (b, block) in blocks.iter().enumerate() {
+            let representative = block[0];
+            if representative < sink && self.finals.contains(states[representative]) {
+                quotient.set_final(b);
+            }
+            for (sym, &to) in next[representative].iter().enumerate() {
+                if block_of[to] != dead {
+                    quotient.add_transition(b, alphabet[sym].clone(), block_of[to]);
+                }
+            }
+        }
+        quotient.trim();
+
+        // Number the states from the initial state
+        let mut renaming = HashMap::from([(quotient.initial, 0)]);
+        let mut queue = VecDeque::from([quotient.initial]);
+        while let Some(state) = queue.pop_front() {
+            for &sym in &alphabet {
+                if let Some(&to) = quotient.transitions.get(&(state, sym.clone())) {
+                    if !renaming.contains_key(&to) {
+                        renaming.insert(to, renaming.len());
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+        let mut minimal = DFA::new(0, self.alphabet.clone());
+        for state in &quotient.finals {
+            minimal.set_final(renaming[state]);
+        }
+        for ((from, sym), to) in &quotient.transitions {
+            minimal.add_transition(renaming[from], sym.clone(), renaming[to]);
+        }
+        minimal
+    }
+}
+
+/// The characters escaped by a backslash in the symbols of [`DFA::to_regex`].
+const REGEX_SYNTAX: [char; 8] = ['|', '*', '(', ')', '\\', 'ε', '∅', 'Ø'];
+
+/// A regular expression built by the state elimination of [`DFA::to_regex`].
+///
+/// The constructors simplify the trivial cases, e.g., `∅|r` to `r` and `εr` to `r`.
+#[derive(Debug, Clone, PartialEq)]
+enum Regex {
+    Empty,
+    Epsilon,
+    Symbol(String),
+    Concat(Vec<Regex>),
+    Union(Vec<Regex>),
+    Star(Box<Regex>),
+}
+
+impl Regex {
+    fn union(self, other: Regex) -> Regex {
+        let mut alternatives = Vec::new();
+        for regex in [self, other] {
+            let parts = match regex {
+                Regex::Empty => vec![],
+                Regex::Union(parts) => parts,
+                regex => vec![regex],
+            };
+            for part in parts {
+                if !alternatives.contains(&part) {
+                    alternatives.push(part);
+                }
+            }
+        }
+        match alternatives.len() {
+            0 => Regex::Empty,
+            1 => alternatives.pop().unwrap(),
+            _ => Regex::Union(alternatives),
+        }
+    }
+
+    fn concat(self, other: Regex) -> Regex {
+        let mut factors = Vec::new();
+        for regex in [self, other] {
+            match regex {
+                Regex::Empty => return Regex::Empty,
+                Regex::Epsilon => {}
+                Regex::Concat(parts) => factors.extend(parts),
+                regex => factors.push(regex),
+            }
+        }
+        match factors.len() {
+            0 => Regex::Epsilon,
+            1 => factors.pop().unwrap(),
+            _ => Regex::Concat(factors),
+        }
+    }
+
+    fn star(self) -> Regex {
+        match self {
+            Regex::Empty | Regex::Epsilon => Regex::Epsilon,
+            Regex::Star(_) => self,
+            regex => Regex::Star(Box::new(regex)),
+        }
+    }
+
+    /// Writes this regex to `out`, where `precedence` is 0 for the operand of a union, 1 for a
+    /// concatenation, and 2 for a star.
+    fn write_to(&self, out: &mut String, precedence: u8) {
+        match self {
+            Regex::Empty => out.push('∅'),
+            Regex::Epsilon => out.push('ε'),
+            Regex::Symbol(symbol) => {
+                // The symbols other than a single character are parenthesized to be read as one
+                // symbol regardless of the precedence
+                let parenthesized = symbol.chars().count() != 1;
+                if parenthesized {
+                    out.push('(');
+                }
+                for c in symbol.chars() {
+                    if REGEX_SYNTAX.contains(&c) {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                if parenthesized {
+                    out.push(')');
+                }
+            }
+            Regex::Concat(parts) | Regex::Union(parts) => {
+                let (separator, inner) = match self {
+                    Regex::Union(_) => ("|", 0),
+                    _ => ("", 1),
+                };
+                let parenthesized = precedence > inner;
+                if parenthesized {
+                    out.push('(');
+                }
+                for (i, part) in parts.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(separator);
+                    }
+                    part.write_to(out, inner);
+                }
+                if parenthesized {
+                    out.push(')');
+                }
+            }
+            Regex::Star(regex) => {
+                regex.write_to(out, 2);
+                out.push('*');
+            }
+        }
+    }
+}
+
+impl<S, A> DFA<S, A>
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash + Clone + Display,
+{
+    /// Returns a regular expression of the language of this DFA by the state elimination, e.g.,
+    /// `0*1(1|00*1)*` for the binary strings ending in `1`.
+    ///
+    /// The regular expression consists of the symbols, the union `|`, the concatenation, the star
+    /// `*`, and the parentheses. The empty language is `∅` and the empty word is `ε`. The symbols
+    /// of multiple characters are parenthesized, e.g., `(login)|(logout)`, and the characters
+    /// `|*()\εØ∅` in the symbols are escaped by a backslash, e.g., `\*` for the symbol `*`. The
+    /// states are eliminated in the breadth-first order from the initial state, so the result is
+    /// deterministic but not necessarily the shortest.
+    pub fn to_regex(&self) -> String {
+        let mut alphabet: Vec<(String, &A)> = self
+            .alphabet
+            .iter()
+            .map(|sym| (sym.to_string(), sym))
+            .collect();
+        alphabet.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // 1) Index the reachable states from 1. The index 0 is the new initial state and the
+        //    index `end` is the new final state.
+        let mut states = vec![&self.initial];
+        let mut index_of: HashMap<&S, usize> = HashMap::from([(&self.initial, 1)]);
+        let mut next = 0;
+        while next < states.len() {
+            let state = states[next];
+            next += 1;
+            for (_, sym) in &alphabet {
+                if let Some(target) = self.transitions.get(&(state.clone(), (*sym).clone())) {
+                    if !index_of.contains_key(target) {
+                        index_of.insert(target, states.len() + 1);
+                        states.push(target);
+                    }
+                }
+            }
+        }
+        let end = states.len() + 1;
+
+        // 2) Label the edges of the generalized NFA by the regexes
+        let mut edges: HashMap<(usize, usize), Regex> = HashMap::from([((0, 1), Regex::Epsilon)]);
+        for (i, &state) in states.iter().enumerate() {
+            for (name, sym) in &alphabet {
+                if let Some(target) = self.transitions.get(&(state.clone(), (*sym).clone())) {
+                    let edge = edges
+                        .entry((i + 1, index_of[target]))
+                        .or_insert(Regex::Empty);
+                    *edge = edge.clone().union(Regex::Symbol(name.clone()));
+                }
+            }
+            if self.finals.contains(state) {
+                edges.insert((i + 1, end), Regex::Epsilon);
+            }
+        }
+
+        // 3) Eliminate the states, bypassing each of them by the edges of its neighbors
+        for eliminated in 1..end {
+            let self_loop = edges
+                .remove(&(eliminated, eliminated))
+                .unwrap_or(Regex::Empty)
+                .star();
+            let mut incoming = Vec::new();
+            let mut outgoing = Vec::new();
+            for (&(from, to), regex) in &edges {
+                if to == eliminated {
+                    incoming.push((from, regex.clone()));
+                } else if from == eliminated {
+                    outgoing.push((to, regex.clone()));
+                }
+            }
+            incoming.sort_by_key(|(from, _)| *from);
+            outgoing.sort_by_key(|(to, _)| *to);
+            edges.retain(|&(from, to), _| from != eliminated && to != eliminated);
+            for (from, before) in &incoming {
+                for (to, after) in &outgoing {
+                    let bypass = before
+                        .clone()
+                        .concat(self_loop.clone())
+                        .concat(after.clone());
+                    let edge = edges.entry((*from, *to)).or_insert(Regex::Empty);
+                    *edge = edge.clone().union(bypass);
+                }
+            }
+        }
+
+        let mut regex = String::new();
+        edges
+            .remove(&(0, end))
+            .unwrap_or(Regex::Empty)
+            .write_to(&mut regex, 0);
+        regex
+    }
+}
 
 impl<S, A> DFA<S, A>
 where
@@ -309,16 +890,19 @@ where
         new_dfa
     }
 
-    /// Make this DFA complete by adding a "sink" state (if needed).
-    /// We name the sink state using a provided function or by generating a fresh label.
+    /// Make this DFA complete by adding a "sink" state named `sink_label`.
     /// Then, for every missing transition (s, a), define s--a--> sink.
     /// And make every transition from sink loop back to sink.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sink_label` is already a state, since its transitions would be overwritten.
     pub fn make_complete(&mut self, sink_label: S) {
-        if self.states.contains(&sink_label) {
-            // your code to handle collision, or panic, or rename
-        } else {
-            // Insert the sink state
-            self.states.insert(sink_label.clone());
+        if !self.states.insert(sink_label.clone()) {
+            panic!(
+                "The sink state {:?} is already a state of the DFA",
+                sink_label
+            );
         }
 
         // For each state s, for each symbol a in the alphabet,
@@ -401,6 +985,234 @@ mod tests {
         assert!(dfa.accepts(&['1', '0', '1', '1', '1']));
     }
 
+    /// Returns the positions where a match of `regex[*pos..]` up to the end of the current
+    /// union, concatenation, or star may end, starting from each of `starts` in `word`.
+    ///
+    /// A tiny matcher of the regexes of [`DFA::to_regex`] over the single-character symbols.
+    fn regex_ends(
+        regex: &[char],
+        pos: &mut usize,
+        word: &[char],
+        starts: HashSet<usize>,
+    ) -> HashSet<usize> {
+        // union := concat ('|' concat)*
+        let mut ends = regex_concat_ends(regex, pos, word, starts.clone());
+        while regex.get(*pos) == Some(&'|') {
+            *pos += 1;
+            ends.extend(regex_concat_ends(regex, pos, word, starts.clone()));
+        }
+        ends
+    }
+
+    fn regex_concat_ends(
+        regex: &[char],
+        pos: &mut usize,
+        word: &[char],
+        mut current: HashSet<usize>,
+    ) -> HashSet<usize> {
+        // concat := (atom '*'?)*
+        while let Some(&c) = regex.get(*pos) {
+            if c == '|' || c == ')' {
+                break;
+            }
+            *pos += 1;
+            let atom_start = *pos;
+            let step = |current: HashSet<usize>, pos: &mut usize| -> HashSet<usize> {
+                match c {
+                    '(' => {
+                        let ends = regex_ends(regex, pos, word, current);
+                        assert_eq!(regex.get(*pos), Some(&')'));
+                        *pos += 1;
+                        ends
+                    }
+                    'ε' => current,
+                    '∅' => HashSet::new(),
+                    '\\' => {
+                        // An escaped character of a symbol
+                        let escaped = regex[*pos];
+                        *pos += 1;
+                        current
+                            .into_iter()
+                            .filter(|&i| word.get(i) == Some(&escaped))
+                            .map(|i| i + 1)
+                            .collect()
+                    }
+                    c => current
+                        .into_iter()
+                        .filter(|&i| word.get(i) == Some(&c))
+                        .map(|i| i + 1)
+                        .collect(),
+                }
+            };
+            let mut next = step(current.clone(), pos);
+            if regex.get(*pos) == Some(&'*') {
+                // Repeat the atom until no new position is reached
+                let atom_end = *pos;
+                let mut reached = current;
+                reached.extend(next);
+                loop {
+                    *pos = atom_start;
+                    let more: HashSet<usize> = step(reached.clone(), pos)
+                        .difference(&reached)
+                        .copied()
+                        .collect();
+                    if more.is_empty() {
+                        break;
+                    }
+                    reached.extend(more);
+                }
+                *pos = atom_end + 1;
+                next = reached;
+            }
+            current = next;
+        }
+        current
+    }
+
+    fn regex_matches(regex: &str, word: &[char]) -> bool {
+        let regex: Vec<char> = regex.chars().collect();
+        let mut pos = 0;
+        let ends = regex_ends(&regex, &mut pos, word, HashSet::from([0]));
+        assert_eq!(pos, regex.len(), "Unbalanced regex {:?}", regex);
+        ends.contains(&word.len())
+    }
+
+    #[test]
+    fn test_to_regex() {
+        // The binary strings ending in '1'
+        let mut dfa = DFA::new(0, HashSet::from(['0', '1']));
+        dfa.set_final(1);
+        dfa.add_transition(0, '0', 0);
+        dfa.add_transition(0, '1', 1);
+        dfa.add_transition(1, '0', 0);
+        dfa.add_transition(1, '1', 1);
+
+        let regex = dfa.to_regex();
+        let mut words = vec![vec![]];
+        for len in 1..=6 {
+            for bits in 0..(1 << len) {
+                words.push(
+                    (0..len)
+                        .map(|i| if bits >> i & 1 == 1 { '1' } else { '0' })
+                        .collect(),
+                );
+            }
+        }
+        for word in words {
+            assert_eq!(
+                regex_matches(&regex, &word),
+                dfa.accepts(&word),
+                "{:?} on {:?}",
+                regex,
+                word
+            );
+        }
+
+        // The empty language and the empty word
+        let mut dfa = DFA::new(0, HashSet::from(['0', '1']));
+        dfa.add_transition(0, '0', 1);
+        assert_eq!(dfa.to_regex(), "∅");
+        dfa.set_final(0);
+        assert_eq!(dfa.to_regex(), "ε");
+
+        // The symbols of multiple characters are parenthesized
+        let mut dfa = DFA::new(
+            0,
+            HashSet::from(["login".to_string(), "logout".to_string()]),
+        );
+        dfa.add_transition(0, "login".to_string(), 1);
+        dfa.add_transition(1, "logout".to_string(), 1);
+        dfa.set_final(1);
+        assert_eq!(dfa.to_regex(), "(login)(logout)*");
+        let mut dfa = DFA::new(
+            0,
+            HashSet::from(["login".to_string(), "logout".to_string()]),
+        );
+        dfa.add_transition(0, "login".to_string(), 1);
+        dfa.add_transition(0, "logout".to_string(), 1);
+        dfa.set_final(1);
+        assert_eq!(dfa.to_regex(), "(login)|(logout)");
+
+        // The words ending in "a|b" over the symbols with the characters of the regex syntax,
+        // where no symbol is a prefix of another, so a word is read back from its characters
+        let symbols = ["a|b", "*", "(ε)"];
+        let mut dfa = DFA::new(0, HashSet::from(symbols));
+        dfa.set_final(1);
+        for state in [0, 1] {
+            dfa.add_transition(state, "a|b", 1);
+            dfa.add_transition(state, "*", 0);
+            dfa.add_transition(state, "(ε)", 0);
+        }
+        let regex = dfa.to_regex();
+        assert!(regex.contains("(a\\|b)"), "{:?}", regex);
+        let mut words = vec![vec![]];
+        for len in 1..=4 {
+            let mut longer = Vec::new();
+            for word in words.iter().filter(|word| word.len() == len - 1) {
+                for symbol in symbols {
+                    let mut word = word.clone();
+                    word.push(symbol);
+                    longer.push(word);
+                }
+            }
+            words.extend(longer);
+        }
+        for word in words {
+            let chars: Vec<char> = word.concat().chars().collect();
+            assert_eq!(
+                regex_matches(&regex, &chars),
+                dfa.accepts(&word),
+                "{:?} on {:?}",
+                regex,
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn test_trim() {
+        // Accepts the words containing "1"
+        let alphabet: HashSet<char> = ['0', '1', '#'].into_iter().collect();
+        let mut dfa = DFA::new(0, alphabet);
+        dfa.set_final(1);
+        dfa.add_transition(0, '0', 0);
+        dfa.add_transition(0, '1', 1);
+        dfa.add_transition(1, '0', 1);
+        dfa.add_transition(1, '1', 1);
+        // State 2 is unreachable, and state 3 is reachable only from 2
+        dfa.add_transition(2, '0', 3);
+        dfa.add_transition(2, '1', 1);
+        dfa.set_final(3);
+        // State 4 is reachable but cannot reach any final state
+        dfa.add_transition(0, '#', 4);
+
+        let words: Vec<Vec<char>> = vec![
+            vec![],
+            vec!['0'],
+            vec!['1'],
+            vec!['0', '1', '0'],
+            vec!['0', '#'],
+        ];
+        let expected = words.iter().map(|w| dfa.accepts(w)).collect::<Vec<_>>();
+
+        dfa.trim();
+        assert_eq!(dfa.states, [0, 1, 4].into_iter().collect());
+        assert_eq!(dfa.finals, [1].into_iter().collect());
+        assert_eq!(dfa.transitions.len(), 5);
+        assert_eq!(
+            words.iter().map(|w| dfa.accepts(w)).collect::<Vec<_>>(),
+            expected
+        );
+
+        dfa.trim_dead();
+        assert_eq!(dfa.states, [0, 1].into_iter().collect());
+        assert_eq!(dfa.transitions.len(), 4);
+        assert_eq!(
+            words.iter().map(|w| dfa.accepts(w)).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
     #[test]
     fn test_brzozowski_minimization() {
         // We'll define a DFA over {0,1} that accepts
@@ -461,6 +1273,98 @@ mod tests {
         // Typically it might produce 3 states anyway for this language, or fewer if merges are possible.
     }
 
+    #[test]
+    fn test_accepts_with_trace() {
+        // The "contains 11" language
+        let sigma: HashSet<char> = ['0', '1'].into_iter().collect();
+        let mut dfa = DFA::new("S0", sigma);
+        dfa.set_final("S2");
+        dfa.add_transition("S0", '0', "S0");
+        dfa.add_transition("S0", '1', "S1");
+        dfa.add_transition("S1", '0', "S0");
+        dfa.add_transition("S1", '1', "S2");
+        dfa.add_transition("S2", '0', "S2");
+        dfa.add_transition("S2", '1', "S2");
+
+        assert_eq!(
+            dfa.accepts_with_trace(&['1', '0', '1']),
+            (false, vec!["S0", "S1", "S0", "S1"])
+        );
+        assert_eq!(
+            dfa.accepts_with_trace(&['0', '1', '1']),
+            (true, vec!["S0", "S0", "S1", "S2"])
+        );
+        assert_eq!(dfa.accepts_with_trace(&[]), (false, vec!["S0"]));
+
+        // Without the transitions from "S2", the trace stops where the rejection occurs
+        dfa.transitions.remove(&("S2", '0'));
+        assert_eq!(
+            dfa.accepts_with_trace(&['1', '1', '0', '1']),
+            (false, vec!["S0", "S1", "S2"])
+        );
+        assert!(!dfa.accepts(&['1', '1', '0', '1']));
+    }
+
+    #[test]
+    fn test_minimize_hopcroft() {
+        // The same "contains 11" language as above, with the redundant states "T1" and "T2"
+        // duplicating "S1" and "S2", and the dead state "D"
+        let sigma: HashSet<char> = ['0', '1'].into_iter().collect();
+        let mut dfa = DFA::new("S0", sigma);
+        dfa.set_final("S2");
+        dfa.set_final("T1");
+        dfa.set_final("T2");
+        dfa.add_transition("S0", '0', "S0");
+        dfa.add_transition("S0", '1', "S1");
+        dfa.add_transition("S1", '0', "S0");
+        dfa.add_transition("S1", '1', "T2");
+        dfa.add_transition("T2", '0', "S2");
+        dfa.add_transition("T2", '1', "T1");
+        dfa.add_transition("T1", '0', "T2");
+        dfa.add_transition("T1", '1', "S2");
+        dfa.add_transition("S2", '0', "S2");
+        dfa.add_transition("S2", '1', "S2");
+        dfa.add_state("D");
+        dfa.add_transition("D", '0', "S0");
+
+        let minimized = dfa.minimize_hopcroft();
+        assert_eq!(minimized.states.len(), 3);
+        assert_eq!(minimized.initial, 0);
+        for word in [
+            &['0', '0', '1', '0'][..],
+            &['1', '1'],
+            &['1', '0', '1', '1', '0'],
+            &['0', '1', '0', '1', '0'],
+            &[],
+        ] {
+            assert_eq!(minimized.accepts(word), dfa.accepts(word), "{:?}", word);
+        }
+
+        // An incomplete DFA stays incomplete without the sink state
+        let mut dfa = DFA::new(0, sigma_of(&['a', 'b']));
+        dfa.set_final(2);
+        dfa.add_transition(0, 'a', 1);
+        dfa.add_transition(1, 'b', 2);
+        dfa.add_transition(0, 'b', 3);
+        dfa.add_transition(3, 'a', 3);
+        let minimized = dfa.minimize_hopcroft();
+        assert_eq!(minimized.states.len(), 3);
+        assert_eq!(minimized.transitions.len(), 2);
+        assert!(minimized.accepts(&['a', 'b']));
+        assert!(!minimized.accepts(&['b', 'a']));
+
+        // The empty language is the single non-final state
+        dfa.finals.clear();
+        let minimized = dfa.minimize_hopcroft();
+        assert_eq!(minimized.states, HashSet::from([0]));
+        assert!(minimized.finals.is_empty());
+        assert!(minimized.transitions.is_empty());
+    }
+
+    fn sigma_of(letters: &[char]) -> HashSet<char> {
+        letters.iter().copied().collect()
+    }
+
     #[test]
     fn test_dfa_negation() {
         // We'll define a complete DFA for "ends in 1"
@@ -500,6 +1404,245 @@ mod tests {
         assert!(!neg_dfa.accepts(&['0', '1']));
         assert!(neg_dfa.accepts(&['1', '0', '1', '0']));
     }
+
+    #[test]
+    fn test_determinize_no_initial_states() {
+        use crate::automata::Automata;
+        use typed_arena::Arena;
+
+        // The transitions are unreachable without initial states
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        let s0 = automaton.add_state(false, false);
+        let s1 = automaton.add_state(false, true);
+        automaton.add_transition(s0, "a".to_string(), s1);
+
+        let mut dfa = automaton.determinize();
+        assert_eq!(dfa.states, HashSet::from([0]));
+        assert_eq!(dfa.initial, 0);
+        assert!(dfa.finals.is_empty());
+        assert!(dfa.transitions.is_empty());
+        assert!(!dfa.accepts(&[]));
+        assert!(!dfa.accepts(&["a".to_string()]));
+
+        // The complement accepts only the empty word over the empty alphabet
+        dfa.make_complete(1);
+        let negated = dfa.negate();
+        assert!(negated.accepts(&[]));
+        assert!(!negated.accepts(&["a".to_string()]));
+    }
+
+    #[test]
+    fn test_determinize_no_transitions() {
+        use crate::automata::Automata;
+        use typed_arena::Arena;
+
+        // A non-final initial state accepts nothing
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        automaton.add_state(true, false);
+        let dfa = automaton.determinize();
+        assert_eq!(dfa.states, HashSet::from([0]));
+        assert!(dfa.finals.is_empty());
+        assert!(!dfa.accepts(&[]));
+
+        // A final initial state accepts only the empty word
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        automaton.add_state(true, true);
+        let dfa = automaton.determinize();
+        assert_eq!(dfa.finals, HashSet::from([0]));
+        assert!(dfa.accepts(&[]));
+        assert!(!dfa.accepts(&["a".to_string()]));
+        assert_eq!(dfa.minimize_hopcroft().states.len(), 1);
+    }
+
+    #[test]
+    fn test_determinize_single_letter() {
+        use crate::automata::Automata;
+        use typed_arena::Arena;
+
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, true);
+        automaton.add_transition(s0, "a".to_string(), s1);
+
+        let mut dfa = automaton.determinize();
+        let a = "a".to_string();
+        assert!(dfa.accepts(std::slice::from_ref(&a)));
+        assert!(!dfa.accepts(&[]));
+
+        // The sink loops on the letter, so the complement accepts everything but "a"
+        dfa.make_complete(2);
+        assert_eq!(dfa.transitions.get(&(2, a.clone())), Some(&2));
+        let negated = dfa.negate();
+        assert!(negated.accepts(&[]));
+        assert!(!negated.accepts(std::slice::from_ref(&a)));
+        assert!(negated.accepts(&[a.clone(), a.clone()]));
+    }
+
+    #[test]
+    #[should_panic(expected = "already a state")]
+    fn test_make_complete_existing_sink() {
+        let mut dfa = DFA::new(0, HashSet::from(['a']));
+        dfa.add_transition(0, 'a', 1);
+        dfa.set_final(1);
+        // Completing with the sink 1 would make the final state 1 a sink
+        dfa.make_complete(1);
+    }
+
+    #[test]
+    fn test_product_with_dfa() {
+        use crate::tests::utils::create_small_automaton;
+        use itertools::Itertools;
+        use typed_arena::Arena;
+
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &trans_arena);
+
+        // The constraint: "b" on the variable 1 appears at most once
+        let letters =
+            [("a", 0), ("b", 1), ("c", 0), ("d", 1)].map(|(action, var)| (action.to_string(), var));
+        let mut dfa = DFA::new(0, letters.iter().cloned().collect());
+        for letter in &letters {
+            let after_b = usize::from(letter.0 == "b");
+            dfa.add_transition(0, letter.clone(), after_b);
+            if letter.0 != "b" {
+                dfa.add_transition(1, letter.clone(), 1);
+            }
+        }
+        dfa.set_final(0);
+        dfa.set_final(1);
+
+        let fast_state_arena = Arena::new();
+        let fast_trans_arena = Arena::new();
+        let fast = automaton.product_with_dfa(&dfa, &fast_state_arena, &fast_trans_arena);
+        let dfa_state_arena = Arena::new();
+        let dfa_trans_arena = Arena::new();
+        let dfa_automaton = dfa.to_automata(&dfa_state_arena, &dfa_trans_arena, 2);
+        let generic_state_arena = Arena::new();
+        let generic_trans_arena = Arena::new();
+        let generic = automaton.product(&dfa_automaton, &generic_state_arena, &generic_trans_arena);
+
+        // The product is no larger than the generic one
+        assert!(fast.states.len() <= generic.states.len());
+        assert_eq!(fast.dimensions, 2);
+
+        // Both products accept the same words, checked on all the words of up to 5 letters
+        let fast_dfa = fast.determinize();
+        let generic_dfa = generic.determinize();
+        let small_dfa = automaton.determinize();
+        let mut accepted = 0;
+        for length in 0..=5 {
+            for word in (0..length)
+                .map(|_| letters.iter().cloned())
+                .multi_cartesian_product()
+            {
+                let expected = small_dfa.accepts(&word) && dfa.accepts(&word);
+                assert_eq!(fast_dfa.accepts(&word), expected, "{:?}", word);
+                assert_eq!(generic_dfa.accepts(&word), expected, "{:?}", word);
+                accepted += usize::from(expected);
+            }
+        }
+        assert!(accepted > 0);
+        // "b" twice is accepted by the small automaton, but not by the product
+        let twice = [("b", 1), ("b", 1), ("c", 0), ("d", 1)].map(|(a, v)| (a.to_string(), v));
+        assert!(small_dfa.accepts(&twice));
+        assert!(!fast_dfa.accepts(&twice));
+    }
+
+    #[test]
+    #[should_panic(expected = "The DFA does not fit the automaton")]
+    fn test_product_with_dfa_different_dimensions() {
+        use crate::tests::utils::create_small_automaton;
+        use typed_arena::Arena;
+
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &trans_arena);
+        // The variable 2 does not exist in the 2-dimensional automaton
+        let dfa = DFA::new(0, HashSet::from([("a".to_string(), 2)]));
+        let product_state_arena = Arena::new();
+        let product_trans_arena = Arena::new();
+        automaton.product_with_dfa(&dfa, &product_state_arena, &product_trans_arena);
+    }
+
+    #[test]
+    fn test_complete_over() {
+        use crate::automata::Automata;
+        use typed_arena::Arena;
+
+        // Accepts only "a"
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, true);
+        automaton.add_transition(s0, "a".to_string(), s1);
+
+        let a = "a".to_string();
+        let b = "b".to_string();
+        let dfa = automaton.complete_over(&HashSet::from([a.clone(), b.clone()]));
+        assert_eq!(dfa.alphabet, HashSet::from([a.clone(), b.clone()]));
+        assert!(dfa.accepts(std::slice::from_ref(&a)));
+        assert!(!dfa.accepts(std::slice::from_ref(&b)));
+
+        // The complement accepts the words using the extra letter "b"
+        let negated = dfa.negate();
+        assert!(!negated.accepts(std::slice::from_ref(&a)));
+        assert!(negated.accepts(&[]));
+        assert!(negated.accepts(std::slice::from_ref(&b)));
+        assert!(negated.accepts(&[a.clone(), b.clone()]));
+        assert!(negated.accepts(&[b, a.clone()]));
+        assert!(negated.accepts(&[a.clone(), a]));
+    }
+
+    #[test]
+    fn test_is_universal() {
+        use crate::automata::Automata;
+        use typed_arena::Arena;
+
+        let alphabet = HashSet::from(["a".to_string()]);
+        // A complete automaton accepting all the words over {a}
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        let s0 = automaton.add_state(true, true);
+        automaton.add_transition(s0, "a".to_string(), s0);
+        assert!(automaton.is_universal(&alphabet));
+        assert_eq!(automaton.non_universal_witness(&alphabet), None);
+        // The words with "b" are not accepted
+        let with_b = HashSet::from(["a".to_string(), "b".to_string()]);
+        assert!(!automaton.is_universal(&with_b));
+        assert_eq!(
+            automaton.non_universal_witness(&with_b),
+            Some(vec!["b".to_string()])
+        );
+
+        // Accepts all the words over {a} but "aa"
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = Automata::<String>::new(&state_arena, &trans_arena, 0);
+        let s0 = automaton.add_state(true, true);
+        let s1 = automaton.add_state(false, true);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_transition(s0, "a".to_string(), s1);
+        automaton.add_transition(s1, "a".to_string(), s2);
+        automaton.add_transition(s2, "a".to_string(), s3);
+        automaton.add_transition(s3, "a".to_string(), s3);
+        assert!(!automaton.is_universal(&alphabet));
+        assert_eq!(
+            automaton.non_universal_witness(&alphabet),
+            Some(vec!["a".to_string(), "a".to_string()])
+        );
+    }
 }
 
 #[test]
@@ -0,0 +1,210 @@
+use std::collections::BTreeSet;
+use std::io::Cursor;
+
+use clap::ValueEnum;
+use itertools::Itertools;
+
+use crate::automata::NFAH;
+use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
+use crate::result_notifier::{MatchingResult, SharedBufferResultNotifier};
+use crate::rng::SplitMix64;
+use crate::shared_buffer::SharedBuffer;
+use crate::{run_hyper_pattern_matching, MatchingOptions, Mode};
+
+/// Returns the sorted list of the actions appearing in the transitions of `automaton`.
+pub fn automaton_alphabet<'a>(automaton: &'a NFAH<'a>) -> Vec<String> {
+    automaton
+        .iter_states()
+        .flat_map(|state| {
+            state
+                .get_transitions()
+                .iter()
+                .map(|transition| transition.letter().to_string())
+                .collect_vec()
+        })
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+/// Generates `num_streams` random input streams of `length` actions over `alphabet`.
+///
+/// The same `seed` always produces the same streams.
+///
+/// # Panics
+///
+/// Panics if `alphabet` is empty and `length` is positive, since there is no action to sample.
+pub fn generate_random_inputs(
+    alphabet: &[String],
+    num_streams: usize,
+    length: usize,
+    seed: u64,
+) -> Vec<Vec<String>> {
+    let mut rng = SplitMix64::new(seed);
+    (0..num_streams)
+        .map(|_| {
+            (0..length)
+                .map(|_| alphabet[rng.next_below(alphabet.len())].clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Runs the matching of the given mode on in-memory input streams and returns the set of matches.
+pub fn collect_matches<'a>(
+    mode: &Mode,
+    automaton: &'a NFAH<'a>,
+    inputs: &[Vec<String>],
+) -> BTreeSet<MatchingResult> {
+    let reader = MultiStreamReader::new(
+        inputs
+            .iter()
+            .map(|input| {
+                let contents = input.iter().map(|action| format!("{}\n", action)).join("");
+                Box::new(Cursor::new(contents.into_bytes())) as Box<dyn StreamSource>
+            })
+            .collect(),
+    );
+    let result_buffer = SharedBuffer::new();
+    let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+    let sources = (0..inputs.len()).collect_vec();
+    run_hyper_pattern_matching(
+        mode,
+        automaton,
+        notifier,
+        reader.into(),
+        &sources,
+        MatchingOptions::default(),
+    )
+    .expect("The matching without checking the alphabet does not fail");
+
+    let mut result_sink = result_buffer.make_sink();
+    let mut matches = BTreeSet::new();
+    while let Some(result) = result_sink.pop() {
+        matches.insert(result);
+    }
+    matches
+}
+
+/// Runs all the matching modes on the given inputs and checks that they report the same matches.
+///
+/// # Returns
+///
+/// * `Ok(matches)` with the common set of matches if all the modes agree.
+/// * `Err(message)` describing the first mode disagreeing with the naive mode otherwise.
+pub fn compare_modes<'a>(
+    automaton: &'a NFAH<'a>,
+    inputs: &[Vec<String>],
+) -> Result<BTreeSet<MatchingResult>, String> {
+    let expected = collect_matches(&Mode::Naive, automaton, inputs);
+    for mode in Mode::value_variants() {
+        let actual = collect_matches(mode, automaton, inputs);
+        if actual != expected {
+            return Err(format!(
+                "{:?} mode disagrees with Naive mode: missing {:?}, unexpected {:?}",
+                mode,
+                expected.difference(&actual).collect_vec(),
+                actual.difference(&expected).collect_vec()
+            ));
+        }
+    }
+    Ok(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::create_small_automaton;
+    use typed_arena::Arena;
+
+    #[test]
+    fn test_generate_random_inputs_is_reproducible() {
+        let alphabet = vec!["a".to_string(), "b".to_string()];
+        let inputs = generate_random_inputs(&alphabet, 2, 10, 42);
+        assert_eq!(inputs.len(), 2);
+        assert!(inputs.iter().all(|input| input.len() == 10));
+        assert!(inputs
+            .iter()
+            .flatten()
+            .all(|action| alphabet.contains(action)));
+        assert_eq!(inputs, generate_random_inputs(&alphabet, 2, 10, 42));
+        assert_ne!(inputs, generate_random_inputs(&alphabet, 2, 10, 43));
+    }
+
+    #[test]
+    fn test_compare_modes_small() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let alphabet = automaton_alphabet(&automaton);
+        assert_eq!(alphabet, vec!["a", "b", "c", "d"]);
+        for seed in 0..5 {
+            let inputs = generate_random_inputs(&alphabet, automaton.dimensions, 8, seed);
+            match compare_modes(&automaton, &inputs) {
+                Ok(_) => {}
+                Err(message) => panic!("Seed {}: {}", seed, message),
+            }
+        }
+    }
+
+    /// Builds a random automaton of `num_states` states over the actions `a` and `b`.
+    ///
+    /// The states form a chain to the final state so that a final state is reachable from every state,
+    /// and random transitions are added on top of it.
+    fn random_automaton<'a>(
+        state_arena: &'a Arena<crate::automata::NFAHState<'a>>,
+        transition_arena: &'a Arena<crate::automata::NFAHTransition<'a>>,
+        num_states: usize,
+        rng: &mut SplitMix64,
+    ) -> NFAH<'a> {
+        let dimensions = 2;
+        let actions = ["a", "b"];
+        let mut automaton = NFAH::new(state_arena, transition_arena, dimensions);
+        let states = (0..num_states)
+            .map(|i| automaton.add_state(i == 0, i + 1 == num_states))
+            .collect_vec();
+        for i in 0..num_states - 1 {
+            let action = actions[rng.next_below(actions.len())].to_string();
+            automaton.add_nfah_transition(states[i], action, i % dimensions, states[i + 1]);
+        }
+        for _ in 0..num_states {
+            let from = states[rng.next_below(num_states - 1)];
+            let to = states[rng.next_below(num_states)];
+            let action = actions[rng.next_below(actions.len())].to_string();
+            automaton.add_nfah_transition(from, action, rng.next_below(dimensions), to);
+        }
+        automaton
+    }
+
+    #[test]
+    fn test_fjs_skips_do_not_miss_matches() {
+        let mut rng = SplitMix64::new(2024);
+        for trial in 0..30 {
+            let state_arena = Arena::new();
+            let transition_arena = Arena::new();
+            let num_states = 3 + rng.next_below(3);
+            let automaton = random_automaton(&state_arena, &transition_arena, num_states, &mut rng);
+            // The matchers assume that each variable reads at least one action in a match
+            if automaton
+                .first_symbols_per_variable()
+                .iter()
+                .any(Option::is_none)
+            {
+                continue;
+            }
+            let alphabet = vec!["a".to_string(), "b".to_string()];
+            let inputs = generate_random_inputs(&alphabet, 2, 6, rng.next_u64());
+
+            let expected = collect_matches(&Mode::Naive, &automaton, &inputs);
+            for mode in [Mode::Fjs, Mode::FjsFiltered] {
+                let actual = collect_matches(&mode, &automaton, &inputs);
+                assert_eq!(
+                    expected, actual,
+                    "Trial {}: {:?} mode disagrees with Naive mode",
+                    trial, mode
+                );
+            }
+        }
+    }
+}
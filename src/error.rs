@@ -0,0 +1,72 @@
+use std::fmt;
+use std::io;
+
+/// The errors reported by the command-line interface.
+#[derive(Debug)]
+pub enum HyppauError {
+    /// The automaton file cannot be opened or read.
+    AutomatonFile { path: String, source: io::Error },
+    /// The automaton file does not contain a valid automaton.
+    InvalidAutomaton { path: String, message: String },
+    /// An input file cannot be opened.
+    InputFile { path: String, source: io::Error },
+    /// The output cannot be written.
+    Output { path: String, source: io::Error },
+    /// The command-line arguments are inconsistent.
+    InvalidArgument(String),
+    /// An input action is not in the automaton with `--check-alphabet error`.
+    UnknownAction { track: usize, action: String },
+    /// The modes disagree on the random inputs of `--generate-random`.
+    ModeMismatch { seed: u64, message: String },
+}
+
+impl HyppauError {
+    /// Returns the exit code of the process for this error.
+    ///
+    /// Inconsistent arguments exit with 2 as the argument parser does; the other errors exit with 1.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            HyppauError::InvalidArgument(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for HyppauError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HyppauError::AutomatonFile { path, source } => {
+                write!(f, "Failed to read automaton file {}: {}", path, source)
+            }
+            HyppauError::InvalidAutomaton { path, message } => {
+                write!(f, "Invalid automaton file {}: {}", path, message)
+            }
+            HyppauError::InputFile { path, source } => {
+                write!(f, "Failed to open input file {}: {}", path, source)
+            }
+            HyppauError::Output { path, source } => {
+                write!(f, "Failed to write to {}: {}", path, source)
+            }
+            HyppauError::InvalidArgument(message) => write!(f, "{}", message),
+            HyppauError::UnknownAction { track, action } => write!(
+                f,
+                "The action {:?} in the track {} is not in the automaton",
+                action, track
+            ),
+            HyppauError::ModeMismatch { seed, message } => {
+                write!(f, "Mismatch with seed {}: {}", seed, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HyppauError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HyppauError::AutomatonFile { source, .. }
+            | HyppauError::InputFile { source, .. }
+            | HyppauError::Output { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,15 +1,18 @@
-use std::{collections::HashMap, marker::PhantomData};
+use std::collections::HashMap;
 
 use itertools::Itertools;
 use log::debug;
 use typed_arena::Arena;
 
 use crate::{
-    automata::NFAH, automata_runner::AppendOnlySequence,
+    automata::NFAH,
+    automata_runner::AppendOnlySequence,
     dfa_earliest_pattern_matcher::DFAEarliestPatternMatcher,
     filtered_single_hyper_pattern_matching::FilteredSingleHyperPatternMatching,
-    hyper_pattern_matching::HyperPatternMatching, matching_filter::MatchingFilter,
+    hyper_pattern_matching::{check_dimensions, shared_sequences, HyperPatternMatching},
+    matching_filter::MatchingFilter,
     result_notifier::ResultNotifier,
+    skip_value_cache::SkipValueCache,
 };
 
 pub struct FilteredHyperPatternMatching<'a, SingleMatching, Notifier>
@@ -21,7 +24,10 @@ where
     filters: HashMap<(usize, usize), MatchingFilter<usize, String>>,
     single_matchings: Vec<SingleMatching>,
     sequences: Vec<AppendOnlySequence<String>>,
-    _notifier: PhantomData<Notifier>,
+    /// The track whose input backs each track
+    sources: Vec<usize>,
+    /// A clone of the notifier of the single matchings to flush
+    notifier: Notifier,
 }
 
 impl<'a, SingleMatching, Notifier> FilteredHyperPatternMatching<'a, SingleMatching, Notifier>
@@ -30,9 +36,19 @@ where
     Notifier: ResultNotifier + Clone,
 {
     pub fn new(automaton: &'a NFAH<'a>, notifier: Notifier, word_size: usize) -> Self {
-        let sequences = (0..word_size)
-            .map(|_| AppendOnlySequence::new())
-            .collect_vec();
+        Self::with_stream_sources(automaton, notifier, &(0..word_size).collect_vec())
+    }
+
+    /// Creates a new matcher where the track `t` is backed by the input of the track
+    /// `sources[t]`. See
+    /// [`crate::hyper_pattern_matching::HyperPatternMatchingAdapter::with_stream_sources`].
+    pub fn with_stream_sources(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        sources: &[usize],
+    ) -> Self {
+        let sequences = shared_sequences(sources);
+        check_dimensions(automaton.dimensions, sequences.len());
 
         let mut filters = HashMap::with_capacity(automaton.dimensions * sequences.len());
         let enfa_state_arena = Arena::new();
@@ -60,6 +76,8 @@ where
 
         let ranges = vec![0..sequences.len(); automaton.dimensions];
         let ids = ranges.into_iter().multi_cartesian_product().collect_vec();
+        // The tables of the automaton are computed once for all the id-assignments
+        let cache = SkipValueCache::new();
         let mut single_matchings = Vec::with_capacity(ids.len());
         for id_vec in ids.into_iter() {
             let mut input_streams = Vec::with_capacity(id_vec.len());
@@ -74,11 +92,12 @@ where
                     );
                 }
             }
-            single_matchings.push(SingleMatching::new(
+            single_matchings.push(SingleMatching::with_cache(
                 automaton,
                 notifier.clone(),
                 input_streams,
                 id_vec,
+                &cache,
             ));
         }
 
@@ -87,7 +106,8 @@ where
             filters,
             single_matchings,
             sequences,
-            _notifier: PhantomData,
+            sources: sources.to_vec(),
+            notifier,
         }
     }
 
@@ -116,12 +136,14 @@ where
 {
     fn feed(&mut self, action: &str, track: usize) {
         self.sequences[track].append(action.to_string());
-        // Run the filters
-        for variable in 0..self.automaton.dimensions {
-            self.filters
-                .get_mut(&(variable, track))
-                .unwrap()
-                .consume_input();
+        // Run the filters of the track and the tracks sharing its input
+        for shared_track in (0..self.sources.len()).filter(|&t| self.sources[t] == track) {
+            for variable in 0..self.automaton.dimensions {
+                self.filters
+                    .get_mut(&(variable, shared_track))
+                    .unwrap()
+                    .consume_input();
+            }
         }
 
         // Apply check_closed for each filter
@@ -139,6 +161,11 @@ where
         self.automaton.dimensions
     }
 
+    fn flush(&mut self) {
+        self.consume();
+        self.notifier.flush();
+    }
+
     fn consume_remaining(&mut self) {
         self.consume();
     }
@@ -1,4 +1,4 @@
-use crate::automata::{NFAHState, NFAHTransition, ValidLabel, NFAH};
+use crate::automata::{NFAHState, NFAHTransition, NFAH};
 use crate::automata_runner::ReadableView;
 use std::{
     cell::Ref,
@@ -93,6 +93,15 @@ impl<'a> FilteredPatternMatchingAutomataRunner<'a> {
     ///
     /// Returns `true` if the configuration set has updated.
     pub fn consume(&mut self) -> bool {
+        self.consume_with(&head_is)
+    }
+
+    /// Consumes the input sequence as [`FilteredPatternMatchingAutomataRunner::consume`], checking
+    /// the heads of the input sequences by `head_is`, e.g., to instrument the checks in the tests.
+    fn consume_with(
+        &mut self,
+        head_is: &impl Fn(&ReadableView<Option<String>>, &str) -> bool,
+    ) -> bool {
         let initial_size = self.len();
         let mut configurations_to_examine = HashSet::with_capacity(self.len());
         configurations_to_examine.extend(self.iter().cloned());
@@ -102,7 +111,7 @@ impl<'a> FilteredPatternMatchingAutomataRunner<'a> {
 
             // Collect successors from every configuration we currently have.
             for current_configuration in configurations_to_examine.iter() {
-                new_configurations.append(&mut current_configuration.successors());
+                new_configurations.append(&mut current_configuration.successors(head_is));
             }
 
             // Insert all newly discovered configurations back into our set.
@@ -124,6 +133,14 @@ impl<'a> Extend<FilteredPatternMatchingAutomataConfiguration<'a>>
     }
 }
 
+/// Returns `true` if the head of `view` is `letter`.
+///
+/// The stream is borrowed only for the comparison, so that it can be appended between the checks,
+/// and the action is compared without cloning it.
+fn head_is(view: &ReadableView<Option<String>>, letter: &str) -> bool {
+    view.readable_slice().first().and_then(Option::as_deref) == Some(letter)
+}
+
 fn masked_head(readable_view: &ReadableView<Option<String>>) -> bool {
     !readable_view.is_empty() && readable_view.readable_slice()[0].is_none()
 }
@@ -168,7 +185,7 @@ impl<'a> FilteredPatternMatchingAutomataConfiguration<'a> {
     }
 
     pub fn is_final(&self) -> bool {
-        self.current_state.is_final
+        self.current_state.is_final()
     }
 
     pub fn is_waiting(&self) -> bool {
@@ -198,19 +215,6 @@ impl<'a> FilteredPatternMatchingAutomataConfiguration<'a> {
         }
     }
 
-    pub fn input_head(&self, i: usize) -> Option<String> {
-        if i < self.input_sequence.len() {
-            let head = self.input_sequence[i].readable_slice();
-            if head.is_empty() {
-                None
-            } else {
-                head[0].clone()
-            }
-        } else {
-            None
-        }
-    }
-
     pub fn input_advance(&mut self, i: usize, count: usize) {
         if i < self.input_sequence.len() {
             self.input_sequence[i].advance_readable(count);
@@ -225,22 +229,101 @@ impl<'a> FilteredPatternMatchingAutomataConfiguration<'a> {
     /// - If the transition’s action is non-empty, it must match the head of
     ///   the corresponding input sequence,
     /// - Then that matching symbol is consumed (the input is advanced).
-    fn successors(&self) -> Vec<Self> {
+    ///
+    /// The heads of the input sequences are checked by `head_is`.
+    fn successors(
+        &self,
+        head_is: &impl Fn(&ReadableView<Option<String>>, &str) -> bool,
+    ) -> Vec<Self> {
         let mut successors = Vec::with_capacity(self.transitions().len());
         for transition in self.transitions().iter() {
-            // Ensure transition.var is within bounds.
-            transition.label.validate(self.dimensions());
+            // The variables are validated when the automaton is constructed.
+            debug_assert!(transition.variable() < self.dimensions());
             // Check if the transition is applicable.
-            let head = self.input_head(transition.label.1);
-            if head.is_none() || transition.label.0 != head.unwrap() {
+            if !head_is(
+                &self.input_sequence[transition.variable()],
+                transition.letter(),
+            ) {
                 continue;
             }
             // Create a tentative successor configuration.
             let mut successor = self.duplicate(transition.next_state);
             // Consume one symbol on the input for the given dimension.
-            successor.input_advance(transition.label.1, 1);
+            successor.input_advance(transition.variable(), 1);
             successors.push(successor);
         }
         successors
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata_runner::AppendOnlySequence;
+    use crate::tests::utils::create_small_automaton;
+    use std::cell::{Cell, RefCell};
+    use std::collections::BTreeSet;
+    use typed_arena::Arena;
+
+    #[test]
+    fn test_head_is_borrows() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        // The inputs of the filtered test_small, where the second one is still open
+        let mut sequences = [AppendOnlySequence::new(), AppendOnlySequence::new()];
+        for (sequence, input) in sequences.iter_mut().zip([["a", "a", "c"], ["a", "d", "d"]]) {
+            sequence.extend(input.iter().map(|action| Some(action.to_string())));
+        }
+        sequences[0].close();
+
+        let mut runner = FilteredPatternMatchingAutomataRunner::new(&automaton, vec![0, 1]);
+        for start0 in 0..3 {
+            for start1 in 0..3 {
+                runner.insert_from_initial_states(vec![
+                    sequences[0].readable_view_from(start0),
+                    sequences[1].readable_view_from(start1),
+                ]);
+            }
+        }
+
+        // Count the checks of the heads, each borrowing the stream once, and append a masked
+        // action in the middle of the step, which fails if a stream is borrowed across the checks
+        let checks = Cell::new(0);
+        let appended = RefCell::new(sequences[1].share());
+        let counting_head_is = |view: &ReadableView<Option<String>>, letter: &str| {
+            checks.set(checks.get() + 1);
+            if checks.get() == 1 {
+                appended.borrow_mut().append(None);
+            }
+            head_is(view, letter)
+        };
+        runner.consume_with(&counting_head_is);
+        assert_eq!(sequences[1].len(), 4);
+        // Each check examines a transition of a configuration
+        let examined: usize = runner.iter().map(|c| c.transitions().len()).sum::<usize>();
+        assert!(checks.get() > 0);
+        assert!(checks.get() <= examined);
+
+        // The same matches as the filtered test_small as (start1, end1, start2, end2)
+        let matches: BTreeSet<Vec<usize>> = runner
+            .get_final_configurations()
+            .iter()
+            .map(|c| {
+                (0..2)
+                    .flat_map(|i| [c.matching_begin[i], c.input_sequence[i].start - 1])
+                    .collect()
+            })
+            .collect();
+        let expected = BTreeSet::from([
+            vec![0, 2, 1, 1],
+            vec![0, 2, 2, 2],
+            vec![1, 2, 1, 1],
+            vec![1, 2, 2, 2],
+            vec![2, 2, 1, 1],
+            vec![2, 2, 2, 2],
+        ]);
+        assert_eq!(matches, expected);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 
 use log::{debug, trace};
 
@@ -6,8 +6,10 @@ use crate::{
     automata::NFAH,
     automata_runner::ReadableView,
     filtered_pattern_matching_automata_runner::FilteredPatternMatchingAutomataRunner,
+    hyper_pattern_matching::hot_trace_enabled,
     naive_hyper_pattern_matching::StartPosition,
     result_notifier::{MatchingInterval, ResultNotifier},
+    skip_value_cache::SkipValueCache,
 };
 
 /// Trait of the algorithms for hyper pattern matching, where the word assignment is already fixed.
@@ -19,6 +21,24 @@ pub trait FilteredSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
         ids: Vec<usize>,
     ) -> Self;
 
+    /// Creates a new matching taking the tables depending only on `automaton`, e.g., the skip
+    /// values, from `cache`, so that the matchings of all the id-assignments share them.
+    ///
+    /// The default implementation ignores `cache` and calls
+    /// [`FilteredSingleHyperPatternMatching::new`].
+    fn with_cache(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<Option<String>>>,
+        ids: Vec<usize>,
+        _cache: &SkipValueCache<'a>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(automaton, notifier, input_streams, ids)
+    }
+
     /// The number of variables in hyper pattern matching.
     fn dimensions(&self) -> usize;
 
@@ -55,6 +75,32 @@ pub trait FilteredSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
         false
     }
 
+    /// Check if the actions at the given start position are in the first symbols of their
+    /// variables, i.e., a matching trial from it may succeed.
+    ///
+    /// `first_symbols` is given by [`NFAH::first_symbols_per_variable`]. The skipped or not yet
+    /// read start positions are kept.
+    fn starts_with_first_symbols(
+        &self,
+        start_position: &StartPosition,
+        first_symbols: &[Option<HashSet<String>>],
+    ) -> bool {
+        start_position
+            .start_indices
+            .iter()
+            .zip(first_symbols)
+            .enumerate()
+            .all(|(i, (&start_index, symbols))| {
+                let Some(symbols) = symbols else {
+                    return true;
+                };
+                match self.get_input_stream(i).readable_slice().get(start_index) {
+                    Some(Some(action)) => symbols.contains(action),
+                    _ => true,
+                }
+            })
+    }
+
     /// Returns the indices where the current value of the stream is None
     fn skipped_streams(&self, start_position: &StartPosition) -> Vec<usize> {
         let mut skipped_streams = Vec::with_capacity(start_position.start_indices.len());
@@ -77,6 +123,12 @@ pub struct NaiveFilteredSingleHyperPatternMatching<'a, Notifier: ResultNotifier>
     input_streams: Vec<ReadableView<Option<String>>>,
     ids: Vec<usize>,
     waiting_queue: BTreeSet<StartPosition>,
+    /// The actions an accepted word can start with on each variable
+    first_symbols: Vec<Option<HashSet<String>>>,
+    /// If false, the start positions are not checked with `first_symbols`
+    check_first_symbols: bool,
+    /// The number of matching trials started so far
+    inserted_trials: usize,
 }
 
 impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifier>
@@ -103,6 +155,9 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
             input_streams,
             ids,
             waiting_queue,
+            first_symbols: automaton.first_symbols_per_variable(),
+            check_first_symbols: true,
+            inserted_trials: 1,
         }
     }
 
@@ -134,7 +189,9 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
         while self.automata_runner.current_configurations.is_empty() {
             while self.automata_runner.current_configurations.is_empty() {
                 let new_position = self.waiting_queue.pop_first();
-                trace!("new_position: {:?}", new_position);
+                if hot_trace_enabled() {
+                    trace!("new_position: {:?}", new_position);
+                }
                 // Start new matching trial
                 if let Some(new_position) = new_position {
                     debug!(
@@ -150,7 +207,10 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
                     // Put the successors to the waiting queue
                     self.waiting_queue.extend(valid_successors);
 
-                    if !self.is_skipped(&new_position) {
+                    if !self.is_skipped(&new_position)
+                        && (!self.check_first_symbols
+                            || self.starts_with_first_symbols(&new_position, &self.first_symbols))
+                    {
                         let mut input_streams = self.input_streams.clone();
                         for variable in 0..dimensions {
                             input_streams[variable]
@@ -158,6 +218,7 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
                         }
                         self.automata_runner
                             .insert_from_initial_states(input_streams);
+                        self.inserted_trials += 1;
                     }
                 } else {
                     trace!("Exit NaiveFilteredSingleHyperPatternMatching::consume_input");
@@ -192,6 +253,17 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
 }
 
 impl<Notifier: ResultNotifier> NaiveFilteredSingleHyperPatternMatching<'_, Notifier> {
+    /// Enables or disables the precheck dropping the start positions whose actions are not in
+    /// the first symbols of the automaton. It is enabled by default.
+    pub fn set_first_symbol_check(&mut self, enabled: bool) {
+        self.check_first_symbols = enabled;
+    }
+
+    /// Returns the number of matching trials started so far, including the one at the beginning.
+    pub fn inserted_trials(&self) -> usize {
+        self.inserted_trials
+    }
+
     fn compute_valid_successors(&self, start_position: &StartPosition) -> Vec<StartPosition> {
         let mut waiting_queue = Vec::new();
         waiting_queue.push(start_position.clone());
@@ -236,6 +308,7 @@ mod tests {
         automata_runner::AppendOnlySequence,
         result_notifier::{MatchingResult, SharedBufferResultNotifier},
         shared_buffer::SharedBuffer,
+        tests::utils::create_small_automaton,
     };
     use itertools::Itertools;
     use typed_arena::Arena;
@@ -407,4 +480,48 @@ mod tests {
             assert!(results.contains(&expected_result));
         }
     }
+
+    /// Runs the naive filtered matcher on the input of `test_small` and returns the results and
+    /// the number of the started matching trials.
+    fn run_small(check_first_symbols: bool) -> (Vec<MatchingResult>, usize) {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let mut sequences = [AppendOnlySequence::new(), AppendOnlySequence::new()];
+        let input_streams = sequences.iter().map(|s| s.readable_view()).collect();
+        let result_buffer = SharedBuffer::new();
+        let mut matcher = NaiveFilteredSingleHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            input_streams,
+            vec![0, 1],
+        );
+        matcher.set_first_symbol_check(check_first_symbols);
+
+        for (action, track) in [("a", 0), ("a", 1), ("a", 0), ("d", 1), ("c", 0), ("d", 1)] {
+            sequences[track].append(Some(action.to_string()));
+            matcher.consume_input();
+        }
+        sequences[0].close();
+        sequences[1].close();
+        matcher.consume_input();
+
+        let mut result_sink = result_buffer.make_sink();
+        let mut results = Vec::new();
+        while let Some(result) = result_sink.pop() {
+            results.push(result);
+        }
+        results.sort();
+        (results, matcher.inserted_trials())
+    }
+
+    #[test]
+    fn test_first_symbol_check() {
+        let (expected, trials_without_check) = run_small(false);
+        let (results, trials_with_check) = run_small(true);
+        assert!(!expected.is_empty());
+        assert_eq!(expected, results);
+        assert!(trials_with_check < trials_without_check);
+    }
 }
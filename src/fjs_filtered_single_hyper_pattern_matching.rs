@@ -1,6 +1,7 @@
 use std::{
     cmp::Reverse,
     collections::{BTreeSet, HashSet},
+    rc::Rc,
 };
 
 use itertools::Itertools;
@@ -11,10 +12,12 @@ use crate::{
     automata_runner::ReadableView,
     filtered_pattern_matching_automata_runner::FilteredPatternMatchingAutomataRunner,
     filtered_single_hyper_pattern_matching::FilteredSingleHyperPatternMatching,
+    hyper_pattern_matching::hot_trace_enabled,
     kmp_skip_values::KMPSkipValues,
     naive_hyper_pattern_matching::StartPosition,
     quick_search_skip_values::QuickSearchSkipValues,
     result_notifier::{MatchingInterval, ResultNotifier},
+    skip_value_cache::{SkipValueCache, SkipValueTables},
 };
 
 pub struct FJSFilteredSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
@@ -25,8 +28,12 @@ pub struct FJSFilteredSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
     waiting_queue: BTreeSet<Reverse<StartPosition>>,
     /// The set of ignored starting positions by the skip values
     skipped_positions: Vec<HashSet<usize>>,
-    quick_search_skip_value: QuickSearchSkipValues,
-    kmp_skip_value: KMPSkipValues<'a>,
+    /// The skip value tables, possibly shared with the other id-assignments via a
+    /// [`SkipValueCache`]
+    quick_search_skip_value: Rc<QuickSearchSkipValues>,
+    kmp_skip_value: Rc<KMPSkipValues<'a>>,
+    /// The actions an accepted word can start with on each variable
+    first_symbols: Vec<Option<HashSet<String>>>,
 }
 
 impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifier>
@@ -38,29 +45,27 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
         input_streams: Vec<ReadableView<Option<String>>>,
         ids: Vec<usize>,
     ) -> Self {
-        let mut automata_runner =
-            FilteredPatternMatchingAutomataRunner::new(automaton, ids.clone());
-        let start_indices = vec![0; automaton.dimensions];
-        let waiting_queue = StartPosition { start_indices }
-            .immediate_successors()
-            .map(Reverse)
-            .collect();
-        let skipped_positions = (0..automaton.dimensions)
-            .map(|_| HashSet::new())
-            .collect_vec();
-
-        automata_runner.insert_from_initial_states(input_streams.clone());
+        let tables = SkipValueTables {
+            quick_search_skip_values: Rc::new(QuickSearchSkipValues::new(automaton)),
+            kmp_skip_values: Rc::new(KMPSkipValues::new(automaton)),
+        };
+        Self::with_tables(automaton, notifier, input_streams, ids, tables)
+    }
 
-        Self {
-            automata_runner,
+    fn with_cache(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<Option<String>>>,
+        ids: Vec<usize>,
+        cache: &SkipValueCache<'a>,
+    ) -> Self {
+        Self::with_tables(
+            automaton,
             notifier,
             input_streams,
             ids,
-            waiting_queue,
-            skipped_positions,
-            quick_search_skip_value: QuickSearchSkipValues::new(automaton),
-            kmp_skip_value: KMPSkipValues::new(automaton),
-        }
+            cache.get(automaton),
+        )
     }
 
     fn dimensions(&self) -> usize {
@@ -89,7 +94,7 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
         // Apply KMP-style skip values
         for c in &self.automata_runner.current_configurations {
             for i in 0..c.ids.len() {
-                if let Some(&skip_value) = self.kmp_skip_value.skip_values[i].get(c.current_state) {
+                if let Some(skip_value) = self.kmp_skip_value.skip_value_for(i, c.current_state) {
                     for j in 1..skip_value {
                         if i < self.skipped_positions.len() {
                             self.skipped_positions[i].insert(c.matching_begin[i] + j);
@@ -121,7 +126,10 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
                 // Put the successors to the waiting queue
                 self.waiting_queue.extend(valid_successors);
 
-                if self.is_valid_position(&position.0) && !self.is_skipped(&position.0) {
+                if self.is_valid_position(&position.0)
+                    && !self.is_skipped(&position.0)
+                    && self.starts_with_first_symbols(&position.0, &self.first_symbols)
+                {
                     let mut input_streams = self.input_streams.clone();
                     for variable in 0..self.dimensions() {
                         input_streams[variable].start = position.0.start_indices[variable];
@@ -145,8 +153,8 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
                     // Apply KMP-style skip values
                     for c in &self.automata_runner.current_configurations {
                         for i in 0..c.ids.len() {
-                            if let Some(&skip_value) =
-                                self.kmp_skip_value.skip_values[i].get(c.current_state)
+                            if let Some(skip_value) =
+                                self.kmp_skip_value.skip_value_for(i, c.current_state)
                             {
                                 for j in 1..skip_value {
                                     if i < self.skipped_positions.len() {
@@ -178,6 +186,42 @@ impl<'a, Notifier: ResultNotifier> FilteredSingleHyperPatternMatching<'a, Notifi
     }
 }
 
+impl<'a, Notifier: ResultNotifier> FJSFilteredSingleHyperPatternMatching<'a, Notifier> {
+    /// Creates a new `FJSFilteredSingleHyperPatternMatching` using the given skip value tables.
+    fn with_tables(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<Option<String>>>,
+        ids: Vec<usize>,
+        tables: SkipValueTables<'a>,
+    ) -> Self {
+        let mut automata_runner =
+            FilteredPatternMatchingAutomataRunner::new(automaton, ids.clone());
+        let start_indices = vec![0; automaton.dimensions];
+        let waiting_queue = StartPosition { start_indices }
+            .immediate_successors()
+            .map(Reverse)
+            .collect();
+        let skipped_positions = (0..automaton.dimensions)
+            .map(|_| HashSet::new())
+            .collect_vec();
+
+        automata_runner.insert_from_initial_states(input_streams.clone());
+
+        Self {
+            automata_runner,
+            notifier,
+            input_streams,
+            ids,
+            waiting_queue,
+            skipped_positions,
+            quick_search_skip_value: tables.quick_search_skip_values,
+            kmp_skip_value: tables.kmp_skip_values,
+            first_symbols: automaton.first_symbols_per_variable(),
+        }
+    }
+}
+
 impl<Notifier: ResultNotifier> FJSFilteredSingleHyperPatternMatching<'_, Notifier> {
     /// Check if a position is valid (not skipped and within range)
     fn is_valid_position(&self, position: &StartPosition) -> bool {
@@ -213,7 +257,8 @@ impl<Notifier: ResultNotifier> FJSFilteredSingleHyperPatternMatching<'_, Notifie
                 .quick_search_skip_value
                 .shortest_accepted_word_length_map[var];
 
-            if shortest_matching_length > 0 {
+            // We need at least one letter of the shortest word to decide the skip
+            if let Some(shortest_matching_length) = shortest_matching_length.filter(|&l| l > 0) {
                 let stream = self.get_input_stream(var);
                 let slice_shortest_end_idx =
                     start_index + shortest_matching_length - 1 - stream.start;
@@ -288,7 +333,9 @@ impl<Notifier: ResultNotifier> FJSFilteredSingleHyperPatternMatching<'_, Notifie
                 .collect_vec();
 
             for successor in successor_candidates.into_iter() {
-                trace!("successor_candidate: {:?}", successor);
+                if hot_trace_enabled() {
+                    trace!("successor_candidate: {:?}", successor);
+                }
                 if self.is_skipped(&successor) {
                     if skipped_streams.is_empty()
                         || skipped_streams.iter().any(|&i| {
@@ -1,14 +1,41 @@
 use crate::automata::NFAH;
 use crate::automata_runner::{AppendOnlySequence, NFAHRunner};
-use crate::hyper_pattern_matching::{HyperPatternMatching, PatternMatchingAutomataRunner};
+use crate::hyper_pattern_matching::{
+    check_dimensions, HyperPatternMatching, PatternMatchingAutomataRunner,
+};
 use crate::kmp_skip_values::KMPSkipValues;
-use crate::naive_hyper_pattern_matching::StartPosition;
+use crate::naive_hyper_pattern_matching::{
+    is_available, notify_final_configurations, starts_with_first_symbols, StartPosition,
+};
 use crate::quick_search_skip_values::QuickSearchSkipValues;
-use crate::result_notifier::{MatchingInterval, ResultNotifier};
+use crate::result_notifier::ResultNotifier;
+use crate::skip_value_cache::SkipValueCache;
 use itertools::Itertools;
-use log::debug;
+use log::{debug, info};
 use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+/// The skip value that made a starting position skipped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SkipReason {
+    /// The KMP-style skip value of the given width
+    Kmp(usize),
+    /// The Quick-Search-style skip value of the given width
+    QuickSearch(usize),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Kmp(width) => write!(f, "KMP-style skip value of width {}", width),
+            SkipReason::QuickSearch(width) => {
+                write!(f, "Quick-Search-style skip value of width {}", width)
+            }
+        }
+    }
+}
 
 /// A struct to store the skipped starting positions
 struct SkippedStartingPositions {
@@ -18,6 +45,9 @@ struct SkippedStartingPositions {
     sequence_size: usize,
     /// Variable -> Word -> Set(Positions)
     skipped_starting_positions: Vec<Vec<HashSet<usize>>>,
+    /// Variable -> Word -> Positions -> The first reason to skip it, recorded only to explain
+    /// the skips
+    reasons: Option<Vec<Vec<HashMap<usize, SkipReason>>>>,
 }
 
 impl SkippedStartingPositions {
@@ -29,10 +59,24 @@ impl SkippedStartingPositions {
             variable_size,
             sequence_size,
             skipped_starting_positions,
+            reasons: None,
         }
     }
 
-    fn insert(&mut self, var: usize, word: usize, position: usize) {
+    /// Starts or stops recording the reasons of the skipped starting positions.
+    fn record_reasons(&mut self, record: bool) {
+        self.reasons = record.then(|| {
+            (0..self.variable_size)
+                .map(|_| {
+                    (0..self.sequence_size)
+                        .map(|_| HashMap::new())
+                        .collect_vec()
+                })
+                .collect_vec()
+        });
+    }
+
+    fn insert(&mut self, var: usize, word: usize, position: usize, reason: SkipReason) {
         if var >= self.variable_size || word >= self.sequence_size {
             panic!(
                 "Out of range given (var: {}, word: {}) for (var_size: {}, word_size: {})",
@@ -40,6 +84,9 @@ impl SkippedStartingPositions {
             );
         }
         self.skipped_starting_positions[var][word].insert(position);
+        if let Some(reasons) = &mut self.reasons {
+            reasons[var][word].entry(position).or_insert(reason);
+        }
     }
 
     fn contains(&self, var: usize, word: usize, position: usize) -> bool {
@@ -52,14 +99,18 @@ impl SkippedStartingPositions {
         self.skipped_starting_positions[var][word].contains(&position)
     }
 
-    fn matchable(&self, start_position: &StartPosition, id: &[usize]) -> bool {
+    /// Returns the first variable whose starting position is skipped, or `None` if
+    /// `start_position` is matchable.
+    fn skipped_variable(&self, start_position: &StartPosition, id: &[usize]) -> Option<usize> {
         assert_eq!(start_position.start_indices.len(), id.len());
-        for i in 0..start_position.start_indices.len() {
-            if self.contains(id[i], i, start_position.start_indices[i]) {
-                return false;
-            }
-        }
-        true
+        (0..start_position.start_indices.len())
+            .find(|&i| self.contains(i, id[i], start_position.start_indices[i]))
+    }
+
+    /// Returns the first reason to skip `position` of `word` on `var` if the reasons are
+    /// recorded.
+    fn reason(&self, var: usize, word: usize, position: usize) -> Option<SkipReason> {
+        self.reasons.as_ref()?[var][word].get(&position).copied()
     }
 }
 
@@ -67,22 +118,45 @@ pub struct FJSHyperPatternMatching<'a, Notifier: ResultNotifier> {
     automata_runner: PatternMatchingAutomataRunner<'a>,
     notifier: Notifier,
     sequences: Vec<AppendOnlySequence<String>>,
+    /// The number of variables of the automaton, which may differ from the number of sequences
+    dimensions: usize,
     read_size: Vec<usize>,
     waiting_queues: HashMap<Vec<usize>, Vec<Reverse<StartPosition>>>,
     /// The set of ignored starting positions by the skip values
     skipped_starting_positions: SkippedStartingPositions,
-    quick_search_skip_value: QuickSearchSkipValues,
-    kmp_skip_value: KMPSkipValues<'a>,
+    /// The skip value tables, possibly shared with other matchers via a [`SkipValueCache`]
+    quick_search_skip_value: Rc<QuickSearchSkipValues>,
+    kmp_skip_value: Rc<KMPSkipValues<'a>>,
     /// Either we reached the end of the sequences
     eof: Vec<bool>,
+    /// If true, only the first match for each id-assignment is notified, i.e., the earliest one
+    /// among the matches found in the same step
+    first_only: bool,
+    /// The id-assignments for which a match is already notified in the first-only mode
+    matched_ids: HashSet<Vec<usize>>,
+    /// The actions an accepted word can start with on each variable
+    first_symbols: Vec<Option<HashSet<String>>>,
+    /// The number of start positions dropped by the first symbols
+    pruned_start_positions: usize,
+    /// The number of matches notified so far
+    matches_found: usize,
+    /// If true, the reason of each skipped start position is logged
+    explain: bool,
 }
 
 impl<'a, Notifier: ResultNotifier> FJSHyperPatternMatching<'a, Notifier> {
+    /// Creates a new `FJSHyperPatternMatching`.
+    ///
+    /// The skip value tables are taken from `cache`, so they are computed only once for all the
+    /// matchers sharing the cache.
     pub fn new(
         automaton: &'a NFAH<'a>,
         notifier: Notifier,
         sequences: Vec<AppendOnlySequence<String>>,
+        first_only: bool,
+        cache: &SkipValueCache<'a>,
     ) -> Self {
+        check_dimensions(automaton.dimensions, sequences.len());
         let mut automata_runner = PatternMatchingAutomataRunner::new(automaton);
         let read_size = vec![0; sequences.len()];
         let eof = vec![false; sequences.len()];
@@ -112,20 +186,51 @@ impl<'a, Notifier: ResultNotifier> FJSHyperPatternMatching<'a, Notifier> {
 
         let skipped_starting_positions =
             SkippedStartingPositions::new(automaton.dimensions, sequences.len());
+        let tables = cache.get(automaton);
 
         Self {
             automata_runner,
             notifier,
             sequences,
+            dimensions: automaton.dimensions,
             read_size,
             waiting_queues,
             eof,
+            first_only,
+            matched_ids: HashSet::new(),
             skipped_starting_positions,
-            quick_search_skip_value: QuickSearchSkipValues::new(automaton),
-            kmp_skip_value: KMPSkipValues::new(automaton),
+            quick_search_skip_value: tables.quick_search_skip_values,
+            kmp_skip_value: tables.kmp_skip_values,
+            first_symbols: automaton.first_symbols_per_variable(),
+            pruned_start_positions: 0,
+            matches_found: 0,
+            explain: false,
+        }
+    }
+
+    /// Logs why each start position is skipped, i.e., which skip value of which width or the
+    /// first symbols, at the info level. This is for understanding the skips on a small input.
+    pub fn set_explain(&mut self, explain: bool) {
+        self.explain = explain;
+        self.skipped_starting_positions.record_reasons(explain);
+    }
+
+    /// Logs that `position` for `id` is skipped because of `reason` if `explain` is set.
+    fn explain_skip(&self, position: &StartPosition, id: &[usize], reason: fmt::Arguments) {
+        if self.explain {
+            info!(
+                "Skip the start position {:?} for {:?}: {}",
+                position.start_indices, id, reason
+            );
         }
     }
 
+    /// Returns the number of start positions dropped without a matching trial because the action
+    /// at the start is not in the first symbols of the automaton.
+    pub fn pruned_start_positions(&self) -> usize {
+        self.pruned_start_positions
+    }
+
     pub fn in_range(&self, start_position: &StartPosition, ids: &[usize]) -> bool {
         assert_eq!(start_position.start_indices.len(), ids.len());
         for i in 0..start_position.start_indices.len() {
@@ -135,38 +240,130 @@ impl<'a, Notifier: ResultNotifier> FJSHyperPatternMatching<'a, Notifier> {
         }
         true
     }
-}
 
-impl<Notifier: ResultNotifier> HyperPatternMatching for FJSHyperPatternMatching<'_, Notifier> {
-    fn feed(&mut self, action: &str, track: usize) {
-        self.sequences[track].append(action.to_string());
-        self.read_size[track] += 1;
-        self.automata_runner.consume();
-        let final_configurations = self.automata_runner.get_final_configurations();
-        let dimensions = self.dimensions();
-        final_configurations.iter().for_each(|c| {
-            let mut intervals = Vec::with_capacity(dimensions);
-            for i in 0..dimensions {
-                let begin = c.matching_begin[i];
-                let end = c.input_sequence[i].start - 1;
-                intervals.push(MatchingInterval::new(begin, end));
+    /// Puts the successors of `position` within the range to the waiting queue of `id`.
+    fn enqueue_successors(&mut self, position: &StartPosition, id: &[usize]) {
+        let mut valid_successors = position
+            .immediate_successors_filtered(|successor| self.in_range(successor, id))
+            .map(Reverse)
+            .collect_vec();
+        let waiting_queue = self.waiting_queues.get_mut(id).unwrap();
+        waiting_queue.append(&mut valid_successors);
+        waiting_queue.sort();
+        waiting_queue.dedup();
+    }
+
+    /// Pops the next start position to try for `id`.
+    ///
+    /// The positions ignorable by the skip values or not starting with the first symbols are
+    /// dropped, but their successors are still put to the waiting queue so that the positions
+    /// behind them are tried. Returns `None` if the
+    /// queue is empty or the next position is not read on all the tracks yet.
+    fn pop_start_position(&mut self, id: &[usize]) -> Option<StartPosition> {
+        loop {
+            let waiting_queue = self.waiting_queues.get_mut(id).unwrap();
+            // Postpone the trial until the start position is read on all the tracks
+            if !waiting_queue
+                .last()
+                .is_some_and(|position| is_available(&self.sequences, &position.0, id))
+            {
+                return None;
             }
-            self.notifier.notify(&intervals, &c.ids);
-        });
+            let Reverse(position) = waiting_queue.pop().unwrap();
+            if !starts_with_first_symbols(&self.sequences, &self.first_symbols, &position, id) {
+                self.pruned_start_positions += 1;
+                self.explain_skip(
+                    &position,
+                    id,
+                    format_args!("an action at the start is not in the first symbols"),
+                );
+            } else if let Some(var) = self
+                .skipped_starting_positions
+                .skipped_variable(&position, id)
+            {
+                let start = position.start_indices[var];
+                if let Some(reason) = self.skipped_starting_positions.reason(var, id[var], start) {
+                    self.explain_skip(
+                        &position,
+                        id,
+                        format_args!("{} on the variable {}", reason, var),
+                    );
+                }
+            } else if let Some((var, width)) = self.try_quick_search_skip(&position, id) {
+                self.explain_skip(
+                    &position,
+                    id,
+                    format_args!("{} on the variable {}", SkipReason::QuickSearch(width), var),
+                );
+            } else {
+                return Some(position);
+            }
+            self.enqueue_successors(&position, id);
+        }
+    }
+
+    /// Returns the variable and the skip width if `position` is ignorable according to quick
+    /// search. The positions ignorable together are recorded in the skipped starting positions.
+    fn try_quick_search_skip(
+        &mut self,
+        position: &StartPosition,
+        id: &[usize],
+    ) -> Option<(usize, usize)> {
+        for (var, &w) in id.iter().enumerate() {
+            let sequence = &self.sequences[w];
+            let start_index = position.start_indices[var];
+            // We need at least one letter of the shortest word to decide the skip
+            let shortest_matching_length = match self
+                .quick_search_skip_value
+                .shortest_accepted_word_length_map[var]
+            {
+                Some(length) if length > 0 => length,
+                _ => continue,
+            };
+            let shortest_end_index = start_index + shortest_matching_length - 1;
+            let next_index = start_index + shortest_matching_length;
+            if let (Some(shortest_end), Some(next)) =
+                (sequence.get(shortest_end_index), sequence.get(next_index))
+            {
+                let last_accepted_words = &self.quick_search_skip_value.last_accepted_word[var];
+                if !last_accepted_words.contains(&shortest_end) {
+                    let skipped_width = self.quick_search_skip_value.skip_value(&next, var);
+                    for i in 0..skipped_width {
+                        self.skipped_starting_positions.insert(
+                            var,
+                            w,
+                            start_index + i,
+                            SkipReason::QuickSearch(skipped_width),
+                        );
+                    }
+                    return Some((var, skipped_width));
+                }
+            }
+        }
+        None
+    }
+
+    /// Runs the configurations on the available input, notifies the matches, and starts new
+    /// matching trials for the id-assignments without running configurations.
+    ///
+    /// Returns `true` if a new matching trial is started.
+    fn step(&mut self) -> bool {
+        self.automata_runner.consume();
+        self.notify_final_configurations();
         // Apply KMP-style skip values
         self.automata_runner
             .current_configurations
             .iter()
             .for_each(|c| {
                 for i in 0..c.ids.len() {
-                    if let Some(&skip_value) =
-                        self.kmp_skip_value.skip_values[i].get(c.current_state)
+                    if let Some(skip_value) = self.kmp_skip_value.skip_value_for(i, c.current_state)
                     {
                         for j in 1..skip_value {
                             self.skipped_starting_positions.insert(
                                 i,
                                 c.ids[i],
                                 c.matching_begin[i] + j,
+                                SkipReason::Kmp(skip_value),
                             );
                         }
                     }
@@ -180,189 +377,108 @@ impl<Notifier: ResultNotifier> HyperPatternMatching for FJSHyperPatternMatching<
             .map(|c| c.ids.clone())
             .collect::<HashSet<Vec<usize>>>();
         let keys = self.waiting_queues.keys().cloned().collect_vec();
+        let mut started = false;
         for id in keys {
             if !current_ids.contains(&id) {
-                let new_position = {
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    fn find_new_position(
-                        id: &Vec<usize>,
-                        waiting_queue: &mut Vec<Reverse<StartPosition>>,
-                        skipped_starting_positions: &mut SkippedStartingPositions,
-                        quick_search_skip_values: &QuickSearchSkipValues,
-                        sequences: &Vec<AppendOnlySequence<String>>,
-                    ) -> Option<Reverse<StartPosition>> {
-                        let new_position_candidate = waiting_queue.pop();
-                        match new_position_candidate {
-                            Some(Reverse(found_new_position)) => {
-                                let start_indices = &found_new_position.start_indices;
-                                assert!(id.len() == start_indices.len());
-                                if !skipped_starting_positions.matchable(&found_new_position, id) {
-                                    // When we already know that this starting position can be skipped
-                                    find_new_position(
-                                        id,
-                                        waiting_queue,
-                                        skipped_starting_positions,
-                                        quick_search_skip_values,
-                                        sequences,
-                                    )
-                                } else {
-                                    for var in 0..id.len() {
-                                        let w = id[var];
-                                        let sequence = &sequences[w];
-                                        let start_index = start_indices[var];
-                                        let shortest_matching_length = quick_search_skip_values
-                                            .shortest_accepted_word_length_map[var];
-                                        if shortest_matching_length > 0 {
-                                            let shortest_end_index =
-                                                start_index + shortest_matching_length - 1;
-                                            let next_index = start_index + shortest_matching_length;
-                                            let last_accepted_words =
-                                                &quick_search_skip_values.last_accepted_word[var];
-                                            if sequence.len()
-                                                < start_index + shortest_matching_length
-                                                && !last_accepted_words.contains(
-                                                    &sequence.get(shortest_end_index).unwrap(),
-                                                )
-                                            {
-                                                // This start position is ignorable according to quick search
-                                                let skipped_width = quick_search_skip_values
-                                                    .skip_value(
-                                                        &sequence.get(next_index).unwrap(),
-                                                        var,
-                                                    );
-                                                (0..skipped_width).for_each(|i| {
-                                                    skipped_starting_positions.insert(
-                                                        var,
-                                                        w,
-                                                        start_index + i,
-                                                    );
-                                                });
-                                                return find_new_position(
-                                                    id,
-                                                    waiting_queue,
-                                                    skipped_starting_positions,
-                                                    quick_search_skip_values,
-                                                    sequences,
-                                                );
-                                            }
-                                        }
-                                    }
-                                    Some(Reverse(found_new_position))
-                                }
-                            }
-                            None => None,
-                        }
-                    }
-                    find_new_position(
-                        &id,
-                        waiting_queue,
-                        &mut self.skipped_starting_positions,
-                        &self.quick_search_skip_value,
-                        &self.sequences,
-                    )
-                };
                 // Start new matching trial
-                if let Some(new_position) = new_position {
-                    let mut valid_successors = new_position
-                        .0
-                        .immediate_successors_filtered(|successor| {
-                            self.in_range(successor, &id)
-                                && self.skipped_starting_positions.matchable(successor, &id)
-                        })
-                        .map(Reverse)
-                        .collect_vec();
-                    // Put the successors to the waiting queue
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.append(&mut valid_successors);
-                    waiting_queue.sort();
-                    waiting_queue.dedup();
-                    debug!("[FJSHyperPatternMatching::feed] Start new matching trial from {:?} for {:?})", new_position, id);
+                if let Some(new_position) = self.pop_start_position(&id) {
+                    started = true;
+                    self.enqueue_successors(&new_position, &id);
+                    debug!("[FJSHyperPatternMatching::step] Start new matching trial from {:?} for {:?})", new_position, id);
                     let input_sequence = id
                         .iter()
-                        .map(|&i| {
-                            let mut view = self.sequences[i].readable_view();
-                            view.advance_readable(new_position.0.start_indices[i]);
-                            view
-                        })
+                        .zip(&new_position.start_indices)
+                        .map(|(&i, &start)| self.sequences[i].readable_view_from(start))
                         .collect_vec();
                     self.automata_runner
                         .insert_from_initial_states(input_sequence, id)
                 }
             }
         }
+        started
+    }
+
+    /// Notifies the matching results of the final configurations.
+    ///
+    /// In the first-only mode, only the earliest match for each id-assignment is notified. See
+    /// [`notify_final_configurations`].
+    fn notify_final_configurations(&mut self) {
+        let dimensions = self.dimensions();
+        self.matches_found += notify_final_configurations(
+            &mut self.automata_runner,
+            dimensions,
+            &mut self.waiting_queues,
+            &mut self.notifier,
+            self.first_only.then_some(&mut self.matched_ids),
+        );
+    }
+}
+
+impl<Notifier: ResultNotifier> HyperPatternMatching for FJSHyperPatternMatching<'_, Notifier> {
+    fn feed(&mut self, action: &str, track: usize) {
+        self.sequences[track].append(action.to_string());
+        self.read_size[track] += 1;
+        self.step();
+    }
+
+    fn flush(&mut self) {
+        // Repeat until all the trials wait for more input
+        while self.step() {}
+        self.notifier.flush();
     }
 
     fn dimensions(&self) -> usize {
-        self.sequences.len()
+        self.dimensions
+    }
+
+    fn matches_found(&self) -> usize {
+        self.matches_found
     }
 
     fn consume_remaining(&mut self) {
         debug!("Call FJSHyperPatternMatching::consume_remaining");
         self.automata_runner.consume();
-        let final_configurations = self.automata_runner.get_final_configurations();
-        let dimensions = self.dimensions();
-        final_configurations.iter().for_each(|c| {
-            let mut intervals = Vec::with_capacity(dimensions);
-            for i in 0..dimensions {
-                let begin = c.matching_begin[i];
-                let end = c.input_sequence[i].start - 1;
-                intervals.push(MatchingInterval::new(begin, end));
-            }
-            self.notifier.notify(&intervals, &c.ids);
-        });
+        self.notify_final_configurations();
         while self.waiting_queues.values().any(|f| !f.is_empty()) {
             self.automata_runner.current_configurations.clear();
             let keys = self.waiting_queues.keys().cloned().collect_vec();
+            let mut started = false;
             for id in keys {
-                let new_position = {
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.pop()
-                };
                 // Start new matching trial
-                if let Some(new_position) = new_position {
-                    let mut valid_successors = new_position
-                        .0
-                        .immediate_successors_filtered(|successor| {
-                            self.in_range(successor, &id)
-                                && self.skipped_starting_positions.matchable(successor, &id)
-                        })
-                        .map(Reverse)
-                        .collect_vec();
-                    // Put the successors to the waiting queue
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.append(&mut valid_successors);
-                    waiting_queue.sort();
-                    waiting_queue.dedup();
+                if let Some(new_position) = self.pop_start_position(&id) {
+                    started = true;
+                    self.enqueue_successors(&new_position, &id);
                     let input_sequence = id
                         .iter()
-                        .map(|&i| {
-                            let mut view = self.sequences[i].readable_view();
-                            view.advance_readable(new_position.0.start_indices[i]);
-                            view
-                        })
+                        .zip(&new_position.start_indices)
+                        .map(|(&i, &start)| self.sequences[i].readable_view_from(start))
                         .collect_vec();
                     self.automata_runner
                         .insert_from_initial_states(input_sequence, id)
                 }
             }
+            // The remaining positions wait for the tracks not closed yet
+            if !started {
+                break;
+            }
             self.automata_runner.consume();
-            let final_configurations = self.automata_runner.get_final_configurations();
-            let dimensions = self.dimensions();
-            final_configurations.iter().for_each(|c| {
-                let mut intervals = Vec::with_capacity(dimensions);
-                for i in 0..dimensions {
-                    let begin = c.matching_begin[i];
-                    let end = c.input_sequence[i].start - 1;
-                    intervals.push(MatchingInterval::new(begin, end));
-                }
-                self.notifier.notify(&intervals, &c.ids);
-            });
+            self.notify_final_configurations();
         }
     }
 
     fn set_eof(&mut self, track: usize) {
         self.sequences[track].close();
         self.eof[track] = true;
+        // Drop the waiting start positions beyond the end of the closed track. The other tracks
+        // may still be open, so the remaining positions keep being tried.
+        let read_size = self.read_size[track];
+        for (ids, waiting_queue) in self.waiting_queues.iter_mut() {
+            waiting_queue.retain(|position| {
+                ids.iter()
+                    .zip(&position.0.start_indices)
+                    .all(|(&id, &start)| id != track || start < read_size)
+            });
+        }
     }
 }
 
@@ -371,8 +487,12 @@ mod tests {
     use super::*;
     use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
     use crate::reading_scheduler::ReadingScheduler;
-    use crate::result_notifier::SharedBufferResultNotifier;
+    use crate::result_notifier::{MatchingInterval, MatchingResult, SharedBufferResultNotifier};
     use crate::shared_buffer::SharedBuffer;
+    use crate::tests::utils::{
+        create_repeated_a_automaton, create_small_automaton, install_recording_logger,
+        verify_first_only,
+    };
     use typed_arena::Arena;
 
     #[test]
@@ -411,6 +531,8 @@ mod tests {
             &automaton,
             notifier,
             vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+            &SkipValueCache::new(),
         );
 
         let mut scheduler = ReadingScheduler::new(matching, reader);
@@ -505,4 +627,400 @@ mod tests {
         }
         assert!(result_sink.pop().is_none());
     }
+
+    #[test]
+    fn test_run_first_only() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_repeated_a_automaton(&state_arena, &transition_arena);
+
+        let result_buffer = SharedBuffer::new();
+        let matching = FJSHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            true,
+            &SkipValueCache::new(),
+        );
+        verify_first_only(matching, &result_buffer);
+    }
+
+    #[test]
+    fn test_shared_skip_value_cache() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let cache = SkipValueCache::new();
+        let mut matchings = Vec::new();
+        for _ in 0..2 {
+            let result_buffer = SharedBuffer::new();
+            matchings.push(FJSHyperPatternMatching::new(
+                &automaton,
+                SharedBufferResultNotifier::new(result_buffer.make_source()),
+                vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+                false,
+                &cache,
+            ));
+        }
+
+        // The tables are computed once and shared by both matchers
+        assert_eq!(cache.computations(), 1);
+        assert!(Rc::ptr_eq(
+            &matchings[0].kmp_skip_value,
+            &matchings[1].kmp_skip_value
+        ));
+        assert!(Rc::ptr_eq(
+            &matchings[0].quick_search_skip_value,
+            &matchings[1].quick_search_skip_value
+        ));
+    }
+
+    #[test]
+    fn test_independent_eof() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+
+        // Accepts "a" on the first variable followed by "b" on the second variable
+        let s1 = automaton.add_state(true, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "b".to_string(), 1, s3);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+
+        let mut matching = FJSHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+            &SkipValueCache::new(),
+        );
+
+        // Stream 0 ends at index 1
+        for action in ["a", "b"] {
+            matching.feed(action, 0);
+        }
+        matching.set_eof(0);
+        // No start position beyond the end of stream 0 is waiting
+        for (ids, waiting_queue) in &matching.waiting_queues {
+            for position in waiting_queue {
+                for (var, &start) in position.0.start_indices.iter().enumerate() {
+                    assert!(ids[var] != 0 || start < 2, "{:?} for {:?}", position, ids);
+                }
+            }
+        }
+
+        // Stream 1 continues to index 5
+        for action in ["b", "c", "a", "c", "c", "b"] {
+            matching.feed(action, 1);
+        }
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        let expected: HashSet<MatchingResult> = [
+            (0, 0, 0, 1),
+            (0, 0, 1, 0),
+            (0, 0, 1, 5),
+            (2, 1, 0, 1),
+            (2, 1, 1, 0),
+            (2, 1, 1, 5),
+        ]
+        .into_iter()
+        .map(|(begin0, id0, id1, begin1)| MatchingResult {
+            intervals: vec![
+                MatchingInterval::new(begin0, begin0),
+                MatchingInterval::new(begin1, begin1),
+            ],
+            ids: vec![id0, id1],
+        })
+        .collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_flush() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 1);
+
+        // Accepts "aac" and "b"
+        let s1 = automaton.add_state(true, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let s4 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "a".to_string(), 0, s3);
+        automaton.add_nfah_transition(s3, "c".to_string(), 0, s4);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s4);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut pop_intervals = || {
+            let mut intervals = vec![];
+            while let Some(result) = result_sink.pop() {
+                intervals.push(result.intervals[0].clone());
+            }
+            intervals
+        };
+
+        let mut matching = FJSHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new()],
+            false,
+            &SkipValueCache::new(),
+        );
+
+        for action in ["a", "a", "b"] {
+            matching.feed(action, 0);
+        }
+        // The trial from 0 fails at "b", and the trials from 1 and 2 are not run yet
+        assert!(pop_intervals().is_empty());
+        matching.flush();
+        assert_eq!(pop_intervals(), vec![MatchingInterval::new(2, 2)]);
+
+        // No new match is found afterwards
+        matching.feed("c", 0);
+        matching.flush();
+        matching.set_eof(0);
+        matching.consume_remaining();
+        assert!(pop_intervals()
+            .iter()
+            .all(|interval| *interval == MatchingInterval::new(2, 2)));
+    }
+
+    #[test]
+    fn test_empty_language() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+
+        // The final state is unreachable, so there is no shortest accepted word
+        let s1 = automaton.add_state(true, false);
+        let s2 = automaton.add_state(false, false);
+        let _s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "b".to_string(), 1, s1);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = FJSHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+            &SkipValueCache::new(),
+        );
+        assert_eq!(
+            matching
+                .quick_search_skip_value
+                .shortest_accepted_word_length_map,
+            vec![None, None]
+        );
+
+        for action in ["a", "b", "a"] {
+            matching.feed(action, 0);
+            matching.feed(action, 1);
+        }
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+        assert!(result_sink.pop().is_none());
+    }
+
+    #[test]
+    fn test_sequences_other_than_dimensions() {
+        // "a" on the first variable followed by "b" on the second variable
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+
+        // The intervals are given for each variable, not for each sequence
+        for (inputs, expected) in [
+            (vec!["ab"], vec![(vec![0, 0], 0, 1)]),
+            (
+                vec!["a", "b", "b"],
+                vec![(vec![0, 1], 0, 0), (vec![0, 2], 0, 0)],
+            ),
+        ] {
+            let result_buffer = SharedBuffer::new();
+            let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+            let mut result_sink = result_buffer.make_sink();
+            let mut matching = FJSHyperPatternMatching::new(
+                &automaton,
+                notifier,
+                inputs.iter().map(|_| AppendOnlySequence::new()).collect(),
+                false,
+                &SkipValueCache::new(),
+            );
+            assert_eq!(matching.dimensions(), 2);
+            for (track, input) in inputs.iter().enumerate() {
+                for action in input.chars() {
+                    matching.feed(&action.to_string(), track);
+                }
+                matching.set_eof(track);
+            }
+            matching.consume_remaining();
+
+            let mut results = HashSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            let expected: HashSet<MatchingResult> = expected
+                .into_iter()
+                .map(|(ids, begin0, begin1)| MatchingResult {
+                    intervals: vec![
+                        MatchingInterval::new(begin0, begin0),
+                        MatchingInterval::new(begin1, begin1),
+                    ],
+                    ids,
+                })
+                .collect();
+            assert_eq!(results, expected);
+        }
+    }
+
+    #[test]
+    fn test_first_symbols_pruning() {
+        // "a" on track 0 and then "b" on track 1
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = FJSHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+            &SkipValueCache::new(),
+        );
+
+        for (track, action) in [
+            (0, "x"),
+            (1, "y"),
+            (0, "a"),
+            (1, "b"),
+            (0, "x"),
+            (1, "y"),
+            (0, "x"),
+        ] {
+            matching.feed(action, track);
+        }
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        assert_eq!(
+            results,
+            HashSet::from([MatchingResult {
+                intervals: vec![MatchingInterval::new(1, 1), MatchingInterval::new(1, 1)],
+                ids: vec![0, 1],
+            }])
+        );
+        // The trials starting at "x" or "y" are not made
+        assert!(matching.pruned_start_positions() > 0);
+    }
+
+    #[test]
+    fn test_explain() {
+        let logger = install_recording_logger();
+
+        // "a b c" on track 0 and then "d" on track 1
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let s4 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "c".to_string(), 0, s3);
+        automaton.add_nfah_transition(s3, "d".to_string(), 1, s4);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = FJSHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+            &SkipValueCache::new(),
+        );
+        matching.set_explain(true);
+        // Track 0 is read ahead so that the skips are decided on the read actions
+        for action in ["a", "a", "b", "x", "a", "b", "c"] {
+            matching.feed(action, 0);
+        }
+        matching.feed("d", 1);
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        assert_eq!(
+            results,
+            HashSet::from([MatchingResult {
+                intervals: vec![MatchingInterval::new(4, 6), MatchingInterval::new(0, 0)],
+                ids: vec![0, 1],
+            }])
+        );
+
+        let explanations = logger
+            .records()
+            .into_iter()
+            .filter(|record| record.contains("Skip the start position"))
+            .collect_vec();
+        // "a a b" on track 0 does not end with "c" and "x" is not in "a b c"
+        assert!(explanations.contains(
+            &"INFO hyppau::fjs_hyper_pattern_matching: Skip the start position [0, 1] for [0, 1]: \
+              Quick-Search-style skip value of width 4 on the variable 0"
+                .to_string()
+        ));
+        // The positions skipped together are explained with the same reason
+        assert!(explanations.contains(
+            &"INFO hyppau::fjs_hyper_pattern_matching: Skip the start position [1, 0] for [0, 1]: \
+              Quick-Search-style skip value of width 4 on the variable 0"
+                .to_string()
+        ));
+        // "b" on track 0 cannot start a match
+        assert!(explanations.contains(
+            &"INFO hyppau::fjs_hyper_pattern_matching: Skip the start position [2, 0] for [0, 1]: \
+              an action at the start is not in the first symbols"
+                .to_string()
+        ));
+    }
 }
@@ -1,6 +1,7 @@
 use std::{
     cmp::Reverse,
     collections::{BTreeSet, HashSet},
+    rc::Rc,
 };
 
 use itertools::Itertools;
@@ -9,12 +10,14 @@ use log::trace;
 use crate::{
     automata::NFAH,
     automata_runner::{NFAHRunner, ReadableView},
-    hyper_pattern_matching::PatternMatchingAutomataRunner,
+    hyper_pattern_matching::{hot_trace_enabled, PatternMatchingAutomataRunner},
     kmp_skip_values::KMPSkipValues,
     naive_hyper_pattern_matching::StartPosition,
     quick_search_skip_values::QuickSearchSkipValues,
     result_notifier::{MatchingInterval, ResultNotifier},
     single_hyper_pattern_matching::SingleHyperPatternMatching,
+    skip_value_cache::{SkipValueCache, SkipValueTables},
+    symbol_matcher::SharedSymbolMatcher,
 };
 
 pub struct FJSSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
@@ -25,8 +28,12 @@ pub struct FJSSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
     waiting_queue: BTreeSet<Reverse<StartPosition>>,
     /// The set of ignored starting positions by the skip values
     skipped_positions: Vec<HashSet<usize>>,
-    quick_search_skip_value: QuickSearchSkipValues,
-    kmp_skip_value: KMPSkipValues<'a>,
+    /// The skip value tables, possibly shared with the other id-assignments via a
+    /// [`SkipValueCache`]
+    quick_search_skip_value: Rc<QuickSearchSkipValues>,
+    kmp_skip_value: Rc<KMPSkipValues<'a>>,
+    /// The number of matches notified so far
+    matches_found: usize,
 }
 
 impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
@@ -38,28 +45,23 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
         input_streams: Vec<ReadableView<String>>,
         ids: Vec<usize>,
     ) -> Self {
-        let mut automata_runner = PatternMatchingAutomataRunner::new(automaton);
-        let start_indices = vec![0; automaton.dimensions];
-        let waiting_queue = StartPosition { start_indices }
-            .immediate_successors()
-            .map(Reverse)
-            .collect();
-        automata_runner.insert_from_initial_states(input_streams.clone(), ids.clone());
-
-        let skipped_positions = (0..automaton.dimensions)
-            .map(|_| HashSet::new())
-            .collect_vec();
+        Self::with_alphabet(automaton, notifier, input_streams, ids, None)
+    }
 
-        Self {
-            automata_runner,
+    fn with_cache(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<String>>,
+        ids: Vec<usize>,
+        cache: &SkipValueCache<'a>,
+    ) -> Self {
+        Self::with_tables(
+            automaton,
             notifier,
             input_streams,
             ids,
-            waiting_queue,
-            skipped_positions,
-            quick_search_skip_value: QuickSearchSkipValues::new(automaton),
-            kmp_skip_value: KMPSkipValues::new(automaton),
-        }
+            cache.get(automaton),
+        )
     }
 
     fn dimensions(&self) -> usize {
@@ -70,11 +72,17 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
         self.ids.get(variable).copied()
     }
 
+    fn matches_found(&self) -> usize {
+        self.matches_found
+    }
+
     fn consume_input(&mut self) {
-        trace!(
-            "configuration_size: {}",
-            self.automata_runner.current_configurations.len()
-        );
+        if hot_trace_enabled() {
+            trace!(
+                "configuration_size: {}",
+                self.automata_runner.current_configurations.len()
+            );
+        }
         self.automata_runner.consume();
         let final_configurations = self.automata_runner.get_final_configurations();
         let dimensions = self.dimensions();
@@ -86,12 +94,13 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
                 intervals.push(MatchingInterval::new(begin, end));
             }
             self.notifier.notify(&intervals, &c.ids);
+            self.matches_found += 1;
         });
 
         // Apply KMP-style skip values
         for c in &self.automata_runner.current_configurations {
             for i in 0..c.ids.len() {
-                if let Some(&skip_value) = self.kmp_skip_value.skip_values[i].get(c.current_state) {
+                if let Some(skip_value) = self.kmp_skip_value.skip_value_for(i, c.current_state) {
                     for j in 1..skip_value {
                         if i < self.skipped_positions.len() {
                             self.skipped_positions[i].insert(c.matching_begin[i] + j);
@@ -128,6 +137,7 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
                             intervals.push(MatchingInterval::new(begin, end));
                         }
                         self.notifier.notify(&intervals, &c.ids);
+                        self.matches_found += 1;
                     });
 
                     self.automata_runner.remove_non_waiting_configurations();
@@ -139,6 +149,18 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
         }
     }
 
+    fn set_max_window(&mut self, _max_window: usize) -> bool {
+        // The skip values assume that a dropped matching trial failed on the input, so pruning
+        // the long trials would make us skip the positions where a short match begins.
+        false
+    }
+
+    fn set_symbol_matcher(&mut self, _symbol_matcher: SharedSymbolMatcher) -> bool {
+        // The skip values are computed from the exact letters, so the symbols matching a letter
+        // only up to the matcher would be skipped.
+        false
+    }
+
     fn get_input_stream(&self, variable: usize) -> &ReadableView<String> {
         if let Some(stream) = self.input_streams.get(variable) {
             stream
@@ -148,6 +170,62 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
     }
 }
 
+impl<'a, Notifier: ResultNotifier> FJSSingleHyperPatternMatching<'a, Notifier> {
+    /// Creates a new `FJSSingleHyperPatternMatching` for inputs over `alphabet`.
+    ///
+    /// If `alphabet` is given, the Quick-Search-style skip values ignore the accepted words using a
+    /// letter outside it (see [`QuickSearchSkipValues::with_alphabet`]), so the inputs must not
+    /// contain such a letter.
+    pub fn with_alphabet(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<String>>,
+        ids: Vec<usize>,
+        alphabet: Option<&HashSet<String>>,
+    ) -> Self {
+        let tables = SkipValueTables {
+            quick_search_skip_values: Rc::new(QuickSearchSkipValues::with_alphabet(
+                automaton, alphabet,
+            )),
+            kmp_skip_values: Rc::new(KMPSkipValues::new(automaton)),
+        };
+        Self::with_tables(automaton, notifier, input_streams, ids, tables)
+    }
+
+    /// Creates a new `FJSSingleHyperPatternMatching` using the given skip value tables.
+    fn with_tables(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<String>>,
+        ids: Vec<usize>,
+        tables: SkipValueTables<'a>,
+    ) -> Self {
+        let mut automata_runner = PatternMatchingAutomataRunner::new(automaton);
+        let start_indices = vec![0; automaton.dimensions];
+        let waiting_queue = StartPosition { start_indices }
+            .immediate_successors()
+            .map(Reverse)
+            .collect();
+        automata_runner.insert_from_initial_states(input_streams.clone(), ids.clone());
+
+        let skipped_positions = (0..automaton.dimensions)
+            .map(|_| HashSet::new())
+            .collect_vec();
+
+        Self {
+            automata_runner,
+            notifier,
+            input_streams,
+            ids,
+            waiting_queue,
+            skipped_positions,
+            quick_search_skip_value: tables.quick_search_skip_values,
+            kmp_skip_value: tables.kmp_skip_values,
+            matches_found: 0,
+        }
+    }
+}
+
 impl<Notifier: ResultNotifier> FJSSingleHyperPatternMatching<'_, Notifier> {
     /// Check if a position is valid (not skipped and within range)
     fn is_valid_position(&self, position: &StartPosition) -> bool {
@@ -183,31 +261,28 @@ impl<Notifier: ResultNotifier> FJSSingleHyperPatternMatching<'_, Notifier> {
                 .quick_search_skip_value
                 .shortest_accepted_word_length_map[var];
 
-            if shortest_matching_length > 0 {
+            // We need at least one letter of the shortest word to decide the skip
+            if let Some(shortest_matching_length) = shortest_matching_length.filter(|&l| l > 0) {
                 let stream = self.get_input_stream(var);
                 let slice_shortest_end_idx =
                     start_index + shortest_matching_length - 1 - stream.start;
                 let slice_next_idx = start_index + shortest_matching_length - stream.start;
 
-                if slice_next_idx < stream.len() {
-                    let readable_data = stream.readable_slice();
-
-                    if slice_next_idx < readable_data.len() {
-                        let last_accepted_words =
-                            &self.quick_search_skip_value.last_accepted_word[var];
-
-                        if !last_accepted_words.contains(&readable_data[slice_shortest_end_idx]) {
-                            let skipped_width = self
-                                .quick_search_skip_value
-                                .skip_value(&readable_data[slice_next_idx], var);
+                if let (Some(shortest_end), Some(next)) = (
+                    stream.readable_get(slice_shortest_end_idx),
+                    stream.readable_get(slice_next_idx),
+                ) {
+                    let last_accepted_words = &self.quick_search_skip_value.last_accepted_word[var];
 
-                            for i in 0..skipped_width {
-                                positions_to_skip.push((var, start_index + i));
-                            }
+                    if !last_accepted_words.contains(&shortest_end) {
+                        let skipped_width = self.quick_search_skip_value.skip_value(&next, var);
 
-                            should_skip = true;
-                            break;
+                        for i in 0..skipped_width {
+                            positions_to_skip.push((var, start_index + i));
                         }
+
+                        should_skip = true;
+                        break;
                     }
                 }
             }
@@ -276,8 +351,9 @@ impl<Notifier: ResultNotifier> FJSSingleHyperPatternMatching<'_, Notifier> {
 mod tests {
     use super::*;
     use crate::{
-        automata_runner::AppendOnlySequence, result_notifier::SharedBufferResultNotifier,
-        shared_buffer::SharedBuffer,
+        automata_runner::AppendOnlySequence, hyper_pattern_matching::HyperPatternMatchingAdapter,
+        result_notifier::SharedBufferResultNotifier, shared_buffer::SharedBuffer,
+        tests::utils::create_small_automaton,
     };
     use typed_arena::Arena;
 
@@ -356,4 +432,103 @@ mod tests {
         }
         assert!(result_sink.pop().is_none());
     }
+
+    #[test]
+    fn test_adapter_shares_skip_values() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let result_buffer = SharedBuffer::new();
+        let adapter = HyperPatternMatchingAdapter::<
+            FJSSingleHyperPatternMatching<SharedBufferResultNotifier>,
+            SharedBufferResultNotifier,
+        >::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            2,
+        );
+
+        // The tables are computed once for the four id-assignments
+        let single_matchings = adapter.single_matchings();
+        assert_eq!(single_matchings.len(), 4);
+        for single_matching in &single_matchings[1..] {
+            assert!(Rc::ptr_eq(
+                &single_matchings[0].kmp_skip_value,
+                &single_matching.kmp_skip_value
+            ));
+            assert!(Rc::ptr_eq(
+                &single_matchings[0].quick_search_skip_value,
+                &single_matching.quick_search_skip_value
+            ));
+        }
+    }
+
+    #[test]
+    fn test_with_alphabet() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+
+        // Accepts "x" or "ab" on the variable 0 followed by "d" on the variable 1, but "x" never
+        // appears in the input
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "x".to_string(), 0, s2);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "d".to_string(), 1, sf);
+
+        let alphabet: HashSet<String> =
+            ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+
+        // Feed the whole input on the variable 0 first so that the skip values are used
+        let run = |alphabet: Option<&HashSet<String>>| {
+            let mut sequences = [AppendOnlySequence::new(), AppendOnlySequence::new()];
+            let result_buffer = SharedBuffer::new();
+            let mut matcher = FJSSingleHyperPatternMatching::with_alphabet(
+                &automaton,
+                SharedBufferResultNotifier::new(result_buffer.make_source()),
+                sequences.iter().map(|s| s.readable_view()).collect(),
+                vec![0, 1],
+                alphabet,
+            );
+            for action in ["c", "c", "c", "c", "a", "b", "c", "c", "c"] {
+                sequences[0].append(action.to_string());
+                matcher.consume_input();
+            }
+            sequences[0].close();
+            for action in ["c", "d"] {
+                sequences[1].append(action.to_string());
+                matcher.consume_input();
+            }
+            sequences[1].close();
+            matcher.consume_input();
+
+            let mut result_sink = result_buffer.make_sink();
+            let mut results = Vec::new();
+            while let Some(result) = result_sink.pop() {
+                results.push(result.intervals);
+            }
+            results.sort();
+            results.dedup();
+            (results, matcher.quick_search_skip_value.skip_value("c", 0))
+        };
+
+        let (results_without, skip_without) = run(None);
+        let (results_with, skip_with) = run(Some(&alphabet));
+        assert_eq!(
+            results_without,
+            vec![vec![
+                MatchingInterval::new(4, 5),
+                MatchingInterval::new(1, 1)
+            ]]
+        );
+        assert_eq!(results_with, results_without);
+        // "c" is not in any accepted word, so it is skipped over the longer shortest word
+        assert_eq!(skip_without, 2);
+        assert_eq!(skip_with, 3);
+    }
 }
@@ -1,34 +1,62 @@
-use crate::automata::{NFAHState, NFAHTransition, NFAH};
+use crate::automata::{Automata, State, Transition, VariableLabel, NFAH};
 use crate::automata_runner::{AppendOnlySequence, NFAHConfiguration, NFAHRunner, ReadableView};
 use crate::result_notifier::ResultNotifier;
 use crate::single_hyper_pattern_matching::SingleHyperPatternMatching;
+use crate::skip_value_cache::SkipValueCache;
+use crate::symbol_matcher::{ExactSymbolMatcher, SharedSymbolMatcher, SymbolMatcher};
 use itertools::Itertools;
+use log::{log_enabled, Level};
 use std::cell::Ref;
 use std::collections::hash_set::Iter;
 use std::collections::HashSet;
 use std::hash::Hash;
-use std::marker::PhantomData;
+use std::rc::Rc;
 
 // Trait of pattern matching algorithms
 pub trait HyperPatternMatching {
     // Feed a string-valued action to the given track
     fn feed(&mut self, action: &str, track: usize);
 
+    // Feed the actions in `line` separated by `delimiter` to the given track in order, e.g., `a b c`
+    // with the delimiter ' '. The empty actions, e.g., between consecutive delimiters, are skipped.
+    fn feed_line(&mut self, line: &str, track: usize, delimiter: char) {
+        for action in line.split(delimiter).filter(|action| !action.is_empty()) {
+            self.feed(action, track);
+        }
+    }
+
     fn dimensions(&self) -> usize;
 
+    // Process the input fed so far and notify the completed matches without closing the tracks,
+    // flushing the results held back by the notifier, e.g., by BufferedResultNotifier
+    fn flush(&mut self);
+
     fn consume_remaining(&mut self);
 
     fn set_eof(&mut self, track: usize);
+
+    // The number of matches notified so far, e.g., for early-stop policies. The matchers not
+    // counting the matches return 0.
+    fn matches_found(&self) -> usize {
+        0
+    }
 }
 
-pub struct PatternMatchingAutomataRunner<'a> {
+pub struct PatternMatchingAutomataRunner<'a, L = (String, usize)> {
     /// The current set of configurations of type `PatternMatchingAutomataConfiguration`.
-    automaton: &'a NFAH<'a>,
+    automaton: &'a Automata<'a, L>,
     /// Each configuration is unique in the set (thanks to `Hash`/`Eq`).
-    pub current_configurations: HashSet<PatternMatchingAutomataConfiguration<'a>>,
+    pub current_configurations: HashSet<PatternMatchingAutomataConfiguration<'a, L>>,
+    /// The maximum number of actions a configuration may consume in each dimension, if any.
+    max_window: Option<usize>,
+    /// The matcher of the input symbols and the letters
+    symbol_matcher: SharedSymbolMatcher,
+    /// The lengths of the input sequences of each variable at the beginning of the current step,
+    /// if any
+    input_snapshot: Option<Vec<usize>>,
 }
 
-impl<'a> PatternMatchingAutomataRunner<'a> {
+impl<'a, L: VariableLabel + Eq + Hash + Clone> PatternMatchingAutomataRunner<'a, L> {
     /// Constructs a new `PatternMatchingAutomataRunner` by inserting configurations for
     /// each initial state of the given `automaton`.
     ///
@@ -39,48 +67,106 @@ impl<'a> PatternMatchingAutomataRunner<'a> {
     /// # Returns
     ///
     /// A new `PatternMatchingAutomataRunner` with initial configurations set up.
-    pub fn new(automaton: &'a NFAH<'a>) -> Self {
+    pub fn new(automaton: &'a Automata<'a, L>) -> Self {
         let current_configurations = HashSet::new();
         Self {
             automaton,
             current_configurations,
+            max_window: None,
+            symbol_matcher: Rc::new(ExactSymbolMatcher),
+            input_snapshot: None,
         }
     }
 
+    /// Reads the input sequences of each variable against the lengths `input_snapshot` taken at the
+    /// beginning of the current step, e.g., by [`ReadableView::snapshot_len`].
+    ///
+    /// The elements appended after the snapshot are read in the next step, so all the
+    /// configurations agree on the available input within a step.
+    pub fn set_input_snapshot(&mut self, input_snapshot: Vec<usize>) {
+        self.input_snapshot = Some(input_snapshot);
+    }
+
+    /// Compares the input symbols and the letters by `symbol_matcher`.
+    pub fn set_symbol_matcher(&mut self, symbol_matcher: SharedSymbolMatcher) {
+        self.symbol_matcher = symbol_matcher;
+    }
+
+    /// Drops the configurations consuming more than `max_window` actions in a dimension.
+    ///
+    /// The matches longer than `max_window` in some dimension are no longer found, but the
+    /// number of the configurations kept for each matching trial is bounded.
+    pub fn set_max_window(&mut self, max_window: usize) {
+        self.max_window = Some(max_window);
+    }
+
     /// Returns the final configurations in the current set.
-    pub fn get_final_configurations(&self) -> Vec<&PatternMatchingAutomataConfiguration<'a>> {
+    pub fn get_final_configurations(&self) -> Vec<&PatternMatchingAutomataConfiguration<'a, L>> {
         self.current_configurations
             .iter()
             .filter(|c| c.is_final())
             .collect()
     }
 
+    /// Returns `true` if `configuration` is waiting for more input, read against the snapshot if
+    /// any (see [`PatternMatchingAutomataRunner::set_input_snapshot`]).
+    pub fn is_waiting(&self, configuration: &PatternMatchingAutomataConfiguration<'a, L>) -> bool {
+        match &self.input_snapshot {
+            Some(snapshot) => configuration.is_waiting_at(snapshot),
+            None => configuration.is_waiting(),
+        }
+    }
+
     /// Removes all configurations that are not in a waiting state.
     pub fn remove_non_waiting_configurations(&mut self) {
-        self.current_configurations.retain(|c| c.is_waiting());
+        match &self.input_snapshot {
+            Some(snapshot) => self
+                .current_configurations
+                .retain(|c| c.is_waiting_at(snapshot)),
+            None => self.current_configurations.retain(|c| c.is_waiting()),
+        }
     }
 }
 
-impl<'a> NFAHRunner<'a, PatternMatchingAutomataConfiguration<'a>>
-    for PatternMatchingAutomataRunner<'a>
+impl<'a, L: VariableLabel + Eq + Hash + Clone>
+    NFAHRunner<'a, PatternMatchingAutomataConfiguration<'a, L>>
+    for PatternMatchingAutomataRunner<'a, L>
 {
     /// Inserts a new configuration into the `HashSet`. Duplicate configurations
     /// (i.e., those that are `Eq`) will be automatically skipped.
-    fn insert(&mut self, configuration: PatternMatchingAutomataConfiguration<'a>) {
+    fn insert(&mut self, configuration: PatternMatchingAutomataConfiguration<'a, L>) {
         self.current_configurations.insert(configuration);
     }
 
-    fn extend(&mut self, configurations: Vec<PatternMatchingAutomataConfiguration<'a>>) {
+    fn extend(&mut self, configurations: Vec<PatternMatchingAutomataConfiguration<'a, L>>) {
         self.current_configurations.extend(configurations);
     }
 
+    fn keeps(&self, configuration: &PatternMatchingAutomataConfiguration<'a, L>) -> bool {
+        self.max_window.is_none_or(|max_window| {
+            configuration
+                .input_sequence
+                .iter()
+                .zip(&configuration.matching_begin)
+                .all(|(sequence, &begin)| sequence.start - begin <= max_window)
+        })
+    }
+
+    fn symbol_matcher(&self) -> &dyn SymbolMatcher {
+        self.symbol_matcher.as_ref()
+    }
+
+    fn input_snapshot(&self) -> Option<&[usize]> {
+        self.input_snapshot.as_deref()
+    }
+
     /// Returns the number of unique configurations in the `HashSet`.
     fn len(&self) -> usize {
         self.current_configurations.len()
     }
 
     /// Returns an iterator over the current configurations in the `HashSet`.
-    fn iter(&mut self) -> Iter<PatternMatchingAutomataConfiguration<'a>> {
+    fn iter(&mut self) -> Iter<PatternMatchingAutomataConfiguration<'a, L>> {
         self.current_configurations.iter()
     }
 
@@ -115,9 +201,9 @@ impl<'a> NFAHRunner<'a, PatternMatchingAutomataConfiguration<'a>>
 }
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
-pub struct PatternMatchingAutomataConfiguration<'a> {
+pub struct PatternMatchingAutomataConfiguration<'a, L = (String, usize)> {
     /// The current state of the automaton.
-    pub current_state: &'a NFAHState<'a>,
+    pub current_state: &'a State<'a, L>,
 
     /// A vector of readable views over the input(s) that the automaton consumes.
     /// Each `ReadableView<String>` tracks how far the automaton has read.
@@ -139,7 +225,7 @@ pub struct PatternMatchingAutomataConfiguration<'a> {
 //     }
 // }
 
-impl<'a> PatternMatchingAutomataConfiguration<'a> {
+impl<'a, L> PatternMatchingAutomataConfiguration<'a, L> {
     /// Creates a new `PatternMatchingAutomataConfiguration` from the given state and
     /// list of `ReadableView`s for each input dimension.
     ///
@@ -149,7 +235,7 @@ impl<'a> PatternMatchingAutomataConfiguration<'a> {
     /// * `input_sequence` - A vector of `ReadableView<String>` representing
     ///   the input stream for the automaton.
     pub fn new(
-        current_state: &'a NFAHState<'a>,
+        current_state: &'a State<'a, L>,
         input_sequence: Vec<ReadableView<String>>,
         ids: Vec<usize>,
     ) -> Self {
@@ -168,7 +254,7 @@ impl<'a> PatternMatchingAutomataConfiguration<'a> {
     }
 
     pub fn is_final(&self) -> bool {
-        self.current_state.is_final
+        self.current_state.is_final()
     }
 
     pub fn is_waiting(&self) -> bool {
@@ -176,18 +262,32 @@ impl<'a> PatternMatchingAutomataConfiguration<'a> {
             .iter()
             .any(|s| !s.is_closed() && s.is_empty())
     }
+
+    /// Returns `true` if the configuration is waiting for more input as of the lengths `snapshot`
+    /// of the input sequences, i.e., some sequence is exhausted at the snapshot and more elements
+    /// may come or have been appended after the snapshot.
+    pub fn is_waiting_at(&self, snapshot: &[usize]) -> bool {
+        self.input_sequence
+            .iter()
+            .zip(snapshot)
+            .any(|(s, &snapshot)| s.len_at(snapshot) == 0 && (!s.is_closed() || !s.is_empty()))
+    }
 }
 
-impl<'a> NFAHConfiguration<'a> for PatternMatchingAutomataConfiguration<'a> {
+impl<'a, L: VariableLabel + 'a> NFAHConfiguration<'a>
+    for PatternMatchingAutomataConfiguration<'a, L>
+{
+    type Label = L;
+
     fn dimensions(&self) -> usize {
         self.input_sequence.len()
     }
 
-    fn transitions(&self) -> Ref<Vec<&NFAHTransition<'a>>> {
+    fn transitions(&self) -> Ref<Vec<&'a Transition<'a, L>>> {
         self.current_state.transitions.borrow()
     }
 
-    fn duplicate(&self, current_state: &'a NFAHState<'a>) -> Self {
+    fn duplicate(&self, current_state: &'a State<'a, L>) -> Self {
         // Create new vectors with preallocated capacity
         let input_sequence_len = self.input_sequence.len();
         let matching_begin_len = self.matching_begin.len();
@@ -223,6 +323,12 @@ impl<'a> NFAHConfiguration<'a> for PatternMatchingAutomataConfiguration<'a> {
         }
     }
 
+    fn input_head_at(&self, i: usize, snapshot: usize) -> Option<String> {
+        self.input_sequence
+            .get(i)
+            .and_then(|s| s.readable_slice_at(snapshot).first().cloned())
+    }
+
     fn input_advance(&mut self, i: usize, count: usize) {
         if i < self.input_sequence.len() {
             self.input_sequence[i].advance_readable(count);
@@ -230,6 +336,58 @@ impl<'a> NFAHConfiguration<'a> for PatternMatchingAutomataConfiguration<'a> {
     }
 }
 
+/// Checks that `num_sequences` input sequences can be assigned to the variables of a
+/// `dimensions`-dimensional automaton.
+///
+/// The matchers try every assignment of the sequences to the variables, i.e., the
+/// `num_sequences ^ dimensions` id tuples, so a single sequence is assigned to all the variables.
+/// Panics if there is no assignment, i.e., there is no sequence or no variable, because then no
+/// match is reported without any error.
+pub fn check_dimensions(dimensions: usize, num_sequences: usize) {
+    if dimensions == 0 {
+        panic!("The automaton has no dimensions, so no sequence can be matched");
+    }
+    if num_sequences == 0 {
+        panic!(
+            "No input sequence for the {}-dimensional automaton; at least one is needed",
+            dimensions
+        );
+    }
+}
+
+/// Returns `true` if the trace records in the hot loops of the matchers should be built.
+///
+/// They are never built with the `no-trace` feature, so that the benchmarking builds do not pay
+/// for the formatting of the configurations even if the trace level is enabled.
+#[inline]
+pub fn hot_trace_enabled() -> bool {
+    cfg!(not(feature = "no-trace")) && log_enabled!(Level::Trace)
+}
+
+/// Makes the sequences of the tracks, where the track `t` shares the sequence of `sources[t]`.
+///
+/// Panics if a source track is not backed by its own input.
+pub fn shared_sequences(sources: &[usize]) -> Vec<AppendOnlySequence<String>> {
+    let mut sequences: Vec<Option<AppendOnlySequence<String>>> = sources
+        .iter()
+        .enumerate()
+        .map(|(track, &source)| (track == source).then(AppendOnlySequence::new))
+        .collect_vec();
+    for (track, &source) in sources.iter().enumerate() {
+        if track != source {
+            let shared = match sources.get(source) {
+                Some(&s) if s == source => sequences[source].as_ref().unwrap().share(),
+                _ => panic!(
+                    "The source {} of the track {} does not have its own input",
+                    source, track
+                ),
+            };
+            sequences[track] = Some(shared);
+        }
+    }
+    sequences.into_iter().map(Option::unwrap).collect_vec()
+}
+
 pub struct HyperPatternMatchingAdapter<'a, SingleMatching, Notifier>
 where
     SingleMatching: SingleHyperPatternMatching<'a, Notifier>,
@@ -238,7 +396,8 @@ where
     automaton: &'a NFAH<'a>,
     single_matchings: Vec<SingleMatching>,
     sequences: Vec<AppendOnlySequence<String>>,
-    _notifier: PhantomData<Notifier>,
+    /// A clone of the notifier of the single matchings to flush
+    notifier: Notifier,
 }
 
 impl<'a, SingleMatching, Notifier> HyperPatternMatchingAdapter<'a, SingleMatching, Notifier>
@@ -247,12 +406,27 @@ where
     Notifier: ResultNotifier + Clone,
 {
     pub fn new(automaton: &'a NFAH<'a>, notifier: Notifier, word_size: usize) -> Self {
-        let sequences = (0..word_size)
-            .map(|_| AppendOnlySequence::new())
-            .collect_vec();
+        Self::with_stream_sources(automaton, notifier, &(0..word_size).collect_vec())
+    }
+
+    /// Creates a new adapter where the track `t` is backed by the input of the track
+    /// `sources[t]`.
+    ///
+    /// A track with `sources[t] == t` has its own input. The other tracks share the sequence of
+    /// their source, so the actions fed to the source are seen by them without being fed again.
+    /// This is how self-hyperproperties over a single input are matched.
+    pub fn with_stream_sources(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        sources: &[usize],
+    ) -> Self {
+        let sequences = shared_sequences(sources);
+        check_dimensions(automaton.dimensions, sequences.len());
 
         let ranges = vec![0..sequences.len(); automaton.dimensions];
         let ids = ranges.into_iter().multi_cartesian_product().collect_vec();
+        // The tables of the automaton are computed once for all the id-assignments
+        let cache = SkipValueCache::new();
         let mut single_matchings = Vec::with_capacity(ids.len());
         for id_vec in ids.into_iter() {
             let mut input_streams = Vec::with_capacity(id_vec.len());
@@ -267,11 +441,12 @@ where
                     );
                 }
             }
-            single_matchings.push(SingleMatching::new(
+            single_matchings.push(SingleMatching::with_cache(
                 automaton,
                 notifier.clone(),
                 input_streams,
                 id_vec,
+                &cache,
             ));
         }
 
@@ -279,10 +454,35 @@ where
             automaton,
             single_matchings,
             sequences,
-            _notifier: PhantomData,
+            notifier,
         }
     }
 
+    /// Returns the matchings of the id-assignments.
+    #[cfg(test)]
+    pub fn single_matchings(&self) -> &[SingleMatching] {
+        &self.single_matchings
+    }
+
+    /// Drops the matching trials consuming more than `max_window` actions in a dimension.
+    ///
+    /// Returns `false` if the matching algorithm does not support it, where the setting is ignored.
+    pub fn set_max_window(&mut self, max_window: usize) -> bool {
+        self.single_matchings
+            .iter_mut()
+            .all(|single_matching| single_matching.set_max_window(max_window))
+    }
+
+    /// Compares the input symbols and the letters of the automaton by `symbol_matcher`.
+    ///
+    /// Returns `false` if the matching algorithm does not support it, where the letters are
+    /// compared exactly.
+    pub fn set_symbol_matcher(&mut self, symbol_matcher: SharedSymbolMatcher) -> bool {
+        self.single_matchings
+            .iter_mut()
+            .all(|single_matching| single_matching.set_symbol_matcher(symbol_matcher.clone()))
+    }
+
     pub fn consume(&mut self) {
         // Run the matchers
         for single_matching in self.single_matchings.iter_mut() {
@@ -306,6 +506,11 @@ where
         self.automaton.dimensions
     }
 
+    fn flush(&mut self) {
+        self.consume();
+        self.notifier.flush();
+    }
+
     fn consume_remaining(&mut self) {
         self.consume();
     }
@@ -314,12 +519,33 @@ where
         self.sequences[track].close();
         self.consume()
     }
+
+    fn matches_found(&self) -> usize {
+        self.single_matchings
+            .iter()
+            .map(|single_matching| single_matching.matches_found())
+            .sum()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::automata::{NFAHState, NFAHTransition};
     use crate::automata_runner::AppendOnlySequence;
+    use crate::fjs_hyper_pattern_matching::FJSHyperPatternMatching;
+    use crate::fjs_single_hyper_pattern_matching::FJSSingleHyperPatternMatching;
+    use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
+    use crate::naive_hyper_pattern_matching::NaiveHyperPatternMatching;
+    use crate::online_single_hyper_pattern_matching::OnlineSingleHyperPatternMatching;
+    use crate::reading_scheduler::ReadingScheduler;
+    use crate::result_notifier::{MatchingResult, SharedBufferResultNotifier};
+    use crate::shared_buffer::SharedBuffer;
+    use crate::single_hyper_pattern_matching::NaiveSingleHyperPatternMatching;
+    use crate::symbol_matcher::PrefixStrippingSymbolMatcher;
+    use crate::tests::utils::create_small_automaton;
+    use std::collections::BTreeSet;
+    use std::io::Cursor;
     use typed_arena::Arena;
 
     #[test]
@@ -511,4 +737,402 @@ mod tests {
             assert!(successors.contains(&config));
         }
     }
+
+    /// Runs the naive matching reading `inputs`, where the track `t` is backed by `sources[t]`.
+    fn run_with_sources<'a>(
+        automaton: &'a NFAH<'a>,
+        inputs: &[&str],
+        sources: &[usize],
+    ) -> BTreeSet<MatchingResult> {
+        let reader = MultiStreamReader::new(
+            inputs
+                .iter()
+                .map(|input| {
+                    Box::new(Cursor::new(input.as_bytes().to_vec())) as Box<dyn StreamSource>
+                })
+                .collect(),
+        );
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let matching = HyperPatternMatchingAdapter::<
+            NaiveSingleHyperPatternMatching<SharedBufferResultNotifier>,
+            SharedBufferResultNotifier,
+        >::with_stream_sources(automaton, notifier, sources);
+        ReadingScheduler::new(matching, reader).run();
+
+        let mut result_sink = result_buffer.make_sink();
+        let mut results = BTreeSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        results
+    }
+
+    #[test]
+    fn test_automata_runner_input_snapshot() {
+        // "a" on track 0 followed by "b" on track 0
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automata = NFAH::new(&state_arena, &transition_arena, 1);
+        let s0 = automata.add_state(true, false);
+        let s1 = automata.add_state(false, false);
+        let s2 = automata.add_state(false, true);
+        automata.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automata.add_nfah_transition(s1, "b".to_string(), 0, s2);
+
+        let mut sequence = AppendOnlySequence::new();
+        sequence.append("a".to_string());
+        let view = sequence.readable_view();
+        let snapshot = view.snapshot_len();
+        // "b" is appended after the snapshot of the step
+        sequence.append("b".to_string());
+
+        let mut runner = PatternMatchingAutomataRunner::new(&automata);
+        runner.insert_from_initial_states(vec![view.clone()], vec![0]);
+        runner.set_input_snapshot(vec![snapshot]);
+        runner.consume();
+        assert!(runner.get_final_configurations().is_empty());
+        // The trial waiting for "b" is kept for the next step
+        runner.remove_non_waiting_configurations();
+        assert_eq!(runner.current_configurations.len(), 1);
+
+        // The next step reads "b"
+        runner.set_input_snapshot(vec![view.snapshot_len()]);
+        runner.consume();
+        assert_eq!(runner.get_final_configurations().len(), 1);
+    }
+
+    #[test]
+    fn test_shared_stream() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+        let input = "a\nb\na\nc\nd\nd\n";
+
+        // One input backing two tracks is the same as duplicating the input
+        let shared = run_with_sources(&automaton, &[input], &[0, 0]);
+        let duplicated = run_with_sources(&automaton, &[input, input], &[0, 1]);
+        assert!(!shared.is_empty());
+        assert_eq!(shared, duplicated);
+    }
+
+    /// Runs `matching` reading `inputs` and returns its `matches_found` and the number of the
+    /// results notified to `result_buffer`.
+    fn count_matches<Matching: HyperPatternMatching>(
+        matching: Matching,
+        inputs: &[&str],
+        result_buffer: &SharedBuffer<MatchingResult>,
+    ) -> (usize, usize) {
+        let reader = MultiStreamReader::new(
+            inputs
+                .iter()
+                .map(|input| {
+                    Box::new(Cursor::new(input.as_bytes().to_vec())) as Box<dyn StreamSource>
+                })
+                .collect(),
+        );
+        let mut scheduler = ReadingScheduler::new(matching, reader);
+        scheduler.run();
+
+        let mut result_sink = result_buffer.make_sink();
+        let mut notifications = 0;
+        while result_sink.pop().is_some() {
+            notifications += 1;
+        }
+        (scheduler.matching().matches_found(), notifications)
+    }
+
+    #[test]
+    fn test_matches_found() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+        let inputs = ["a\nb\na\nc\nd\nd\n", "b\na\nc\nd\na\n"];
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let (found, notified) = count_matches(
+            HyperPatternMatchingAdapter::<
+                NaiveSingleHyperPatternMatching<SharedBufferResultNotifier>,
+                SharedBufferResultNotifier,
+            >::new(&automaton, notifier, 2),
+            &inputs,
+            &result_buffer,
+        );
+        assert!(notified > 0);
+        assert_eq!(found, notified);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let (found, notified) = count_matches(
+            HyperPatternMatchingAdapter::<
+                OnlineSingleHyperPatternMatching<SharedBufferResultNotifier>,
+                SharedBufferResultNotifier,
+            >::new(&automaton, notifier, 2),
+            &inputs,
+            &result_buffer,
+        );
+        assert!(notified > 0);
+        assert_eq!(found, notified);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let (found, notified) = count_matches(
+            HyperPatternMatchingAdapter::<
+                FJSSingleHyperPatternMatching<SharedBufferResultNotifier>,
+                SharedBufferResultNotifier,
+            >::new(&automaton, notifier, 2),
+            &inputs,
+            &result_buffer,
+        );
+        assert!(notified > 0);
+        assert_eq!(found, notified);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let sequences = vec![AppendOnlySequence::new(), AppendOnlySequence::new()];
+        let (found, notified) = count_matches(
+            NaiveHyperPatternMatching::new(&automaton, notifier, sequences, false),
+            &inputs,
+            &result_buffer,
+        );
+        assert!(notified > 0);
+        assert_eq!(found, notified);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let sequences = vec![AppendOnlySequence::new(), AppendOnlySequence::new()];
+        let cache = SkipValueCache::new();
+        let (found, notified) = count_matches(
+            FJSHyperPatternMatching::new(&automaton, notifier, sequences, false, &cache),
+            &inputs,
+            &result_buffer,
+        );
+        assert!(notified > 0);
+        assert_eq!(found, notified);
+    }
+
+    #[test]
+    fn test_shared_sequences() {
+        let mut sequences = shared_sequences(&[0, 1, 0]);
+        sequences[0].append("a".to_string());
+        sequences[1].append("b".to_string());
+        assert_eq!(sequences[2].len(), 1);
+        assert_eq!(sequences[2].get(0), Some("a".to_string()));
+        sequences[0].close();
+        assert!(sequences[2].is_closed());
+        assert!(!sequences[1].is_closed());
+    }
+
+    #[test]
+    fn test_feed_line() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let run = |feed: &dyn Fn(&mut dyn HyperPatternMatching)| {
+            let result_buffer = SharedBuffer::new();
+            let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+            let mut result_sink = result_buffer.make_sink();
+            let mut matching = NaiveHyperPatternMatching::new(
+                &automaton,
+                notifier,
+                vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+                false,
+            );
+            feed(&mut matching);
+            matching.set_eof(0);
+            matching.set_eof(1);
+            matching.consume_remaining();
+
+            let mut results = BTreeSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            results
+        };
+
+        let expected = run(&|matching| {
+            for (track, input) in [["a", "a", "c"], ["a", "d", "d"]].iter().enumerate() {
+                for action in input {
+                    matching.feed(action, track);
+                }
+            }
+        });
+        assert!(!expected.is_empty());
+        // The empty actions around the delimiters are skipped
+        assert_eq!(
+            run(&|matching| {
+                matching.feed_line("a,a,c", 0, ',');
+                matching.feed_line(",a,,d,d,", 1, ',');
+            }),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_max_window() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let run = |max_window: Option<usize>| {
+            let result_buffer = SharedBuffer::new();
+            let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+            let mut result_sink = result_buffer.make_sink();
+            let mut matching = HyperPatternMatchingAdapter::<
+                NaiveSingleHyperPatternMatching<SharedBufferResultNotifier>,
+                SharedBufferResultNotifier,
+            >::new(&automaton, notifier, 2);
+            if let Some(max_window) = max_window {
+                assert!(matching.set_max_window(max_window));
+            }
+            for (track, input) in [["a", "a", "c"], ["a", "d", "d"]].iter().enumerate() {
+                for action in input {
+                    matching.feed(action, track);
+                }
+                matching.set_eof(track);
+            }
+            matching.consume_remaining();
+
+            let mut results = BTreeSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            results
+        };
+
+        let all = run(None);
+        let windowed = run(Some(1));
+        assert!(!windowed.is_empty());
+        // Only the matches within the window remain
+        let expected: BTreeSet<_> = all
+            .into_iter()
+            .filter(|r| r.intervals.iter().all(|i| i.end + 1 - i.start <= 1))
+            .collect();
+        assert_eq!(windowed, expected);
+    }
+
+    /// Returns a 3-dimensional automaton accepting "a" on each variable in order
+    fn create_three_dimensional_automaton<'a>(
+        state_arena: &'a Arena<NFAHState<'a>>,
+        transition_arena: &'a Arena<NFAHTransition<'a>>,
+    ) -> NFAH<'a> {
+        let mut automaton = NFAH::new(state_arena, transition_arena, 3);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "a".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "a".to_string(), 2, s3);
+        automaton
+    }
+
+    #[test]
+    fn test_symbol_matcher() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        fn run<'a, SingleMatching>(
+            automaton: &'a NFAH<'a>,
+            prefix: &str,
+            symbol_matcher: Option<SharedSymbolMatcher>,
+        ) -> BTreeSet<MatchingResult>
+        where
+            SingleMatching: SingleHyperPatternMatching<'a, SharedBufferResultNotifier>,
+        {
+            let result_buffer = SharedBuffer::new();
+            let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+            let mut result_sink = result_buffer.make_sink();
+            let mut matching = HyperPatternMatchingAdapter::<
+                SingleMatching,
+                SharedBufferResultNotifier,
+            >::new(automaton, notifier, 2);
+            if let Some(symbol_matcher) = symbol_matcher {
+                assert!(matching.set_symbol_matcher(symbol_matcher));
+            }
+            for (track, input) in [["a", "a", "c"], ["a", "d", "d"]].iter().enumerate() {
+                for action in input {
+                    matching.feed(&format!("{}{}", prefix, action), track);
+                }
+                matching.set_eof(track);
+            }
+            matching.consume_remaining();
+
+            let mut results = BTreeSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            results
+        }
+
+        let prefix_stripping =
+            || Some(Rc::new(PrefixStrippingSymbolMatcher::new("evt:")) as SharedSymbolMatcher);
+        let expected = run::<NaiveSingleHyperPatternMatching<_>>(&automaton, "", None);
+        assert!(!expected.is_empty());
+        // "evt:a" matches "a" only with the prefix-stripping matcher
+        assert!(run::<NaiveSingleHyperPatternMatching<_>>(&automaton, "evt:", None).is_empty());
+        assert_eq!(
+            run::<NaiveSingleHyperPatternMatching<_>>(&automaton, "evt:", prefix_stripping()),
+            expected
+        );
+        assert_eq!(
+            run::<OnlineSingleHyperPatternMatching<_>>(&automaton, "evt:", prefix_stripping()),
+            expected
+        );
+
+        // The skip values of FJS are computed from the exact letters
+        let notifier = SharedBufferResultNotifier::new(SharedBuffer::new().make_source());
+        let mut matching = HyperPatternMatchingAdapter::<
+            FJSSingleHyperPatternMatching<SharedBufferResultNotifier>,
+            SharedBufferResultNotifier,
+        >::new(&automaton, notifier, 2);
+        assert!(!matching.set_symbol_matcher(prefix_stripping().unwrap()));
+    }
+
+    #[test]
+    fn test_check_dimensions_single_sequence() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_three_dimensional_automaton(&state_arena, &transition_arena);
+
+        // A single sequence is assigned to all the variables
+        let results = run_with_sources(&automaton, &["a\n"], &[0]);
+        let ids = results.iter().map(|r| r.ids.clone()).collect_vec();
+        assert_eq!(ids, vec![vec![0, 0, 0]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No input sequence for the 3-dimensional automaton")]
+    fn test_check_dimensions_no_sequence() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_three_dimensional_automaton(&state_arena, &transition_arena);
+        run_with_sources(&automaton, &[], &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No input sequence for the 3-dimensional automaton")]
+    fn test_check_dimensions_no_sequence_naive() {
+        use crate::naive_hyper_pattern_matching::NaiveHyperPatternMatching;
+
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_three_dimensional_automaton(&state_arena, &transition_arena);
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        NaiveHyperPatternMatching::new(&automaton, notifier, vec![], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "The automaton has no dimensions")]
+    fn test_check_dimensions_no_dimension() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 0);
+        automaton.add_state(true, true);
+        run_with_sources(&automaton, &["a\n"], &[0]);
+    }
 }
@@ -61,7 +61,7 @@ impl<'a> KMPSkipValues<'a> {
                                 if left_trans.label == right_trans.label {
                                     let next_right_state = right_trans.next_state;
                                     // Since for any state, there is at least one reachable finial state, we stop when we arrive at a final state
-                                    if next_left_state.is_final || next_right_state.is_final {
+                                    if next_left_state.is_final() || next_right_state.is_final() {
                                         found_accepting = true;
                                         skip_value.insert(loc, i);
                                         break;
@@ -98,6 +98,13 @@ impl<'a> KMPSkipValues<'a> {
         debug!("skip_values: {:?}", skip_values);
         KMPSkipValues { skip_values }
     }
+
+    /// Returns the skip value of `state` for the variable `var`.
+    ///
+    /// Returns `None` if `var` is out of bounds or `state` is not a state of the automaton.
+    pub fn skip_value_for(&self, var: usize, state: &NFAHState<'a>) -> Option<usize> {
+        self.skip_values.get(var)?.get(state).copied()
+    }
 }
 
 #[cfg(test)]
@@ -136,5 +143,26 @@ mod tests {
         assert_eq!(1, kmp_skip_values.skip_values[1][s2]);
         assert_eq!(1, kmp_skip_values.skip_values[1][s3]);
         assert_eq!(1, kmp_skip_values.skip_values[1][sf]);
+
+        assert_eq!(Some(2), kmp_skip_values.skip_value_for(0, s3));
+        assert_eq!(Some(0), kmp_skip_values.skip_value_for(1, s1));
+        assert_eq!(None, kmp_skip_values.skip_value_for(2, s0));
+    }
+
+    #[test]
+    fn test_skip_value_for_unknown_state() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 1);
+        let s0 = automaton.add_state(true, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, sf);
+        let kmp_skip_values = KMPSkipValues::new(&automaton);
+
+        let other_state_arena = Arena::new();
+        let other_trans_arena = Arena::new();
+        let mut other = NFAH::new(&other_state_arena, &other_trans_arena, 1);
+        let unknown = other.add_state(false, false);
+        assert_eq!(None, kmp_skip_values.skip_value_for(0, unknown));
     }
 }
@@ -1,22 +1,52 @@
+use alphabet_check::AlphabetCheckingMatching;
 use clap::{ArgAction, Parser, ValueEnum};
 use env_logger::Env;
 use filtered_single_hyper_pattern_matching::NaiveFilteredSingleHyperPatternMatching;
 use hyper_pattern_matching::HyperPatternMatchingAdapter;
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::ExitCode;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use typed_arena::Arena;
 
-use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
+use crate::automata::{
+    Automata, ClassNFAH, LabelMatch, NFAHState, NFAHTransition, State, Transition, NFAH,
+};
+use crate::automata_runner::AppendOnlySequence;
+use crate::capture::{read_capture, replay, Capture, CapturingMatching};
+use crate::differential_testing::{automaton_alphabet, compare_modes, generate_random_inputs};
+use crate::error::HyppauError;
+use crate::multi_stream_reader::{
+    ConcatenatedReader, FollowingReader, JsonFieldReader, MultiStreamReader, StreamSource,
+};
+use crate::naive_hyper_pattern_matching::NaiveHyperPatternMatching;
 use crate::result_notifier::{
-    FileResultNotifier, MatchingInterval, ResultNotifier, StdoutResultNotifier,
+    json_line_with_spans, BufferedResultNotifier, CountingResultNotifier, FileResultNotifier,
+    GroupedJsonResultNotifier, JsonResultNotifier, LengthFilter, LengthFilteredResultNotifier,
+    MatchingInterval, MatchingResult, Report, ResultNotifier, Sampling, SamplingResultNotifier,
+    SortedResultNotifier, SpanCallbackResultNotifier, StdoutResultNotifier, TallyResultNotifier,
+    TimestampedResultNotifier,
+};
+use crate::serialization::{
+    automaton_to_dot, automaton_to_graphml, deserialize_class_nfah_with_dimensions,
+    deserialize_nfah_with_dimensions, load_nfah_bincode, save_nfah_bincode, serialize_nfa,
 };
-use crate::serialization::{automaton_to_dot, deserialize_nfa};
+#[cfg(feature = "server")]
+use crate::sse_result_notifier::SseResultNotifier;
+use crate::symbol_matcher::{PrefixStrippingSymbolMatcher, SharedSymbolMatcher};
 
 #[derive(Clone)]
 enum ResultNotifierType {
     Stdout(StdoutResultNotifier),
     File(FileResultNotifier),
+    Json(JsonResultNotifier),
+    #[cfg(feature = "server")]
+    Sse(SseResultNotifier),
 }
 
 impl ResultNotifier for ResultNotifierType {
@@ -24,11 +54,58 @@ impl ResultNotifier for ResultNotifierType {
         match self {
             ResultNotifierType::Stdout(notifier) => notifier.notify(intervals, ids),
             ResultNotifierType::File(notifier) => notifier.notify(intervals, ids),
+            ResultNotifierType::Json(notifier) => notifier.notify(intervals, ids),
+            #[cfg(feature = "server")]
+            ResultNotifierType::Sse(notifier) => notifier.notify(intervals, ids),
+        }
+    }
+
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        match self {
+            ResultNotifierType::Stdout(notifier) => notifier.notify_all(results),
+            ResultNotifierType::File(notifier) => notifier.notify_all(results),
+            ResultNotifierType::Json(notifier) => notifier.notify_all(results),
+            #[cfg(feature = "server")]
+            ResultNotifierType::Sse(notifier) => notifier.notify_all(results),
+        }
+    }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        match self {
+            ResultNotifierType::Stdout(notifier) => {
+                notifier.notify_with_timestamp(intervals, ids, timestamp)
+            }
+            ResultNotifierType::File(notifier) => {
+                notifier.notify_with_timestamp(intervals, ids, timestamp)
+            }
+            ResultNotifierType::Json(notifier) => {
+                notifier.notify_with_timestamp(intervals, ids, timestamp)
+            }
+            #[cfg(feature = "server")]
+            ResultNotifierType::Sse(notifier) => {
+                notifier.notify_with_timestamp(intervals, ids, timestamp)
+            }
+        }
+    }
+    fn flush(&mut self) {
+        match self {
+            ResultNotifierType::Stdout(notifier) => notifier.flush(),
+            ResultNotifierType::File(notifier) => notifier.flush(),
+            ResultNotifierType::Json(notifier) => notifier.flush(),
+            #[cfg(feature = "server")]
+            ResultNotifierType::Sse(notifier) => notifier.flush(),
         }
     }
 }
 
+use crate::hyper_pattern_matching::HyperPatternMatching;
 use crate::reading_scheduler::ReadingScheduler;
+use crate::single_hyper_pattern_matching::SingleHyperPatternMatching;
 
 #[derive(Clone, Debug, ValueEnum)]
 enum Mode {
@@ -40,18 +117,107 @@ enum Mode {
     FjsFiltered,
 }
 
+/// How to handle the input actions not in the automaton
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CheckAlphabet {
+    /// Warn about each unknown action once per input
+    Warn,
+    /// Stop matching at the first unknown action with an error
+    Error,
+}
+
+/// Which intervals of a match must be within the bounds of --min-len and --max-len
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum LenIn {
+    /// The intervals of all the inputs
+    All,
+    /// The interval of at least one input
+    Any,
+}
+
+/// The file format of the automata
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum AutomatonFormat {
+    /// The JSON format described in the README
+    Json,
+    /// The compact binary format of bincode, written by --save-bincode
+    Bincode,
+}
+
 /// A prototype tool for Hyper Pattern Matching
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Read an automaton written in JSON format from FILE.
+    /// Read an automaton written in JSON format (or --automaton-format) from FILE.
     #[arg(short = 'f', long = "automaton", value_name = "FILE")]
     automaton: String,
 
-    /// Read the log from FILE (can be used multiple times).
+    /// The format of the automaton files: json or bincode (default: json)
+    #[arg(long = "automaton-format", value_enum, value_name = "FORMAT", default_value_t = AutomatonFormat::Json)]
+    automaton_format: AutomatonFormat,
+
+    /// Write the automaton in the bincode format to FILE instead of matching, e.g., to load a large
+    /// automaton faster with --automaton-format bincode.
+    #[arg(long = "save-bincode", value_name = "FILE")]
+    save_bincode: Option<String>,
+
+    /// Read the log from FILE (can be used multiple times). A glob pattern reads the matching
+    /// files as one log, e.g., rotated logs.
     #[arg(short = 'i', long = "input", value_name = "FILE")]
     input: Vec<String>,
 
+    /// Read the logs of all the tracks from FILE, where each line is TRACK:ACTION, e.g., `1:login`.
+    /// The tracks are numbered from 0 to the dimensions of the automaton minus 1.
+    #[arg(long = "interleaved", value_name = "FILE")]
+    interleaved: Option<String>,
+
+    /// Keep reading the input files as they grow, like `tail -f`, e.g., for live monitoring.
+    /// The matches found so far are output whenever all the files are at their ends. The matching
+    /// runs until Ctrl-C, which matches the rest of the files before exiting.
+    #[arg(long = "follow")]
+    follow: bool,
+
+    /// Read each line of the logs as a JSON event and use its field at the JSON pointer POINTER as
+    /// the action, e.g., `/evt` for `{"evt":"login","user":1}`.
+    #[arg(long = "json-field", value_name = "POINTER")]
+    json_field: Option<String>,
+
+    /// Read each line of the logs as the actions separated by CHAR, e.g., `,` for `a,b,c`, instead
+    /// of as a single action. The empty actions, e.g., between consecutive delimiters, are skipped.
+    #[arg(long = "delimiter", value_name = "CHAR")]
+    delimiter: Option<char>,
+
+    /// Write the actions and the ends of the tracks fed to the matching to FILE, e.g., to reproduce
+    /// a run by --replay.
+    #[arg(long = "capture", value_name = "FILE")]
+    capture: Option<String>,
+
+    /// Feed the actions and the ends of the tracks captured by --capture in FILE instead of reading
+    /// the input files. The --share-stream options must be the same as in the captured run.
+    #[arg(long = "replay", value_name = "FILE")]
+    replay: Option<String>,
+
+    /// Use N as the dimensions of the automaton instead of the one in the file, e.g., for a slightly
+    /// wrong automaton file. The transitions must read variables less than N.
+    #[arg(long = "dimensions", value_name = "N")]
+    dimensions: Option<usize>,
+
+    /// Read the classes of letters in the automaton, e.g., `{"not": ["a", "b"]}` for any letter
+    /// except a and b, or `"*"` for any letter (`"\\*"` for the letter *), and match them in naive
+    /// mode. Only the input files, the output, and the delimiter of the actions can be given with
+    /// it.
+    #[arg(
+        long = "letter-classes",
+        conflicts_with_all = [
+            "automaton_format", "save_bincode", "interleaved", "follow", "json_field", "capture",
+            "replay", "graphviz", "graphml", "product", "var_names", "byte_spans", "share_stream",
+            "max_window", "check_alphabet", "strip_prefix", "sample_every", "sample_fraction",
+            "timestamps", "min_len", "max_len", "len_in", "sorted", "tally", "grouped_json",
+            "dry_run", "count", "explain", "generate_random",
+        ]
+    )]
+    letter_classes: bool,
+
     /// Quiet mode. Causes any results to be suppressed.
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
@@ -60,10 +226,48 @@ struct Args {
     #[arg(short = 'g', long = "graphviz")]
     graphviz: bool,
 
+    /// Print the automaton in GraphML format, e.g., for Gephi or yEd.
+    #[arg(long = "graphml")]
+    graphml: bool,
+
+    /// Print the product of the automaton and the one in FILE (in JSON, or DOT with --graphviz).
+    #[arg(long = "product", value_name = "FILE")]
+    product: Option<String>,
+
     /// Write the output to FILE instead of stdout.
     #[arg(short = 'o', long = "output", value_name = "FILE")]
     output: Option<String>,
 
+    /// Output each match as a line of JSON labeling the intervals with the variables.
+    #[arg(long = "json")]
+    json: bool,
+
+    /// Report both ends of the interval of each match, or only its begin or end positions, e.g.,
+    /// the trigger points for alerting.
+    #[arg(long = "report", value_enum, value_name = "POSITIONS", default_value_t = Report::Interval)]
+    report: Report,
+
+    /// Name the variables of the automaton in the order of the tracks, e.g., x,y, to label the
+    /// intervals in the output of --json.
+    #[arg(long = "var-names", value_name = "NAMES", value_delimiter = ',')]
+    var_names: Option<Vec<String>>,
+
+    /// Add the byte span `[begin, end]` in the input file to each interval in the output of
+    /// --json, where `end` is exclusive, e.g., to highlight the matched regions in an editor.
+    #[arg(long = "byte-spans", requires = "json")]
+    byte_spans: bool,
+
+    /// Stream each match as a Server-Sent Event of JSON to the HTTP clients connected to the
+    /// port, e.g., for a live dashboard with --follow.
+    #[cfg(feature = "server")]
+    #[arg(long = "sse-port", value_name = "PORT")]
+    sse_port: Option<u16>,
+
+    /// The address to serve --sse-port on. Use 0.0.0.0 to accept the clients from the network.
+    #[cfg(feature = "server")]
+    #[arg(long = "sse-host", value_name = "ADDR", default_value = "127.0.0.1")]
+    sse_host: String,
+
     /// Verbose mode. Use -v for debug messages and -vv for trace messages.
     #[arg(short = 'v', long = "verbose", action = ArgAction::Count)]
     verbose: u8,
@@ -71,12 +275,120 @@ struct Args {
     /// Choose the matching mode: naive or online (default: naive)
     #[arg(short = 'm', long = "mode", value_enum, default_value_t = Mode::Naive)]
     mode: Mode,
+
+    /// Write the results every K matches (default: 1). The remaining results are written at the end.
+    #[arg(long = "flush-every", value_name = "K", default_value_t = 1)]
+    flush_every: usize,
+
+    /// Add the track TRACK reading the same input as the track SOURCE, e.g., for
+    /// self-hyperproperties (can be used multiple times). The added tracks are numbered after
+    /// the input files.
+    #[arg(long = "share-stream", value_name = "TRACK=SOURCE")]
+    share_stream: Vec<String>,
+
+    /// Drop the matches consuming more than W actions of an input, bounding the memory usage.
+    /// Only the naive and online modes support it.
+    #[arg(long = "max-window", value_name = "W")]
+    max_window: Option<usize>,
+
+    /// Check that the input actions appear in the automaton, warning about or rejecting the
+    /// others, e.g., typos in the logs.
+    #[arg(long = "check-alphabet", value_enum, value_name = "MODE")]
+    check_alphabet: Option<CheckAlphabet>,
+
+    /// Ignore PREFIX at the beginning of the input actions when they are compared with the
+    /// letters of the automaton, e.g., "evt:a" matches "a" with --strip-prefix evt:. Only the
+    /// naive and online modes support it.
+    #[arg(long = "strip-prefix", value_name = "PREFIX")]
+    strip_prefix: Option<String>,
+
+    /// Output only every K-th match to keep a large output manageable. The total number of
+    /// matches is logged at the end.
+    #[arg(long = "sample-every", value_name = "K")]
+    sample_every: Option<usize>,
+
+    /// Output each match with the probability P, e.g., 0.01, choosing the matches pseudo-randomly
+    /// by --seed. The total number of matches is logged at the end.
+    #[arg(long = "sample-fraction", value_name = "P")]
+    sample_fraction: Option<f64>,
+
+    /// Append the wall-clock time of the detection to each match, e.g.,
+    /// (0: 1, 2) @ 1700000000.123456, or as the field "ts" with --json, to correlate the matches
+    /// with external events.
+    #[arg(long = "timestamps")]
+    timestamps: bool,
+
+    /// Drop the matches with an interval shorter than L actions, e.g., --min-len 2 ignores the
+    /// trivial matches of one action.
+    #[arg(long = "min-len", value_name = "L")]
+    min_len: Option<usize>,
+
+    /// Drop the matches with an interval longer than U actions.
+    #[arg(long = "max-len", value_name = "U")]
+    max_len: Option<usize>,
+
+    /// Check the bounds of --min-len and --max-len on all the intervals of a match or any of them.
+    #[arg(long = "len-in", value_enum, value_name = "DIMS", default_value_t = LenIn::All)]
+    len_in: LenIn,
+
+    /// Output the distinct matches at the end, sorted by the input files and then the intervals,
+    /// so that the output is reproducible.
+    #[arg(long = "sorted")]
+    sorted: bool,
+
+    /// Print only the number of matches for each tuple of input files at the end.
+    #[arg(long = "tally")]
+    tally: bool,
+
+    /// Print the matches grouped by the tuple of the input files as a single JSON document at the
+    /// end, e.g., `{"[0,1]":[[{"end":2,"start":0},{"end":1,"start":1}]]}`, for offline analysis.
+    #[arg(long = "grouped-json")]
+    grouped_json: bool,
+
+    /// Load the automaton and open the input files without matching, e.g., to check the
+    /// arguments.
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+
+    /// Print the number of actions in each input file, e.g., to estimate the workload. Requires
+    /// --dry-run.
+    #[arg(long = "stats", requires = "dry_run")]
+    stats: bool,
+
+    /// Print only the number of the matches at the end, like `grep -c`, i.e., the number of the
+    /// lines output without --count.
+    #[arg(long = "count")]
+    count: bool,
+
+    /// Log which start positions are skipped and why, e.g., by which skip value. Only the fjs
+    /// mode supports it.
+    #[arg(long = "explain")]
+    explain: bool,
+
+    /// Log the wall-clock time spent in loading the automaton, constructing the matcher, e.g., the
+    /// skip tables of the FJS modes, feeding the input, and draining the remaining matches at the
+    /// end.
+    #[arg(long = "profile")]
+    profile: bool,
+
+    /// Run all the modes on N random actions per stream and check that they agree.
+    #[arg(long = "generate-random", value_name = "N", hide = true)]
+    generate_random: Option<usize>,
+
+    /// The seed of the pseudo-random choices, e.g., of the matches output by --sample-fraction.
+    #[arg(long = "seed", value_name = "S", default_value_t = 0)]
+    seed: u64,
 }
 
+mod alphabet_check;
+mod async_reading_scheduler;
 mod automata;
 mod automata_runner;
+mod capture;
 mod dfa;
 mod dfa_earliest_pattern_matcher;
+mod differential_testing;
+mod error;
 mod filtered_hyper_pattern_matching;
 mod filtered_pattern_matching_automata_runner;
 mod filtered_single_hyper_pattern_matching;
@@ -95,13 +407,18 @@ mod online_single_hyper_pattern_matching;
 mod quick_search_skip_values;
 mod reading_scheduler;
 mod result_notifier;
+mod rng;
 mod serialization;
 mod shared_buffer;
 mod single_hyper_pattern_matching;
+mod skip_value_cache;
+#[cfg(feature = "server")]
+mod sse_result_notifier;
+mod symbol_matcher;
 #[cfg(test)]
 mod tests;
 
-fn main() {
+fn main() -> ExitCode {
     // Parse the command-line arguments
     let args = Args::parse();
 
@@ -120,6 +437,17 @@ fn main() {
     let env = Env::default().filter_or("RUST_LOG", log_level);
     env_logger::Builder::from_env(env).init();
 
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+/// Runs the command-line flow specified by `args`.
+fn run(args: Args) -> Result<(), HyppauError> {
     // Log the parsed arguments for debugging
     trace!("Parsed command-line arguments: {:?}", args);
 
@@ -133,26 +461,34 @@ fn main() {
     debug!("Matching mode: {:?}", args.mode);
 
     // Read the automaton file
-    let mut file = match File::open(&args.automaton) {
-        Ok(file) => file,
-        Err(e) => {
-            error!("Failed to open automaton file: {}", e);
-            return;
-        }
-    };
+    let loading_started = Instant::now();
+    let contents = read_automaton_file(&args.automaton)?;
+    #[cfg(feature = "server")]
+    if args.sse_port.is_some() && (args.json || args.output.is_some()) {
+        return Err(HyppauError::InvalidArgument(
+            "--sse-port cannot be used with --json or --output".to_string(),
+        ));
+    }
 
-    let mut contents = String::new();
-    if let Err(e) = file.read_to_string(&mut contents) {
-        error!("Failed to read automaton file: {}", e);
-        return;
+    // If --letter-classes is used, match the automaton with the classes of letters instead
+    if args.letter_classes {
+        return run_letter_classes(&args, &contents, loading_started);
     }
 
     // Create arenas for states and transitions
     let state_arena = Arena::new();
     let trans_arena = Arena::new();
 
-    // Deserialize the JSON content into an automaton
-    let automaton = deserialize_nfa(&contents, &state_arena, &trans_arena);
+    // Deserialize the content into an automaton
+    let automaton = parse_automaton(
+        &args.automaton,
+        &contents,
+        args.automaton_format,
+        args.dimensions,
+        &state_arena,
+        &trans_arena,
+    )?;
+    let loading = loading_started.elapsed();
 
     // Print some information about the constructed automaton
     debug!("Automaton constructed successfully");
@@ -162,85 +498,1243 @@ fn main() {
         automaton.initial_states.len()
     );
     debug!("Number of dimensions: {}", automaton.dimensions);
+    warn_if_empty_language(&automaton, "The automaton");
+
+    // If the --product option is used, print the product automaton instead of matching
+    if let Some(second_file) = &args.product {
+        let second_contents = read_automaton_file(second_file)?;
+        let second_state_arena = Arena::new();
+        let second_trans_arena = Arena::new();
+        let second = parse_automaton(
+            second_file,
+            &second_contents,
+            args.automaton_format,
+            None,
+            &second_state_arena,
+            &second_trans_arena,
+        )?;
+        if automaton.dimensions != second.dimensions {
+            return Err(HyppauError::InvalidArgument(format!(
+                "The dimensions of the automata differ: {} and {}",
+                automaton.dimensions, second.dimensions
+            )));
+        }
+
+        let product_state_arena = Arena::new();
+        let product_trans_arena = Arena::new();
+        let product = automaton.product(&second, &product_state_arena, &product_trans_arena);
+        debug!("Number of states of the product: {}", product.states.len());
+        warn_if_empty_language(&product, "The product automaton");
+        let output = if args.graphviz {
+            automaton_to_dot(&product)
+        } else {
+            serialize_nfa(&product)
+        };
+        write_output_or_stdout(args.output.as_deref(), &output, "Product automaton")?;
+        return Ok(());
+    }
+
+    // If the --save-bincode option is used, convert the automaton instead of matching
+    if let Some(output_file) = &args.save_bincode {
+        write_output(output_file, save_nfah_bincode(&automaton))?;
+        info!("Bincode automaton written to file: {}", output_file);
+        return Ok(());
+    }
 
     // If the --graphviz option is used, generate the automaton in DOT format
     if args.graphviz {
         let dot_output = automaton_to_dot(&automaton);
+        write_output_or_stdout(args.output.as_deref(), &dot_output, "DOT output")?;
+        return Ok(());
+    }
 
-        // If an output file is specified, write to the file; otherwise, print to stdout
-        if let Some(output_file) = args.output {
-            match std::fs::write(&output_file, dot_output) {
-                Ok(_) => info!("DOT output written to file: {}", output_file),
-                Err(e) => error!("Failed to write DOT output to file: {}", e),
+    // If the --graphml option is used, generate the automaton in GraphML format
+    if args.graphml {
+        let graphml_output = automaton_to_graphml(&automaton);
+        write_output_or_stdout(args.output.as_deref(), &graphml_output, "GraphML output")?;
+        return Ok(());
+    }
+    // If --generate-random is used, run all the modes on random inputs and compare the results
+    if let Some(length) = args.generate_random {
+        let alphabet = automaton_alphabet(&automaton);
+        if alphabet.is_empty() {
+            return Err(HyppauError::InvalidArgument(
+                "--generate-random needs an automaton with transitions to sample the actions from"
+                    .to_string(),
+            ));
+        }
+        let inputs = generate_random_inputs(&alphabet, automaton.dimensions, length, args.seed);
+        debug!("Random inputs: {:?}", inputs);
+        match compare_modes(&automaton, &inputs) {
+            Ok(matches) => info!(
+                "All the modes agree on {} matches with seed {}",
+                matches.len(),
+                args.seed
+            ),
+            Err(message) => {
+                return Err(HyppauError::ModeMismatch {
+                    seed: args.seed,
+                    message,
+                })
             }
-        } else {
-            println!("{}", dot_output);
         }
-        return;
+        return Ok(());
     }
+
     // If no input files are specified, print a message and return
-    if args.input.is_empty() {
+    if args.input.is_empty() && args.interleaved.is_none() && args.replay.is_none() {
         info!("No input files specified; nothing to do");
-        return;
+        return Ok(());
     }
 
-    // Construct MultiStreamReader from the input files
-    debug!(
-        "Construct MultiStreamReader from input files: {:?}",
-        args.input
-    );
-    let multi_stream_reader = MultiStreamReader::new(
+    // The matchers need at least one variable to assign the inputs to
+    if automaton.dimensions == 0 {
+        return Err(HyppauError::InvalidAutomaton {
+            path: args.automaton.clone(),
+            message: "the automaton has no dimensions".to_string(),
+        });
+    }
+
+    // The matches are output as the followed files grow, so they are neither collected to the end
+    // nor read from a single interleaved file
+    if args.follow && (args.interleaved.is_some() || args.sorted || args.tally || args.count) {
+        return Err(HyppauError::InvalidArgument(
+            "--follow cannot be used with --interleaved, --sorted, --tally, or --count".to_string(),
+        ));
+    }
+
+    // Read the captured run with --replay
+    let replay_capture = if let Some(replay) = &args.replay {
+        if !args.input.is_empty() || args.interleaved.is_some() || args.follow || args.dry_run {
+            return Err(HyppauError::InvalidArgument(
+                "--replay cannot be used with --input, --interleaved, --follow, or --dry-run"
+                    .to_string(),
+            ));
+        }
+        let file = File::open(replay).map_err(|source| HyppauError::InputFile {
+            path: replay.clone(),
+            source,
+        })?;
+        let capture =
+            read_capture(BufReader::new(file)).map_err(|source| HyppauError::InputFile {
+                path: replay.clone(),
+                source,
+            })?;
+        debug!(
+            "Replay {} events of {} streams from {}",
+            capture.events().len(),
+            capture.num_streams(),
+            replay
+        );
+        Some(capture)
+    } else {
+        None
+    };
+
+    // Construct the input streams from the input files, or by demultiplexing --interleaved
+    let input_streams = if args.replay.is_some() {
+        Vec::new()
+    } else if let Some(interleaved) = &args.interleaved {
+        if !args.input.is_empty() {
+            return Err(HyppauError::InvalidArgument(
+                "--interleaved cannot be used with --input".to_string(),
+            ));
+        }
+        debug!(
+            "Demultiplex {} into {} tracks",
+            interleaved, automaton.dimensions
+        );
+        demultiplex(open_input(interleaved)?, automaton.dimensions)
+    } else if args.follow {
+        debug!("Follow the input files: {:?}", args.input);
+        // Stop following on Ctrl-C, so that the rest of the files is matched and the buffered
+        // matches are output before exiting. The second Ctrl-C exits immediately.
+        let stop = Arc::new(AtomicBool::new(false));
+        let interrupted = stop.clone();
+        let handled = ctrlc::set_handler(move || {
+            if interrupted.swap(true, Ordering::AcqRel) {
+                std::process::exit(130);
+            }
+        });
+        if let Err(e) = handled {
+            warn!(
+                "Failed to handle Ctrl-C; the buffered matches are lost if interrupted: {}",
+                e
+            );
+        }
         args.input
             .iter()
             .map(|path| {
-                let file = std::fs::File::open(path).unwrap();
-                Box::new(BufReader::new(file)) as Box<dyn StreamSource>
+                let file = File::open(path).map_err(|source| HyppauError::InputFile {
+                    path: path.to_string(),
+                    source,
+                })?;
+                Ok(Box::new(FollowingReader::new(file, stop.clone())) as Box<dyn StreamSource>)
             })
-            .collect(),
-    );
-
-    // Construct ResultNotifier
-    let result_notifier = if let Some(output_file) = args.output {
-        ResultNotifierType::File(FileResultNotifier::new(&output_file).unwrap())
+            .collect::<Result<_, _>>()?
+    } else {
+        debug!("Open the input files: {:?}", args.input);
+        args.input
+            .iter()
+            .map(|path| open_input(path))
+            .collect::<Result<_, _>>()?
+    };
+    let input_streams: Vec<Box<dyn StreamSource>> = if let Some(pointer) = &args.json_field {
+        debug!(
+            "Use the field {} of the JSON events as the actions",
+            pointer
+        );
+        input_streams
+            .into_iter()
+            .map(|stream| {
+                Box::new(JsonFieldReader::new(stream, pointer.as_str())) as Box<dyn StreamSource>
+            })
+            .collect()
     } else {
-        ResultNotifierType::Stdout(StdoutResultNotifier)
+        input_streams
     };
+    // With --replay, the input streams are the captured streams
+    let num_streams = match &replay_capture {
+        Some(capture) => capture.num_streams(),
+        None => input_streams.len(),
+    };
+    // Compute the tracks backing each track
+    let sources = stream_sources(num_streams, &args.share_stream)
+        .map_err(|e| HyppauError::InvalidArgument(format!("Invalid --share-stream: {}", e)))?;
+    debug!("Sources of the tracks: {:?}", sources);
+
+    // Construct MultiStreamReader from the input streams
+    let multi_stream_reader = MultiStreamReader::new(input_streams);
+
+    // If --dry-run is used, stop before matching, printing the number of actions in each input
+    // with --stats
+    if args.dry_run {
+        if !args.stats {
+            info!("Dry run completed successfully");
+            return Ok(());
+        }
+        if args.follow || args.delimiter.is_some() {
+            return Err(HyppauError::InvalidArgument(
+                "--stats cannot be used with --follow or --delimiter".to_string(),
+            ));
+        }
+        let counts = multi_stream_reader.count_symbols();
+        let output: String = counts
+            .iter()
+            .enumerate()
+            .map(|(stream, count)| format!("{}: {}\n", stream, count))
+            .collect();
+        write_output_or_stdout(args.output.as_deref(), &output, "Symbol counts")?;
+        return Ok(());
+    }
+    let input = MatchingInput {
+        reader: multi_stream_reader,
+        replay: replay_capture,
+        capture: args.capture.clone(),
+        delimiter: args.delimiter,
+    };
+
+    if let (Some(min_len), Some(max_len)) = (args.min_len, args.max_len) {
+        if min_len > max_len {
+            return Err(HyppauError::InvalidArgument(format!(
+                "--min-len {} is larger than --max-len {}",
+                min_len, max_len
+            )));
+        }
+    }
+    if args.explain && !matches!(args.mode, Mode::Fjs) {
+        return Err(HyppauError::InvalidArgument(format!(
+            "--explain is not supported in {:?} mode",
+            args.mode
+        )));
+    }
+    if let Some(fraction) = args.sample_fraction {
+        if args.sample_every.is_some() {
+            return Err(HyppauError::InvalidArgument(
+                "--sample-fraction cannot be used with --sample-every".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(HyppauError::InvalidArgument(format!(
+                "--sample-fraction must be between 0 and 1, but {} is given",
+                fraction
+            )));
+        }
+    }
+    if args.timestamps && (args.sorted || args.tally || args.count) {
+        // The matches are not output when they are detected
+        return Err(HyppauError::InvalidArgument(
+            "--timestamps cannot be used with --sorted, --tally, or --count".to_string(),
+        ));
+    }
+    if let Some(var_names) = &args.var_names {
+        if !args.json {
+            return Err(HyppauError::InvalidArgument(
+                "--var-names can be used only with --json".to_string(),
+            ));
+        }
+        if var_names.len() != automaton.dimensions {
+            return Err(HyppauError::InvalidArgument(format!(
+                "--var-names gives {} names but the automaton has {} variables",
+                var_names.len(),
+                automaton.dimensions
+            )));
+        }
+    }
+    if args.byte_spans
+        && (args.interleaved.is_some()
+            || args.replay.is_some()
+            || args.json_field.is_some()
+            || args.delimiter.is_some()
+            || args.capture.is_some()
+            || args.sorted
+            || args.tally
+            || args.count
+            || args.timestamps
+            || args.sample_every.is_some()
+            || args.sample_fraction.is_some()
+            || args.explain)
+    {
+        // The spans are of the lines in the input files, reported as the matches are found
+        return Err(HyppauError::InvalidArgument(
+            "--byte-spans cannot be used with --interleaved, --replay, --json-field, --delimiter, \
+             --capture, --sorted, --tally, --count, --timestamps, --sample-every, \
+             --sample-fraction, or --explain"
+                .to_string(),
+        ));
+    }
+    if args.grouped_json
+        && (args.json || args.tally || args.count || args.follow || args.timestamps)
+    {
+        return Err(HyppauError::InvalidArgument(
+            "--grouped-json cannot be used with --json, --tally, --count, --follow, or --timestamps"
+                .to_string(),
+        ));
+    }
+    let length_filter = LengthFilter {
+        min: args.min_len,
+        max: args.max_len,
+        any_dimension: args.len_in == LenIn::Any,
+    };
+
+    // If --count is used, print only the number of the matches at the end
+    if args.count {
+        if args.tally {
+            return Err(HyppauError::InvalidArgument(
+                "--count cannot be used with --tally".to_string(),
+            ));
+        }
+        info!("Start hyper pattern matching with {:?} mode", args.mode);
+        let counting_notifier = CountingResultNotifier::new();
+        let profile = run_matching(
+            &args,
+            &automaton,
+            LengthFilteredResultNotifier::new(counting_notifier.clone(), length_filter),
+            input,
+            &sources,
+        )?;
+        report_profile(&args, Profile { loading, ..profile });
+        let output = format!("{}\n", counting_notifier.count());
+        write_output_or_stdout(args.output.as_deref(), &output, "Match count")?;
+        info!("Hyper Pattern Matching completed successfully");
+        return Ok(());
+    }
+
+    // If --tally is used, count the matches and print the counts at the end
+    if args.tally {
+        info!("Start hyper pattern matching with {:?} mode", args.mode);
+        let tally_notifier = TallyResultNotifier::new();
+        let profile = run_matching(
+            &args,
+            &automaton,
+            LengthFilteredResultNotifier::new(tally_notifier.clone(), length_filter),
+            input,
+            &sources,
+        )?;
+        report_profile(&args, Profile { loading, ..profile });
+        let mut tally: Vec<_> = tally_notifier.tally().into_iter().collect();
+        tally.sort();
+        let output: String = tally
+            .iter()
+            .map(|(ids, count)| format!("{:?}: {}\n", ids, count))
+            .collect();
+        write_output_or_stdout(args.output.as_deref(), &output, "Match tally")?;
+        info!("Hyper Pattern Matching completed successfully");
+        return Ok(());
+    }
+
+    // If --grouped-json is used, print the matches grouped by their ids at the end
+    if args.grouped_json {
+        info!("Start hyper pattern matching with {:?} mode", args.mode);
+        // The document is written when the last clone of the notifier is dropped after matching
+        let grouped_notifier = GroupedJsonResultNotifier::new(open_output(args.output.as_deref())?);
+        let profile = run_matching(
+            &args,
+            &automaton,
+            LengthFilteredResultNotifier::new(grouped_notifier, length_filter),
+            input,
+            &sources,
+        )?;
+        report_profile(&args, Profile { loading, ..profile });
+        info!("Hyper Pattern Matching completed successfully");
+        return Ok(());
+    }
+
+    // If --byte-spans is used, output the matches with the byte spans of their intervals
+    if args.byte_spans {
+        info!("Start hyper pattern matching with {:?} mode", args.mode);
+        let mut output = open_output(args.output.as_deref())?;
+        let profile = run_with_match_callback(
+            &args.mode,
+            &automaton,
+            input.reader,
+            &sources,
+            matching_options(&args),
+            |result, spans| {
+                if !length_filter.keeps(&result.intervals) {
+                    return;
+                }
+                let line = json_line_with_spans(
+                    &result.intervals,
+                    &result.ids,
+                    args.var_names.as_deref(),
+                    args.report,
+                    spans,
+                );
+                writeln!(output, "{}", line).expect("Failed to write the output");
+            },
+        )?;
+        report_profile(&args, Profile { loading, ..profile });
+        info!("Hyper Pattern Matching completed successfully");
+        return Ok(());
+    }
+
+    // Construct ResultNotifier
+    let result_notifier = open_result_notifier(&args)?;
+    let result_notifier = LengthFilteredResultNotifier::new(
+        BufferedResultNotifier::new(result_notifier, args.flush_every),
+        length_filter,
+    );
 
     // Construct HyperPatternMatching and ReadingScheduler depending on the mode argument
     info!("Start hyper pattern matching with {:?} mode", args.mode);
-    match args.mode {
+    let profile = if args.timestamps {
+        run_sampled(
+            &args,
+            &automaton,
+            TimestampedResultNotifier::new(result_notifier),
+            input,
+            &sources,
+        )?
+    } else {
+        run_sampled(&args, &automaton, result_notifier, input, &sources)?
+    };
+    report_profile(&args, Profile { loading, ..profile });
+
+    info!("Hyper Pattern Matching completed successfully");
+    Ok(())
+}
+
+/// Matches the automaton with the classes of letters in `contents` for `--letter-classes`, where
+/// the loading of the automaton started at `loading_started`.
+///
+/// The labels are matched by [`VariableLabel::matches_with`](crate::automata::VariableLabel), so
+/// the matcher is [`NaiveHyperPatternMatching`], which is generic over the labels.
+fn run_letter_classes(
+    args: &Args,
+    contents: &[u8],
+    loading_started: Instant,
+) -> Result<(), HyppauError> {
+    if !matches!(args.mode, Mode::Naive) {
+        return Err(HyppauError::InvalidArgument(format!(
+            "--letter-classes is not supported in {:?} mode",
+            args.mode
+        )));
+    }
+    let state_arena = Arena::new();
+    let trans_arena = Arena::new();
+    let automaton = parse_class_automaton(
+        &args.automaton,
+        contents,
+        args.dimensions,
+        &state_arena,
+        &trans_arena,
+    )?;
+    let loading = loading_started.elapsed();
+    debug!("Number of states: {}", automaton.states.len());
+    debug!("Number of dimensions: {}", automaton.dimensions);
+    warn_if_empty_language(&automaton, "The automaton");
+    if automaton.dimensions == 0 {
+        return Err(HyppauError::InvalidAutomaton {
+            path: args.automaton.clone(),
+            message: "the automaton has no dimensions".to_string(),
+        });
+    }
+    if args.input.is_empty() {
+        info!("No input files specified; nothing to do");
+        return Ok(());
+    }
+
+    debug!("Open the input files: {:?}", args.input);
+    let input_streams = args
+        .input
+        .iter()
+        .map(|path| open_input(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let reader = MultiStreamReader::new(input_streams);
+    let result_notifier =
+        BufferedResultNotifier::new(open_result_notifier(args)?, args.flush_every);
+
+    info!("Start hyper pattern matching with the letter classes in Naive mode");
+    let started = Instant::now();
+    let sequences = (0..reader.num_streams())
+        .map(|_| AppendOnlySequence::new())
+        .collect();
+    let matching = NaiveHyperPatternMatching::new(&automaton, result_notifier, sequences, false);
+    let input = MatchingInput {
+        reader,
+        replay: None,
+        capture: None,
+        delimiter: args.delimiter,
+    };
+    let (_, profile) = run_timed(matching, input, started.elapsed())?;
+    report_profile(args, Profile { loading, ..profile });
+
+    info!("Hyper Pattern Matching completed successfully");
+    Ok(())
+}
+
+/// Runs the hyper pattern matching specified by `args` and notifies the matches to `notifier`.
+///
+/// With `--sample-every` or `--sample-fraction`, only a sample of the matches is notified and the
+/// total is logged.
+fn run_sampled<'a, Notifier: ResultNotifier + Clone>(
+    args: &Args,
+    automaton: &'a NFAH<'a>,
+    notifier: Notifier,
+    input: MatchingInput,
+    sources: &[usize],
+) -> Result<Profile, HyppauError> {
+    let sampling = match (args.sample_every, args.sample_fraction) {
+        (Some(stride), _) => Some(Sampling::Every(stride)),
+        (None, Some(fraction)) => Some(Sampling::Fraction {
+            fraction,
+            seed: args.seed,
+        }),
+        (None, None) => None,
+    };
+    let profile = if let Some(sampling) = sampling {
+        // Output only the sampled matches, but count all of them
+        let sampling_notifier = SamplingResultNotifier::new(notifier, sampling);
+        let profile = run_to_notifier(args, automaton, sampling_notifier.clone(), input, sources)?;
+        info!(
+            "Output {} of {} matches",
+            sampling_notifier.forwarded(),
+            sampling_notifier.total()
+        );
+        profile
+    } else {
+        run_to_notifier(args, automaton, notifier, input, sources)?
+    };
+    Ok(profile)
+}
+
+/// Runs the hyper pattern matching specified by `args` and notifies the matches to `notifier`.
+///
+/// With `--sorted`, the matches are collected and notified at the end in a reproducible order.
+fn run_to_notifier<'a, Notifier: ResultNotifier + Clone>(
+    args: &Args,
+    automaton: &'a NFAH<'a>,
+    mut notifier: Notifier,
+    input: MatchingInput,
+    sources: &[usize],
+) -> Result<Profile, HyppauError> {
+    if !args.sorted {
+        return run_matching(args, automaton, notifier, input, sources);
+    }
+    let sorted_notifier = SortedResultNotifier::new();
+    let profile = run_matching(args, automaton, sorted_notifier.clone(), input, sources)?;
+    notifier.notify_all(&sorted_notifier.results());
+    Ok(profile)
+}
+
+/// Runs the hyper pattern matching specified by `args`, explaining the skips with `--explain`.
+fn run_matching<'a, Notifier: ResultNotifier + Clone>(
+    args: &Args,
+    automaton: &'a NFAH<'a>,
+    notifier: Notifier,
+    input: MatchingInput,
+    sources: &[usize],
+) -> Result<Profile, HyppauError> {
+    if !args.explain {
+        return run_hyper_pattern_matching(
+            &args.mode,
+            automaton,
+            notifier,
+            input,
+            sources,
+            matching_options(args),
+        );
+    }
+    if args.max_window.is_some() {
+        warn!("--max-window is not supported with --explain and ignored");
+    }
+    if args.strip_prefix.is_some() {
+        warn!("--strip-prefix is not supported with --explain and ignored");
+    }
+    use crate::fjs_hyper_pattern_matching::FJSHyperPatternMatching;
+    use crate::hyper_pattern_matching::shared_sequences;
+    use crate::skip_value_cache::SkipValueCache;
+    // The skips are decided over all the id-assignments, so use the non-adapted FJS matching
+    let started = Instant::now();
+    let mut hyper_pattern_matching = FJSHyperPatternMatching::new(
+        automaton,
+        notifier,
+        shared_sequences(sources),
+        false,
+        &SkipValueCache::new(),
+    );
+    hyper_pattern_matching.set_explain(true);
+    run_reading_scheduler(
+        hyper_pattern_matching,
+        input,
+        automaton,
+        args.check_alphabet,
+        None,
+        started,
+    )
+}
+
+/// Returns the options of the matching given by `args`.
+fn matching_options(args: &Args) -> MatchingOptions {
+    MatchingOptions {
+        max_window: args.max_window,
+        symbol_matcher: args.strip_prefix.as_ref().map(|prefix| {
+            Rc::new(PrefixStrippingSymbolMatcher::new(prefix.as_str())) as SharedSymbolMatcher
+        }),
+        check_alphabet: args.check_alphabet,
+    }
+}
+
+/// Opens the output file at `path` if any, or stdout otherwise, to write the matches.
+fn open_output(path: Option<&str>) -> Result<Box<dyn Write + Send>, HyppauError> {
+    match path {
+        Some(path) => Ok(Box::new(File::create(path).map_err(|source| {
+            HyppauError::Output {
+                path: path.to_string(),
+                source,
+            }
+        })?)),
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+/// Writes `contents` to the output file at `path`.
+fn write_output(path: &str, contents: impl AsRef<[u8]>) -> Result<(), HyppauError> {
+    std::fs::write(path, contents).map_err(|source| HyppauError::Output {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Writes `contents` to the output file at `path` if any, or prints it to stdout otherwise.
+///
+/// The output to stdout always ends with a newline. `description` names the output in the log.
+fn write_output_or_stdout(
+    path: Option<&str>,
+    contents: &str,
+    description: &str,
+) -> Result<(), HyppauError> {
+    match path {
+        Some(path) => {
+            write_output(path, contents)?;
+            info!("{} written to file: {}", description, path);
+        }
+        None if contents.ends_with('\n') => print!("{}", contents),
+        None => println!("{}", contents),
+    }
+    Ok(())
+}
+
+/// The wall-clock time spent in each phase of a run, logged by `--profile`.
+#[derive(Clone, Copy, Debug, Default)]
+struct Profile {
+    /// Reading and deserializing the automaton
+    loading: Duration,
+    /// Constructing the matcher, e.g., the skip tables of the FJS modes
+    matcher_construction: Duration,
+    /// Feeding the input until the end of all the streams
+    feeding: Duration,
+    /// Consuming the remaining input and reporting the matches completing at the end
+    draining: Duration,
+}
+
+/// Opens the notifier of the matches given by `--json`, `--output`, or `--sse-port`, or the one
+/// printing to the standard output.
+fn open_result_notifier(args: &Args) -> Result<ResultNotifierType, HyppauError> {
+    let result_notifier = if args.json {
+        let output = open_output(args.output.as_deref())?;
+        ResultNotifierType::Json(
+            JsonResultNotifier::new(output, args.var_names.clone()).with_report(args.report),
+        )
+    } else if let Some(output_file) = &args.output {
+        ResultNotifierType::File(
+            FileResultNotifier::new(output_file)
+                .map_err(|source| HyppauError::Output {
+                    path: output_file.clone(),
+                    source,
+                })?
+                .with_report(args.report),
+        )
+    } else {
+        ResultNotifierType::Stdout(StdoutResultNotifier::default().with_report(args.report))
+    };
+    #[cfg(feature = "server")]
+    let result_notifier =
+        match args.sse_port {
+            Some(port) => {
+                let sse_notifier = SseResultNotifier::bind((args.sse_host.as_str(), port))
+                    .map_err(|source| HyppauError::Output {
+                        path: format!("{}:{}", args.sse_host, port),
+                        source,
+                    })?;
+                info!(
+                    "Streaming the matches as SSE on {}",
+                    sse_notifier.local_addr()
+                );
+                ResultNotifierType::Sse(sse_notifier)
+            }
+            None => result_notifier,
+        };
+    Ok(result_notifier)
+}
+
+/// Logs `profile` if `--profile` is given.
+fn report_profile(args: &Args, profile: Profile) {
+    if !args.profile {
+        return;
+    }
+    info!("Profile: loading: {:?}", profile.loading);
+    info!(
+        "Profile: matcher construction: {:?}",
+        profile.matcher_construction
+    );
+    info!("Profile: feeding: {:?}", profile.feeding);
+    info!("Profile: draining: {:?}", profile.draining);
+}
+
+/// The options of the matching in [`run_hyper_pattern_matching`].
+#[derive(Default)]
+struct MatchingOptions {
+    /// The matches consuming more than this number of actions of an input are dropped
+    max_window: Option<usize>,
+    /// The matcher of the input actions and the letters if they are not compared exactly
+    symbol_matcher: Option<SharedSymbolMatcher>,
+    /// How the input actions are checked against the alphabet of the automaton
+    check_alphabet: Option<CheckAlphabet>,
+}
+
+/// The input of the matching and where to capture the events fed to it.
+struct MatchingInput {
+    /// The input streams
+    reader: MultiStreamReader,
+    /// The run captured by `--capture`, fed instead of the input streams with `--replay`
+    replay: Option<Capture>,
+    /// The file to capture the events fed to the matching with `--capture`
+    capture: Option<String>,
+    /// The delimiter of the actions in a line of the input streams given by `--delimiter`
+    delimiter: Option<char>,
+}
+
+impl From<MultiStreamReader> for MatchingInput {
+    fn from(reader: MultiStreamReader) -> Self {
+        Self {
+            reader,
+            replay: None,
+            capture: None,
+            delimiter: None,
+        }
+    }
+}
+
+/// Feeds `input` to `matching` until the end of the input streams or the captured events.
+///
+/// If `check_alphabet` is given, the input actions are checked against the alphabet of
+/// `automaton`; with [`CheckAlphabet::Error`], the first unknown action is an error. They are
+/// compared with the letters by `symbol_matcher` if any, as in `matching`.
+///
+/// Returns the time spent in the phases of the matching, where the construction of `matching`
+/// started at `started`. The time to load the automaton is not measured here.
+fn run_reading_scheduler<'a, Matching: HyperPatternMatching>(
+    matching: Matching,
+    input: MatchingInput,
+    automaton: &'a NFAH<'a>,
+    check_alphabet: Option<CheckAlphabet>,
+    symbol_matcher: Option<SharedSymbolMatcher>,
+    started: Instant,
+) -> Result<Profile, HyppauError> {
+    let matcher_construction = started.elapsed();
+    let Some(check_alphabet) = check_alphabet else {
+        return run_timed(matching, input, matcher_construction).map(|(_, profile)| profile);
+    };
+    let strict = check_alphabet == CheckAlphabet::Error;
+    let mut matching = AlphabetCheckingMatching::new(matching, automaton, strict);
+    if let Some(symbol_matcher) = symbol_matcher {
+        matching = matching.with_symbol_matcher(symbol_matcher);
+    }
+    let (matching, profile) = run_timed(matching, input, matcher_construction)?;
+    match matching.first_unknown() {
+        Some((track, action)) if strict => Err(HyppauError::UnknownAction {
+            track: *track,
+            action: action.clone(),
+        }),
+        _ => Ok(profile),
+    }
+}
+
+/// Runs `matching` on `input` to the end, measuring the feeding and the draining separately.
+///
+/// The events fed to `matching` are captured to the file of `--capture`, if any, including the
+/// actions rejected by the alphabet check. Returns `matching` with the time spent in the phases.
+fn run_timed<Matching: HyperPatternMatching>(
+    matching: Matching,
+    input: MatchingInput,
+    matcher_construction: Duration,
+) -> Result<(Matching, Profile), HyppauError> {
+    let MatchingInput {
+        reader,
+        replay: replay_capture,
+        capture,
+        delimiter,
+    } = input;
+    let output = match &capture {
+        Some(path) => {
+            let file = File::create(path).map_err(|source| HyppauError::Output {
+                path: path.clone(),
+                source,
+            })?;
+            Some(Box::new(io::BufWriter::new(file)) as Box<dyn Write>)
+        }
+        None => None,
+    };
+    let num_streams = match &replay_capture {
+        Some(replay_capture) => replay_capture.num_streams(),
+        None => reader.num_streams(),
+    };
+    let matching = CapturingMatching::new(matching, output, num_streams);
+    let feeding_started = Instant::now();
+    let (matching, draining_started) = match replay_capture {
+        Some(replay_capture) => {
+            let mut matching = matching;
+            replay(&replay_capture, &mut matching);
+            let draining_started = Instant::now();
+            matching.consume_remaining();
+            (matching, draining_started)
+        }
+        None => {
+            let mut reading_scheduler = ReadingScheduler::new(matching, reader);
+            if let Some(delimiter) = delimiter {
+                reading_scheduler.set_delimiter(delimiter);
+            }
+            reading_scheduler.feed_until_eof();
+            let draining_started = Instant::now();
+            reading_scheduler.close_all();
+            (reading_scheduler.into_matching(), draining_started)
+        }
+    };
+    let profile = Profile {
+        loading: Duration::ZERO,
+        matcher_construction,
+        feeding: draining_started - feeding_started,
+        draining: draining_started.elapsed(),
+    };
+    let matching = matching.finish().map_err(|source| HyppauError::Output {
+        path: capture.unwrap_or_default(),
+        source,
+    })?;
+    Ok((matching, profile))
+}
+
+/// Runs the hyper pattern matching in `mode` and calls `callback` for each match as it is found.
+///
+/// The second argument of `callback` is the `(byte_begin, byte_end)` span of the interval in each
+/// dimension, where `byte_end` is exclusive, so that the matched regions in the input text can be
+/// highlighted directly. The track `t` reads the stream `sources[t]` of `reader`. This is how
+/// `--byte-spans` is output.
+fn run_with_match_callback<'a, F: FnMut(&MatchingResult, &[(usize, usize)])>(
+    mode: &Mode,
+    automaton: &'a NFAH<'a>,
+    mut reader: MultiStreamReader,
+    sources: &[usize],
+    options: MatchingOptions,
+    callback: F,
+) -> Result<Profile, HyppauError> {
+    let line_spans = reader.record_line_spans();
+    let notifier = SpanCallbackResultNotifier::new(callback, line_spans, sources.to_vec());
+    run_hyper_pattern_matching(mode, automaton, notifier, reader.into(), sources, options)
+}
+
+/// Applies `--max-window` and `--strip-prefix` in `options` to `matching`, warning if the mode
+/// does not support them.
+///
+/// Returns the symbol matcher applied to `matching`, if any.
+fn apply_options<'a, SingleMatching, Notifier>(
+    matching: &mut HyperPatternMatchingAdapter<'a, SingleMatching, Notifier>,
+    options: &MatchingOptions,
+    mode: &Mode,
+) -> Option<SharedSymbolMatcher>
+where
+    SingleMatching: SingleHyperPatternMatching<'a, Notifier>,
+    Notifier: ResultNotifier + Clone,
+{
+    if let Some(max_window) = options.max_window {
+        if !matching.set_max_window(max_window) {
+            warn!(
+                "--max-window is not supported in {:?} mode and ignored",
+                mode
+            );
+        }
+    }
+    let symbol_matcher = options.symbol_matcher.clone()?;
+    if !matching.set_symbol_matcher(symbol_matcher.clone()) {
+        warn!(
+            "--strip-prefix is not supported in {:?} mode and ignored",
+            mode
+        );
+        return None;
+    }
+    Some(symbol_matcher)
+}
+
+/// Warns that no matches can ever be produced if the language of `automaton` is empty.
+fn warn_if_empty_language<L>(automaton: &Automata<L>, name: &str) {
+    if automaton.is_empty() {
+        warn!("{} accepts no words: no matches can ever be produced", name);
+    }
+}
+
+/// Reads the whole automaton file at `path`.
+fn read_automaton_file(path: &str) -> Result<Vec<u8>, HyppauError> {
+    std::fs::read(path).map_err(|source| HyppauError::AutomatonFile {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Builds the automaton from the `contents` of the file at `path` in the given `format`.
+///
+/// If `dimensions` is given, it replaces the dimensions in the file (see `--dimensions`).
+fn parse_automaton<'a>(
+    path: &str,
+    contents: &'a [u8],
+    format: AutomatonFormat,
+    dimensions: Option<usize>,
+    state_arena: &'a Arena<NFAHState<'a>>,
+    trans_arena: &'a Arena<NFAHTransition<'a>>,
+) -> Result<NFAH<'a>, HyppauError> {
+    let automaton = match format {
+        AutomatonFormat::Json => std::str::from_utf8(contents)
+            .map_err(|e| format!("The JSON file is not valid UTF-8: {}", e))
+            .and_then(|contents| {
+                deserialize_nfah_with_dimensions(contents, dimensions, state_arena, trans_arena)
+            }),
+        AutomatonFormat::Bincode => {
+            load_nfah_bincode(contents, dimensions, state_arena, trans_arena)
+        }
+    };
+    automaton.map_err(|e| invalid_automaton(path, dimensions, e))
+}
+
+/// Builds the automaton with the classes of letters from the `contents` of the JSON file at
+/// `path`, as [`parse_automaton`] for `--letter-classes`.
+fn parse_class_automaton<'a>(
+    path: &str,
+    contents: &'a [u8],
+    dimensions: Option<usize>,
+    state_arena: &'a Arena<State<'a, (LabelMatch, usize)>>,
+    trans_arena: &'a Arena<Transition<'a, (LabelMatch, usize)>>,
+) -> Result<ClassNFAH<'a>, HyppauError> {
+    std::str::from_utf8(contents)
+        .map_err(|e| format!("The JSON file is not valid UTF-8: {}", e))
+        .and_then(|contents| {
+            deserialize_class_nfah_with_dimensions(contents, dimensions, state_arena, trans_arena)
+        })
+        .map_err(|e| invalid_automaton(path, dimensions, e))
+}
+
+/// Makes the error of the invalid automaton file at `path`, loaded with `--dimensions` if given.
+fn invalid_automaton(path: &str, dimensions: Option<usize>, message: String) -> HyppauError {
+    HyppauError::InvalidAutomaton {
+        path: path.to_string(),
+        message: match dimensions {
+            Some(dimensions) => format!("{} (with --dimensions {})", message, dimensions),
+            None => message,
+        },
+    }
+}
+
+/// Opens the input file at `path` as a stream.
+///
+/// If no file is at `path` and it is a glob pattern, e.g., `logs/app-*.log` for rotated logs, the
+/// matching files are concatenated into one stream in the sorted order of their paths.
+fn open_input(path: &str) -> Result<Box<dyn StreamSource>, HyppauError> {
+    let input_file_error = |source| HyppauError::InputFile {
+        path: path.to_string(),
+        source,
+    };
+    if std::path::Path::new(path).exists() || !path.contains(['*', '?', '[']) {
+        let file = File::open(path).map_err(input_file_error)?;
+        return Ok(Box::new(BufReader::new(file)));
+    }
+
+    let entries = glob::glob(path).map_err(|e| {
+        HyppauError::InvalidArgument(format!("Invalid input pattern {}: {}", path, e))
+    })?;
+    let mut paths = entries
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| input_file_error(e.into()))?;
+    if paths.is_empty() {
+        return Err(input_file_error(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no file matches the pattern",
+        )));
+    }
+    paths.sort();
+    debug!("Input files matching {}: {:?}", path, paths);
+    let files = paths
+        .iter()
+        .map(|file_path| {
+            File::open(file_path)
+                .map(BufReader::new)
+                .map_err(|source| HyppauError::InputFile {
+                    path: file_path.display().to_string(),
+                    source,
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Box::new(ConcatenatedReader::new(files)))
+}
+
+/// An input interleaving the actions of several tracks as `TRACK:ACTION` lines.
+///
+/// The lines read ahead for the other tracks are kept until their tracks read them.
+struct InterleavedInput {
+    reader: Box<dyn StreamSource>,
+    /// The lines of each track read from `reader` but not by the track yet
+    pending: Vec<VecDeque<String>>,
+    /// The number of lines read from `reader`
+    line_number: usize,
+}
+
+impl InterleavedInput {
+    /// Returns the next line of `track` with a newline, reading `reader` ahead if necessary.
+    ///
+    /// Returns `None` at the end of `reader`. The malformed lines and the lines of unknown tracks
+    /// are skipped with a warning, and the blank lines are skipped silently.
+    fn next_line(&mut self, track: usize) -> io::Result<Option<String>> {
+        while self.pending[track].is_empty() {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            match line
+                .split_once(':')
+                .and_then(|(track, action)| Some((track.trim().parse::<usize>().ok()?, action)))
+            {
+                Some((track, action)) if track < self.pending.len() => {
+                    self.pending[track].push_back(format!("{}\n", action));
+                }
+                _ => warn!(
+                    "Skip the line {} of the interleaved input: {:?} is not of the form TRACK:ACTION with TRACK less than {}",
+                    self.line_number,
+                    line,
+                    self.pending.len()
+                ),
+            }
+        }
+        Ok(self.pending[track].pop_front())
+    }
+}
+
+/// The stream of a track of an [`InterleavedInput`].
+struct InterleavedTrack {
+    input: Arc<Mutex<InterleavedInput>>,
+    track: usize,
+    /// The current line and the number of its bytes consumed
+    line: Vec<u8>,
+    consumed: usize,
+}
+
+impl Read for InterleavedTrack {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for InterleavedTrack {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.consumed == self.line.len() {
+            let next_line = self.input.lock().unwrap().next_line(self.track)?;
+            self.line = next_line.map(String::into_bytes).unwrap_or_default();
+            self.consumed = 0;
+        }
+        Ok(&self.line[self.consumed..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed = (self.consumed + amt).min(self.line.len());
+    }
+}
+
+/// Demultiplexes the `TRACK:ACTION` lines of `reader` into the streams of `num_tracks` tracks.
+///
+/// Each track reads its actions in the order of `reader`.
+fn demultiplex(reader: Box<dyn StreamSource>, num_tracks: usize) -> Vec<Box<dyn StreamSource>> {
+    let input = Arc::new(Mutex::new(InterleavedInput {
+        reader,
+        pending: vec![VecDeque::new(); num_tracks],
+        line_number: 0,
+    }));
+    (0..num_tracks)
+        .map(|track| {
+            Box::new(InterleavedTrack {
+                input: input.clone(),
+                track,
+                line: Vec::new(),
+                consumed: 0,
+            }) as Box<dyn StreamSource>
+        })
+        .collect()
+}
+
+/// Returns the track backing each track, given the number of input files and the `TRACK=SOURCE`
+/// specifications of `--share-stream`.
+///
+/// The tracks of the input files back themselves. The shared tracks must be numbered
+/// consecutively after the input files, and their sources must be input files.
+fn stream_sources(num_inputs: usize, shares: &[String]) -> Result<Vec<usize>, String> {
+    let mut sources: Vec<Option<usize>> = (0..num_inputs).map(Some).collect();
+    sources.resize(num_inputs + shares.len(), None);
+    for share in shares {
+        let (track, source) = share
+            .split_once('=')
+            .and_then(|(track, source)| Some((track.parse::<usize>().ok()?, source.parse().ok()?)))
+            .ok_or_else(|| format!("{} is not of the form TRACK=SOURCE", share))?;
+        if track < num_inputs || track >= sources.len() {
+            return Err(format!(
+                "the track {} must be between {} and {}",
+                track,
+                num_inputs,
+                sources.len() - 1
+            ));
+        }
+        if source >= num_inputs {
+            return Err(format!("the source {} is not an input file", source));
+        }
+        if sources[track].replace(source).is_some() {
+            return Err(format!("the track {} is specified twice", track));
+        }
+    }
+    Ok(sources.into_iter().map(Option::unwrap).collect())
+}
+
+/// Runs the hyper pattern matching of the given mode until the end of all the input streams.
+///
+/// The track `t` is backed by the input stream `sources[t]`. The `max_window` and
+/// `symbol_matcher` of `options` are applied if the mode supports them. If `check_alphabet` is
+/// given, the input actions are checked against the alphabet of the automaton.
+///
+/// Returns the time spent in the phases of the matching for `--profile`.
+fn run_hyper_pattern_matching<'a, Notifier: ResultNotifier + Clone>(
+    mode: &Mode,
+    automaton: &'a NFAH<'a>,
+    result_notifier: Notifier,
+    input: MatchingInput,
+    sources: &[usize],
+    options: MatchingOptions,
+) -> Result<Profile, HyppauError> {
+    let started = Instant::now();
+    if matches!(
+        mode,
+        Mode::NaiveFiltered | Mode::OnlineFiltered | Mode::FjsFiltered
+    ) {
+        if options.max_window.is_some() {
+            warn!(
+                "--max-window is not supported in {:?} mode and ignored",
+                mode
+            );
+        }
+        if options.symbol_matcher.is_some() {
+            warn!(
+                "--strip-prefix is not supported in {:?} mode and ignored",
+                mode
+            );
+        }
+    }
+    let check_alphabet = options.check_alphabet;
+    match mode {
         Mode::Naive => {
             use crate::single_hyper_pattern_matching::NaiveSingleHyperPatternMatching;
-            let hyper_pattern_matching =
-                HyperPatternMatchingAdapter::<
-                    NaiveSingleHyperPatternMatching<ResultNotifierType>,
-                    ResultNotifierType,
-                >::new(&automaton, result_notifier, multi_stream_reader.size());
-            let mut reading_scheduler =
-                ReadingScheduler::new(hyper_pattern_matching, multi_stream_reader);
-            reading_scheduler.run();
+            let mut hyper_pattern_matching = HyperPatternMatchingAdapter::<
+                NaiveSingleHyperPatternMatching<Notifier>,
+                Notifier,
+            >::with_stream_sources(
+                automaton, result_notifier, sources
+            );
+            let symbol_matcher = apply_options(&mut hyper_pattern_matching, &options, mode);
+            run_reading_scheduler(
+                hyper_pattern_matching,
+                input,
+                automaton,
+                check_alphabet,
+                symbol_matcher,
+                started,
+            )
         }
         Mode::Online => {
             use crate::online_single_hyper_pattern_matching::OnlineSingleHyperPatternMatching;
-            let hyper_pattern_matching =
-                HyperPatternMatchingAdapter::<
-                    OnlineSingleHyperPatternMatching<ResultNotifierType>,
-                    ResultNotifierType,
-                >::new(&automaton, result_notifier, multi_stream_reader.size());
-            let mut reading_scheduler =
-                ReadingScheduler::new(hyper_pattern_matching, multi_stream_reader);
-            reading_scheduler.run();
+            let mut hyper_pattern_matching = HyperPatternMatchingAdapter::<
+                OnlineSingleHyperPatternMatching<Notifier>,
+                Notifier,
+            >::with_stream_sources(
+                automaton, result_notifier, sources
+            );
+            let symbol_matcher = apply_options(&mut hyper_pattern_matching, &options, mode);
+            run_reading_scheduler(
+                hyper_pattern_matching,
+                input,
+                automaton,
+                check_alphabet,
+                symbol_matcher,
+                started,
+            )
         }
         Mode::Fjs => {
             use crate::fjs_single_hyper_pattern_matching::FJSSingleHyperPatternMatching;
-            let hyper_pattern_matching =
-                HyperPatternMatchingAdapter::<
-                    FJSSingleHyperPatternMatching<ResultNotifierType>,
-                    ResultNotifierType,
-                >::new(&automaton, result_notifier, multi_stream_reader.size());
-            let mut reading_scheduler =
-                ReadingScheduler::new(hyper_pattern_matching, multi_stream_reader);
-            reading_scheduler.run();
+            let mut hyper_pattern_matching = HyperPatternMatchingAdapter::<
+                FJSSingleHyperPatternMatching<Notifier>,
+                Notifier,
+            >::with_stream_sources(
+                automaton, result_notifier, sources
+            );
+            let symbol_matcher = apply_options(&mut hyper_pattern_matching, &options, mode);
+            run_reading_scheduler(
+                hyper_pattern_matching,
+                input,
+                automaton,
+                check_alphabet,
+                symbol_matcher,
+                started,
+            )
         }
         // Mode::Fjs => {
         //     use crate::fjs_hyper_pattern_matching::FJSHyperPatternMatching;
@@ -260,38 +1754,51 @@ fn main() {
             use crate::filtered_hyper_pattern_matching::FilteredHyperPatternMatching;
             let hyper_pattern_matching =
                 FilteredHyperPatternMatching::<
-                    NaiveFilteredSingleHyperPatternMatching<ResultNotifierType>,
-                    ResultNotifierType,
-                >::new(&automaton, result_notifier, multi_stream_reader.size());
-            let mut reading_scheduler =
-                ReadingScheduler::new(hyper_pattern_matching, multi_stream_reader);
-            reading_scheduler.run();
+                    NaiveFilteredSingleHyperPatternMatching<Notifier>,
+                    Notifier,
+                >::with_stream_sources(automaton, result_notifier, sources);
+            run_reading_scheduler(
+                hyper_pattern_matching,
+                input,
+                automaton,
+                check_alphabet,
+                None,
+                started,
+            )
         }
         Mode::OnlineFiltered => {
             use crate::filtered_hyper_pattern_matching::FilteredHyperPatternMatching;
             use crate::online_filtered_single_hyper_pattern_matching::OnlineFilteredSingleHyperPatternMatching;
             let hyper_pattern_matching =
                 FilteredHyperPatternMatching::<
-                    OnlineFilteredSingleHyperPatternMatching<ResultNotifierType>,
-                    ResultNotifierType,
-                >::new(&automaton, result_notifier, multi_stream_reader.size());
-            let mut reading_scheduler =
-                ReadingScheduler::new(hyper_pattern_matching, multi_stream_reader);
-            reading_scheduler.run();
+                    OnlineFilteredSingleHyperPatternMatching<Notifier>,
+                    Notifier,
+                >::with_stream_sources(automaton, result_notifier, sources);
+            run_reading_scheduler(
+                hyper_pattern_matching,
+                input,
+                automaton,
+                check_alphabet,
+                None,
+                started,
+            )
         }
         Mode::FjsFiltered => {
             use crate::filtered_hyper_pattern_matching::FilteredHyperPatternMatching;
             use crate::fjs_filtered_single_hyper_pattern_matching::FJSFilteredSingleHyperPatternMatching;
             let hyper_pattern_matching =
                 FilteredHyperPatternMatching::<
-                    FJSFilteredSingleHyperPatternMatching<ResultNotifierType>,
-                    ResultNotifierType,
-                >::new(&automaton, result_notifier, multi_stream_reader.size());
-            let mut reading_scheduler =
-                ReadingScheduler::new(hyper_pattern_matching, multi_stream_reader);
-            reading_scheduler.run();
+                    FJSFilteredSingleHyperPatternMatching<Notifier>,
+                    Notifier,
+                >::with_stream_sources(automaton, result_notifier, sources);
+            run_reading_scheduler(
+                hyper_pattern_matching,
+                input,
+                automaton,
+                check_alphabet,
+                None,
+                started,
+            )
         }
     }
-
-    info!("Hyper Pattern Matching completed successfully");
 }
@@ -1,10 +1,15 @@
+use itertools::Itertools;
 use log::debug;
 
 use crate::{
     automata_runner::{AppendOnlySequence, ReadableView},
     dfa_earliest_pattern_matcher::DFAEarliestPatternMatcher,
+    result_notifier::{MatchingInterval, MatchingResult},
+};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque},
+    hash::Hash,
 };
-use std::{collections::VecDeque, hash::Hash};
 
 /// Given a stream, it masks the elements not appearing in any matching
 /// pattern by replacing them with None. Elements that are part of a match
@@ -110,9 +115,7 @@ where
                     }
 
                     // Append the batch to the output stream
-                    for item in batch {
-                        self.output_stream.append(item);
-                    }
+                    self.output_stream.extend(batch);
                 }
 
                 // Mark the elements in a match as matched
@@ -140,9 +143,7 @@ where
                     }
 
                     // Append the batch to the output stream
-                    for item in batch {
-                        self.output_stream.append(item);
-                    }
+                    self.output_stream.extend(batch);
                 }
             }
         }
@@ -179,9 +180,7 @@ where
                 }
 
                 // Append the batch to the output stream
-                for item in batch {
-                    self.output_stream.append(item);
-                }
+                self.output_stream.extend(batch);
             }
 
             // Close the output stream
@@ -190,6 +189,194 @@ where
     }
 }
 
+/// Merges the matching results whose intervals are adjacent or overlapping into maximal spans.
+///
+/// Two results are merged if they have the same ids and the same intervals except for one
+/// dimension, where the intervals overlap or are adjacent, e.g., `(0, 1)` and `(2, 3)`. The merge
+/// is repeated until no more results can be merged. This is intended as a post-pass for compact
+/// reporting, e.g., when the same pattern matches at consecutive positions.
+///
+/// The returned results are sorted.
+pub fn merge_adjacent(results: &[MatchingResult]) -> Vec<MatchingResult> {
+    let mut merged: Vec<MatchingResult> = results.to_vec();
+    let dimensions = merged.iter().map(|r| r.intervals.len()).max().unwrap_or(0);
+    loop {
+        let before = merged.len();
+        for dimension in 0..dimensions {
+            merged = merge_adjacent_in(merged, dimension);
+        }
+        if merged.len() == before {
+            break;
+        }
+    }
+    merged.sort();
+    merged
+}
+
+/// Merges the results whose intervals differ only in `dimension`, where they overlap or are adjacent.
+fn merge_adjacent_in(results: Vec<MatchingResult>, dimension: usize) -> Vec<MatchingResult> {
+    // Group the results by the ids and the intervals of the other dimensions
+    let mut groups: BTreeMap<(Vec<usize>, Vec<MatchingInterval>), Vec<MatchingInterval>> =
+        BTreeMap::new();
+    let mut merged = Vec::with_capacity(results.len());
+    for result in results {
+        if dimension >= result.intervals.len() {
+            merged.push(result);
+            continue;
+        }
+        let mut others = result.intervals;
+        let interval = others.remove(dimension);
+        groups
+            .entry((result.ids, others))
+            .or_default()
+            .push(interval);
+    }
+
+    for ((ids, others), mut intervals) in groups {
+        intervals.sort();
+        let mut spans: Vec<MatchingInterval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match spans.last_mut() {
+                Some(last) if interval.start <= last.end + 1 => {
+                    last.end = last.end.max(interval.end);
+                }
+                _ => spans.push(interval),
+            }
+        }
+        for span in spans {
+            let mut intervals = others.clone();
+            intervals.insert(dimension, span);
+            merged.push(MatchingResult {
+                intervals,
+                ids: ids.clone(),
+            });
+        }
+    }
+    merged
+}
+
+/// Which of the matching results with the same ids and start positions [`dedup_by_start`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupPolicy {
+    /// The first result in the given order
+    First,
+    /// The result with the fewest positions in total, or the first one among them
+    Shortest,
+    /// The result with the most positions in total, or the first one among them
+    Longest,
+}
+
+/// Keeps only one matching result for each pair of the ids and the start positions, e.g., to
+/// drop the results differing only in their end positions.
+///
+/// The kept result is selected by `policy`. Unlike the leftmost-longest semantics, this looks only
+/// at the results, not at the automaton. The kept results are returned in the order of the first
+/// result with the same ids and start positions.
+pub fn dedup_by_start(results: &[MatchingResult], policy: DedupPolicy) -> Vec<MatchingResult> {
+    let total_len = |result: &MatchingResult| -> usize {
+        result.intervals.iter().map(MatchingInterval::len).sum()
+    };
+    let mut kept: Vec<MatchingResult> = Vec::new();
+    let mut index_of: HashMap<(&[usize], Vec<usize>), usize> = HashMap::new();
+    for result in results {
+        let starts = result.intervals.iter().map(|i| i.start).collect();
+        match index_of.entry((&result.ids, starts)) {
+            Entry::Vacant(entry) => {
+                entry.insert(kept.len());
+                kept.push(result.clone());
+            }
+            Entry::Occupied(entry) => {
+                let current = &mut kept[*entry.get()];
+                let replace = match policy {
+                    DedupPolicy::First => false,
+                    DedupPolicy::Shortest => total_len(result) < total_len(current),
+                    DedupPolicy::Longest => total_len(result) > total_len(current),
+                };
+                if replace {
+                    *current = result.clone();
+                }
+            }
+        }
+    }
+    debug!(
+        "Deduplicated {} results into {} by the start positions",
+        results.len(),
+        kept.len()
+    );
+    kept
+}
+
+/// Keeps the matching results whose intervals in `dimension` do not overlap, ignoring the other
+/// dimensions, e.g., to tokenize a track.
+///
+/// The results are examined greedily from the earliest start in `dimension`, preferring the
+/// longest interval among the same start and then the given order. A result is kept unless its
+/// interval overlaps with a kept one in `dimension` on the same input, i.e., with the same id of
+/// `dimension`. The kept results are returned in the order they are examined.
+///
+/// Panics if a result has no interval in `dimension`.
+pub fn select_non_overlapping_in(
+    results: &[MatchingResult],
+    dimension: usize,
+) -> Vec<MatchingResult> {
+    let mut candidates = results.iter().collect_vec();
+    candidates.sort_by_key(|result| {
+        let interval = &result.intervals[dimension];
+        (interval.start, std::cmp::Reverse(interval.end))
+    });
+    // The end of the last kept interval on each input
+    let mut last_end: HashMap<usize, usize> = HashMap::new();
+    let mut kept = Vec::new();
+    for result in candidates {
+        let interval = &result.intervals[dimension];
+        let id = result.ids[dimension];
+        if last_end.get(&id).is_some_and(|&end| interval.start <= end) {
+            continue;
+        }
+        last_end.insert(id, interval.end);
+        kept.push(result.clone());
+    }
+    debug!(
+        "Selected {} of {} results not overlapping in the dimension {}",
+        kept.len(),
+        results.len(),
+        dimension
+    );
+    kept
+}
+
+/// A constraint aligning the intervals of a matching result across the dimensions, e.g., to
+/// require synchronous matches without encoding the timing in the automaton.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlignedBy {
+    /// All the intervals have the same length
+    Length,
+    /// All the intervals start at the same position
+    Start,
+    /// All the intervals are the same
+    Interval,
+}
+
+impl AlignedBy {
+    /// Returns `true` if `intervals` satisfy this constraint.
+    pub fn keeps(&self, intervals: &[MatchingInterval]) -> bool {
+        match self {
+            AlignedBy::Length => intervals.iter().map(|i| i.len()).all_equal(),
+            AlignedBy::Start => intervals.iter().map(|i| i.start).all_equal(),
+            AlignedBy::Interval => intervals.iter().all_equal(),
+        }
+    }
+}
+
+/// Keeps only the matching results whose intervals satisfy `constraint`, preserving their order.
+pub fn filter_aligned(results: &[MatchingResult], constraint: AlignedBy) -> Vec<MatchingResult> {
+    results
+        .iter()
+        .filter(|result| constraint.keeps(&result.intervals))
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::Itertools;
@@ -590,4 +777,163 @@ mod tests {
             }
         }
     }
+
+    fn result(intervals: &[(usize, usize)], ids: &[usize]) -> MatchingResult {
+        MatchingResult {
+            intervals: intervals
+                .iter()
+                .map(|&(start, end)| MatchingInterval::new(start, end))
+                .collect(),
+            ids: ids.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_merge_adjacent() {
+        // Three consecutive single-position matches in dimension 0
+        let results = vec![
+            result(&[(2, 2), (5, 6)], &[0, 1]),
+            result(&[(0, 0), (5, 6)], &[0, 1]),
+            result(&[(1, 1), (5, 6)], &[0, 1]),
+        ];
+        assert_eq!(
+            merge_adjacent(&results),
+            vec![result(&[(0, 2), (5, 6)], &[0, 1])]
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent_keeps_separate() {
+        let results = vec![
+            // Not adjacent
+            result(&[(0, 0), (5, 6)], &[0, 1]),
+            result(&[(2, 3), (5, 6)], &[0, 1]),
+            // Different ids
+            result(&[(1, 1), (5, 6)], &[1, 0]),
+            // Overlapping in dimension 1
+            result(&[(2, 3), (6, 7)], &[0, 1]),
+        ];
+        assert_eq!(
+            merge_adjacent(&results),
+            vec![
+                result(&[(0, 0), (5, 6)], &[0, 1]),
+                result(&[(2, 3), (5, 7)], &[0, 1]),
+                result(&[(1, 1), (5, 6)], &[1, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_by_start() {
+        // The results of the small automaton on the inputs generated by `seq 10 | gen_abcd.awk`,
+        // with a duplicate and longer variants ending at the later positions
+        let results = vec![
+            result(&[(9, 9), (0, 0)], &[0, 0]),
+            result(&[(9, 9), (1, 2)], &[0, 0]),
+            result(&[(9, 9), (1, 3)], &[0, 0]),
+            result(&[(9, 9), (2, 2)], &[0, 0]),
+            result(&[(9, 9), (1, 2)], &[0, 0]),
+            result(&[(9, 9), (6, 7)], &[0, 0]),
+            result(&[(9, 9), (6, 7)], &[0, 1]),
+            result(&[(9, 9), (1, 4)], &[0, 0]),
+            result(&[(9, 9), (7, 7)], &[0, 0]),
+        ];
+
+        assert_eq!(
+            dedup_by_start(&results, DedupPolicy::First),
+            vec![
+                result(&[(9, 9), (0, 0)], &[0, 0]),
+                result(&[(9, 9), (1, 2)], &[0, 0]),
+                result(&[(9, 9), (2, 2)], &[0, 0]),
+                result(&[(9, 9), (6, 7)], &[0, 0]),
+                result(&[(9, 9), (6, 7)], &[0, 1]),
+                result(&[(9, 9), (7, 7)], &[0, 0]),
+            ]
+        );
+        // Only the results starting at 1 differ in the ends, and the first of them is the shortest
+        let shortest = dedup_by_start(&results, DedupPolicy::Shortest);
+        assert_eq!(shortest, dedup_by_start(&results, DedupPolicy::First));
+        let longest = dedup_by_start(&results, DedupPolicy::Longest);
+        assert_eq!(longest.len(), 6);
+        assert_eq!(longest[1], result(&[(9, 9), (1, 4)], &[0, 0]));
+        assert_eq!(
+            longest
+                .iter()
+                .filter(|r| r.intervals[1].start != 1)
+                .collect_vec(),
+            shortest
+                .iter()
+                .filter(|r| r.intervals[1].start != 1)
+                .collect_vec()
+        );
+
+        assert!(dedup_by_start(&[], DedupPolicy::Longest).is_empty());
+    }
+
+    #[test]
+    fn test_select_non_overlapping_in() {
+        let results = vec![
+            result(&[(1, 3), (7, 7)], &[0, 1]),
+            result(&[(0, 1), (0, 0)], &[0, 1]),
+            result(&[(0, 2), (5, 5)], &[0, 1]),
+            // Overlapping with the one above only in the dimension 1
+            result(&[(3, 4), (5, 5)], &[0, 1]),
+            result(&[(4, 4), (9, 9)], &[0, 1]),
+            // On another input in the dimension 0
+            result(&[(2, 2), (1, 1)], &[1, 0]),
+        ];
+
+        // The earliest and then longest one is kept among the overlapping ones in the dimension 0
+        assert_eq!(
+            select_non_overlapping_in(&results, 0),
+            vec![
+                result(&[(0, 2), (5, 5)], &[0, 1]),
+                result(&[(2, 2), (1, 1)], &[1, 0]),
+                result(&[(3, 4), (5, 5)], &[0, 1]),
+            ]
+        );
+        assert_eq!(
+            select_non_overlapping_in(&results, 1),
+            vec![
+                result(&[(0, 1), (0, 0)], &[0, 1]),
+                result(&[(2, 2), (1, 1)], &[1, 0]),
+                result(&[(0, 2), (5, 5)], &[0, 1]),
+                result(&[(1, 3), (7, 7)], &[0, 1]),
+                result(&[(4, 4), (9, 9)], &[0, 1]),
+            ]
+        );
+        assert!(select_non_overlapping_in(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_filter_aligned() {
+        // The results of `test_run` of the reading scheduler for the inputs "a a c" and "a d d"
+        let results = vec![
+            result(&[(0, 2), (1, 1)], &[0, 1]),
+            result(&[(1, 2), (1, 1)], &[0, 1]),
+            result(&[(2, 2), (1, 1)], &[0, 1]),
+            result(&[(0, 2), (2, 2)], &[0, 1]),
+            result(&[(1, 2), (2, 2)], &[0, 1]),
+            result(&[(2, 2), (2, 2)], &[0, 1]),
+        ];
+
+        assert_eq!(
+            filter_aligned(&results, AlignedBy::Interval),
+            vec![result(&[(2, 2), (2, 2)], &[0, 1])]
+        );
+        assert_eq!(
+            filter_aligned(&results, AlignedBy::Length),
+            vec![
+                result(&[(2, 2), (1, 1)], &[0, 1]),
+                result(&[(2, 2), (2, 2)], &[0, 1]),
+            ]
+        );
+        assert_eq!(
+            filter_aligned(&results, AlignedBy::Start),
+            vec![
+                result(&[(1, 2), (1, 1)], &[0, 1]),
+                result(&[(2, 2), (2, 2)], &[0, 1]),
+            ]
+        );
+    }
 }
@@ -1,6 +1,13 @@
-use std::collections::HashMap;
-use std::io::{self, BufRead};
+use log::warn;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// The interval to check a stream again when no line is available yet, e.g., in a followed file
+pub const POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// A trait representing a generic stream source.
 pub trait StreamSource: BufRead + Send {
@@ -15,10 +22,285 @@ impl<T: BufRead + Send + 'static> StreamSource for T {
     }
 }
 
+/// A reader concatenating several readers into one stream, e.g., rotated log files.
+///
+/// The lines continue across the boundaries of the readers. If a reader does not end with a
+/// newline, we insert one so that its last line is not merged with the first line of the next
+/// reader.
+pub struct ConcatenatedReader<R: BufRead> {
+    readers: VecDeque<R>,
+    /// The last byte consumed from the current reader
+    last_byte: Option<u8>,
+    /// Whether a newline is inserted before reading the next reader
+    pending_newline: bool,
+}
+
+impl<R: BufRead> ConcatenatedReader<R> {
+    /// Constructs a new `ConcatenatedReader` reading `readers` in order.
+    pub fn new(readers: Vec<R>) -> Self {
+        Self {
+            readers: readers.into(),
+            last_byte: None,
+            pending_newline: false,
+        }
+    }
+
+    /// Drops the exhausted readers until some data or the end of the last reader.
+    fn skip_exhausted(&mut self) -> io::Result<()> {
+        while !self.pending_newline && self.readers.len() > 1 {
+            if !self.readers[0].fill_buf()?.is_empty() {
+                break;
+            }
+            self.readers.pop_front();
+            self.pending_newline = self.last_byte.is_some_and(|byte| byte != b'\n');
+            self.last_byte = None;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ConcatenatedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for ConcatenatedReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.skip_exhausted()?;
+        if self.pending_newline {
+            return Ok(b"\n");
+        }
+        match self.readers.front_mut() {
+            Some(reader) => reader.fill_buf(),
+            None => Ok(&[]),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt == 0 {
+            return;
+        }
+        if self.pending_newline {
+            self.pending_newline = false;
+            return;
+        }
+        if let Some(reader) = self.readers.front_mut() {
+            // The consumed bytes are still in the buffer filled by `fill_buf`
+            if let Ok(buf) = reader.fill_buf() {
+                self.last_byte = buf.get(amt - 1).copied();
+            }
+            reader.consume(amt);
+        }
+    }
+}
+
+/// A reader following a growing input, e.g., a log file being written, like `tail -f`.
+///
+/// Only the complete lines are provided. If no complete line is available yet, the reader returns
+/// an error of [`io::ErrorKind::WouldBlock`] instead of the end of the input, so that
+/// [`ReadingScheduler`](crate::reading_scheduler::ReadingScheduler) waits for more lines. The end
+/// of the input is signaled only after `stop` is set and all the appended data is read.
+pub struct FollowingReader<R: Read> {
+    inner: R,
+    /// The bytes read from `inner`
+    pending: Vec<u8>,
+    /// The number of the bytes of `pending` already consumed
+    consumed: usize,
+    /// Whether to stop following at the current end of the input
+    stop: Arc<AtomicBool>,
+}
+
+impl<R: Read> FollowingReader<R> {
+    /// Constructs a new `FollowingReader` following `inner` until `stop` is set.
+    pub fn new(inner: R, stop: Arc<AtomicBool>) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+            consumed: 0,
+            stop,
+        }
+    }
+
+    /// Reads all the data currently available from `inner`.
+    fn read_available(&mut self) -> io::Result<()> {
+        self.pending.drain(..self.consumed);
+        self.consumed = 0;
+        let mut chunk = [0; 8192];
+        loop {
+            match self.inner.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(n) => self.pending.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for FollowingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for FollowingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Check the flag before reading so that the data appended before stopping is not missed
+        let stopped = self.stop.load(Ordering::Acquire);
+        if !self.pending[self.consumed..].contains(&b'\n') {
+            self.read_available()?;
+        }
+        let available = &self.pending[self.consumed..];
+        match available.iter().rposition(|&byte| byte == b'\n') {
+            Some(last_newline) => Ok(&available[..=last_newline]),
+            // The incomplete last line is provided only at the end
+            None if stopped => Ok(available),
+            None => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "waiting for a new line",
+            )),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed = (self.consumed + amt).min(self.pending.len());
+    }
+}
+
+/// A reader providing a field of the JSON events of each line as the actions, e.g., `a` of
+/// `{"evt":"a","id":1}` for the field `/evt`.
+///
+/// The field is specified by a JSON pointer (RFC 6901), e.g., `/evt` or `/payload/0`. A string
+/// field is provided as it is and the other fields in JSON, e.g., `1` or `true`. The rest of the
+/// event is ignored. The lines that are not JSON or do not have the field are skipped with a
+/// warning, and the blank lines are skipped silently.
+pub struct JsonFieldReader<R: BufRead> {
+    inner: R,
+    /// The JSON pointer to the field
+    pointer: String,
+    /// The current action and the number of its bytes consumed
+    line: Vec<u8>,
+    consumed: usize,
+    /// The number of the lines read from `inner`
+    line_number: usize,
+}
+
+impl<R: BufRead> JsonFieldReader<R> {
+    /// Constructs a new `JsonFieldReader` providing the field at `pointer` of the lines of `inner`.
+    pub fn new(inner: R, pointer: impl Into<String>) -> Self {
+        Self {
+            inner,
+            pointer: pointer.into(),
+            line: Vec::new(),
+            consumed: 0,
+            line_number: 0,
+        }
+    }
+
+    /// Returns the next action, or `None` at the end of `inner`.
+    fn next_action(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let mut line = String::new();
+            if self.inner.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(event) => match event.pointer(&self.pointer) {
+                    Some(serde_json::Value::String(action)) => return Ok(Some(action.clone())),
+                    Some(action) => return Ok(Some(action.to_string())),
+                    None => warn!(
+                        "Skip the line {} of the JSON input: {:?} has no field {}",
+                        self.line_number, line, self.pointer
+                    ),
+                },
+                Err(e) => warn!(
+                    "Skip the line {} of the JSON input: {:?} is not JSON: {}",
+                    self.line_number, line, e
+                ),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Read for JsonFieldReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for JsonFieldReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.consumed == self.line.len() {
+            self.line = self
+                .next_action()?
+                .map(|action| format!("{}\n", action).into_bytes())
+                .unwrap_or_default();
+            self.consumed = 0;
+        }
+        Ok(&self.line[self.consumed..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed = (self.consumed + amt).min(self.line.len());
+    }
+}
+
+/// The byte spans of the lines read by a [`MultiStreamReader`], shared among the clones.
+///
+/// The span of a line is `(begin, end)` in bytes from the beginning of its stream, where `end` is
+/// exclusive and the trailing whitespace is excluded, i.e., it is the span of the action fed to the
+/// matching.
+#[derive(Clone, Default)]
+pub struct LineSpans {
+    /// The spans of the lines of each stream
+    spans: Arc<Mutex<Vec<StreamLineSpans>>>,
+}
+
+/// The spans of the lines read from a stream.
+type StreamLineSpans = Vec<(usize, usize)>;
+
+impl LineSpans {
+    /// Returns the span of the `line`-th line (from 0) of the stream `n`, if it is read.
+    pub fn get(&self, n: usize, line: usize) -> Option<(usize, usize)> {
+        self.spans.lock().unwrap().get(n)?.get(line).copied()
+    }
+
+    /// Records the span of the next line of the stream `n`.
+    fn push(&self, n: usize, span: (usize, usize)) {
+        let mut spans = self.spans.lock().unwrap();
+        if spans.len() <= n {
+            spans.resize_with(n + 1, Vec::new);
+        }
+        spans[n].push(span);
+    }
+}
+
 /// A multi-stream reader supporting generic stream sources.
 pub struct MultiStreamReader {
     pub readers: Vec<Arc<Mutex<Box<dyn StreamSource>>>>,
     positions: Mutex<HashMap<usize, usize>>, // Keeps track of the read positions
+    /// The number of bytes read from each stream
+    byte_positions: Mutex<Vec<usize>>,
+    /// The spans of the lines read so far, if recorded
+    line_spans: Option<LineSpans>,
 }
 
 impl MultiStreamReader {
@@ -33,17 +315,49 @@ impl MultiStreamReader {
             positions.insert(i, 0);
         }
 
+        let byte_positions = Mutex::new(vec![0; readers.len()]);
         Self {
             readers,
             positions: Mutex::new(positions),
+            byte_positions,
+            line_spans: None,
         }
     }
 
+    /// Starts recording the byte spans of the lines, and returns them.
+    ///
+    /// The spans are kept for all the lines until the end, so they are recorded only on demand,
+    /// e.g., to highlight the matched regions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a line is already read, since its span would be missing.
+    pub fn record_line_spans(&mut self) -> LineSpans {
+        assert!(
+            self.byte_positions
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|&bytes| bytes == 0),
+            "The line spans must be recorded before reading any line"
+        );
+        self.line_spans
+            .get_or_insert_with(LineSpans::default)
+            .clone()
+    }
+
     /// Returns the number of streams.
-    pub fn size(&self) -> usize {
+    pub fn num_streams(&self) -> usize {
         self.readers.len()
     }
 
+    /// Returns the number of lines read from the specified stream so far.
+    ///
+    /// Reading at the end of the stream is not counted. Returns `None` for an invalid index.
+    pub fn lines_read(&self, n: usize) -> Option<usize> {
+        self.positions.lock().unwrap().get(&n).copied()
+    }
+
     /// Reads a line from the specified stream.
     pub fn read_line(&self, n: usize) -> io::Result<String> {
         let reader = self
@@ -53,18 +367,53 @@ impl MultiStreamReader {
 
         let mut reader = reader.lock().unwrap(); // Acquire mutable access
         let mut line = String::new();
-        reader.read_line(&mut line)?;
+        let bytes = reader.read_line(&mut line)?;
 
         // Update the position
-        let mut positions = self.positions.lock().unwrap();
-        if let Some(pos) = positions.get_mut(&n) {
-            *pos += 1;
+        if bytes > 0 {
+            let mut positions = self.positions.lock().unwrap();
+            if let Some(pos) = positions.get_mut(&n) {
+                *pos += 1;
+            }
+            let mut byte_positions = self.byte_positions.lock().unwrap();
+            let begin = byte_positions[n];
+            byte_positions[n] += bytes;
+            if let Some(line_spans) = &self.line_spans {
+                line_spans.push(n, (begin, begin + line.trim_end().len()));
+            }
         }
 
         Ok(line)
     }
 
+    /// Counts the lines in each stream from the current positions to the end, e.g., to estimate
+    /// the workload before the matching.
+    ///
+    /// The lines are read, so this exhausts the streams: no more line is read afterwards. For a
+    /// stream that is not at the end yet, e.g., a followed file, this waits for its end.
+    pub fn count_symbols(&self) -> Vec<usize> {
+        (0..self.num_streams())
+            .map(|n| {
+                let mut count = 0;
+                loop {
+                    match self.read_line(n) {
+                        // An empty string without a newline is the end of the stream
+                        Ok(line) if !line.is_empty() => count += 1,
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(POLL_INTERVAL)
+                        }
+                        _ => break,
+                    }
+                }
+                count
+            })
+            .collect()
+    }
+
     /// Checks if a line can be read from the specified stream without blocking.
+    ///
+    /// Returns an error of [`io::ErrorKind::WouldBlock`] if the stream is not at the end but no
+    /// line is available yet, e.g., for [`FollowingReader`].
     pub fn is_available(&self, n: usize) -> io::Result<bool> {
         let reader = self
             .readers
@@ -74,6 +423,8 @@ impl MultiStreamReader {
         let mut reader = reader.lock().unwrap(); // Acquire mutable access
         match reader.fill_buf() {
             Ok(buf) => Ok(!buf.is_empty()), // Data available
+            // Not at the end of the stream, e.g., of a followed file
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Err(e),
             Err(_) => Ok(false),
         }
     }
@@ -85,6 +436,7 @@ mod tests {
     use crate::shared_buffer::SharedBuffer;
     use std::fs::File;
     use std::io::BufReader;
+    use std::io::Cursor;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -132,7 +484,7 @@ mod tests {
 
         let reader = MultiStreamReader::new(sources);
 
-        assert_eq!(reader.size(), 3);
+        assert_eq!(reader.num_streams(), 3);
         Ok(())
     }
 
@@ -157,6 +509,144 @@ mod tests {
         Ok(())
     }
 
+    /// Tests counting the remaining lines of each stream.
+    #[test]
+    fn test_count_symbols() -> io::Result<()> {
+        let temp_file1 = create_temp_file("line1\nline2\nline3");
+        let temp_file2 = create_temp_file("lineA\nlineB");
+
+        let sources: Vec<Box<dyn StreamSource>> = vec![
+            Box::new(BufReader::new(File::open(temp_file1.path())?)),
+            Box::new(BufReader::new(File::open(temp_file2.path())?)),
+        ];
+
+        let multi_reader = MultiStreamReader::new(sources);
+
+        assert_eq!(multi_reader.read_line(0)?, "line1\n");
+        assert_eq!(multi_reader.count_symbols(), vec![2, 2]);
+        // The streams are exhausted
+        assert_eq!(multi_reader.count_symbols(), vec![0, 0]);
+
+        Ok(())
+    }
+
+    /// Tests counting the lines read from each stream.
+    #[test]
+    fn test_lines_read() -> io::Result<()> {
+        let temp_file1 = create_temp_file("line1\nline2");
+        let temp_file2 = create_temp_file("lineA");
+
+        let sources: Vec<Box<dyn StreamSource>> = vec![
+            Box::new(BufReader::new(File::open(temp_file1.path())?)),
+            Box::new(BufReader::new(File::open(temp_file2.path())?)),
+        ];
+
+        let multi_reader = MultiStreamReader::new(sources);
+        assert_eq!(multi_reader.num_streams(), 2);
+        assert_eq!(multi_reader.lines_read(0), Some(0));
+
+        multi_reader.read_line(0)?;
+        multi_reader.read_line(0)?;
+        multi_reader.read_line(1)?;
+        // Reading at the end of the stream is not counted
+        assert_eq!(multi_reader.read_line(1)?, "");
+        assert_eq!(multi_reader.lines_read(0), Some(2));
+        assert_eq!(multi_reader.lines_read(1), Some(1));
+        assert_eq!(multi_reader.lines_read(2), None);
+
+        Ok(())
+    }
+
+    /// Tests recording the byte spans of the lines.
+    #[test]
+    fn test_line_spans() -> io::Result<()> {
+        let sources: Vec<Box<dyn StreamSource>> = vec![
+            Box::new(Cursor::new(b"ab\nc  \n\ndef".to_vec())),
+            Box::new(Cursor::new(b"xyz\n".to_vec())),
+        ];
+        let mut multi_reader = MultiStreamReader::new(sources);
+        let spans = multi_reader.record_line_spans();
+        for _ in 0..5 {
+            multi_reader.read_line(0)?;
+        }
+        multi_reader.read_line(1)?;
+
+        // The trailing whitespace is not in the span
+        assert_eq!(spans.get(0, 0), Some((0, 2)));
+        assert_eq!(spans.get(0, 1), Some((3, 4)));
+        assert_eq!(spans.get(0, 2), Some((7, 7)));
+        assert_eq!(spans.get(0, 3), Some((8, 11)));
+        assert_eq!(spans.get(0, 4), None);
+        assert_eq!(spans.get(1, 0), Some((0, 3)));
+        assert_eq!(spans.get(2, 0), None);
+
+        Ok(())
+    }
+
+    /// Tests concatenating readers into one stream.
+    #[test]
+    fn test_concatenated_reader() -> io::Result<()> {
+        let readers = vec![
+            io::Cursor::new(b"line1\nline2".to_vec()),
+            io::Cursor::new(Vec::new()),
+            io::Cursor::new(b"line3\n".to_vec()),
+            io::Cursor::new(b"line4".to_vec()),
+        ];
+        let sources: Vec<Box<dyn StreamSource>> = vec![Box::new(ConcatenatedReader::new(readers))];
+        let multi_reader = MultiStreamReader::new(sources);
+
+        // The last line of a reader without the newline is not merged with the next one
+        assert_eq!(multi_reader.read_line(0)?, "line1\n");
+        assert_eq!(multi_reader.read_line(0)?, "line2\n");
+        assert!(multi_reader.is_available(0)?);
+        assert_eq!(multi_reader.read_line(0)?, "line3\n");
+        assert_eq!(multi_reader.read_line(0)?, "line4");
+        assert!(!multi_reader.is_available(0)?);
+        assert_eq!(multi_reader.read_line(0)?, "");
+        assert_eq!(multi_reader.lines_read(0), Some(4));
+
+        let mut contents = String::new();
+        ConcatenatedReader::new(vec![
+            io::Cursor::new(b"a".to_vec()),
+            io::Cursor::new(b"b\n".to_vec()),
+        ])
+        .read_to_string(&mut contents)?;
+        assert_eq!(contents, "a\nb\n");
+
+        Ok(())
+    }
+
+    /// Tests providing a field of the JSON events as the actions.
+    #[test]
+    fn test_json_field_reader() -> io::Result<()> {
+        let events = concat!(
+            "{\"evt\":\"a\",\"id\":1}\n",
+            "\n",
+            "not json\n",
+            "{\"id\":2}\n",
+            "{\"evt\":3,\"id\":3}\n",
+            "{\"evt\":\"b\"}"
+        );
+        let sources: Vec<Box<dyn StreamSource>> = vec![Box::new(JsonFieldReader::new(
+            Cursor::new(events.as_bytes().to_vec()),
+            "/evt",
+        ))];
+        let multi_reader = MultiStreamReader::new(sources);
+
+        // The blank, non-JSON, and field-less lines are skipped
+        assert_eq!(multi_reader.read_line(0)?, "a\n");
+        assert_eq!(multi_reader.read_line(0)?, "3\n");
+        assert_eq!(multi_reader.read_line(0)?, "b\n");
+        assert_eq!(multi_reader.read_line(0)?, "");
+
+        let mut contents = String::new();
+        JsonFieldReader::new(Cursor::new(b"{\"e\":[\"x\",\"y\"]}\n".to_vec()), "/e/1")
+            .read_to_string(&mut contents)?;
+        assert_eq!(contents, "y\n");
+
+        Ok(())
+    }
+
     /// Tests reading lines and checking availability.
     #[test]
     fn test_is_available_with_temp_files() -> io::Result<()> {
@@ -171,7 +661,7 @@ mod tests {
 
         let reader = MultiStreamReader::new(sources);
 
-        assert_eq!(reader.size(), 3);
+        assert_eq!(reader.num_streams(), 3);
 
         // Check availability and read lines
         assert!(reader.is_available(0)?);
@@ -1,10 +1,14 @@
-use crate::automata::NFAH;
+use crate::automata::{Automata, VariableLabel};
 use crate::automata_runner::{AppendOnlySequence, NFAHRunner};
-use crate::hyper_pattern_matching::{HyperPatternMatching, PatternMatchingAutomataRunner};
+use crate::hyper_pattern_matching::{
+    check_dimensions, hot_trace_enabled, HyperPatternMatching, PatternMatchingAutomataRunner,
+};
 use crate::result_notifier::{MatchingInterval, ResultNotifier};
+use crate::symbol_matcher::SharedSymbolMatcher;
 use itertools::Itertools;
 use log::trace;
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
 /// the element in the waiting queue of hyper pattern matching algorithms based on priority-queue.
 #[derive(Debug, Clone, Ord, Eq, PartialEq)]
@@ -72,22 +76,132 @@ impl PartialOrd for StartPosition {
     }
 }
 
-pub struct NaiveHyperPatternMatching<'a, Notifier: ResultNotifier> {
-    automata_runner: PatternMatchingAutomataRunner<'a>,
+/// Returns `true` if the start position is already read on all the tracks assigned by `ids`.
+///
+/// A trial starting at an unread position must wait because the readable views are clamped to
+/// the end of the sequences.
+pub fn is_available(
+    sequences: &[AppendOnlySequence<String>],
+    start_position: &StartPosition,
+    ids: &[usize],
+) -> bool {
+    ids.iter()
+        .zip(&start_position.start_indices)
+        .all(|(&id, &start)| start <= sequences[id].len())
+}
+
+/// Returns `false` if a matching trial from `start_position` never succeeds because the action at
+/// the start on some track is not in the first symbols of its variable.
+///
+/// `first_symbols` is given by [`NFAH::first_symbols_per_variable`]. The start positions not read
+/// yet are kept unless the track is closed.
+pub fn starts_with_first_symbols(
+    sequences: &[AppendOnlySequence<String>],
+    first_symbols: &[Option<HashSet<String>>],
+    start_position: &StartPosition,
+    ids: &[usize],
+) -> bool {
+    ids.iter()
+        .zip(&start_position.start_indices)
+        .zip(first_symbols)
+        .all(|((&id, &start), symbols)| {
+            let Some(symbols) = symbols else {
+                return true;
+            };
+            match sequences[id].get(start) {
+                Some(action) => symbols.contains(&action),
+                None => !sequences[id].is_closed(),
+            }
+        })
+}
+
+/// Notifies the matches of the final configurations of `automata_runner` with `dimensions`
+/// variables to `notifier` and returns the number of the notified matches.
+///
+/// With `matched_ids`, i.e., in the first-only mode, only the earliest match is notified for each
+/// id-assignment not in `matched_ids` yet. Then, the id-assignment is added to `matched_ids`, and
+/// its waiting queue and configurations are dropped so that no more matching trials are made for
+/// it. The earliest match is chosen only among the final configurations at the call, i.e., the
+/// matches found in the same step: a later step may find a match starting earlier, but it is not
+/// notified.
+pub fn notify_final_configurations<
+    Notifier: ResultNotifier,
+    Position,
+    L: VariableLabel + Eq + Hash + Clone,
+>(
+    automata_runner: &mut PatternMatchingAutomataRunner<L>,
+    dimensions: usize,
+    waiting_queues: &mut HashMap<Vec<usize>, Vec<Position>>,
+    notifier: &mut Notifier,
+    matched_ids: Option<&mut HashSet<Vec<usize>>>,
+) -> usize {
+    let mut results = automata_runner
+        .get_final_configurations()
+        .iter()
+        .map(|c| {
+            let intervals = (0..dimensions)
+                .map(|i| MatchingInterval::new(c.matching_begin[i], c.input_sequence[i].start - 1))
+                .collect_vec();
+            (c.ids.clone(), intervals)
+        })
+        .collect_vec();
+    let Some(matched_ids) = matched_ids else {
+        for (ids, intervals) in &results {
+            notifier.notify(intervals, ids);
+        }
+        return results.len();
+    };
+    // Sort so that the earliest match comes first for each id-assignment
+    results.sort();
+    let mut notified = 0;
+    for (ids, intervals) in results {
+        if matched_ids.insert(ids.clone()) {
+            notifier.notify(&intervals, &ids);
+            notified += 1;
+            if let Some(waiting_queue) = waiting_queues.get_mut(&ids) {
+                waiting_queue.clear();
+            }
+            automata_runner
+                .current_configurations
+                .retain(|c| c.ids != ids);
+        }
+    }
+    notified
+}
+
+pub struct NaiveHyperPatternMatching<'a, Notifier: ResultNotifier, L = (String, usize)> {
+    automata_runner: PatternMatchingAutomataRunner<'a, L>,
     notifier: Notifier,
     sequences: Vec<AppendOnlySequence<String>>,
+    /// The number of variables of the automaton, which may differ from the number of sequences
+    dimensions: usize,
     read_size: Vec<usize>,
     waiting_queues: HashMap<Vec<usize>, Vec<StartPosition>>,
     /// Either we reached the end of the sequences
     eof: Vec<bool>,
+    /// If true, only the first match for each id-assignment is notified, i.e., the earliest one
+    /// among the matches found in the same step
+    first_only: bool,
+    /// The id-assignments for which a match is already notified in the first-only mode
+    matched_ids: HashSet<Vec<usize>>,
+    /// The actions an accepted word can start with on each variable
+    first_symbols: Vec<Option<HashSet<String>>>,
+    /// The number of start positions dropped by the first symbols
+    pruned_start_positions: usize,
+    /// The number of matches notified so far
+    matches_found: usize,
 }
 
-impl<'a, Notifier: ResultNotifier> NaiveHyperPatternMatching<'a, Notifier> {
+impl<'a, Notifier: ResultNotifier, L: VariableLabel + Eq + Hash + Clone>
+    NaiveHyperPatternMatching<'a, Notifier, L>
+{
     pub fn new(
-        automaton: &'a NFAH<'a>,
+        automaton: &'a Automata<'a, L>,
         notifier: Notifier,
         sequences: Vec<AppendOnlySequence<String>>,
+        first_only: bool,
     ) -> Self {
+        check_dimensions(automaton.dimensions, sequences.len());
         let mut automata_runner = PatternMatchingAutomataRunner::new(automaton);
         let read_size = vec![0; sequences.len()];
         let eof = vec![false; sequences.len()];
@@ -114,9 +228,64 @@ impl<'a, Notifier: ResultNotifier> NaiveHyperPatternMatching<'a, Notifier> {
             automata_runner,
             notifier,
             sequences,
+            dimensions: automaton.dimensions,
             read_size,
             waiting_queues,
             eof,
+            first_only,
+            matched_ids: HashSet::new(),
+            first_symbols: automaton.first_symbols_per_variable(),
+            pruned_start_positions: 0,
+            matches_found: 0,
+        }
+    }
+
+    /// Compares the input symbols and the letters of the automaton by `symbol_matcher`.
+    ///
+    /// The start positions are no longer pruned by the first symbols, which are the exact letters.
+    pub fn set_symbol_matcher(&mut self, symbol_matcher: SharedSymbolMatcher) {
+        self.automata_runner.set_symbol_matcher(symbol_matcher);
+        self.first_symbols.fill(None);
+    }
+
+    /// Returns the number of start positions dropped without a matching trial because the action
+    /// at the start is not in the first symbols of the automaton.
+    pub fn pruned_start_positions(&self) -> usize {
+        self.pruned_start_positions
+    }
+
+    /// Puts the successors of `position` within the range to the waiting queue of `id`.
+    fn enqueue_successors(&mut self, position: &StartPosition, id: &[usize]) {
+        let mut valid_successors = position
+            .immediate_successors_filtered(|successor| self.in_range(successor, id))
+            .collect_vec();
+        let waiting_queue = self.waiting_queues.get_mut(id).unwrap();
+        waiting_queue.append(&mut valid_successors);
+        waiting_queue.sort_by(|a, b| a.cmp(b).reverse());
+        waiting_queue.dedup();
+    }
+
+    /// Pops the next start position to try for `id`.
+    ///
+    /// The positions not starting with the first symbols are dropped, but their successors are
+    /// still put to the waiting queue. Returns `None` if the queue is empty or the next position is
+    /// not read on all the tracks yet.
+    fn pop_start_position(&mut self, id: &[usize]) -> Option<StartPosition> {
+        loop {
+            let waiting_queue = self.waiting_queues.get_mut(id).unwrap();
+            // Postpone the trial until the start position is read on all the tracks
+            if !waiting_queue
+                .last()
+                .is_some_and(|position| is_available(&self.sequences, position, id))
+            {
+                return None;
+            }
+            let position = waiting_queue.pop().unwrap();
+            if starts_with_first_symbols(&self.sequences, &self.first_symbols, &position, id) {
+                return Some(position);
+            }
+            self.pruned_start_positions += 1;
+            self.enqueue_successors(&position, id);
         }
     }
 
@@ -129,42 +298,28 @@ impl<'a, Notifier: ResultNotifier> NaiveHyperPatternMatching<'a, Notifier> {
         }
         true
     }
-}
 
-impl<Notifier: ResultNotifier> HyperPatternMatching for NaiveHyperPatternMatching<'_, Notifier> {
-    fn feed(&mut self, action: &str, track: usize) {
-        trace!(
-            "Call of NaiveHyperPatternMatching::feed({}, {})",
-            action,
-            track
-        );
-        self.sequences[track].append(action.to_string());
-        self.read_size[track] += 1;
+    /// Runs the configurations on the available input, notifies the matches, and starts new
+    /// matching trials for the id-assignments without running configurations.
+    ///
+    /// Returns `true` if a new matching trial is started.
+    fn step(&mut self) -> bool {
         self.automata_runner.consume();
-        let final_configurations = self.automata_runner.get_final_configurations();
-        let dimensions = self.dimensions();
-        trace!(
-            "{:?} matching are found in NaiveHyperPatternMatching::feed.",
-            final_configurations.len()
-        );
-        final_configurations.iter().for_each(|c| {
-            let mut intervals = Vec::with_capacity(dimensions);
-            for i in 0..dimensions {
-                let begin = c.matching_begin[i];
-                let end = c.input_sequence[i].start - 1;
-                intervals.push(MatchingInterval::new(begin, end));
-            }
-            self.notifier.notify(&intervals, &c.ids);
-        });
-        trace!(
-            "Number of configurations before reduction: {:?}.",
-            self.automata_runner.current_configurations.len()
-        );
+        self.notify_final_configurations();
+        let trace_enabled = hot_trace_enabled();
+        if trace_enabled {
+            trace!(
+                "Number of configurations before reduction: {:?}.",
+                self.automata_runner.current_configurations.len()
+            );
+        }
         self.automata_runner.remove_non_waiting_configurations();
-        trace!(
-            "Number of configurations after reduction: {:?}.",
-            self.automata_runner.current_configurations.len()
-        );
+        if trace_enabled {
+            trace!(
+                "Number of configurations after reduction: {:?}.",
+                self.automata_runner.current_configurations.len()
+            );
+        }
         let current_ids = self
             .automata_runner
             .current_configurations
@@ -172,29 +327,23 @@ impl<Notifier: ResultNotifier> HyperPatternMatching for NaiveHyperPatternMatchin
             .map(|c| c.ids.clone())
             .collect::<HashSet<Vec<usize>>>();
         let keys = self.waiting_queues.keys().cloned().collect_vec();
+        let mut started = false;
         for id in keys {
             if !current_ids.contains(&id) {
-                let new_position = {
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.pop()
-                };
                 // Start new matching trial
-                if let Some(new_position) = new_position {
-                    let mut valid_successors = new_position
-                        .immediate_successors_filtered(|successor| self.in_range(successor, &id))
-                        .collect_vec();
-                    // Put the successors to the waiting queue
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.append(&mut valid_successors);
-                    waiting_queue.sort_by(|a, b| a.cmp(b).reverse());
-                    waiting_queue.dedup();
+                if let Some(new_position) = self.pop_start_position(&id) {
+                    started = true;
+                    self.enqueue_successors(&new_position, &id);
 
-                    trace!("[NaiveHyperPatternMatching::feed] Start new matching trial from {:?} for {:?})", new_position, id);
+                    if trace_enabled {
+                        trace!("[NaiveHyperPatternMatching::step] Start new matching trial from {:?} for {:?})", new_position, id);
+                    }
                     let input_sequence = id
                         .iter()
-                        .map(|&i| {
+                        .zip(&new_position.start_indices)
+                        .map(|(&i, &start)| {
                             let mut view = self.sequences[i].readable_view();
-                            view.advance_readable(new_position.start_indices[i]);
+                            view.advance_readable(start);
                             view
                         })
                         .collect_vec();
@@ -203,48 +352,80 @@ impl<Notifier: ResultNotifier> HyperPatternMatching for NaiveHyperPatternMatchin
                 }
             }
         }
+        started
+    }
+
+    /// Notifies the matching results of the final configurations.
+    ///
+    /// In the first-only mode, only the earliest match for each id-assignment is notified. See
+    /// [`notify_final_configurations`].
+    fn notify_final_configurations(&mut self) {
+        let dimensions = self.dimensions();
+        let notified = notify_final_configurations(
+            &mut self.automata_runner,
+            dimensions,
+            &mut self.waiting_queues,
+            &mut self.notifier,
+            self.first_only.then_some(&mut self.matched_ids),
+        );
+        if hot_trace_enabled() {
+            trace!(
+                "{:?} matching are found in NaiveHyperPatternMatching::feed.",
+                notified
+            );
+        }
+        self.matches_found += notified;
+    }
+}
+
+impl<Notifier: ResultNotifier, L: VariableLabel + Eq + Hash + Clone> HyperPatternMatching
+    for NaiveHyperPatternMatching<'_, Notifier, L>
+{
+    fn feed(&mut self, action: &str, track: usize) {
+        if hot_trace_enabled() {
+            trace!(
+                "Call of NaiveHyperPatternMatching::feed({}, {})",
+                action,
+                track
+            );
+        }
+        self.sequences[track].append(action.to_string());
+        self.read_size[track] += 1;
+        self.step();
+    }
+
+    fn flush(&mut self) {
+        // Repeat until all the trials wait for more input
+        while self.step() {}
+        self.notifier.flush();
     }
 
     fn dimensions(&self) -> usize {
-        self.sequences.len()
+        self.dimensions
+    }
+
+    fn matches_found(&self) -> usize {
+        self.matches_found
     }
 
     fn consume_remaining(&mut self) {
         self.automata_runner.consume();
-        let final_configurations = self.automata_runner.get_final_configurations();
-        let dimensions = self.dimensions();
-        final_configurations.iter().for_each(|c| {
-            let mut intervals = Vec::with_capacity(dimensions);
-            for i in 0..dimensions {
-                let begin = c.matching_begin[i];
-                let end = c.input_sequence[i].start - 1;
-                intervals.push(MatchingInterval::new(begin, end));
-            }
-            self.notifier.notify(&intervals, &c.ids);
-        });
+        self.notify_final_configurations();
         while self.waiting_queues.values().any(|f| !f.is_empty()) {
             self.automata_runner.current_configurations.clear();
             let keys = self.waiting_queues.keys().cloned().collect_vec();
+            let mut started = false;
             for id in keys {
-                let new_position = {
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.pop()
-                };
                 // Start new matching trial
-                if let Some(new_position) = new_position {
-                    let mut valid_successors = new_position
-                        .immediate_successors_filtered(|successor| self.in_range(successor, &id))
-                        .collect_vec();
-                    // Put the successors to the waiting queue
-                    let waiting_queue = self.waiting_queues.get_mut(&id).unwrap();
-                    waiting_queue.append(&mut valid_successors);
-                    waiting_queue.sort_by(|a, b| a.cmp(b).reverse());
-                    waiting_queue.dedup();
+                if let Some(new_position) = self.pop_start_position(&id) {
+                    started = true;
+                    self.enqueue_successors(&new_position, &id);
                     let input_sequence = id
                         .iter()
-                        .map(|&i| {
+                        .zip(&new_position.start_indices)
+                        .map(|(&i, &start)| {
                             let mut view = self.sequences[i].readable_view();
-                            view.advance_readable(new_position.start_indices[i]);
+                            view.advance_readable(start);
                             view
                         })
                         .collect_vec();
@@ -252,36 +433,77 @@ impl<Notifier: ResultNotifier> HyperPatternMatching for NaiveHyperPatternMatchin
                         .insert_from_initial_states(input_sequence, id)
                 }
             }
+            // The remaining positions wait for the tracks not closed yet
+            if !started {
+                break;
+            }
             self.automata_runner.consume();
-            let final_configurations = self.automata_runner.get_final_configurations();
-            let dimensions = self.dimensions();
-            final_configurations.iter().for_each(|c| {
-                let mut intervals = Vec::with_capacity(dimensions);
-                for i in 0..dimensions {
-                    let begin = c.matching_begin[i];
-                    let end = c.input_sequence[i].start - 1;
-                    intervals.push(MatchingInterval::new(begin, end));
-                }
-                self.notifier.notify(&intervals, &c.ids);
-            });
+            self.notify_final_configurations();
         }
     }
 
     fn set_eof(&mut self, track: usize) {
         self.sequences[track].close();
         self.eof[track] = true;
+        // Drop the waiting start positions beyond the end of the closed track. The other tracks
+        // may still be open, so the remaining positions keep being tried.
+        let read_size = self.read_size[track];
+        for (ids, waiting_queue) in self.waiting_queues.iter_mut() {
+            waiting_queue.retain(|position| {
+                ids.iter()
+                    .zip(&position.start_indices)
+                    .all(|(&id, &start)| id != track || start < read_size)
+            });
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::automata::NFAH;
     use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
     use crate::reading_scheduler::ReadingScheduler;
-    use crate::result_notifier::SharedBufferResultNotifier;
+    use crate::result_notifier::{
+        GroupedJsonResultNotifier, MatchingResult, SharedBufferResultNotifier, TallyResultNotifier,
+    };
     use crate::shared_buffer::SharedBuffer;
+    use crate::symbol_matcher::PrefixStrippingSymbolMatcher;
+    use crate::tests::utils::{
+        create_repeated_a_automaton, create_small_automaton, install_recording_logger,
+        verify_first_only,
+    };
+    use std::rc::Rc;
     use typed_arena::Arena;
 
+    /// Runs the naive matching on the input of `test_run` notifying the results to `notifier`.
+    fn run_test_input<'a, Notifier: ResultNotifier>(automaton: &'a NFAH<'a>, notifier: Notifier) {
+        let input_buffers = vec![SharedBuffer::new(), SharedBuffer::new()];
+        let reader = MultiStreamReader::new(
+            input_buffers
+                .clone()
+                .into_iter()
+                .map(|buf| Box::new(buf) as Box<dyn StreamSource>)
+                .collect(),
+        );
+        let matching = NaiveHyperPatternMatching::new(
+            automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+        let mut scheduler = ReadingScheduler::new(matching, reader);
+
+        input_buffers[0].push("a");
+        input_buffers[1].push("b");
+        input_buffers[0].push("a");
+        input_buffers[1].push("b");
+        input_buffers[0].push("c");
+        input_buffers[1].push("d");
+
+        scheduler.run();
+    }
+
     #[test]
     fn test_run() {
         let state_arena = Arena::new();
@@ -318,6 +540,7 @@ mod tests {
             &automaton,
             notifier,
             vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
         );
 
         let mut scheduler = ReadingScheduler::new(matching, reader);
@@ -413,6 +636,78 @@ mod tests {
         assert!(result_sink.pop().is_none());
     }
 
+    #[test]
+    fn test_run_tally() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let result_buffer = SharedBuffer::new();
+        let mut result_sink = result_buffer.make_sink();
+        run_test_input(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+        );
+        let mut total = 0;
+        while result_sink.pop().is_some() {
+            total += 1;
+        }
+
+        let notifier = TallyResultNotifier::new();
+        run_test_input(&automaton, notifier.clone());
+        let tally = notifier.tally();
+
+        assert!(total > 0);
+        assert_eq!(tally.values().sum::<usize>(), total);
+        assert_eq!(tally[&vec![0, 1]], total);
+    }
+
+    #[test]
+    fn test_run_grouped_json() -> std::io::Result<()> {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let temp_file = tempfile::NamedTempFile::new()?;
+        run_test_input(
+            &automaton,
+            GroupedJsonResultNotifier::new(Box::new(temp_file.reopen()?)),
+        );
+
+        // The document is written when the notifier is dropped
+        let document: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(temp_file.path())?)?;
+        let groups = document.as_object().unwrap();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["[0,1]"]);
+        let matches = groups["[0,1]"].as_array().unwrap();
+        let mut expected = Vec::new();
+        for start0 in 0..=2 {
+            for start1 in 0..=2 {
+                expected.push(serde_json::json!([
+                    { "start": start0, "end": 2 },
+                    { "start": start1, "end": 2 },
+                ]));
+            }
+        }
+        assert_eq!(matches, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_first_only() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_repeated_a_automaton(&state_arena, &transition_arena);
+
+        let result_buffer = SharedBuffer::new();
+        let matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            true,
+        );
+        verify_first_only(matching, &result_buffer);
+    }
     #[test]
     fn test_start_position_order() {
         let start_positions = [
@@ -439,4 +734,345 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_independent_eof() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+
+        // Accepts "a" on the first variable followed by "b" on the second variable
+        let s1 = automaton.add_state(true, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "b".to_string(), 1, s3);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+
+        let mut matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+
+        // Stream 0 ends at index 1
+        for action in ["a", "b"] {
+            matching.feed(action, 0);
+        }
+        matching.set_eof(0);
+        // No start position beyond the end of stream 0 is waiting
+        for (ids, waiting_queue) in &matching.waiting_queues {
+            for position in waiting_queue {
+                for (var, &start) in position.start_indices.iter().enumerate() {
+                    assert!(ids[var] != 0 || start < 2, "{:?} for {:?}", position, ids);
+                }
+            }
+        }
+
+        // Stream 1 continues to index 5
+        for action in ["b", "c", "a", "c", "c", "b"] {
+            matching.feed(action, 1);
+        }
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        let expected: HashSet<MatchingResult> = [
+            (0, 0, 0, 1),
+            (0, 0, 1, 0),
+            (0, 0, 1, 5),
+            (2, 1, 0, 1),
+            (2, 1, 1, 0),
+            (2, 1, 1, 5),
+        ]
+        .into_iter()
+        .map(|(begin0, id0, id1, begin1)| MatchingResult {
+            intervals: vec![
+                MatchingInterval::new(begin0, begin0),
+                MatchingInterval::new(begin1, begin1),
+            ],
+            ids: vec![id0, id1],
+        })
+        .collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_flush() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 1);
+
+        // Accepts "aac" and "b"
+        let s1 = automaton.add_state(true, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let s4 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "a".to_string(), 0, s3);
+        automaton.add_nfah_transition(s3, "c".to_string(), 0, s4);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s4);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut pop_intervals = || {
+            let mut intervals = vec![];
+            while let Some(result) = result_sink.pop() {
+                intervals.push(result.intervals[0].clone());
+            }
+            intervals
+        };
+
+        let mut matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new()],
+            false,
+        );
+
+        for action in ["a", "a", "b"] {
+            matching.feed(action, 0);
+        }
+        // The trial from 0 fails at "b", and the trials from 1 and 2 are not run yet
+        assert!(pop_intervals().is_empty());
+        matching.flush();
+        assert_eq!(pop_intervals(), vec![MatchingInterval::new(2, 2)]);
+
+        // No new match is found afterwards
+        matching.feed("c", 0);
+        matching.flush();
+        matching.set_eof(0);
+        matching.consume_remaining();
+        assert!(pop_intervals()
+            .iter()
+            .all(|interval| *interval == MatchingInterval::new(2, 2)));
+    }
+
+    #[test]
+    fn test_sequences_other_than_dimensions() {
+        // "a" on the first variable followed by "b" on the second variable
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+
+        // The intervals are given for each variable, not for each sequence
+        for (inputs, expected) in [
+            (vec!["ab"], vec![(vec![0, 0], 0, 1)]),
+            (
+                vec!["a", "b", "b"],
+                vec![(vec![0, 1], 0, 0), (vec![0, 2], 0, 0)],
+            ),
+        ] {
+            let result_buffer = SharedBuffer::new();
+            let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+            let mut result_sink = result_buffer.make_sink();
+            let mut matching = NaiveHyperPatternMatching::new(
+                &automaton,
+                notifier,
+                inputs.iter().map(|_| AppendOnlySequence::new()).collect(),
+                false,
+            );
+            assert_eq!(matching.dimensions(), 2);
+            for (track, input) in inputs.iter().enumerate() {
+                for action in input.chars() {
+                    matching.feed(&action.to_string(), track);
+                }
+                matching.set_eof(track);
+            }
+            matching.consume_remaining();
+
+            let mut results = HashSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            let expected: HashSet<MatchingResult> = expected
+                .into_iter()
+                .map(|(ids, begin0, begin1)| MatchingResult {
+                    intervals: vec![
+                        MatchingInterval::new(begin0, begin0),
+                        MatchingInterval::new(begin1, begin1),
+                    ],
+                    ids,
+                })
+                .collect();
+            assert_eq!(results, expected);
+        }
+    }
+
+    #[test]
+    fn test_first_symbols_pruning() {
+        // "a" on track 0 and then "b" on track 1
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+
+        for (track, action) in [
+            (0, "x"),
+            (1, "y"),
+            (0, "a"),
+            (1, "b"),
+            (0, "x"),
+            (1, "y"),
+            (0, "x"),
+        ] {
+            matching.feed(action, track);
+        }
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        assert_eq!(
+            results,
+            HashSet::from([MatchingResult {
+                intervals: vec![MatchingInterval::new(1, 1), MatchingInterval::new(1, 1)],
+                ids: vec![0, 1],
+            }])
+        );
+        // The trials starting at "x" or "y" are not made
+        assert!(matching.pruned_start_positions() > 0);
+    }
+
+    #[test]
+    fn test_symbol_matcher() {
+        // "a" on track 0 and then "b" on track 1
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+        matching.set_symbol_matcher(Rc::new(PrefixStrippingSymbolMatcher::new("evt:")));
+
+        for (track, action) in [(0, "evt:x"), (1, "evt:y"), (0, "evt:a"), (1, "evt:b")] {
+            matching.feed(action, track);
+        }
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        assert_eq!(
+            results,
+            HashSet::from([MatchingResult {
+                intervals: vec![MatchingInterval::new(1, 1), MatchingInterval::new(1, 1)],
+                ids: vec![0, 1],
+            }])
+        );
+        // "evt:a" is not a first symbol, so no start position is pruned
+        assert_eq!(matching.pruned_start_positions(), 0);
+    }
+
+    /// Runs the matching on a long input with the trace level enabled. The results must be the
+    /// same with and without the `no-trace` feature, which only skips the trace records.
+    #[test]
+    fn test_run_with_trace_level() {
+        let logger = install_recording_logger();
+        assert_eq!(hot_trace_enabled(), cfg!(not(feature = "no-trace")));
+
+        // "nt-a" on track 0 and then "nt-b" on track 1, using the letters unlikely to be used by
+        // other tests logging concurrently
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "nt-a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "nt-b".to_string(), 1, s2);
+
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            notifier,
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+
+        // "nt-a" and "nt-b" at the even positions of the tracks 0 and 1, respectively
+        let repetitions = 30;
+        for _ in 0..repetitions {
+            for (track, action) in [(0, "nt-a"), (1, "nt-b"), (0, "nt-c"), (1, "nt-c")] {
+                matching.feed(action, track);
+            }
+        }
+        matching.set_eof(0);
+        matching.set_eof(1);
+        matching.consume_remaining();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        let expected = (0..repetitions)
+            .cartesian_product(0..repetitions)
+            .map(|(i, j)| MatchingResult {
+                intervals: vec![
+                    MatchingInterval::new(2 * i, 2 * i),
+                    MatchingInterval::new(2 * j, 2 * j),
+                ],
+                ids: vec![0, 1],
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(results, expected);
+
+        let feed_records = logger
+            .records()
+            .into_iter()
+            .filter(|record| record.contains("NaiveHyperPatternMatching::feed(nt-a, 0)"))
+            .count();
+        if cfg!(feature = "no-trace") {
+            assert_eq!(feed_records, 0);
+        } else {
+            assert_eq!(feed_records, repetitions);
+        }
+    }
 }
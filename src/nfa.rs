@@ -96,7 +96,7 @@ impl<'a> EpsilonNFA<'a> {
         let mut subset_to_state = HashMap::new();
 
         // Create the new initial state in the result
-        let is_final = initial_closure.iter().any(|st| st.is_final);
+        let is_final = initial_closure.iter().any(|st| st.is_final());
         let new_init = result.add_state(true, is_final);
         subset_to_state.insert(init_canonical.clone(), new_init);
 
@@ -138,7 +138,7 @@ impl<'a> EpsilonNFA<'a> {
                 let new_super_state = match subset_to_state.get(&closure_repr) {
                     Some(&st) => st,
                     None => {
-                        let is_final = closure.iter().any(|st| st.is_final);
+                        let is_final = closure.iter().any(|st| st.is_final());
                         let st_new = result.add_state(false, is_final);
                         subset_to_state.insert(closure_repr.clone(), st_new);
                         queue.push_back(closure_repr.clone());
@@ -154,6 +154,186 @@ impl<'a> EpsilonNFA<'a> {
 
         result
     }
+
+    /// Returns the index of the strongly connected component of the ε-transitions for each state
+    /// reachable from the initial states, using Tarjan's algorithm.
+    fn epsilon_scc_indices(&self) -> HashMap<*const State<'a, Option<String>>, usize> {
+        // The ε-successors of the reachable states, in the order of the BFS
+        let mut reachable = Vec::new();
+        let mut epsilon_successors = HashMap::new();
+        let mut queue: VecDeque<_> = self.initial_states.iter().copied().collect();
+        let mut visited: HashSet<_> = queue.iter().map(|&st| st as *const _).collect();
+        while let Some(st) = queue.pop_front() {
+            reachable.push(st);
+            let mut successors = Vec::new();
+            for &trans in st.transitions.borrow().iter() {
+                if trans.label.is_none() {
+                    successors.push(trans.next_state);
+                }
+                if visited.insert(trans.next_state as *const _) {
+                    queue.push_back(trans.next_state);
+                }
+            }
+            epsilon_successors.insert(st as *const _, successors);
+        }
+
+        let mut index: HashMap<*const State<'a, Option<String>>, usize> = HashMap::new();
+        let mut lowlink: HashMap<*const State<'a, Option<String>>, usize> = HashMap::new();
+        let mut on_stack = HashSet::new();
+        let mut stack: Vec<&State<'a, Option<String>>> = Vec::new();
+        let mut component = HashMap::new();
+        let mut components = 0;
+        for &root in &reachable {
+            if index.contains_key(&(root as *const _)) {
+                continue;
+            }
+            // The DFS is iterative to avoid the stack overflow on long ε-chains
+            let mut call_stack = vec![(root, 0)];
+            index.insert(root as *const _, index.len());
+            lowlink.insert(root as *const _, index[&(root as *const _)]);
+            stack.push(root);
+            on_stack.insert(root as *const _);
+            while let Some((st, child)) = call_stack.last().copied() {
+                let st_ptr = st as *const _;
+                if let Some(&next) = epsilon_successors[&st_ptr].get(child) {
+                    call_stack.last_mut().unwrap().1 += 1;
+                    let next_ptr = next as *const _;
+                    if !index.contains_key(&next_ptr) {
+                        index.insert(next_ptr, index.len());
+                        lowlink.insert(next_ptr, index[&next_ptr]);
+                        stack.push(next);
+                        on_stack.insert(next_ptr);
+                        call_stack.push((next, 0));
+                    } else if on_stack.contains(&next_ptr) {
+                        lowlink.insert(st_ptr, lowlink[&st_ptr].min(index[&next_ptr]));
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    let parent_ptr = parent as *const _;
+                    lowlink.insert(parent_ptr, lowlink[&parent_ptr].min(lowlink[&st_ptr]));
+                }
+                if lowlink[&st_ptr] == index[&st_ptr] {
+                    // `st` is the root of a component
+                    while let Some(member) = stack.pop() {
+                        on_stack.remove(&(member as *const _));
+                        component.insert(member as *const _, components);
+                        if std::ptr::eq(member, st) {
+                            break;
+                        }
+                    }
+                    components += 1;
+                }
+            }
+        }
+        component
+    }
+
+    /// Returns a copy of `self` where each strongly connected component of the ε-transitions is
+    /// merged into a single state, built in the given arenas.
+    ///
+    /// The states in an ε-cycle reach each other without reading any symbol, so merging them does
+    /// not change the language. A merged state is initial (resp. final) if any of its members is
+    /// initial (resp. final), and the ε-transitions inside a component are dropped. This shrinks the
+    /// ε-cycles of regex-derived automata before [`EpsilonNFA::to_nfa_powerset`]. Only the states
+    /// reachable from the initial states are copied.
+    pub fn collapse_epsilon_sccs<'b>(
+        &self,
+        new_states_arena: &'b Arena<State<'b, Option<String>>>,
+        new_trans_arena: &'b Arena<Transition<'b, Option<String>>>,
+    ) -> EpsilonNFA<'b> {
+        let component = self.epsilon_scc_indices();
+        let mut result = EpsilonNFA::new(new_states_arena, new_trans_arena, self.dimensions);
+        let mut members: Vec<Vec<&State<'a, Option<String>>>> =
+            vec![Vec::new(); component.values().max().map_or(0, |&max| max + 1)];
+        for (&ptr, &index) in &component {
+            members[index].push(unsafe { &*ptr });
+        }
+        let initial_states: HashSet<_> = self
+            .initial_states
+            .iter()
+            .map(|&st| st as *const State<'a, Option<String>>)
+            .collect();
+        let new_states = members
+            .iter()
+            .map(|members| {
+                result.add_state(
+                    members
+                        .iter()
+                        .any(|&st| initial_states.contains(&(st as *const _))),
+                    members.iter().any(|st| st.is_final()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // The transitions between the same components are added once
+        let mut added = HashSet::new();
+        for (index, members) in members.iter().enumerate() {
+            for &st in members {
+                for &trans in st.transitions.borrow().iter() {
+                    let next = component[&(trans.next_state as *const _)];
+                    if trans.label.is_none() && next == index {
+                        continue;
+                    }
+                    if added.insert((index, trans.label.clone(), next)) {
+                        result.add_transition(
+                            new_states[index],
+                            trans.label.clone(),
+                            new_states[next],
+                        );
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl<'a> NFA<'a> {
+    /// Lift this NFA to an EpsilonNFA by wrapping each label `l` as `Some(l)`.
+    ///
+    /// * `new_states_arena`, `new_trans_arena`: typed_arena for the newly constructed EpsilonNFA.
+    ///
+    /// Only the states reachable from the initial states are copied.
+    /// The resulting automaton has no ε-transitions and accepts the same language.
+    pub fn to_epsilon_nfa<'b>(
+        &self,
+        new_states_arena: &'b Arena<State<'b, Option<String>>>,
+        new_trans_arena: &'b Arena<Transition<'b, Option<String>>>,
+    ) -> EpsilonNFA<'b> {
+        let mut result = EpsilonNFA::new(new_states_arena, new_trans_arena, self.dimensions);
+
+        // A map from the old states to the new states
+        let mut state_map = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &initial_state in &self.initial_states {
+            if let std::collections::hash_map::Entry::Vacant(e) =
+                state_map.entry(initial_state as *const _)
+            {
+                e.insert(result.add_state(true, initial_state.is_final()));
+                queue.push_back(initial_state);
+            }
+        }
+
+        // BFS over the original NFA, copying the states and transitions
+        while let Some(old_state) = queue.pop_front() {
+            let new_state = state_map[&(old_state as *const _)];
+            for &trans in old_state.transitions.borrow().iter() {
+                let next_state = *state_map
+                    .entry(trans.next_state as *const _)
+                    .or_insert_with(|| {
+                        queue.push_back(trans.next_state);
+                        result.add_state(false, trans.next_state.is_final())
+                    });
+                result.add_transition(new_state, Some(trans.label.clone()), next_state);
+            }
+        }
+
+        result
+    }
 }
 
 #[cfg(test)]
@@ -212,7 +392,7 @@ mod tests {
                     format!("{:p}", st),
                     tr.label.clone(),
                     format!("{:p}", tr.next_state),
-                    tr.next_state.is_final,
+                    tr.next_state.is_final(),
                 ));
                 let nxt_ptr = tr.next_state as *const State<String>;
                 if !visited.contains(&nxt_ptr) {
@@ -236,7 +416,7 @@ mod tests {
         );
 
         // Check that there's at least one final state in the new NFA
-        let any_final_state = visited.iter().any(|&ptr| unsafe { &*ptr }.is_final);
+        let any_final_state = visited.iter().any(|&ptr| unsafe { &*ptr }.is_final());
         assert!(
             any_final_state,
             "Expected at least one final state in the powerset-constructed NFA."
@@ -245,4 +425,113 @@ mod tests {
         // For debugging, you could print out the transitions_info
         // println!("Transitions in powerset NFA: {:#?}", transitions_info);
     }
+
+    #[test]
+    fn test_collapse_epsilon_sccs() {
+        use itertools::Itertools;
+        use typed_arena::Arena;
+
+        // An ε-cycle s0 -> s1 -> s2 -> s0 reading "a" from s1 and "b" from s2, and then "c" to the
+        // final ε-cycle s3 <-> s4
+        let eps_states = Arena::new();
+        let eps_trans = Arena::new();
+        let mut eps_nfa = EpsilonNFA::new(&eps_states, &eps_trans, 0);
+        let s0 = eps_nfa.add_state(true, false);
+        let s1 = eps_nfa.add_state(false, false);
+        let s2 = eps_nfa.add_state(false, false);
+        let s3 = eps_nfa.add_state(false, false);
+        let s4 = eps_nfa.add_state(false, true);
+        eps_nfa.add_transition(s0, None, s1);
+        eps_nfa.add_transition(s1, None, s2);
+        eps_nfa.add_transition(s2, None, s0);
+        eps_nfa.add_transition(s1, Some("a".to_string()), s0);
+        eps_nfa.add_transition(s2, Some("b".to_string()), s2);
+        eps_nfa.add_transition(s0, Some("c".to_string()), s3);
+        eps_nfa.add_transition(s3, None, s4);
+        eps_nfa.add_transition(s4, None, s3);
+
+        let collapsed_states = Arena::new();
+        let collapsed_trans = Arena::new();
+        let collapsed = eps_nfa.collapse_epsilon_sccs(&collapsed_states, &collapsed_trans);
+        assert_eq!(collapsed.states.len(), 2);
+        assert_eq!(collapsed.initial_states.len(), 1);
+        assert!(!collapsed.initial_states[0].is_final());
+        // No ε-transitions are left since the components have no ε-transitions between them
+        assert!(collapsed
+            .iter_states()
+            .all(|st| st.get_transitions().iter().all(|t| t.label.is_some())));
+
+        // The languages, (a|b)* c, are the same
+        let original_states = Arena::new();
+        let original_trans = Arena::new();
+        let original_dfa = eps_nfa
+            .to_nfa_powerset(&original_states, &original_trans)
+            .determinize();
+        let back_states = Arena::new();
+        let back_trans = Arena::new();
+        let collapsed_dfa = collapsed
+            .to_nfa_powerset(&back_states, &back_trans)
+            .determinize();
+        let alphabet = ["a", "b", "c"];
+        for length in 0..=5 {
+            for word in (0..length)
+                .map(|_| alphabet.iter().map(|c| c.to_string()))
+                .multi_cartesian_product()
+            {
+                assert_eq!(
+                    original_dfa.accepts(&word),
+                    collapsed_dfa.accepts(&word),
+                    "The languages differ on {:?}",
+                    word
+                );
+            }
+        }
+        assert!(collapsed_dfa.accepts(&["a", "b", "c"].map(String::from)));
+    }
+
+    #[test]
+    fn test_nfa_to_epsilon_nfa() {
+        use crate::automata::NFA;
+        use itertools::Itertools;
+        use typed_arena::Arena;
+
+        // An NFA accepting a(b|c)*b
+        let nfa_states = Arena::new();
+        let nfa_trans = Arena::new();
+        let mut nfa = NFA::new(&nfa_states, &nfa_trans, 0);
+        let s0 = nfa.add_state(true, false);
+        let s1 = nfa.add_state(false, false);
+        let s2 = nfa.add_state(false, true);
+        nfa.add_transition(s0, "a".to_string(), s1);
+        nfa.add_transition(s1, "b".to_string(), s1);
+        nfa.add_transition(s1, "c".to_string(), s1);
+        nfa.add_transition(s1, "b".to_string(), s2);
+
+        let eps_states = Arena::new();
+        let eps_trans = Arena::new();
+        let eps_nfa = nfa.to_epsilon_nfa(&eps_states, &eps_trans);
+        assert_eq!(eps_nfa.initial_states.len(), 1);
+
+        // Convert back and compare the languages
+        let back_states = Arena::new();
+        let back_trans = Arena::new();
+        let back = eps_nfa.to_nfa_powerset(&back_states, &back_trans);
+
+        let original_dfa = nfa.determinize();
+        let back_dfa = back.determinize();
+        let alphabet = ["a", "b", "c"];
+        for length in 0..=5 {
+            for word in (0..length)
+                .map(|_| alphabet.iter().map(|c| c.to_string()))
+                .multi_cartesian_product()
+            {
+                assert_eq!(
+                    original_dfa.accepts(&word),
+                    back_dfa.accepts(&word),
+                    "The languages differ on {:?}",
+                    word
+                );
+            }
+        }
+    }
 }
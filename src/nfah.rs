@@ -1,5 +1,11 @@
-use crate::automata::{EpsilonNFA, NFAHState, NFAHTransition, State, Transition, NFAH};
-use std::collections::HashMap;
+use crate::automata::{EpsilonNFA, NFAHState, NFAHTransition, State, Transition, ValidLabel, NFAH};
+use crate::automata_runner::{AppendOnlySequence, NFAHRunner};
+use crate::dfa::DFA;
+use crate::hyper_pattern_matching::PatternMatchingAutomataRunner;
+use crate::result_notifier::MatchingInterval;
+use std::collections::BTreeSet;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 use typed_arena::Arena;
 
 impl<'a> NFAH<'a> {
@@ -14,6 +20,46 @@ impl<'a> NFAH<'a> {
         self.add_transition(from, (action, var), to)
     }
 
+    /// Adds the transitions `(from, action, var, to)` in `edges` at once.
+    ///
+    /// The transitions are allocated together, and the transitions of each source state are
+    /// borrowed only once. The resulting transitions are the same as calling
+    /// [`NFAH::add_nfah_transition`] for each edge in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable is out of range for the dimensions. No transition is added then.
+    pub fn add_nfah_transitions_bulk(
+        &self,
+        edges: &[(&'a NFAHState<'a>, String, usize, &'a NFAHState<'a>)],
+    ) {
+        for (_, _, var, _) in edges {
+            if let Err(message) = (String::new(), *var).validate(self.dimensions) {
+                panic!("{}", message);
+            }
+        }
+        let transitions: &'a [NFAHTransition<'a>] =
+            self.transitions
+                .alloc_extend(edges.iter().map(|(_, action, var, to)| Transition {
+                    label: (action.clone(), *var),
+                    next_state: *to,
+                    meta: None,
+                }));
+        // Group the transitions by the source state, keeping the order of the edges
+        let mut grouped: Vec<(&'a NFAHState<'a>, Vec<&'a NFAHTransition<'a>>)> = Vec::new();
+        let mut index: HashMap<*const NFAHState<'a>, usize> = HashMap::new();
+        for (&(from, _, _, _), transition) in edges.iter().zip(transitions.iter()) {
+            let i = *index.entry(from as *const _).or_insert_with(|| {
+                grouped.push((from, Vec::new()));
+                grouped.len() - 1
+            });
+            grouped[i].1.push(transition);
+        }
+        for (from, new_transitions) in grouped {
+            from.transitions.borrow_mut().extend(new_transitions);
+        }
+    }
+
     /// A helper version of `project_with_map` that returns both the new EpsilonNFA
     /// **and** a mapping from old (pointer) state to the newly created state.
     ///
@@ -94,7 +140,7 @@ impl<'a> NFAH<'a> {
             var,
             states_arena,
             trans_arena,
-            |old_state| unsafe { (*old_state).is_final },
+            |old_state| unsafe { (*old_state).is_final() },
             &mut dummy_map,
         )
     }
@@ -130,13 +176,229 @@ impl<'a> NFAH<'a> {
             &mut old_to_new,
         )
     }
+
+    /// Returns the actions of the transitions labeled with the variable `var`.
+    pub fn letters_for_variable(&'a self, var: usize) -> HashSet<String> {
+        self.iter_states()
+            .flat_map(|state| {
+                state
+                    .get_transitions()
+                    .iter()
+                    .filter(|transition| transition.variable() == var)
+                    .map(|transition| transition.letter().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Returns a copy of `self` without the transitions whose action is not in `alphabet`.
+    ///
+    /// The result accepts exactly the accepted words of `self` over `alphabet`. We copy only those
+    /// states reachable from the initial states (via BFS).
+    pub fn restrict_to_alphabet<'b>(
+        &self,
+        alphabet: &HashSet<String>,
+        states_arena: &'b Arena<NFAHState<'b>>,
+        trans_arena: &'b Arena<NFAHTransition<'b>>,
+    ) -> NFAH<'b> {
+        let mut restricted = NFAH::new(states_arena, trans_arena, self.dimensions);
+        let mut old_to_new: HashMap<*const NFAHState<'a>, &'b NFAHState<'b>> = HashMap::new();
+        let mut queue = std::collections::VecDeque::new();
+
+        for &init_state in &self.initial_states {
+            let new_init = restricted.add_state(true, init_state.is_final());
+            old_to_new.insert(init_state as *const _, new_init);
+            queue.push_back(init_state);
+        }
+
+        while let Some(old_st) = queue.pop_front() {
+            let new_st = old_to_new[&(old_st as *const _)];
+            for &trans in old_st.transitions.borrow().iter() {
+                let (ref action, var) = trans.label;
+                if !alphabet.contains(action) {
+                    continue;
+                }
+                let old_next = trans.next_state as *const _;
+                if let std::collections::hash_map::Entry::Vacant(e) = old_to_new.entry(old_next) {
+                    e.insert(restricted.add_state(false, trans.next_state.is_final()));
+                    queue.push_back(trans.next_state);
+                }
+                restricted.add_transition_with_meta(
+                    new_st,
+                    (action.clone(), var),
+                    old_to_new[&old_next],
+                    trans.meta.clone(),
+                );
+            }
+        }
+
+        restricted
+    }
+
+    /// Returns the states reachable from `state` only by the transitions of the empty letter
+    /// `""`, including `state` itself.
+    fn empty_letter_closure(state: &'a NFAHState<'a>) -> Vec<&'a NFAHState<'a>> {
+        let mut closure = vec![state];
+        let mut visited: HashSet<*const NFAHState<'a>> = HashSet::from([state as *const _]);
+        let mut stack = vec![state];
+        while let Some(current) = stack.pop() {
+            for trans in current.transitions.borrow().iter() {
+                if trans.letter().is_empty() && visited.insert(trans.next_state as *const _) {
+                    closure.push(trans.next_state);
+                    stack.push(trans.next_state);
+                }
+            }
+        }
+        closure
+    }
+
+    /// Returns `true` if a state in the closure of `state` over the empty letter is final.
+    fn is_final_closure(state: &'a NFAHState<'a>) -> bool {
+        Self::empty_letter_closure(state)
+            .iter()
+            .any(|state| state.is_final())
+    }
+
+    /// Returns a copy of `self` without the transitions of the empty letter `""`, treating them
+    /// as ε-transitions.
+    ///
+    /// For each state, the transitions of the other letters from its closure over the empty
+    /// letter are spliced to it, and it is final if a state in the closure is final. The result
+    /// accepts the words of `self` with the empty letters removed, so the matchers can assume
+    /// that no transition reads the empty letter. We copy only those states reachable from the
+    /// initial states (via BFS) by the letters other than the empty one.
+    pub fn eliminate_empty_letters<'b>(
+        &self,
+        states_arena: &'b Arena<NFAHState<'b>>,
+        trans_arena: &'b Arena<NFAHTransition<'b>>,
+    ) -> NFAH<'b> {
+        let mut eliminated = NFAH::new(states_arena, trans_arena, self.dimensions);
+        let mut old_to_new: HashMap<*const NFAHState<'a>, &'b NFAHState<'b>> = HashMap::new();
+        let mut queue = std::collections::VecDeque::new();
+
+        for &init_state in &self.initial_states {
+            if let std::collections::hash_map::Entry::Vacant(e) =
+                old_to_new.entry(init_state as *const _)
+            {
+                e.insert(eliminated.add_state(true, Self::is_final_closure(init_state)));
+                queue.push_back(init_state);
+            }
+        }
+
+        while let Some(old_st) = queue.pop_front() {
+            let new_st = old_to_new[&(old_st as *const _)];
+            let closure = Self::empty_letter_closure(old_st);
+            // The same transition may be spliced from several states in the closure
+            let mut added = HashSet::new();
+            for state in closure {
+                for &trans in state.transitions.borrow().iter() {
+                    if trans.letter().is_empty() {
+                        continue;
+                    }
+                    let old_next = trans.next_state as *const _;
+                    if !added.insert((trans.label.clone(), old_next)) {
+                        continue;
+                    }
+                    if let std::collections::hash_map::Entry::Vacant(e) = old_to_new.entry(old_next)
+                    {
+                        let is_final = Self::is_final_closure(trans.next_state);
+                        e.insert(eliminated.add_state(false, is_final));
+                        queue.push_back(trans.next_state);
+                    }
+                    eliminated.add_transition_with_meta(
+                        new_st,
+                        trans.label.clone(),
+                        old_to_new[&old_next],
+                        trans.meta.clone(),
+                    );
+                }
+            }
+        }
+
+        eliminated
+    }
+
+    /// Returns all the intervals `[i, j]` such that `word[i..=j]` is accepted by the
+    /// one-dimensional `self`, in the lexicographic order.
+    ///
+    /// This is the usual substring matching, where the matching trials from all the positions of
+    /// `word` are run by [`PatternMatchingAutomataRunner`] without the setup for the hyper pattern
+    /// matching. The empty word is never matched.
+    ///
+    /// Panics if `self` is not one-dimensional.
+    pub fn word_acceptance_positions(&'a self, word: &[String]) -> Vec<MatchingInterval> {
+        if self.dimensions != 1 {
+            panic!(
+                "Only a one-dimensional automaton can scan a word, but it has {} dimensions",
+                self.dimensions
+            );
+        }
+        let mut sequence = AppendOnlySequence::new();
+        sequence.extend(word.iter().cloned());
+        sequence.close();
+
+        let mut runner = PatternMatchingAutomataRunner::new(self);
+        for start in 0..word.len() {
+            runner.insert_from_initial_states(vec![sequence.readable_view_from(start)], vec![0]);
+        }
+        runner.consume();
+
+        runner
+            .get_final_configurations()
+            .iter()
+            .filter(|c| c.input_sequence[0].start > c.matching_begin[0])
+            .map(|c| MatchingInterval::new(c.matching_begin[0], c.input_sequence[0].start - 1))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the minimal deterministic NFAH accepting the same language, built in the given
+    /// arenas.
+    ///
+    /// This determinizes `self` over the `(action, var)` alphabet, minimizes the DFA with
+    /// [`DFA::minimize_hopcroft`](crate::dfa::DFA::minimize_hopcroft), and converts it back to an
+    /// NFAH. The result has no dead states, and it is the smallest among the deterministic NFAHs
+    /// for the language. Since it is deterministic, it may have more states than a
+    /// nondeterministic `self`.
+    pub fn minimized<'b>(
+        &self,
+        states_arena: &'b Arena<NFAHState<'b>>,
+        trans_arena: &'b Arena<NFAHTransition<'b>>,
+    ) -> NFAH<'b> {
+        self.determinize().minimize_hopcroft().to_automata(
+            states_arena,
+            trans_arena,
+            self.dimensions,
+        )
+    }
+
+    /// Converts `dfa` over the `(action, var)` alphabet to an NFAH with `dimensions` variables,
+    /// built in the given arenas.
+    ///
+    /// Each state of `dfa` becomes a state of the NFAH, the initial state of `dfa` is the unique
+    /// initial state, and the transitions are copied with their labels. This is the inverse of
+    /// [`Automata::determinize`](crate::automata::Automata::determinize) up to the language.
+    ///
+    /// Returns an error message if a label reads a variable not less than `dimensions`.
+    pub fn from_dfa<S: Eq + Hash + Clone>(
+        dfa: &DFA<S, (String, usize)>,
+        dimensions: usize,
+        states_arena: &'a Arena<NFAHState<'a>>,
+        trans_arena: &'a Arena<NFAHTransition<'a>>,
+    ) -> Result<NFAH<'a>, String> {
+        for label in dfa.transitions.keys().map(|(_, label)| label) {
+            label.validate(dimensions)?;
+        }
+        Ok(dfa.to_automata(states_arena, trans_arena, dimensions))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::{HashSet, VecDeque};
 
-    use crate::automata::NFAH;
+    use crate::automata::{NFAHState, NFAH};
 
     #[test]
     fn test_project() {
@@ -314,4 +576,392 @@ mod tests {
         let none_count = transitions_vec.iter().filter(|t| t.label.is_none()).count();
         assert_eq!(none_count, 1, "Expected exactly 1 ε-transitions (None).");
     }
+
+    #[test]
+    fn test_minimized() {
+        use itertools::Itertools;
+        use typed_arena::Arena;
+
+        // Accepts (a on 0)(b on 1) and (c on 0)(b on 1) by separate branches
+        let nfa_states = Arena::new();
+        let nfa_trans = Arena::new();
+        let mut nfa_h = NFAH::new(&nfa_states, &nfa_trans, 2);
+        let s0 = nfa_h.add_state(true, false);
+        let s1 = nfa_h.add_state(false, false);
+        let s2 = nfa_h.add_state(false, false);
+        let s3 = nfa_h.add_state(false, true);
+        let s4 = nfa_h.add_state(false, true);
+        let dead = nfa_h.add_state(false, false);
+        nfa_h.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        nfa_h.add_nfah_transition(s0, "c".to_string(), 0, s2);
+        nfa_h.add_nfah_transition(s1, "b".to_string(), 1, s3);
+        nfa_h.add_nfah_transition(s2, "b".to_string(), 1, s4);
+        nfa_h.add_nfah_transition(s2, "a".to_string(), 1, dead);
+
+        let min_states = Arena::new();
+        let min_trans = Arena::new();
+        let minimized = nfa_h.minimized(&min_states, &min_trans);
+        assert_eq!(minimized.dimensions, 2);
+        assert_eq!(minimized.initial_states.len(), 1);
+        assert!(minimized.iter_states().count() <= nfa_h.iter_states().count());
+        assert_eq!(minimized.iter_states().count(), 3);
+
+        // Language equivalence on all the words up to length 4
+        let original = nfa_h.determinize();
+        let reduced = minimized.determinize();
+        let letters =
+            [("a", 0), ("b", 1), ("c", 0), ("a", 1)].map(|(action, var)| (action.to_string(), var));
+        for length in 0..=4 {
+            for word in
+                std::iter::repeat_n(letters.iter().cloned(), length).multi_cartesian_product()
+            {
+                assert_eq!(
+                    original.accepts(&word),
+                    reduced.accepts(&word),
+                    "{:?}",
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_dfa() {
+        use itertools::Itertools;
+        use typed_arena::Arena;
+
+        // Nondeterministically reads "a" on 0 and then "b" or "c" on 1, or "a" on 0 twice
+        let nfa_states = Arena::new();
+        let nfa_trans = Arena::new();
+        let mut nfa_h = NFAH::new(&nfa_states, &nfa_trans, 2);
+        let s0 = nfa_h.add_state(true, false);
+        let s1 = nfa_h.add_state(false, false);
+        let s2 = nfa_h.add_state(false, false);
+        let s3 = nfa_h.add_state(false, true);
+        nfa_h.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        nfa_h.add_nfah_transition(s0, "a".to_string(), 0, s2);
+        nfa_h.add_nfah_transition(s1, "b".to_string(), 1, s3);
+        nfa_h.add_nfah_transition(s1, "c".to_string(), 1, s3);
+        nfa_h.add_nfah_transition(s2, "a".to_string(), 0, s3);
+
+        let dfa = nfa_h.determinize();
+        let states = Arena::new();
+        let trans = Arena::new();
+        let converted = NFAH::from_dfa(&dfa, 2, &states, &trans).unwrap();
+        assert_eq!(converted.dimensions, 2);
+        assert_eq!(converted.initial_states.len(), 1);
+        assert_eq!(converted.iter_states().count(), dfa.states.len());
+
+        // Language equivalence on all the words up to length 3
+        let original = nfa_h.determinize();
+        let round_trip = converted.determinize();
+        let letters =
+            [("a", 0), ("b", 1), ("c", 1), ("a", 1)].map(|(action, var)| (action.to_string(), var));
+        for length in 0..=3 {
+            for word in
+                std::iter::repeat_n(letters.iter().cloned(), length).multi_cartesian_product()
+            {
+                assert_eq!(
+                    original.accepts(&word),
+                    round_trip.accepts(&word),
+                    "{:?}",
+                    word
+                );
+            }
+        }
+
+        // The labels must be valid for the dimensions
+        let invalid_states = Arena::new();
+        let invalid_trans = Arena::new();
+        assert!(NFAH::from_dfa(&dfa, 1, &invalid_states, &invalid_trans).is_err());
+    }
+
+    #[test]
+    fn test_add_nfah_transitions_bulk() {
+        use crate::automata::NFAHState;
+        use typed_arena::Arena;
+
+        // (from, action, var, to) with the indices of the states
+        let edges = [
+            (0, "a", 0, 1),
+            (1, "b", 1, 2),
+            (0, "c", 1, 2),
+            (2, "a", 0, 0),
+            (1, "b", 0, 1),
+            (0, "a", 0, 1),
+        ];
+
+        let single_states = Arena::new();
+        let single_transitions = Arena::new();
+        let mut single = NFAH::new(&single_states, &single_transitions, 2);
+        let single_ids: Vec<_> = (0..3).map(|i| single.add_state(i == 0, i == 2)).collect();
+        for &(from, action, var, to) in &edges {
+            single.add_nfah_transition(single_ids[from], action.to_string(), var, single_ids[to]);
+        }
+
+        let bulk_states = Arena::new();
+        let bulk_transitions = Arena::new();
+        let mut bulk = NFAH::new(&bulk_states, &bulk_transitions, 2);
+        let bulk_ids: Vec<_> = (0..3).map(|i| bulk.add_state(i == 0, i == 2)).collect();
+        let bulk_edges: Vec<_> = edges
+            .iter()
+            .map(|&(from, action, var, to)| (bulk_ids[from], action.to_string(), var, bulk_ids[to]))
+            .collect();
+        bulk.add_nfah_transitions_bulk(&bulk_edges);
+
+        // The transitions of each state are the same, in the same order
+        fn to_indices<'a>(
+            ids: &[&NFAHState<'a>],
+            state: &NFAHState<'a>,
+        ) -> Vec<((String, usize), usize)> {
+            state
+                .transitions
+                .borrow()
+                .iter()
+                .map(|t| {
+                    let to = ids.iter().position(|&s| s == t.next_state).unwrap();
+                    (t.label.clone(), to)
+                })
+                .collect()
+        }
+        for i in 0..3 {
+            assert_eq!(
+                to_indices(&single_ids, single_ids[i]),
+                to_indices(&bulk_ids, bulk_ids[i])
+            );
+        }
+        assert_eq!(single_transitions.len(), bulk_transitions.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_add_nfah_transitions_bulk_invalid_variable() {
+        use typed_arena::Arena;
+
+        let states = Arena::new();
+        let transitions = Arena::new();
+        let mut automaton = NFAH::new(&states, &transitions, 1);
+        let s0 = automaton.add_state(true, true);
+        automaton.add_nfah_transitions_bulk(&[(s0, "a".to_string(), 1, s0)]);
+    }
+
+    #[test]
+    fn test_first_symbols_per_variable() {
+        use typed_arena::Arena;
+
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 3);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s0, "x".to_string(), 1, s0);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "c".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "d".to_string(), 2, s2);
+
+        let first_symbols = automaton.first_symbols_per_variable();
+        assert_eq!(first_symbols[0], Some(HashSet::from(["a".to_string()])));
+        assert_eq!(
+            first_symbols[1],
+            Some(HashSet::from(["x".to_string(), "c".to_string()]))
+        );
+        // The final state is reachable without reading the variable 2
+        assert_eq!(first_symbols[2], None);
+    }
+
+    /// Returns `true` if `nfah` accepts `word`, where the transitions of the empty letter are
+    /// taken without reading the word.
+    fn accepts_with_empty_letters<'a>(nfah: &NFAH<'a>, word: &[(String, usize)]) -> bool {
+        fn closure<'a>(states: Vec<&'a NFAHState<'a>>) -> Vec<&'a NFAHState<'a>> {
+            let mut closure = states.clone();
+            let mut queue: VecDeque<_> = states.into_iter().collect();
+            while let Some(state) = queue.pop_front() {
+                for trans in state.get_transitions().iter() {
+                    if trans.letter().is_empty() && !closure.contains(&trans.next_state) {
+                        closure.push(trans.next_state);
+                        queue.push_back(trans.next_state);
+                    }
+                }
+            }
+            closure
+        }
+        let mut current = closure(nfah.initial_states.clone());
+        for label in word {
+            let next = current
+                .iter()
+                .flat_map(|state| {
+                    state
+                        .get_transitions()
+                        .iter()
+                        .filter(|trans| &trans.label == label)
+                        .map(|trans| trans.next_state)
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            current = closure(next);
+        }
+        current.iter().any(|state| state.is_final())
+    }
+
+    #[test]
+    fn test_eliminate_empty_letters() {
+        use itertools::Itertools;
+        use typed_arena::Arena;
+
+        // Accepts (a on 0)(b on 1)* with empty letters around the loop, and "" alone via s3
+        let nfa_states = Arena::new();
+        let nfa_trans = Arena::new();
+        let mut nfa_h = NFAH::new(&nfa_states, &nfa_trans, 2);
+        let s0 = nfa_h.add_state(true, false);
+        let s1 = nfa_h.add_state(false, false);
+        let s2 = nfa_h.add_state(false, false);
+        let s3 = nfa_h.add_state(false, false);
+        let sf = nfa_h.add_state(false, true);
+        nfa_h.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        nfa_h.add_nfah_transition(s1, "".to_string(), 1, s2);
+        nfa_h.add_nfah_transition(s2, "b".to_string(), 1, s1);
+        nfa_h.add_nfah_transition(s2, "".to_string(), 0, sf);
+        nfa_h.add_nfah_transition(s0, "".to_string(), 0, s3);
+        nfa_h.add_nfah_transition(s3, "".to_string(), 1, s0);
+        nfa_h.add_nfah_transition(s3, "b".to_string(), 0, sf);
+
+        let eliminated_states = Arena::new();
+        let eliminated_trans = Arena::new();
+        let eliminated = nfa_h.eliminate_empty_letters(&eliminated_states, &eliminated_trans);
+        assert_eq!(eliminated.dimensions, 2);
+        assert!(eliminated.iter_states().all(|state| state
+            .get_transitions()
+            .iter()
+            .all(|t| !t.letter().is_empty())));
+
+        // The same words are accepted up to the length 4
+        let alphabet = [
+            ("a".to_string(), 0),
+            ("b".to_string(), 0),
+            ("b".to_string(), 1),
+        ];
+        let mut accepted = 0;
+        for length in 0..=4 {
+            for word in
+                itertools::repeat_n(alphabet.iter().cloned(), length).multi_cartesian_product()
+            {
+                let expected = accepts_with_empty_letters(&nfa_h, &word);
+                assert_eq!(
+                    accepts_with_empty_letters(&eliminated, &word),
+                    expected,
+                    "{:?}",
+                    word
+                );
+                accepted += expected as usize;
+            }
+        }
+        // "a", "a b", "a b b", "a b b b", and "b" on 0
+        assert_eq!(accepted, 5);
+        assert!(!eliminated.accepts_empty());
+    }
+
+    #[test]
+    fn test_word_acceptance_positions() {
+        use crate::result_notifier::MatchingInterval;
+        use crate::tests::utils::create_small_automaton;
+        use typed_arena::Arena;
+
+        // Project the small automaton to the variable 0, i.e., a* c, by replacing the letters on
+        // the variable 1 with the empty letter
+        let small_states = Arena::new();
+        let small_trans = Arena::new();
+        let small = create_small_automaton(&small_states, &small_trans);
+        let mapped_states = Arena::new();
+        let mapped_trans = Arena::new();
+        let mapped = small
+            .map_labels(
+                |(letter, var)| match var {
+                    0 => (letter.clone(), 0),
+                    _ => (String::new(), 0),
+                },
+                &mapped_states,
+                &mapped_trans,
+            )
+            .unwrap();
+        let projected_states = Arena::new();
+        let projected_trans = Arena::new();
+        let mut projected = mapped.eliminate_empty_letters(&projected_states, &projected_trans);
+        projected.dimensions = 1;
+
+        let word = ["a", "a", "c"].map(String::from);
+        assert_eq!(
+            projected.word_acceptance_positions(&word),
+            vec![
+                MatchingInterval::new(0, 2),
+                MatchingInterval::new(1, 2),
+                MatchingInterval::new(2, 2),
+            ]
+        );
+        assert!(projected.word_acceptance_positions(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_restrict_to_alphabet() {
+        use typed_arena::Arena;
+
+        // Accepts (x on 0) and (a on 0)(b on 1)
+        let nfa_states = Arena::new();
+        let nfa_trans = Arena::new();
+        let mut nfa_h = NFAH::new(&nfa_states, &nfa_trans, 2);
+        let s0 = nfa_h.add_state(true, false);
+        let s1 = nfa_h.add_state(false, false);
+        let sf = nfa_h.add_state(false, true);
+        nfa_h.add_nfah_transition(s0, "x".to_string(), 0, sf);
+        nfa_h.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        nfa_h.add_nfah_transition(s1, "b".to_string(), 1, sf);
+        assert_eq!(nfa_h.shortest_accepted_word_length(), 1);
+
+        let alphabet: HashSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let restricted_states = Arena::new();
+        let restricted_trans = Arena::new();
+        let restricted =
+            nfa_h.restrict_to_alphabet(&alphabet, &restricted_states, &restricted_trans);
+        assert_eq!(restricted.dimensions, 2);
+        assert_eq!(restricted.shortest_accepted_word_length(), 2);
+        let letters = restricted
+            .iter_states()
+            .flat_map(|state| {
+                state
+                    .get_transitions()
+                    .iter()
+                    .map(|t| t.label.0.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(letters, alphabet);
+
+        // Without any letter of the accepted words, the language is empty
+        let alphabet: HashSet<String> = ["c".to_string()].into_iter().collect();
+        let empty_states = Arena::new();
+        let empty_trans = Arena::new();
+        assert!(nfa_h
+            .restrict_to_alphabet(&alphabet, &empty_states, &empty_trans)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_letters_for_variable() {
+        use typed_arena::Arena;
+
+        let nfa_states = Arena::new();
+        let nfa_trans = Arena::new();
+        let mut nfa_h = NFAH::new(&nfa_states, &nfa_trans, 3);
+        let s0 = nfa_h.add_state(true, false);
+        let s1 = nfa_h.add_state(false, true);
+        nfa_h.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        nfa_h.add_nfah_transition(s0, "b".to_string(), 0, s0);
+        nfa_h.add_nfah_transition(s1, "a".to_string(), 1, s0);
+
+        let letters = |letters: &[&str]| letters.iter().map(|s| s.to_string()).collect();
+        assert_eq!(nfa_h.letters_for_variable(0), letters(&["a", "b"]));
+        assert_eq!(nfa_h.letters_for_variable(1), letters(&["a"]));
+        assert!(nfa_h.letters_for_variable(2).is_empty());
+    }
 }
@@ -1,11 +1,13 @@
 use itertools::Itertools;
+use log::trace;
 
 use crate::{
     automata::NFAH,
     automata_runner::{NFAHRunner, ReadableView},
-    hyper_pattern_matching::PatternMatchingAutomataRunner,
-    result_notifier::{MatchingInterval, ResultNotifier},
+    hyper_pattern_matching::{hot_trace_enabled, PatternMatchingAutomataRunner},
+    result_notifier::{IncompleteMatch, IncompleteMatchNotifier, MatchingInterval, ResultNotifier},
     single_hyper_pattern_matching::SingleHyperPatternMatching,
+    symbol_matcher::SharedSymbolMatcher,
 };
 
 pub struct OnlineSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
@@ -13,13 +15,101 @@ pub struct OnlineSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
     notifier: Notifier,
     input_streams: Vec<ReadableView<String>>,
     ids: Vec<usize>,
+    /// The number of matches notified so far
+    matches_found: usize,
+    /// The channel to report the incomplete trials at the end of the input, if any
+    incomplete_notifier: Option<Box<dyn IncompleteMatchNotifier<'a> + 'a>>,
+    /// The furthest-progressed trials dropped so far without reaching a final state
+    furthest_incomplete: Vec<IncompleteMatch<'a>>,
 }
 
-impl<Notifier: ResultNotifier> OnlineSingleHyperPatternMatching<'_, Notifier> {
+impl<'a, Notifier: ResultNotifier> OnlineSingleHyperPatternMatching<'a, Notifier> {
+    /// Reports the furthest-progressed incomplete trials to `incomplete_notifier` at the end of
+    /// the input, i.e., once all the input streams are closed.
+    ///
+    /// The incomplete trials are the ones dropped without reaching a final state, either because
+    /// no transition can read the next symbol or because the input ended. Among them, only the
+    /// ones consuming the most symbols in total are reported, which are the near-misses to look
+    /// at when a pattern did not match.
+    pub fn set_incomplete_notifier(
+        &mut self,
+        incomplete_notifier: impl IncompleteMatchNotifier<'a> + 'a,
+    ) {
+        self.incomplete_notifier = Some(Box::new(incomplete_notifier));
+    }
+
+    /// Keeps the furthest-progressed trials among the ones to be dropped without reaching a final
+    /// state.
+    fn record_incomplete_trials(&mut self) {
+        let dropped = self
+            .automata_runner
+            .current_configurations
+            .iter()
+            .filter(|c| !c.is_final() && !self.automata_runner.is_waiting(c));
+        for c in dropped {
+            let consumed = c
+                .input_sequence
+                .iter()
+                .zip(&c.matching_begin)
+                .map(|(sequence, &begin)| sequence.full_slice()[begin..sequence.start].to_vec())
+                .collect_vec();
+            let incomplete = IncompleteMatch {
+                state: c.current_state,
+                begin: c.matching_begin.clone(),
+                consumed,
+                ids: c.ids.clone(),
+            };
+            let furthest = self
+                .furthest_incomplete
+                .first()
+                .map_or(0, IncompleteMatch::progress);
+            // The trials consuming nothing are not near-misses
+            if incomplete.progress() == 0 || incomplete.progress() < furthest {
+                continue;
+            }
+            if incomplete.progress() > furthest {
+                self.furthest_incomplete.clear();
+            }
+            self.furthest_incomplete.push(incomplete);
+        }
+    }
+
+    /// Notifies the recorded incomplete trials if all the input streams are closed.
+    fn report_incomplete_trials(&mut self) {
+        if !self.input_streams.iter().all(|stream| stream.is_closed()) {
+            return;
+        }
+        if let Some(incomplete_notifier) = self.incomplete_notifier.as_mut() {
+            for incomplete in self.furthest_incomplete.drain(..) {
+                incomplete_notifier.notify_incomplete(&incomplete);
+            }
+        }
+    }
+
+    /// Returns the number of live matching trials, i.e., the configurations waiting for more input.
+    ///
+    /// Each trial keeps views of the input streams alive, so this is a measure of the memory
+    /// usage, e.g., to decide if a window should be set with `set_max_window`.
+    pub fn pending_trials(&self) -> usize {
+        self.automata_runner.current_configurations.len()
+    }
+
     fn get_read_size(&self, variable: usize) -> usize {
         self.input_streams[variable].start
     }
 
+    /// Returns the number of symbols fed to each track since the beginning of a match.
+    ///
+    /// This is the latency of reporting the match per dimension: the track with the largest
+    /// value is the one the match had to wait for the longest.
+    fn match_latencies(&self, matching_begin: &[usize]) -> Vec<usize> {
+        matching_begin
+            .iter()
+            .enumerate()
+            .map(|(variable, &begin)| self.get_read_size(variable) - begin)
+            .collect()
+    }
+
     fn build_initial_positions(&self, inserted_var: usize) -> Vec<Vec<usize>> {
         let variable_size = self.dimensions();
         let mut all_dims = Vec::with_capacity(variable_size);
@@ -60,10 +150,11 @@ impl<Notifier: ResultNotifier> OnlineSingleHyperPatternMatching<'_, Notifier> {
         result
     }
 
-    fn insert_initial_positions(&mut self) {
+    /// Inserts the matching trials starting at the positions read up to `snapshot`.
+    fn insert_initial_positions(&mut self, snapshot: &[usize]) {
         let variable_size = self.dimensions();
-        for variable in 0..variable_size {
-            while !self.input_streams[variable].is_empty() {
+        for (variable, &snapshot) in snapshot.iter().enumerate() {
+            while self.input_streams[variable].len_at(snapshot) > 0 {
                 self.input_streams[variable].advance_readable(1);
                 // Get initial positions with optimized memory usage
                 let initial_positions = self.build_initial_positions(variable);
@@ -102,6 +193,9 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
             notifier,
             input_streams,
             ids,
+            matches_found: 0,
+            incomplete_notifier: None,
+            furthest_incomplete: Vec::new(),
         }
     }
 
@@ -113,13 +207,28 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
         self.ids.get(variable).copied()
     }
 
+    fn matches_found(&self) -> usize {
+        self.matches_found
+    }
+
     fn consume_input(&mut self) {
+        // Take the snapshot of the input once per step so that all the reads agree on it
+        let snapshot = self
+            .input_streams
+            .iter()
+            .map(ReadableView::snapshot_len)
+            .collect_vec();
         // Insert the initial positions as much as possible
-        self.insert_initial_positions();
-        // Thus, all input streams should be empty
-        assert!(self.input_streams.iter().all(|stream| stream.is_empty()));
+        self.insert_initial_positions(&snapshot);
+        // Thus, all input streams should be empty as of the snapshot
+        assert!(self
+            .input_streams
+            .iter()
+            .zip(&snapshot)
+            .all(|(stream, &snapshot)| stream.len_at(snapshot) == 0));
 
         // Process configurations
+        self.automata_runner.set_input_snapshot(snapshot);
         self.automata_runner.consume();
 
         // Get and process final configurations
@@ -137,12 +246,36 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
                 result.push(MatchingInterval::new(begin, end));
             }
 
+            if hot_trace_enabled() {
+                trace!(
+                    "[OnlineSingleHyperPatternMatching::consume_input] match latency: ids={:?} intervals={:?} fed_per_track={:?}",
+                    c.ids,
+                    result,
+                    self.match_latencies(&c.matching_begin)
+                );
+            }
+
             // Notify with the result
             self.notifier.notify(&result, &c.ids);
+            self.matches_found += 1;
         }
 
         // Remove configurations that are not in a waiting state
+        if self.incomplete_notifier.is_some() {
+            self.record_incomplete_trials();
+        }
         self.automata_runner.remove_non_waiting_configurations();
+        self.report_incomplete_trials();
+    }
+
+    fn set_max_window(&mut self, max_window: usize) -> bool {
+        self.automata_runner.set_max_window(max_window);
+        true
+    }
+
+    fn set_symbol_matcher(&mut self, symbol_matcher: SharedSymbolMatcher) -> bool {
+        self.automata_runner.set_symbol_matcher(symbol_matcher);
+        true
     }
 
     fn get_input_stream(&self, variable: usize) -> &ReadableView<String> {
@@ -155,8 +288,10 @@ mod tests {
 
     use super::*;
     use crate::automata_runner::AppendOnlySequence;
-    use crate::result_notifier::SharedBufferResultNotifier;
+    use crate::result_notifier::{CollectingIncompleteMatchNotifier, SharedBufferResultNotifier};
     use crate::shared_buffer::SharedBuffer;
+    #[cfg(not(feature = "no-trace"))]
+    use crate::tests::utils::install_recording_logger;
     use crate::tests::utils::verify_intervals;
     use typed_arena::Arena;
 
@@ -214,6 +349,65 @@ mod tests {
         assert!(result_sink.pop().is_none());
     }
 
+    #[test]
+    fn test_pending_trials() {
+        // "a" on track 0, then "b" on track 1, then "c" or "d" on track 0 through two branches
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let s4 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s3);
+        automaton.add_nfah_transition(s2, "c".to_string(), 0, s4);
+        automaton.add_nfah_transition(s3, "d".to_string(), 0, s4);
+
+        let mut sequences = [AppendOnlySequence::new(), AppendOnlySequence::new()];
+        let input_streams = sequences.iter().map(|s| s.readable_view()).collect();
+        let result_buffer = SharedBuffer::new();
+        let mut matcher = OnlineSingleHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            input_streams,
+            vec![0, 1],
+        );
+        let mut result_sink = result_buffer.make_sink();
+        assert_eq!(matcher.pending_trials(), 0);
+
+        // After "a", the trial waits for track 0 at s1, s2, and s3
+        sequences[0].append("a".to_string());
+        sequences[1].append("b".to_string());
+        matcher.consume_input();
+        assert_eq!(matcher.pending_trials(), 3);
+        assert!(result_sink.pop().is_none());
+
+        // The match through s2 completes, but the trials at s2, s3, and s4 may still read track 1
+        sequences[0].append("c".to_string());
+        matcher.consume_input();
+        assert_eq!(matcher.pending_trials(), 3);
+        assert_eq!(
+            result_sink.pop().map(|r| r.intervals),
+            Some(vec![
+                MatchingInterval::new(0, 1),
+                MatchingInterval::new(0, 0)
+            ])
+        );
+
+        // Once track 1 ends, only the trial at s4 waits for track 0
+        sequences[1].close();
+        matcher.consume_input();
+        assert_eq!(matcher.pending_trials(), 1);
+
+        // No trial is live once all the inputs end
+        sequences[0].close();
+        matcher.consume_input();
+        assert_eq!(matcher.pending_trials(), 0);
+    }
+
     #[test]
     fn test_single_hyper_pattern_matching() {
         let state_arena = Arena::new();
@@ -279,4 +473,119 @@ mod tests {
 
         verify_intervals(&results, &expected_intervals);
     }
+
+    #[test]
+    #[cfg(not(feature = "no-trace"))]
+    fn test_match_latency_logging() {
+        let logger = install_recording_logger();
+
+        // "a" on track 0 followed by "b" on track 1
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+
+        let mut sequences = [AppendOnlySequence::new(), AppendOnlySequence::new()];
+        let input_streams = sequences.iter().map(|s| s.readable_view()).collect();
+        // Use ids unlikely to be used by other tests logging concurrently
+        let ids = vec![4242, 4243];
+
+        let result_buffer = SharedBuffer::new();
+        let mut matcher = OnlineSingleHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            input_streams,
+            ids.clone(),
+        );
+        let mut result_sink = result_buffer.make_sink();
+
+        // The match on track 0 is complete after the first symbol, but it is reported only
+        // after "b" is fed to track 1, when three symbols are already fed to track 0.
+        for c in ["a", "c", "c"] {
+            sequences[0].append(c.to_string());
+            matcher.consume_input();
+        }
+        sequences[1].append("b".to_string());
+        matcher.consume_input();
+
+        let result = result_sink.pop().unwrap();
+        assert_eq!(
+            vec![MatchingInterval::new(0, 0), MatchingInterval::new(0, 0)],
+            result.intervals
+        );
+        assert!(result_sink.pop().is_none());
+
+        let latency_records = logger
+            .records()
+            .into_iter()
+            .filter(|record| record.contains("match latency: ids=[4242, 4243]"))
+            .collect_vec();
+        assert_eq!(latency_records.len(), 1);
+        assert!(
+            latency_records[0].ends_with("fed_per_track=[3, 1]"),
+            "Unexpected record: {}",
+            latency_records[0]
+        );
+    }
+
+    #[test]
+    fn test_incomplete_notifier() {
+        // "a" on track 0, "b" on track 1, and then "c" on track 0
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 1, s2);
+        automaton.add_nfah_transition(s2, "c".to_string(), 0, s3);
+
+        let mut sequences = [AppendOnlySequence::new(), AppendOnlySequence::new()];
+        let input_streams = sequences.iter().map(|s| s.readable_view()).collect();
+        let result_buffer = SharedBuffer::new();
+        let mut matcher = OnlineSingleHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            input_streams,
+            vec![0, 1],
+        );
+        let mut result_sink = result_buffer.make_sink();
+        let incomplete_notifier = CollectingIncompleteMatchNotifier::default();
+        matcher.set_incomplete_notifier(incomplete_notifier.clone());
+
+        // The input almost matches: "x" is read instead of "c"
+        sequences[0].append("a".to_string());
+        sequences[1].append("b".to_string());
+        sequences[0].append("x".to_string());
+        matcher.consume_input();
+        sequences[0].close();
+        matcher.consume_input();
+        // Nothing is reported until the end of the input
+        assert!(incomplete_notifier.collected().is_empty());
+        sequences[1].close();
+        matcher.consume_input();
+        assert!(result_sink.pop().is_none());
+
+        // Only the trial stuck at s2 after reading "a" and "b" is reported
+        let reported = incomplete_notifier.collected();
+        assert_eq!(reported.len(), 1);
+        assert!(std::ptr::eq(reported[0].state, s2));
+        assert_eq!(reported[0].begin, vec![0, 0]);
+        assert_eq!(
+            reported[0].consumed,
+            vec![vec!["a".to_string()], vec!["b".to_string()]]
+        );
+        assert_eq!(reported[0].ids, vec![0, 1]);
+        assert_eq!(reported[0].progress(), 2);
+
+        // The report is not repeated
+        matcher.consume_input();
+        assert_eq!(incomplete_notifier.collected().len(), 1);
+    }
 }
@@ -4,28 +4,16 @@ use std::{
 };
 
 use log::{debug, info, trace};
+use typed_arena::Arena;
 
 use crate::automata::NFAH;
 
-pub trait VectorWithVer<T> {
-    fn project(&self, variable: usize) -> Vec<T>;
-}
-
-impl<T> VectorWithVer<T> for Vec<(T, usize)>
-where
-    T: Clone,
-{
-    fn project(&self, variable: usize) -> Vec<T> {
-        self.iter()
-            .filter(|(_, var)| *var == variable)
-            .map(|(val, _)| val.clone())
-            .collect()
-    }
-}
-
 pub struct QuickSearchSkipValues {
-    /// Returns the length of the shortest accepted word projected to each variable.
-    pub shortest_accepted_word_length_map: Vec<usize>,
+    /// Returns the length of the shortest accepted word projected to each variable, given by
+    /// [`crate::automata::Automata::shortest_accepted_word_length_per_variable`].
+    ///
+    /// The entry is `None` if the automaton accepts no word, i.e., there is no shortest word.
+    pub shortest_accepted_word_length_map: Vec<Option<usize>>,
     pub last_accepted_word: Vec<HashSet<String>>,
     skip_values_map: Vec<HashMap<String, usize>>,
 }
@@ -37,55 +25,69 @@ impl QuickSearchSkipValues {
     ///
     /// A new `QuickSearchSkipValues` instance.
     pub fn new(autom: &NFAH) -> Self {
+        Self::with_alphabet(autom, None)
+    }
+
+    /// Creates a new `QuickSearchSkipValues` instance for inputs over `alphabet`.
+    ///
+    /// The letters not in the automaton already get the largest skip value, but the accepted words
+    /// using a letter outside `alphabet` cannot match such inputs. We ignore them, which makes the
+    /// shortest accepted words longer and the skip values larger. If `alphabet` is `None`, this is
+    /// the same as [`QuickSearchSkipValues::new`]. The skip values are unsound if the input contains
+    /// a letter not in `alphabet`.
+    pub fn with_alphabet(autom: &NFAH, alphabet: Option<&HashSet<String>>) -> Self {
+        match alphabet {
+            Some(alphabet) => {
+                let state_arena = Arena::new();
+                let trans_arena = Arena::new();
+                let restricted = autom.restrict_to_alphabet(alphabet, &state_arena, &trans_arena);
+                Self::from_automaton(&restricted)
+            }
+            None => Self::from_automaton(autom),
+        }
+    }
+
+    fn from_automaton(autom: &NFAH) -> Self {
         // Start measuring the time it takes to construct the skip value table
         let start = Instant::now();
 
-        let shortest_length = autom.shortest_accepted_word_length();
-        let accepted_prefixes = autom.accepted_prefixes(shortest_length);
-        let shortest_accepted_word_length_map: Vec<usize> = (0..autom.dimensions)
-            .map(|var| {
-                accepted_prefixes
-                    .iter()
-                    .map(|prefix| prefix.project(var).len())
-                    .min()
-                    .unwrap_or(0)
-            })
-            .collect();
-        let accepted_words: Vec<Vec<Vec<String>>> = (0..autom.dimensions)
-            .map(|var| {
-                accepted_prefixes
-                    .iter()
-                    .map(|prefix| prefix.project(var).clone())
-                    .collect()
+        // The skip values are learned from the prefixes of the shortest length on each variable.
+        // If the language is empty, there is no accepted word to learn them from.
+        let shortest_accepted_word_length_map = autom.shortest_accepted_word_length_per_variable();
+        let accepted_words: Vec<Vec<Vec<String>>> = shortest_accepted_word_length_map
+            .iter()
+            .enumerate()
+            .map(|(var, length)| match length {
+                Some(length) => autom
+                    .accepted_prefixes_on_variable(var, *length)
+                    .into_iter()
+                    .map(|prefix| prefix.into_iter().map(|(letter, _)| letter).collect())
+                    .collect(),
+                None => Vec::new(),
             })
             .collect();
 
         let last_accepted_word = (0..autom.dimensions)
-            .map(|var| {
-                if shortest_accepted_word_length_map[var] == 0 {
-                    HashSet::new()
-                } else {
-                    let ind = shortest_accepted_word_length_map[var] - 1;
-                    accepted_words[var]
-                        .iter()
-                        .map(|word| word[ind].clone())
-                        .collect()
-                }
+            .map(|var| match shortest_accepted_word_length_map[var] {
+                Some(length) if length > 0 => accepted_words[var]
+                    .iter()
+                    .map(|word| word[length - 1].clone())
+                    .collect(),
+                _ => HashSet::new(),
             })
             .collect();
 
         let mut skip_values_map = Vec::with_capacity(autom.dimensions);
         for var in 0..autom.dimensions {
             let mut skip_values = HashMap::new();
-            let shortest_accepted_word_length = shortest_accepted_word_length_map[var];
+            let shortest_accepted_word_length = shortest_accepted_word_length_map[var].unwrap_or(0);
             for word in accepted_words[var].iter() {
+                // Each letter is shifted to its last occurrence in the word
                 for i in 0..shortest_accepted_word_length {
                     let key = &word[shortest_accepted_word_length - 1 - i];
-                    if !skip_values.contains_key(key) {
-                        skip_values.insert(key.clone(), i + 1);
-                        break;
-                    } else if skip_values[key] > i + 1 {
-                        *skip_values.get_mut(key).unwrap() = i + 1;
+                    let skip_value = skip_values.entry(key.clone()).or_insert(i + 1);
+                    if *skip_value > i + 1 {
+                        *skip_value = i + 1;
                     }
                 }
             }
@@ -118,7 +120,7 @@ impl QuickSearchSkipValues {
         if self.skip_values_map[variable].contains_key(action) {
             self.skip_values_map[variable][action]
         } else {
-            self.shortest_accepted_word_length_map[variable] + 1
+            self.shortest_accepted_word_length_map[variable].unwrap_or(0) + 1
         }
     }
 }
@@ -150,7 +152,7 @@ mod tests {
         let quick_search_skip_values = QuickSearchSkipValues::new(&automaton);
         assert_eq!(
             quick_search_skip_values.shortest_accepted_word_length_map,
-            vec![2, 1]
+            vec![Some(2), Some(1)]
         );
         assert_eq!(quick_search_skip_values.last_accepted_word[0].len(), 2);
         assert!(quick_search_skip_values.last_accepted_word[0].contains("a"));
@@ -165,4 +167,136 @@ mod tests {
         assert_eq!(quick_search_skip_values.skip_value("b", 1), 2);
         assert_eq!(quick_search_skip_values.skip_value("c", 1), 1);
     }
+
+    #[test]
+    fn test_skip_values_of_earlier_letters() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 1);
+
+        // Accepts only "ab"
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, sf);
+
+        let quick_search_skip_values = QuickSearchSkipValues::new(&automaton);
+        // Skipping 3 after "a" would miss the occurrence of "ab" starting at "a"
+        assert_eq!(quick_search_skip_values.skip_value("a", 0), 2);
+        assert_eq!(quick_search_skip_values.skip_value("b", 0), 1);
+        assert_eq!(quick_search_skip_values.skip_value("c", 0), 3);
+    }
+
+    #[test]
+    fn test_skip_values_of_longer_words_on_variable() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 2);
+
+        // Accepts "a a" on the variable 0 with "b" or "b b" on the variable 1. The prefix "b b a"
+        // of the shortest length 3 reads only one "a", but every accepted word reads two.
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, false);
+        let s4 = automaton.add_state(false, false);
+        let s5 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "b".to_string(), 1, sf);
+        automaton.add_nfah_transition(s0, "b".to_string(), 1, s3);
+        automaton.add_nfah_transition(s3, "b".to_string(), 1, s4);
+        automaton.add_nfah_transition(s4, "a".to_string(), 0, s5);
+        automaton.add_nfah_transition(s5, "a".to_string(), 0, sf);
+
+        let quick_search_skip_values = QuickSearchSkipValues::new(&automaton);
+        assert_eq!(
+            quick_search_skip_values.shortest_accepted_word_length_map,
+            automaton.shortest_accepted_word_length_per_variable()
+        );
+        assert_eq!(
+            quick_search_skip_values.shortest_accepted_word_length_map,
+            vec![Some(2), Some(1)]
+        );
+        assert_eq!(
+            quick_search_skip_values.last_accepted_word[0],
+            HashSet::from(["a".to_string()])
+        );
+        assert_eq!(quick_search_skip_values.skip_value("a", 0), 1);
+        assert_eq!(quick_search_skip_values.skip_value("c", 0), 3);
+        assert_eq!(quick_search_skip_values.skip_value("b", 1), 1);
+        assert_eq!(quick_search_skip_values.skip_value("c", 1), 2);
+    }
+
+    #[test]
+    fn test_shortest_accepted_word_length_map_without_shortest_word() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 2);
+
+        // The variable 1 does not appear in any accepted word
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, sf);
+
+        let quick_search_skip_values = QuickSearchSkipValues::new(&automaton);
+        assert_eq!(
+            quick_search_skip_values.shortest_accepted_word_length_map,
+            vec![Some(2), Some(0)]
+        );
+        assert!(quick_search_skip_values.last_accepted_word[1].is_empty());
+        assert_eq!(quick_search_skip_values.skip_value("a", 1), 1);
+
+        // Without any final state, the language is empty and there is no shortest word
+        sf.set_final(false);
+        let quick_search_skip_values = QuickSearchSkipValues::new(&automaton);
+        assert_eq!(
+            quick_search_skip_values.shortest_accepted_word_length_map,
+            vec![None, None]
+        );
+        assert!(quick_search_skip_values.last_accepted_word[0].is_empty());
+        assert_eq!(quick_search_skip_values.skip_value("a", 0), 1);
+    }
+
+    #[test]
+    fn test_skip_values_with_alphabet() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let mut automaton = NFAH::new(&state_arena, &trans_arena, 1);
+
+        // Accepts "x" and "ab", but "x" never appears in the input
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let sf = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "x".to_string(), 0, sf);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, sf);
+
+        let without_alphabet = QuickSearchSkipValues::new(&automaton);
+        let alphabet: HashSet<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let with_alphabet = QuickSearchSkipValues::with_alphabet(&automaton, Some(&alphabet));
+        assert_eq!(
+            without_alphabet.shortest_accepted_word_length_map,
+            vec![Some(1)]
+        );
+        assert_eq!(
+            with_alphabet.shortest_accepted_word_length_map,
+            vec![Some(2)]
+        );
+        assert_eq!(
+            with_alphabet.last_accepted_word[0],
+            HashSet::from(["b".to_string()])
+        );
+
+        assert_eq!(without_alphabet.skip_value("a", 0), 1);
+        assert_eq!(without_alphabet.skip_value("b", 0), 2);
+        assert_eq!(without_alphabet.skip_value("c", 0), 2);
+        assert_eq!(with_alphabet.skip_value("a", 0), 2);
+        assert_eq!(with_alphabet.skip_value("b", 0), 1);
+        assert_eq!(with_alphabet.skip_value("c", 0), 3);
+    }
 }
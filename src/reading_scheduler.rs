@@ -1,6 +1,8 @@
 use crate::hyper_pattern_matching::HyperPatternMatching;
-use crate::multi_stream_reader::MultiStreamReader;
+use crate::multi_stream_reader::{MultiStreamReader, POLL_INTERVAL};
 use log::debug;
+use std::io;
+use std::thread;
 
 /// A scheduler that continuously reads from multiple input streams and feeds lines into a
 /// [`HyperPatternMatching`] implementation.
@@ -12,6 +14,12 @@ use log::debug;
 pub struct ReadingScheduler<Matching: HyperPatternMatching> {
     matching: Matching,
     reader: MultiStreamReader,
+    /// Whether each track is notified of its end by [`HyperPatternMatching::set_eof`]
+    eof: Vec<bool>,
+    /// Whether [`HyperPatternMatching::consume_remaining`] is called
+    finished: bool,
+    /// The delimiter of the actions in a line, if a line is not a single action
+    delimiter: Option<char>,
 }
 
 impl<Matching: HyperPatternMatching> ReadingScheduler<Matching> {
@@ -26,14 +34,44 @@ impl<Matching: HyperPatternMatching> ReadingScheduler<Matching> {
     ///
     /// A new `ReadingScheduler` instance.
     pub fn new(matching: Matching, reader: MultiStreamReader) -> Self {
-        Self { matching, reader }
+        let eof = vec![false; reader.num_streams()];
+        Self {
+            matching,
+            reader,
+            eof,
+            finished: false,
+            delimiter: None,
+        }
+    }
+
+    /// Feeds each line as the actions separated by `delimiter` by
+    /// [`HyperPatternMatching::feed_line`], e.g., `a,b,c` with the delimiter `,`, instead of as a
+    /// single action.
+    pub fn set_delimiter(&mut self, delimiter: char) {
+        self.delimiter = Some(delimiter);
+    }
+
+    /// Returns the matching engine fed by this scheduler.
+    pub fn matching(&self) -> &Matching {
+        &self.matching
+    }
+
+    /// Returns the matching engine fed by this scheduler, consuming the scheduler.
+    pub fn into_matching(self) -> Matching {
+        self.matching
     }
 
     /// Runs the scheduler until the end of all streams.
     ///
     /// The scheduler repeatedly reads lines from each available stream. When a line is
-    /// successfully read, it is passed to the [`HyperPatternMatching::feed`] method, which
-    /// processes it according to the pattern-matching logic.
+    /// successfully read, it is passed to the [`HyperPatternMatching::feed`] method, or to
+    /// [`HyperPatternMatching::feed_line`] with a delimiter, which processes it according to the
+    /// pattern-matching logic. If no stream has a new line but some
+    /// are not at the end, e.g., a file followed by
+    /// [`FollowingReader`](crate::multi_stream_reader::FollowingReader), the scheduler sleeps
+    /// briefly before checking them again. Before the first sleep after new lines, the matching is
+    /// flushed by [`HyperPatternMatching::flush`], so the matches found so far are output while
+    /// waiting, even if the notifier buffers them.
     ///
     /// # Examples
     ///
@@ -45,28 +83,81 @@ impl<Matching: HyperPatternMatching> ReadingScheduler<Matching> {
     /// scheduler.run();
     /// ```
     pub fn run(&mut self) {
-        let mut done: Vec<bool> = (0..self.reader.size()).map(|_| false).collect();
-        while done.iter().any(|x| !*x) {
-            for i in 0..self.reader.size() {
-                if !done[i] {
-                    let line = self.reader.read_line(i);
-                    if line.is_err() {
-                        done[i] = true;
-                    } else {
-                        let line = line.unwrap().trim_end().to_string();
-                        self.matching.feed(&line, i);
-                        let availability = self.reader.is_available(i);
-                        done[i] = availability.is_err() || availability.is_ok_and(|f| !f);
-                    }
-                    if done[i] {
-                        debug!("stream {} is closed", i);
-                        self.matching.set_eof(i);
+        self.feed_until_eof();
+        self.close_all();
+    }
+
+    /// Feeds the lines of the streams until the end of all of them, as [`ReadingScheduler::run`]
+    /// does, but without consuming the remaining input by [`ReadingScheduler::close_all`].
+    pub fn feed_until_eof(&mut self) {
+        // Whether the matching is flushed after the last line fed
+        let mut flushed = true;
+        while self.eof.iter().any(|eof| !eof) {
+            let mut waiting = false;
+            let mut progressed = false;
+            for i in 0..self.reader.num_streams() {
+                if self.eof[i] {
+                    continue;
+                }
+                match self.reader.read_line(i) {
+                    // An empty string without a newline is the end of the stream, not an action
+                    Ok(line) if !line.is_empty() => {
+                        match self.delimiter {
+                            Some(delimiter) => {
+                                self.matching.feed_line(line.trim_end(), i, delimiter)
+                            }
+                            None => self.matching.feed(line.trim_end(), i),
+                        }
+                        progressed = true;
+                        if let Ok(false) = self.reader.is_available(i) {
+                            self.close_track(i);
+                        }
                     }
+                    // No new line yet, e.g., in a followed file
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => waiting = true,
+                    _ => self.close_track(i),
+                }
+            }
+            if progressed {
+                flushed = false;
+            } else if waiting {
+                if !flushed {
+                    // Output the matches so far, as no more input may come for a while
+                    self.matching.flush();
+                    flushed = true;
                 }
+                // Wait for more input without busy-spinning
+                thread::sleep(POLL_INTERVAL);
             }
         }
+    }
 
-        self.matching.consume_remaining();
+    /// Notifies the end of all the tracks not closed yet, and then consumes the remaining input.
+    ///
+    /// The matches completing only at the end of the input are reported here. The remaining
+    /// input is consumed only once, so calling this again has no effect.
+    pub fn close_all(&mut self) {
+        for i in 0..self.eof.len() {
+            self.close_track(i);
+        }
+        if !self.finished {
+            self.finished = true;
+            self.matching.consume_remaining();
+        }
+    }
+
+    /// Notifies the end of the track `i` unless it is already notified.
+    fn close_track(&mut self, i: usize) {
+        if self.eof[i] {
+            return;
+        }
+        debug!(
+            "stream {} is closed after {} lines",
+            i,
+            self.reader.lines_read(i).unwrap_or(0)
+        );
+        self.eof[i] = true;
+        self.matching.set_eof(i);
     }
 }
 
@@ -75,13 +166,65 @@ mod tests {
     use super::*;
     use crate::automata::Automata;
     use crate::automata_runner::AppendOnlySequence;
+    use crate::hyper_pattern_matching::HyperPatternMatchingAdapter;
+    use crate::multi_stream_reader::FollowingReader;
     use crate::multi_stream_reader::StreamSource;
     use crate::naive_hyper_pattern_matching::NaiveHyperPatternMatching;
+    use crate::online_single_hyper_pattern_matching::OnlineSingleHyperPatternMatching;
     use crate::result_notifier::{MatchingInterval, MatchingResult, SharedBufferResultNotifier};
     use crate::shared_buffer::{SharedBuffer, SharedBufferSource};
+    use crate::tests::utils::create_small_automaton;
     use std::collections::HashSet;
+    use std::io::{Cursor, Write};
+    use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tempfile::NamedTempFile;
     use typed_arena::Arena;
 
+    #[test]
+    fn test_delimiter() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &transition_arena);
+
+        let run = |inputs: [&str; 2], delimiter: Option<char>| {
+            let reader = MultiStreamReader::new(
+                inputs
+                    .iter()
+                    .map(|input| Box::new(Cursor::new(input.to_string())) as Box<dyn StreamSource>)
+                    .collect(),
+            );
+            let result_buffer = SharedBuffer::new();
+            let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+            let mut result_sink = result_buffer.make_sink();
+            let matching = NaiveHyperPatternMatching::new(
+                &automaton,
+                notifier,
+                vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+                false,
+            );
+            let mut scheduler = ReadingScheduler::new(matching, reader);
+            if let Some(delimiter) = delimiter {
+                scheduler.set_delimiter(delimiter);
+            }
+            scheduler.run();
+
+            let mut results = HashSet::new();
+            while let Some(result) = result_sink.pop() {
+                results.insert(result);
+            }
+            results
+        };
+
+        let expected = run(["a\na\nc\n", "a\nd\nd\n"], None);
+        assert_eq!(expected.len(), 6);
+        // The actions are split at the delimiter, skipping the empty ones
+        assert_eq!(run(["a,a\nc\n", "a,,d,d\n"], Some(',')), expected);
+        // Without the delimiter, each line is an action
+        assert!(run(["a,a\nc\n", "a,,d,d\n"], None).is_empty());
+    }
+
     #[test]
     fn test_run() {
         let state_arena = Arena::new();
@@ -120,6 +263,7 @@ mod tests {
             &automaton,
             notifier,
             vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
         );
 
         let mut scheduler = ReadingScheduler::new(matching, reader);
@@ -167,4 +311,213 @@ mod tests {
             ids: vec![0, 1]
         }));
     }
+
+    /// Builds a reader over the given lines of each stream.
+    fn reader_of(streams: &[&[&'static str]]) -> MultiStreamReader {
+        let buffers: Vec<SharedBuffer<&str>> =
+            streams.iter().map(|_| SharedBuffer::new()).collect();
+        for (buffer, lines) in buffers.iter().zip(streams) {
+            let source = buffer.make_source();
+            for &line in lines.iter() {
+                source.push(line);
+            }
+        }
+        MultiStreamReader::new(
+            buffers
+                .into_iter()
+                .map(|buf| Box::new(buf) as Box<dyn StreamSource>)
+                .collect(),
+        )
+    }
+
+    /// A `HyperPatternMatching` recording the calls from the scheduler.
+    struct RecordingMatching {
+        dimensions: usize,
+        calls: Vec<String>,
+    }
+
+    impl HyperPatternMatching for RecordingMatching {
+        fn feed(&mut self, action: &str, track: usize) {
+            self.calls.push(format!("feed {} {}", track, action));
+        }
+
+        fn dimensions(&self) -> usize {
+            self.dimensions
+        }
+
+        fn flush(&mut self) {
+            self.calls.push("flush".to_string());
+        }
+
+        fn consume_remaining(&mut self) {
+            self.calls.push("consume_remaining".to_string());
+        }
+
+        fn set_eof(&mut self, track: usize) {
+            self.calls.push(format!("eof {}", track));
+        }
+    }
+
+    #[test]
+    fn test_close_all_ordering() {
+        let matching = RecordingMatching {
+            dimensions: 3,
+            calls: Vec::new(),
+        };
+        // The streams end at different rounds, and the last one is empty
+        let mut scheduler = ReadingScheduler::new(matching, reader_of(&[&["a", "b"], &["c"], &[]]));
+        scheduler.run();
+        // Calling it again does nothing
+        scheduler.close_all();
+
+        assert_eq!(
+            scheduler.matching().calls,
+            vec![
+                "feed 0 a",
+                "feed 1 c",
+                "eof 1",
+                "eof 2",
+                "feed 0 b",
+                "eof 0",
+                "consume_remaining",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flush_while_waiting() {
+        let matching = RecordingMatching {
+            dimensions: 1,
+            calls: Vec::new(),
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let followed = FollowingReader::new(Cursor::new("a\n"), stop.clone());
+        let reader = MultiStreamReader::new(vec![Box::new(followed) as Box<dyn StreamSource>]);
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            stop.store(true, AtomicOrdering::Release);
+        });
+        let mut scheduler = ReadingScheduler::new(matching, reader);
+        scheduler.run();
+        stopper.join().unwrap();
+
+        // Flushed only once while waiting for the lines after "a"
+        assert_eq!(
+            scheduler.matching().calls,
+            vec!["feed 0 a", "flush", "eof 0", "consume_remaining"]
+        );
+    }
+
+    #[test]
+    fn test_run_staggered_eof() {
+        // "a" then "b" on track 0 and then "c" on track 1
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = Automata::new(&state_arena, &transition_arena, 2);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, false);
+        let s3 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s2);
+        automaton.add_nfah_transition(s2, "c".to_string(), 1, s3);
+
+        let result_buffer = SharedBuffer::new();
+        let mut result_sink = result_buffer.make_sink();
+        let matching = NaiveHyperPatternMatching::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            vec![AppendOnlySequence::new(), AppendOnlySequence::new()],
+            false,
+        );
+
+        // Track 1 ends long before the match completes with the last action of track 0
+        let mut scheduler =
+            ReadingScheduler::new(matching, reader_of(&[&["x", "x", "a", "b"], &["c"]]));
+        scheduler.run();
+
+        let mut results = HashSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        assert_eq!(
+            results,
+            HashSet::from([MatchingResult {
+                intervals: vec![MatchingInterval::new(2, 3), MatchingInterval::new(0, 0)],
+                ids: vec![0, 1]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_run_following_file() {
+        // "a" then "b"
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automaton = Automata::new(&state_arena, &transition_arena, 1);
+        let s0 = automaton.add_state(true, false);
+        let s1 = automaton.add_state(false, false);
+        let s2 = automaton.add_state(false, true);
+        automaton.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automaton.add_nfah_transition(s1, "b".to_string(), 0, s2);
+
+        let result_buffer = SharedBuffer::new();
+        let matching = HyperPatternMatchingAdapter::<
+            OnlineSingleHyperPatternMatching<SharedBufferResultNotifier>,
+            SharedBufferResultNotifier,
+        >::new(
+            &automaton,
+            SharedBufferResultNotifier::new(result_buffer.make_source()),
+            1,
+        );
+
+        let log = NamedTempFile::new().unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let followed = FollowingReader::new(log.reopen().unwrap(), stop.clone());
+        let reader = MultiStreamReader::new(vec![Box::new(followed) as Box<dyn StreamSource>]);
+
+        // Append the lines while the scheduler is running, and wait for the matches to be reported
+        let mut file = log.reopen().unwrap();
+        let mut result_sink = result_buffer.make_sink();
+        let writer = thread::spawn(move || {
+            let mut results = HashSet::new();
+            let mut reported = Vec::new();
+            // The last line "b" is incomplete until the newline is appended
+            for (lines, expected) in [("x\na\n", 0), ("b\n", 1), ("a\nb", 1), ("\n", 2)] {
+                file.write_all(lines.as_bytes()).unwrap();
+                file.flush().unwrap();
+                let deadline = Instant::now() + Duration::from_secs(5);
+                loop {
+                    thread::sleep(Duration::from_millis(50));
+                    while let Some(result) = result_sink.pop() {
+                        results.insert(result);
+                    }
+                    if results.len() >= expected || Instant::now() > deadline {
+                        break;
+                    }
+                }
+                reported.push(results.len());
+            }
+            stop.store(true, AtomicOrdering::Release);
+            (reported, results)
+        });
+        ReadingScheduler::new(matching, reader).run();
+        let (reported, results) = writer.join().unwrap();
+
+        // Each match is reported as soon as its last line is appended
+        assert_eq!(reported, vec![0, 1, 1, 2]);
+        assert_eq!(
+            results,
+            HashSet::from([
+                MatchingResult {
+                    intervals: vec![MatchingInterval::new(1, 2)],
+                    ids: vec![0]
+                },
+                MatchingResult {
+                    intervals: vec![MatchingInterval::new(3, 4)],
+                    ids: vec![0]
+                },
+            ])
+        );
+    }
 }
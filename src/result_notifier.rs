@@ -1,8 +1,21 @@
+use crate::automata::NFAHState;
+use crate::multi_stream_reader::LineSpans;
+use crate::rng::SplitMix64;
 use crate::shared_buffer::SharedBufferSource;
+use clap::ValueEnum;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
 use std::io::{self, Write};
+use std::rc::Rc;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Represents a matching interval with a start and end position.
+///
+/// The intervals are ordered lexicographically by `(start, end)`.
 #[derive(PartialEq, Eq, Debug, Hash, Clone, PartialOrd, Ord)]
 pub struct MatchingInterval {
     pub start: usize,
@@ -13,10 +26,18 @@ impl MatchingInterval {
     pub fn new(start: usize, end: usize) -> Self {
         Self { start, end }
     }
+
+    /// Returns the number of positions in the interval, i.e., both ends are included.
+    pub fn len(&self) -> usize {
+        self.end + 1 - self.start
+    }
 }
 
 /// Contains matching intervals along with their corresponding identifiers.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///
+/// The results are ordered lexicographically by `(ids, intervals)`, i.e., the results for the same
+/// tuple of identifiers are adjacent after sorting, and ordered by their intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MatchingResult {
     pub intervals: Vec<MatchingInterval>,
     pub ids: Vec<usize>,
@@ -30,6 +51,74 @@ impl MatchingResult {
         }
         Self { intervals, ids }
     }
+
+    /// Returns the bounds of the intervals as `[start0, end0, start1, end1, ...]`, e.g., to compare
+    /// the result with a literal in tests.
+    pub fn flat_bounds(&self) -> Vec<usize> {
+        self.intervals
+            .iter()
+            .flat_map(|interval| [interval.start, interval.end])
+            .collect()
+    }
+}
+
+impl Ord for MatchingResult {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ids
+            .cmp(&other.ids)
+            .then_with(|| self.intervals.cmp(&other.intervals))
+    }
+}
+
+impl PartialOrd for MatchingResult {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A matching trial that did not reach a final state, e.g., a near-miss reported at the end of the
+/// input.
+#[derive(Debug, Clone)]
+pub struct IncompleteMatch<'a> {
+    /// The state the trial is stuck at.
+    pub state: &'a NFAHState<'a>,
+    /// The position where the trial began in each dimension.
+    pub begin: Vec<usize>,
+    /// The symbols consumed by the trial in each dimension.
+    pub consumed: Vec<Vec<String>>,
+    pub ids: Vec<usize>,
+}
+
+impl IncompleteMatch<'_> {
+    /// Returns the total number of symbols consumed over the dimensions.
+    pub fn progress(&self) -> usize {
+        self.consumed.iter().map(Vec::len).sum()
+    }
+}
+
+/// A trait for notifying incomplete matching trials, i.e., a second channel besides
+/// [`ResultNotifier`] to diagnose why a pattern did not match.
+pub trait IncompleteMatchNotifier<'a> {
+    fn notify_incomplete(&mut self, incomplete: &IncompleteMatch<'a>);
+}
+
+/// An `IncompleteMatchNotifier` collecting the incomplete trials, shared among the clones.
+#[derive(Clone, Default)]
+pub struct CollectingIncompleteMatchNotifier<'a> {
+    collected: Rc<RefCell<Vec<IncompleteMatch<'a>>>>,
+}
+
+impl<'a> CollectingIncompleteMatchNotifier<'a> {
+    /// Returns the incomplete trials notified so far.
+    pub fn collected(&self) -> Vec<IncompleteMatch<'a>> {
+        self.collected.borrow().clone()
+    }
+}
+
+impl<'a> IncompleteMatchNotifier<'a> for CollectingIncompleteMatchNotifier<'a> {
+    fn notify_incomplete(&mut self, incomplete: &IncompleteMatch<'a>) {
+        self.collected.borrow_mut().push(incomplete.clone());
+    }
 }
 
 /// A trait for notifying or recording matching results.
@@ -48,6 +137,105 @@ impl MatchingResult {
 pub trait ResultNotifier {
     /// Notifies matching results, given slices of intervals and their corresponding identifiers.
     fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]);
+
+    /// Notifies a batch of matching results at once.
+    ///
+    /// The default implementation calls [`ResultNotifier::notify`] for each result. Notifiers
+    /// writing to an output should override it to write the whole batch at once.
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        for result in results {
+            self.notify(&result.intervals, &result.ids);
+        }
+    }
+
+    /// Notifies a matching result detected at `timestamp`, e.g., by [`TimestampedResultNotifier`].
+    ///
+    /// The default implementation ignores the timestamp and calls [`ResultNotifier::notify`].
+    /// Notifiers that can record the timestamp should override it.
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        _timestamp: SystemTime,
+    ) {
+        self.notify(intervals, ids);
+    }
+
+    /// Outputs the results held back so far, e.g., by [`BufferedResultNotifier`].
+    ///
+    /// The default implementation does nothing, as most notifiers output each result as it is
+    /// notified. Notifiers wrapping another one should pass the call through.
+    fn flush(&mut self) {}
+}
+
+/// Formats `timestamp` as the seconds since the Unix epoch with microseconds, e.g.,
+/// `1700000000.123456`.
+fn format_timestamp(timestamp: SystemTime) -> String {
+    let since_epoch = timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+    format!(
+        "{}.{:06}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_micros()
+    )
+}
+
+/// Which positions of the matching intervals are reported in the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Report {
+    /// Only the first position of each interval, e.g., the trigger point of an alert
+    Begin,
+    /// Only the last position of each interval
+    End,
+    /// Both ends of each interval
+    #[default]
+    Interval,
+}
+
+impl Report {
+    /// Formats the reported positions of `interval`, e.g., `1, 2` for the interval or `2` for the end.
+    fn format_positions(self, interval: &MatchingInterval) -> String {
+        match self {
+            Report::Begin => interval.start.to_string(),
+            Report::End => interval.end.to_string(),
+            Report::Interval => format!("{}, {}", interval.start, interval.end),
+        }
+    }
+}
+
+/// Formats a matching result as printed by [`StdoutResultNotifier`], e.g., `(0: 1, 2), (1: 3, 4)`.
+fn format_stdout_line(intervals: &[MatchingInterval], ids: &[usize], report: Report) -> String {
+    let mut output = String::new();
+    for i in 0..intervals.len() {
+        output.push_str(&format!(
+            "({}: {})",
+            ids[i],
+            report.format_positions(&intervals[i])
+        ));
+        if i + 1 < intervals.len() {
+            output.push_str(", ");
+        }
+    }
+    output
+}
+
+/// Formats a matching result as written by [`FileResultNotifier`], e.g., `0: (1, 2), 1: (3, 4)`,
+/// or `0: 2, 1: 4` if only one end is reported.
+fn format_file_line(intervals: &[MatchingInterval], ids: &[usize], report: Report) -> String {
+    let mut line = String::new();
+    for i in 0..intervals.len() {
+        let positions = report.format_positions(&intervals[i]);
+        if report == Report::Interval {
+            line.push_str(&format!("{}: ({})", ids[i], positions));
+        } else {
+            line.push_str(&format!("{}: {}", ids[i], positions));
+        }
+        if i + 1 < intervals.len() {
+            line.push_str(", ");
+        }
+    }
+    line
 }
 
 /// A `ResultNotifier` implementation that prints matching results to `stdout`.
@@ -55,30 +243,60 @@ pub trait ResultNotifier {
 /// # Examples
 ///
 /// ```rust,ignore
-/// let mut notifier = StdoutResultNotifier;
+/// let mut notifier = StdoutResultNotifier::default();
 /// notifier.notify(
 ///     &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
 ///     &[0, 1]
 /// ); // prints "(0: 1, 2), (1: 3, 4)" to stdout
 /// ```
-#[derive(Clone)]
-pub struct StdoutResultNotifier;
+#[derive(Clone, Default)]
+pub struct StdoutResultNotifier {
+    report: Report,
+}
+
+impl StdoutResultNotifier {
+    /// Reports only the positions selected by `report`, e.g., `(0: 2), (1: 4)` for [`Report::End`].
+    pub fn with_report(mut self, report: Report) -> Self {
+        self.report = report;
+        self
+    }
+}
 
 impl ResultNotifier for StdoutResultNotifier {
     fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
         // Build a single string containing all results, then print once.
         // This approach is efficient in a single-threaded context.
+        println!("{}", format_stdout_line(intervals, ids, self.report));
+    }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        println!(
+            "{} @ {}",
+            format_stdout_line(intervals, ids, self.report),
+            format_timestamp(timestamp)
+        );
+    }
+
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        // Build all the lines, then write them with a single lock of stdout.
         let mut output = String::new();
-        for i in 0..intervals.len() {
-            output.push_str(&format!(
-                "({}: {}, {})",
-                ids[i], intervals[i].start, intervals[i].end
+        for result in results {
+            output.push_str(&format_stdout_line(
+                &result.intervals,
+                &result.ids,
+                self.report,
             ));
-            if i + 1 < intervals.len() {
-                output.push_str(", ");
-            }
+            output.push('\n');
         }
-        println!("{}", output);
+        io::stdout()
+            .lock()
+            .write_all(output.as_bytes())
+            .expect("Failed to write to stdout");
     }
 }
 
@@ -107,6 +325,124 @@ impl ResultNotifier for SharedBufferResultNotifier {
     }
 }
 
+/// A `ResultNotifier` counting the matching results for each tuple of identifiers.
+///
+/// The counts are shared among the clones, so the tally covers all the matches
+/// found by the matchers using a clone of this notifier.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut notifier = TallyResultNotifier::new();
+/// notifier.notify(&[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)], &[0, 1]);
+/// notifier.notify(&[MatchingInterval::new(1, 3), MatchingInterval::new(3, 4)], &[0, 1]);
+/// assert_eq!(notifier.tally()[&vec![0, 1]], 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct TallyResultNotifier {
+    counts: Arc<Mutex<HashMap<Vec<usize>, usize>>>,
+}
+
+impl TallyResultNotifier {
+    /// Creates a new `TallyResultNotifier` with no matches counted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of matching results for each tuple of identifiers.
+    pub fn tally(&self) -> HashMap<Vec<usize>, usize> {
+        self.counts.lock().unwrap().clone()
+    }
+}
+
+impl ResultNotifier for TallyResultNotifier {
+    fn notify(&mut self, _intervals: &[MatchingInterval], ids: &[usize]) {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(ids) {
+            *count += 1;
+        } else {
+            counts.insert(ids.to_vec(), 1);
+        }
+    }
+}
+
+/// A `ResultNotifier` collecting the matching results to list them in a reproducible order.
+///
+/// The matchers notify the results in an order depending on the iteration order of hash sets.
+/// This notifier keeps the distinct results sorted by the order of [`MatchingResult`], i.e.,
+/// grouped by the tuple of identifiers. The results are shared among the clones.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut notifier = SortedResultNotifier::new();
+/// notifier.notify(&[MatchingInterval::new(3, 4)], &[1]);
+/// notifier.notify(&[MatchingInterval::new(1, 2)], &[0]);
+/// assert_eq!(notifier.results()[0].ids, vec![0]);
+/// ```
+#[derive(Clone, Default)]
+pub struct SortedResultNotifier {
+    results: Arc<Mutex<BTreeSet<MatchingResult>>>,
+}
+
+impl SortedResultNotifier {
+    /// Creates a new `SortedResultNotifier` with no results.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the distinct results notified so far in the sorted order.
+    pub fn results(&self) -> Vec<MatchingResult> {
+        self.results.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+impl ResultNotifier for SortedResultNotifier {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        self.results.lock().unwrap().insert(MatchingResult {
+            intervals: intervals.to_vec(),
+            ids: ids.to_vec(),
+        });
+    }
+}
+
+/// A `ResultNotifier` counting the matching results.
+///
+/// Every notification is counted, so the count is the number of the results the other notifiers
+/// would output, including the ones notified more than once by some matchers. The count is shared
+/// among the clones.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut notifier = CountingResultNotifier::new();
+/// notifier.notify(&[MatchingInterval::new(1, 2)], &[0]);
+/// notifier.notify(&[MatchingInterval::new(1, 3)], &[0]);
+/// assert_eq!(notifier.count(), 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct CountingResultNotifier {
+    count: Arc<AtomicUsize>,
+}
+
+impl CountingResultNotifier {
+    /// Creates a new `CountingResultNotifier` with no matches counted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of the results notified so far.
+    pub fn count(&self) -> usize {
+        self.count.load(atomic::Ordering::Relaxed)
+    }
+}
+
+impl ResultNotifier for CountingResultNotifier {
+    fn notify(&mut self, _intervals: &[MatchingInterval], _ids: &[usize]) {
+        self.count.fetch_add(1, atomic::Ordering::Relaxed);
+    }
+}
+
 /// A `ResultNotifier` that writes matching results to a file.
 ///
 /// # Examples
@@ -120,6 +456,7 @@ impl ResultNotifier for SharedBufferResultNotifier {
 /// ```
 pub struct FileResultNotifier {
     file: File,
+    report: Report,
 }
 
 impl FileResultNotifier {
@@ -130,87 +467,1343 @@ impl FileResultNotifier {
     /// Returns an `io::Error` if the file cannot be created.
     pub fn new(file_path: &str) -> io::Result<Self> {
         let file = File::create(file_path)?;
-        Ok(Self { file })
+        Ok(Self {
+            file,
+            report: Report::Interval,
+        })
+    }
+
+    /// Reports only the positions selected by `report`, e.g., `0: 2, 1: 4` for [`Report::End`].
+    pub fn with_report(mut self, report: Report) -> Self {
+        self.report = report;
+        self
     }
 }
 
 impl ResultNotifier for FileResultNotifier {
     fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
         // Build a single line containing all matching results, then write it at once.
-        let mut line = String::new();
-        for i in 0..intervals.len() {
-            line.push_str(&format!(
-                "{}: ({}, {})",
-                ids[i], intervals[i].start, intervals[i].end
-            ));
-            if i + 1 < intervals.len() {
-                line.push_str(", ");
-            }
-        }
+        let line = format_file_line(intervals, ids, self.report);
         // Append a newline at the end of the line.
         writeln!(self.file, "{}", line).expect("Failed to write to file");
     }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        let line = format_file_line(intervals, ids, self.report);
+        writeln!(self.file, "{} @ {}", line, format_timestamp(timestamp))
+            .expect("Failed to write to file");
+    }
+
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        // Build all the lines, then write them at once.
+        let mut output = String::new();
+        for result in results {
+            output.push_str(&format_file_line(
+                &result.intervals,
+                &result.ids,
+                self.report,
+            ));
+            output.push('\n');
+        }
+        self.file
+            .write_all(output.as_bytes())
+            .expect("Failed to write to file");
+    }
 }
 
 impl Clone for FileResultNotifier {
     fn clone(&self) -> Self {
         // Reopen the file to ensure the new instance has its own file handle.
         let file = self.file.try_clone().expect("Failed to clone file handle");
-        Self { file }
+        Self {
+            file,
+            report: self.report,
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::shared_buffer::SharedBuffer;
-    use tempfile::NamedTempFile;
+/// A `ResultNotifier` writing each matching result as a line of JSON, e.g.,
+/// `{"intervals":[{"end":2,"id":0,"start":1,"variable":"x"},{"end":4,"id":1,"start":3,"variable":"y"}]}`.
+///
+/// The i-th interval is labeled with the name of the i-th variable of the automaton if the names
+/// are given, and with the index `i` otherwise. A result notified with a timestamp, e.g., via
+/// [`TimestampedResultNotifier`], also has the field `"ts"` of the seconds since the Unix epoch,
+/// e.g., `{"intervals":[...],"ts":1700000000.123456}`. The clones share the output.
+#[derive(Clone)]
+pub struct JsonResultNotifier {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    variable_names: Option<Arc<[String]>>,
+    report: Report,
+}
 
-    #[test]
-    fn test_stdout_result_notifier() {
-        // While testing stdout automatically is challenging, this ensures no panics occur.
-        let mut notifier = StdoutResultNotifier;
-        notifier.notify(
-            &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
-            &[0, 1],
-        );
+impl JsonResultNotifier {
+    /// Creates a new `JsonResultNotifier` writing to `output`, labeling the intervals with
+    /// `variable_names` if given.
+    pub fn new(output: Box<dyn Write + Send>, variable_names: Option<Vec<String>>) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            variable_names: variable_names.map(Arc::from),
+            report: Report::Interval,
+        }
     }
 
-    #[test]
-    fn test_file_result_notifier() -> io::Result<()> {
-        let temp_file = NamedTempFile::new()?;
-        {
-            let mut notifier = FileResultNotifier::new(temp_file.path().to_str().unwrap())?;
-            notifier.notify(
-                &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
-                &[0, 1],
-            );
+    /// Reports only the positions selected by `report`, e.g., only `end` for [`Report::End`].
+    pub fn with_report(mut self, report: Report) -> Self {
+        self.report = report;
+        self
+    }
+
+    /// Formats a matching result as a line of JSON without the trailing newline.
+    fn format_line(
+        &self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: Option<SystemTime>,
+    ) -> String {
+        json_line(
+            intervals,
+            ids,
+            self.variable_names.as_deref(),
+            self.report,
+            timestamp,
+        )
+    }
+}
+
+/// Formats a matching result as a line of JSON without the trailing newline, e.g.,
+/// `{"intervals":[{"end":2,"id":0,"start":1,"variable":0}]}`.
+///
+/// The intervals are labeled with `variable_names` if given, and only the positions selected by
+/// `report` are written. The `timestamp`, if any, is written as the field `"ts"` of the seconds
+/// since the Unix epoch.
+pub fn json_line(
+    intervals: &[MatchingInterval],
+    ids: &[usize],
+    variable_names: Option<&[String]>,
+    report: Report,
+    timestamp: Option<SystemTime>,
+) -> String {
+    json_value(intervals, ids, variable_names, report, timestamp).to_string()
+}
+
+/// Formats a matching result as a line of JSON like [`json_line`], adding the byte span
+/// `[begin, end]` of each interval, e.g.,
+/// `{"intervals":[{"bytes":[2,7],"end":2,"id":0,"start":1,"variable":0}]}`.
+pub fn json_line_with_spans(
+    intervals: &[MatchingInterval],
+    ids: &[usize],
+    variable_names: Option<&[String]>,
+    report: Report,
+    spans: &[(usize, usize)],
+) -> String {
+    let mut line = json_value(intervals, ids, variable_names, report, None);
+    for (i, &(begin, end)) in spans.iter().enumerate() {
+        line["intervals"][i]["bytes"] = serde_json::json!([begin, end]);
+    }
+    line.to_string()
+}
+
+/// Builds the JSON value of a matching result written by [`json_line`].
+fn json_value(
+    intervals: &[MatchingInterval],
+    ids: &[usize],
+    variable_names: Option<&[String]>,
+    report: Report,
+    timestamp: Option<SystemTime>,
+) -> serde_json::Value {
+    let intervals: Vec<_> = intervals
+        .iter()
+        .zip(ids)
+        .enumerate()
+        .map(|(variable, (interval, id))| {
+            let variable = match variable_names {
+                Some(names) => serde_json::Value::from(names[variable].as_str()),
+                None => serde_json::Value::from(variable),
+            };
+            let mut labeled = serde_json::json!({ "variable": variable, "id": id });
+            if report != Report::End {
+                labeled["start"] = interval.start.into();
+            }
+            if report != Report::Begin {
+                labeled["end"] = interval.end.into();
+            }
+            labeled
+        })
+        .collect();
+    let mut line = serde_json::json!({ "intervals": intervals });
+    if let Some(timestamp) = timestamp {
+        let since_epoch = timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        line["ts"] = since_epoch.as_secs_f64().into();
+    }
+    line
+}
+
+impl ResultNotifier for JsonResultNotifier {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        let line = self.format_line(intervals, ids, None);
+        writeln!(self.output.lock().unwrap(), "{}", line).expect("Failed to write the output");
+    }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        let line = self.format_line(intervals, ids, Some(timestamp));
+        writeln!(self.output.lock().unwrap(), "{}", line).expect("Failed to write the output");
+    }
+
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        // Build all the lines, then write them at once.
+        let mut output = String::new();
+        for result in results {
+            output.push_str(&self.format_line(&result.intervals, &result.ids, None));
+            output.push('\n');
         }
-        let content = std::fs::read_to_string(temp_file.path())?;
-        assert_eq!(content.trim(), "0: (1, 2), 1: (3, 4)");
-        Ok(())
+        self.output
+            .lock()
+            .unwrap()
+            .write_all(output.as_bytes())
+            .expect("Failed to write the output");
     }
+}
 
-    #[test]
-    fn test_shared_buffer_result_notifier() {
-        let buffer = SharedBuffer::new();
-        let source = buffer.make_source();
-        let mut notifier = SharedBufferResultNotifier::new(source);
-        notifier.notify(
-            &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
-            &[0, 1],
-        );
+/// The distinct matching intervals grouped by the ids.
+type MatchGroups = BTreeMap<Vec<usize>, BTreeSet<Vec<MatchingInterval>>>;
 
-        let mut sink = buffer.make_sink();
-        let result = sink.pop().expect("No data in shared buffer");
-        assert_eq!(result.intervals.len(), 2);
-        assert_eq!(result.ids.len(), 2);
-        assert_eq!(
-            result,
-            MatchingResult {
-                intervals: vec![MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
-                ids: vec![0, 1]
+/// A `ResultNotifier` grouping the matching results by their ids and writing them as a single JSON
+/// document when the last clone is dropped, e.g.,
+/// `{"[0,1]":[[{"end":2,"start":0},{"end":2,"start":1}]],"[1,1]":[[{"end":3,"start":3},{"end":3,"start":3}]]}`.
+///
+/// The keys are the ids formatted as JSON arrays. Each group holds the distinct matching intervals
+/// in lexicographic order, so the document does not depend on the order of notification. The
+/// clones share the groups and the output.
+#[derive(Clone)]
+pub struct GroupedJsonResultNotifier {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    groups: Arc<Mutex<MatchGroups>>,
+}
+
+impl GroupedJsonResultNotifier {
+    /// Creates a new `GroupedJsonResultNotifier` writing to `output` with no results.
+    pub fn new(output: Box<dyn Write + Send>) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(output)),
+            groups: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Returns the JSON document of the results notified so far.
+    pub fn document(&self) -> String {
+        let document: serde_json::Map<_, _> = self
+            .groups
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(ids, matches)| {
+                let matches: Vec<_> = matches
+                    .iter()
+                    .map(|intervals| {
+                        intervals
+                            .iter()
+                            .map(|interval| {
+                                serde_json::json!({ "start": interval.start, "end": interval.end })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                (serde_json::json!(ids).to_string(), matches.into())
+            })
+            .collect();
+        serde_json::Value::Object(document).to_string()
+    }
+}
+
+impl ResultNotifier for GroupedJsonResultNotifier {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        self.groups
+            .lock()
+            .unwrap()
+            .entry(ids.to_vec())
+            .or_default()
+            .insert(intervals.to_vec());
+    }
+}
+
+impl Drop for GroupedJsonResultNotifier {
+    fn drop(&mut self) {
+        // Only the last clone writes the document
+        if Arc::strong_count(&self.groups) == 1 {
+            let document = self.document();
+            writeln!(self.output.lock().unwrap(), "{}", document)
+                .expect("Failed to write the output");
+        }
+    }
+}
+
+/// The matching results with their timestamps, if any.
+type TimestampedResults = Vec<(MatchingResult, Option<SystemTime>)>;
+
+/// A `ResultNotifier` wrapper batching the matching results before passing them to the inner notifier.
+///
+/// The buffered results are passed to [`ResultNotifier::notify_all`] of the inner notifier every
+/// `flush_every` matches, and when the last clone of the wrapper is dropped, so that no results are
+/// lost. The buffer is shared among the clones, so the results of all the matchers sharing the
+/// notifier are flushed together, in the order they are notified.
+///
+/// The timestamps of the results notified by [`ResultNotifier::notify_with_timestamp`], e.g., via
+/// [`TimestampedResultNotifier`], are kept in the buffer and passed to the inner notifier, so they
+/// are the times of the detection rather than of the flush.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut notifier = BufferedResultNotifier::new(StdoutResultNotifier::default(), 1024);
+/// notifier.notify(&[MatchingInterval::new(1, 2)], &[0]); // nothing is printed yet
+/// drop(notifier); // prints "(0: 1, 2)"
+/// ```
+pub struct BufferedResultNotifier<Notifier: ResultNotifier> {
+    inner: Notifier,
+    /// The results buffered by all the clones with their timestamps, if any
+    buffer: Rc<RefCell<TimestampedResults>>,
+    flush_every: usize,
+}
+
+impl<Notifier: ResultNotifier> BufferedResultNotifier<Notifier> {
+    /// Creates a new `BufferedResultNotifier` passing the results to `inner` every `flush_every` matches.
+    ///
+    /// `flush_every` of zero is treated as one, i.e., no buffering. The buffer grows as the results
+    /// are notified, so a large `flush_every` does not allocate up front.
+    pub fn new(inner: Notifier, flush_every: usize) -> Self {
+        Self {
+            inner,
+            buffer: Rc::new(RefCell::new(Vec::new())),
+            flush_every: flush_every.max(1),
+        }
+    }
+
+    /// Passes all the buffered results, including the ones of the clones, to the inner notifier.
+    fn flush_buffer(&mut self) {
+        let buffered = std::mem::take(&mut *self.buffer.borrow_mut());
+        if buffered.iter().all(|(_, timestamp)| timestamp.is_none()) {
+            let results: Vec<_> = buffered.into_iter().map(|(result, _)| result).collect();
+            if !results.is_empty() {
+                self.inner.notify_all(&results);
             }
-        );
+            return;
+        }
+        for (result, timestamp) in buffered {
+            match timestamp {
+                Some(timestamp) => {
+                    self.inner
+                        .notify_with_timestamp(&result.intervals, &result.ids, timestamp)
+                }
+                None => self.inner.notify(&result.intervals, &result.ids),
+            }
+        }
+    }
+
+    /// Buffers a result with its timestamp, if any, and flushes the buffer if it is full.
+    fn push(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: Option<SystemTime>,
+    ) {
+        let buffered = {
+            let mut buffer = self.buffer.borrow_mut();
+            let result = MatchingResult {
+                intervals: intervals.to_vec(),
+                ids: ids.to_vec(),
+            };
+            buffer.push((result, timestamp));
+            buffer.len()
+        };
+        if buffered >= self.flush_every {
+            self.flush_buffer();
+        }
+    }
+}
+
+impl<Notifier: ResultNotifier> ResultNotifier for BufferedResultNotifier<Notifier> {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        self.push(intervals, ids, None);
+    }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        self.push(intervals, ids, Some(timestamp));
+    }
+
+    fn flush(&mut self) {
+        self.flush_buffer();
+        self.inner.flush();
+    }
+}
+
+impl<Notifier: ResultNotifier + Clone> Clone for BufferedResultNotifier<Notifier> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            buffer: self.buffer.clone(),
+            flush_every: self.flush_every,
+        }
+    }
+}
+
+impl<Notifier: ResultNotifier> Drop for BufferedResultNotifier<Notifier> {
+    fn drop(&mut self) {
+        // The last clone flushes the results buffered by all of them
+        if Rc::strong_count(&self.buffer) == 1 {
+            self.flush_buffer();
+        }
+    }
+}
+
+/// How [`SamplingResultNotifier`] chooses the forwarded matches.
+#[derive(Clone, Copy, Debug)]
+pub enum Sampling {
+    /// Forward every `n`-th match, starting from the first one.
+    Every(usize),
+    /// Forward each match with the probability `fraction`, using a pseudo-random number generator
+    /// initialized with `seed`.
+    Fraction { fraction: f64, seed: u64 },
+}
+
+/// The state of [`SamplingResultNotifier`] shared among its clones.
+struct SamplingState {
+    sampling: Sampling,
+    rng: SplitMix64,
+    /// The number of matches notified so far
+    total: usize,
+    /// The number of matches forwarded to the inner notifier so far
+    forwarded: usize,
+}
+
+impl SamplingState {
+    /// Counts a match and returns `true` if it should be forwarded.
+    fn sample(&mut self) -> bool {
+        let index = self.total;
+        self.total += 1;
+        let forward = match self.sampling {
+            Sampling::Every(n) => index.is_multiple_of(n.max(1)),
+            Sampling::Fraction { fraction, .. } => {
+                // Use the upper 53 bits to make a uniform number in [0, 1)
+                let sample = (self.rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+                sample < fraction
+            }
+        };
+        if forward {
+            self.forwarded += 1;
+        }
+        forward
+    }
+}
+
+/// A `ResultNotifier` wrapper forwarding only a sample of the matching results to the inner
+/// notifier and counting all of them.
+///
+/// This keeps the output manageable when there are millions of matches. The counts are shared
+/// among the clones, so the sampling is over all the matches of the matchers sharing the notifier.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut notifier = SamplingResultNotifier::new(StdoutResultNotifier::default(), Sampling::Every(2));
+/// notifier.notify(&[MatchingInterval::new(1, 2)], &[0]); // prints "(0: 1, 2)"
+/// notifier.notify(&[MatchingInterval::new(1, 3)], &[0]); // prints nothing
+/// assert_eq!(notifier.total(), 2);
+/// ```
+pub struct SamplingResultNotifier<Notifier: ResultNotifier> {
+    inner: Notifier,
+    state: Arc<Mutex<SamplingState>>,
+}
+
+impl<Notifier: ResultNotifier> SamplingResultNotifier<Notifier> {
+    /// Creates a new `SamplingResultNotifier` forwarding the matches chosen by `sampling` to `inner`.
+    pub fn new(inner: Notifier, sampling: Sampling) -> Self {
+        let seed = match sampling {
+            Sampling::Every(_) => 0,
+            Sampling::Fraction { seed, .. } => seed,
+        };
+        Self {
+            inner,
+            state: Arc::new(Mutex::new(SamplingState {
+                sampling,
+                rng: SplitMix64::new(seed),
+                total: 0,
+                forwarded: 0,
+            })),
+        }
+    }
+
+    /// Returns the number of matches notified so far, including the ones not forwarded.
+    pub fn total(&self) -> usize {
+        self.state.lock().unwrap().total
+    }
+
+    /// Returns the number of matches forwarded to the inner notifier so far.
+    pub fn forwarded(&self) -> usize {
+        self.state.lock().unwrap().forwarded
+    }
+}
+
+impl<Notifier: ResultNotifier> ResultNotifier for SamplingResultNotifier<Notifier> {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        let forward = self.state.lock().unwrap().sample();
+        if forward {
+            self.inner.notify(intervals, ids);
+        }
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+impl<Notifier: ResultNotifier + Clone> Clone for SamplingResultNotifier<Notifier> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+/// The bounds of the interval lengths of the matches kept by [`LengthFilteredResultNotifier`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LengthFilter {
+    /// The minimum length, if any
+    pub min: Option<usize>,
+    /// The maximum length, if any
+    pub max: Option<usize>,
+    /// If `true`, a match is kept when the interval of some dimension is within the bounds.
+    /// Otherwise, the intervals of all the dimensions must be within the bounds.
+    pub any_dimension: bool,
+}
+
+impl LengthFilter {
+    /// Returns `true` if the matching result with `intervals` is kept.
+    pub fn keeps(&self, intervals: &[MatchingInterval]) -> bool {
+        let within = |interval: &MatchingInterval| {
+            let len = interval.len();
+            self.min.is_none_or(|min| len >= min) && self.max.is_none_or(|max| len <= max)
+        };
+        if self.any_dimension {
+            intervals.iter().any(within)
+        } else {
+            intervals.iter().all(within)
+        }
+    }
+}
+
+/// A `ResultNotifier` wrapper forwarding only the matching results with interval lengths within
+/// the bounds of a [`LengthFilter`], e.g., to ignore the trivial matches of one action.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let filter = LengthFilter { min: Some(2), ..Default::default() };
+/// let mut notifier = LengthFilteredResultNotifier::new(StdoutResultNotifier::default(), filter);
+/// notifier.notify(&[MatchingInterval::new(1, 1)], &[0]); // prints nothing
+/// notifier.notify(&[MatchingInterval::new(1, 2)], &[0]); // prints "(0: 1, 2)"
+/// ```
+#[derive(Clone)]
+pub struct LengthFilteredResultNotifier<Notifier: ResultNotifier> {
+    inner: Notifier,
+    filter: LengthFilter,
+}
+
+impl<Notifier: ResultNotifier> LengthFilteredResultNotifier<Notifier> {
+    /// Creates a new `LengthFilteredResultNotifier` forwarding the results kept by `filter` to `inner`.
+    pub fn new(inner: Notifier, filter: LengthFilter) -> Self {
+        Self { inner, filter }
+    }
+}
+
+impl<Notifier: ResultNotifier> ResultNotifier for LengthFilteredResultNotifier<Notifier> {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        if self.filter.keeps(intervals) {
+            self.inner.notify(intervals, ids);
+        }
+    }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        if self.filter.keeps(intervals) {
+            self.inner.notify_with_timestamp(intervals, ids, timestamp);
+        }
+    }
+
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        let kept: Vec<MatchingResult> = results
+            .iter()
+            .filter(|result| self.filter.keeps(&result.intervals))
+            .cloned()
+            .collect();
+        self.inner.notify_all(&kept);
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+/// A `ResultNotifier` wrapper attaching the wall-clock time of the detection to each matching result.
+///
+/// The results are passed to [`ResultNotifier::notify_with_timestamp`] of the inner notifier,
+/// e.g., to correlate the detections with external events in live monitoring. The timestamps are
+/// measured with a monotonic clock from the wall-clock time at the construction, so they never
+/// decrease even if the system time is adjusted during a run.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut notifier = TimestampedResultNotifier::new(StdoutResultNotifier::default());
+/// notifier.notify(&[MatchingInterval::new(1, 2)], &[0]); // prints "(0: 1, 2) @ 1700000000.123456"
+/// ```
+#[derive(Clone)]
+pub struct TimestampedResultNotifier<Notifier: ResultNotifier> {
+    inner: Notifier,
+    /// The wall-clock time at the construction
+    started_at: SystemTime,
+    /// The monotonic time at the construction
+    started: Instant,
+}
+
+impl<Notifier: ResultNotifier> TimestampedResultNotifier<Notifier> {
+    /// Creates a new `TimestampedResultNotifier` passing the timestamped results to `inner`.
+    pub fn new(inner: Notifier) -> Self {
+        Self {
+            inner,
+            started_at: SystemTime::now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the current wall-clock time measured with the monotonic clock.
+    fn now(&self) -> SystemTime {
+        self.started_at + self.started.elapsed()
+    }
+}
+
+impl<Notifier: ResultNotifier> ResultNotifier for TimestampedResultNotifier<Notifier> {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        let timestamp = self.now();
+        self.inner.notify_with_timestamp(intervals, ids, timestamp);
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+/// A `ResultNotifier` passing each matching result to a callback together with the byte spans of
+/// its intervals, e.g., to highlight the matched regions in an editor.
+///
+/// The spans are `(byte_begin, byte_end)` for each dimension, where `byte_end` is exclusive. They
+/// are looked up in the [`LineSpans`] of the input streams, where the track `t` reads the stream
+/// `sources[t]`. The callback is shared among the clones.
+pub struct SpanCallbackResultNotifier<F: FnMut(&MatchingResult, &[(usize, usize)])> {
+    callback: Rc<RefCell<F>>,
+    line_spans: LineSpans,
+    sources: Vec<usize>,
+}
+
+impl<F: FnMut(&MatchingResult, &[(usize, usize)])> SpanCallbackResultNotifier<F> {
+    /// Creates a new `SpanCallbackResultNotifier` calling `callback` for each matching result.
+    pub fn new(callback: F, line_spans: LineSpans, sources: Vec<usize>) -> Self {
+        Self {
+            callback: Rc::new(RefCell::new(callback)),
+            line_spans,
+            sources,
+        }
+    }
+
+    /// Returns the byte span of `interval` in the track `track`.
+    ///
+    /// An empty interval, i.e., `end < start`, is the empty span at the beginning of `start`.
+    fn span(&self, interval: &MatchingInterval, track: usize) -> (usize, usize) {
+        let stream = self.sources[track];
+        let line_span = |line: usize| {
+            self.line_spans
+                .get(stream, line)
+                .expect("The matched line must be read")
+        };
+        if interval.end < interval.start {
+            let begin = match interval.start.checked_sub(1) {
+                Some(previous) => line_span(previous).1,
+                None => 0,
+            };
+            return (begin, begin);
+        }
+        (line_span(interval.start).0, line_span(interval.end).1)
+    }
+}
+
+impl<F: FnMut(&MatchingResult, &[(usize, usize)])> ResultNotifier
+    for SpanCallbackResultNotifier<F>
+{
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        let spans: Vec<(usize, usize)> = intervals
+            .iter()
+            .zip(ids)
+            .map(|(interval, &track)| self.span(interval, track))
+            .collect();
+        let result = MatchingResult {
+            intervals: intervals.to_vec(),
+            ids: ids.to_vec(),
+        };
+        (self.callback.borrow_mut())(&result, &spans);
+    }
+}
+
+impl<F: FnMut(&MatchingResult, &[(usize, usize)])> Clone for SpanCallbackResultNotifier<F> {
+    fn clone(&self) -> Self {
+        Self {
+            callback: Rc::clone(&self.callback),
+            line_spans: self.line_spans.clone(),
+            sources: self.sources.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_buffer::SharedBuffer;
+    use itertools::Itertools;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_stdout_result_notifier() {
+        // While testing stdout automatically is challenging, this ensures no panics occur.
+        let mut notifier = StdoutResultNotifier::default();
+        notifier.notify(
+            &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+            &[0, 1],
+        );
+    }
+
+    #[test]
+    fn test_file_result_notifier() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        {
+            let mut notifier = FileResultNotifier::new(temp_file.path().to_str().unwrap())?;
+            notifier.notify(
+                &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+                &[0, 1],
+            );
+        }
+        let content = std::fs::read_to_string(temp_file.path())?;
+        assert_eq!(content.trim(), "0: (1, 2), 1: (3, 4)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_result_notifier_with_report() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        for (report, expected) in [
+            (Report::Begin, "0: 1, 1: 3"),
+            (Report::End, "0: 2, 1: 4"),
+            (Report::Interval, "0: (1, 2), 1: (3, 4)"),
+        ] {
+            {
+                let mut notifier = FileResultNotifier::new(temp_file.path().to_str().unwrap())?
+                    .with_report(report);
+                notifier.notify(
+                    &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+                    &[0, 1],
+                );
+            }
+            let content = std::fs::read_to_string(temp_file.path())?;
+            assert_eq!(content.trim(), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_counting_result_notifier() {
+        let notifier = CountingResultNotifier::new();
+        let mut clone = notifier.clone();
+        clone.notify(&[MatchingInterval::new(1, 2)], &[0]);
+        clone.notify(&[MatchingInterval::new(1, 2)], &[0]);
+        clone.notify(&[MatchingInterval::new(1, 2)], &[1]);
+        clone.notify(&[MatchingInterval::new(1, 3)], &[0]);
+        // Every notification is counted, and the count is shared with the clone
+        assert_eq!(notifier.count(), 4);
+    }
+
+    #[test]
+    fn test_json_result_notifier() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let names = vec!["x".to_string(), "y".to_string()];
+        {
+            let file = File::create(temp_file.path())?;
+            let mut notifier = JsonResultNotifier::new(Box::new(file), Some(names));
+            notifier.notify(
+                &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+                &[0, 1],
+            );
+            let file = File::options().append(true).open(temp_file.path())?;
+            let mut notifier = JsonResultNotifier::new(Box::new(file), None);
+            notifier.notify_all(&[MatchingResult::new(
+                vec![MatchingInterval::new(5, 6)],
+                vec![1],
+            )]);
+        }
+        let content = std::fs::read_to_string(temp_file.path())?;
+        assert_eq!(
+            content.lines().collect_vec(),
+            vec![
+                r#"{"intervals":[{"end":2,"id":0,"start":1,"variable":"x"},{"end":4,"id":1,"start":3,"variable":"y"}]}"#,
+                r#"{"intervals":[{"end":6,"id":1,"start":5,"variable":0}]}"#,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_result_notifier_with_timestamp() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let before = SystemTime::now();
+        {
+            let json_notifier = JsonResultNotifier::new(Box::new(temp_file.reopen()?), None);
+            let mut notifier = TimestampedResultNotifier::new(json_notifier);
+            notifier.notify(&[MatchingInterval::new(1, 2)], &[0]);
+            notifier.notify(&[MatchingInterval::new(3, 4)], &[0]);
+        }
+        let content = std::fs::read_to_string(temp_file.path())?;
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0]["intervals"],
+            serde_json::json!([{"end": 2, "id": 0, "start": 1, "variable": 0}])
+        );
+        let timestamps: Vec<f64> = lines
+            .iter()
+            .map(|line| line["ts"].as_f64().expect("No timestamp"))
+            .collect();
+        let before = before.duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+        assert!(timestamps[0] >= before - 1e-6);
+        assert!(timestamps[0] <= timestamps[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_grouped_json_result_notifier() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        {
+            let mut notifier = GroupedJsonResultNotifier::new(Box::new(temp_file.reopen()?));
+            let mut clone = notifier.clone();
+            notifier.notify(
+                &[MatchingInterval::new(3, 4), MatchingInterval::new(1, 2)],
+                &[1, 0],
+            );
+            clone.notify(
+                &[MatchingInterval::new(0, 1), MatchingInterval::new(1, 2)],
+                &[0, 1],
+            );
+            notifier.notify(
+                &[MatchingInterval::new(3, 4), MatchingInterval::new(1, 2)],
+                &[1, 0],
+            );
+            drop(clone);
+            // Nothing is written until the last clone is dropped
+            assert_eq!(std::fs::read_to_string(temp_file.path())?, "");
+        }
+        let content = std::fs::read_to_string(temp_file.path())?;
+        assert_eq!(
+            content,
+            concat!(
+                r#"{"[0,1]":[[{"end":1,"start":0},{"end":2,"start":1}]],"#,
+                r#""[1,0]":[[{"end":4,"start":3},{"end":2,"start":1}]]}"#,
+                "\n"
+            )
+        );
+        Ok(())
+    }
+
+    /// Records the timestamps of the notified results.
+    #[derive(Clone, Default)]
+    struct TimestampRecorder {
+        timestamps: Rc<RefCell<Vec<SystemTime>>>,
+    }
+
+    impl ResultNotifier for TimestampRecorder {
+        fn notify(&mut self, _intervals: &[MatchingInterval], _ids: &[usize]) {
+            panic!("The timestamp is not passed");
+        }
+
+        fn notify_with_timestamp(
+            &mut self,
+            _intervals: &[MatchingInterval],
+            _ids: &[usize],
+            timestamp: SystemTime,
+        ) {
+            self.timestamps.borrow_mut().push(timestamp);
+        }
+    }
+
+    #[test]
+    fn test_timestamped_result_notifier() {
+        let recorder = TimestampRecorder::default();
+        let before = SystemTime::now();
+        let mut notifier = TimestampedResultNotifier::new(recorder.clone());
+        for i in 0..100 {
+            notifier.notify(&[MatchingInterval::new(i, i + 1)], &[0]);
+        }
+
+        let timestamps = recorder.timestamps.borrow();
+        assert_eq!(timestamps.len(), 100);
+        assert!(timestamps[0] >= before);
+        assert!(timestamps
+            .iter()
+            .tuple_windows()
+            .all(|(earlier, later)| earlier <= later));
+    }
+
+    #[test]
+    fn test_file_result_notifier_with_timestamp() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        {
+            let file_notifier = FileResultNotifier::new(temp_file.path().to_str().unwrap())?;
+            let mut notifier = TimestampedResultNotifier::new(file_notifier);
+            notifier.notify(&[MatchingInterval::new(1, 2)], &[0]);
+        }
+        let content = std::fs::read_to_string(temp_file.path())?;
+        let (line, timestamp) = content.trim().split_once(" @ ").unwrap();
+        assert_eq!(line, "0: (1, 2)");
+        let (secs, micros) = timestamp.split_once('.').unwrap();
+        assert!(secs.parse::<u64>().unwrap() > 0);
+        assert_eq!(micros.len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_buffer_result_notifier() {
+        let buffer = SharedBuffer::new();
+        let source = buffer.make_source();
+        let mut notifier = SharedBufferResultNotifier::new(source);
+        notifier.notify(
+            &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+            &[0, 1],
+        );
+
+        let mut sink = buffer.make_sink();
+        let result = sink.pop().expect("No data in shared buffer");
+        assert_eq!(result.intervals.len(), 2);
+        assert_eq!(result.ids.len(), 2);
+        assert_eq!(
+            result,
+            MatchingResult {
+                intervals: vec![MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+                ids: vec![0, 1]
+            }
+        );
+    }
+
+    #[test]
+    fn test_tally_result_notifier() {
+        let notifier = TallyResultNotifier::new();
+        let mut cloned = notifier.clone();
+        cloned.notify(&[MatchingInterval::new(1, 2)], &[0, 1]);
+        cloned.notify(&[MatchingInterval::new(1, 3)], &[0, 1]);
+        cloned.notify(&[MatchingInterval::new(1, 3)], &[1, 0]);
+
+        // The counts are shared among the clones
+        let tally = notifier.tally();
+        assert_eq!(tally.len(), 2);
+        assert_eq!(tally[&vec![0, 1]], 2);
+        assert_eq!(tally[&vec![1, 0]], 1);
+    }
+
+    #[test]
+    fn test_length_filtered_result_notifier() {
+        let buffer = SharedBuffer::new();
+        let mut sink = buffer.make_sink();
+        let filter = LengthFilter {
+            min: Some(2),
+            max: Some(3),
+            any_dimension: false,
+        };
+        let mut notifier = LengthFilteredResultNotifier::new(
+            SharedBufferResultNotifier::new(buffer.make_source()),
+            filter,
+        );
+        notifier.notify(
+            &[MatchingInterval::new(0, 0), MatchingInterval::new(0, 1)],
+            &[0, 1],
+        );
+        notifier.notify(
+            &[MatchingInterval::new(0, 1), MatchingInterval::new(0, 2)],
+            &[0, 1],
+        );
+        notifier.notify(
+            &[MatchingInterval::new(0, 1), MatchingInterval::new(0, 3)],
+            &[0, 1],
+        );
+        assert_eq!(
+            sink.pop().map(|r| r.intervals),
+            Some(vec![
+                MatchingInterval::new(0, 1),
+                MatchingInterval::new(0, 2)
+            ])
+        );
+        assert!(sink.pop().is_none());
+
+        // Some dimension within the bounds is enough
+        let mut notifier = LengthFilteredResultNotifier::new(
+            SharedBufferResultNotifier::new(buffer.make_source()),
+            LengthFilter {
+                any_dimension: true,
+                ..filter
+            },
+        );
+        notifier.notify_all(&[
+            MatchingResult::new(
+                vec![MatchingInterval::new(0, 0), MatchingInterval::new(0, 1)],
+                vec![0, 1],
+            ),
+            MatchingResult::new(
+                vec![MatchingInterval::new(0, 0), MatchingInterval::new(0, 4)],
+                vec![0, 1],
+            ),
+        ]);
+        assert_eq!(
+            sink.pop().map(|r| r.intervals),
+            Some(vec![
+                MatchingInterval::new(0, 0),
+                MatchingInterval::new(0, 1)
+            ])
+        );
+        assert!(sink.pop().is_none());
+    }
+
+    #[test]
+    fn test_sorted_result_notifier() {
+        let notifier = SortedResultNotifier::new();
+        let mut cloned = notifier.clone();
+        cloned.notify(
+            &[MatchingInterval::new(2, 3), MatchingInterval::new(0, 0)],
+            &[1, 0],
+        );
+        cloned.notify(
+            &[MatchingInterval::new(1, 3), MatchingInterval::new(0, 0)],
+            &[0, 1],
+        );
+        cloned.notify(
+            &[MatchingInterval::new(1, 2), MatchingInterval::new(0, 0)],
+            &[0, 1],
+        );
+        // Duplicated results are listed once
+        cloned.notify(
+            &[MatchingInterval::new(1, 3), MatchingInterval::new(0, 0)],
+            &[0, 1],
+        );
+
+        let results = notifier.results();
+        let order = results
+            .iter()
+            .map(|r| (r.ids.clone(), r.intervals[0].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![
+                (vec![0, 1], MatchingInterval::new(1, 2)),
+                (vec![0, 1], MatchingInterval::new(1, 3)),
+                (vec![1, 0], MatchingInterval::new(2, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_buffered_result_notifier_flushes_on_drop() {
+        let buffer = SharedBuffer::new();
+        let mut sink = buffer.make_sink();
+        {
+            let mut notifier = BufferedResultNotifier::new(
+                SharedBufferResultNotifier::new(buffer.make_source()),
+                10,
+            );
+            for i in 0..3 {
+                notifier.notify(&[MatchingInterval::new(i, i + 1)], &[0]);
+            }
+            // Nothing is passed to the inner notifier before reaching the limit
+            assert!(sink.pop().is_none());
+        }
+        for i in 0..3 {
+            let result = sink.pop().expect("A buffered match is lost");
+            assert_eq!(result.intervals, vec![MatchingInterval::new(i, i + 1)]);
+        }
+        assert!(sink.pop().is_none());
+    }
+
+    #[test]
+    fn test_flush_through_wrapper() {
+        let buffer = SharedBuffer::new();
+        let mut sink = buffer.make_sink();
+        let mut notifier = LengthFilteredResultNotifier::new(
+            BufferedResultNotifier::new(SharedBufferResultNotifier::new(buffer.make_source()), 10),
+            LengthFilter::default(),
+        );
+        notifier.notify(&[MatchingInterval::new(0, 1)], &[0]);
+        assert!(sink.pop().is_none());
+        // The buffered match is output without reaching the limit or dropping the notifier
+        notifier.flush();
+        assert_eq!(
+            sink.pop().unwrap().intervals,
+            vec![MatchingInterval::new(0, 1)]
+        );
+        assert!(sink.pop().is_none());
+    }
+
+    #[test]
+    fn test_buffered_result_notifier_flushes_every_k() {
+        let buffer = SharedBuffer::new();
+        let mut sink = buffer.make_sink();
+        let mut notifier =
+            BufferedResultNotifier::new(SharedBufferResultNotifier::new(buffer.make_source()), 2);
+        notifier.notify(&[MatchingInterval::new(0, 1)], &[0]);
+        assert!(sink.pop().is_none());
+        notifier.notify(&[MatchingInterval::new(1, 2)], &[0]);
+        assert_eq!(
+            sink.pop().unwrap().intervals,
+            vec![MatchingInterval::new(0, 1)]
+        );
+        assert_eq!(
+            sink.pop().unwrap().intervals,
+            vec![MatchingInterval::new(1, 2)]
+        );
+        notifier.notify(&[MatchingInterval::new(2, 3)], &[0]);
+        assert!(sink.pop().is_none());
+        notifier.flush();
+        assert_eq!(
+            sink.pop().unwrap().intervals,
+            vec![MatchingInterval::new(2, 3)]
+        );
+    }
+
+    #[test]
+    fn test_buffered_result_notifier_keeps_timestamps() {
+        let recorder = TimestampRecorder::default();
+        let mut notifier =
+            TimestampedResultNotifier::new(BufferedResultNotifier::new(recorder.clone(), 10));
+        let mut detected = Vec::new();
+        for i in 0..3 {
+            notifier.notify(&[MatchingInterval::new(i, i + 1)], &[0]);
+            detected.push(SystemTime::now());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(recorder.timestamps.borrow().is_empty());
+        drop(notifier);
+
+        // The timestamps are of the detection, not of the flush
+        let timestamps = recorder.timestamps.borrow();
+        assert_eq!(timestamps.len(), 3);
+        assert!(timestamps
+            .iter()
+            .zip(&detected)
+            .all(|(timestamp, detected)| timestamp <= detected));
+        assert!(timestamps[2] > timestamps[0]);
+    }
+
+    #[test]
+    fn test_sampling_result_notifier_every() {
+        let buffer = SharedBuffer::new();
+        let mut sink = buffer.make_sink();
+        let notifier = SamplingResultNotifier::new(
+            SharedBufferResultNotifier::new(buffer.make_source()),
+            Sampling::Every(3),
+        );
+        let mut cloned = notifier.clone();
+        for i in 0..9 {
+            cloned.notify(&[MatchingInterval::new(i, i)], &[0]);
+        }
+
+        // The counts are shared among the clones
+        assert_eq!(notifier.total(), 9);
+        assert_eq!(notifier.forwarded(), 3);
+        for i in [0, 3, 6] {
+            assert_eq!(
+                sink.pop().unwrap().intervals,
+                vec![MatchingInterval::new(i, i)]
+            );
+        }
+        assert!(sink.pop().is_none());
+    }
+
+    #[test]
+    fn test_sampling_result_notifier_fraction() {
+        let sample = |fraction: f64, seed: u64| {
+            let buffer = SharedBuffer::new();
+            let mut sink = buffer.make_sink();
+            let mut notifier = SamplingResultNotifier::new(
+                SharedBufferResultNotifier::new(buffer.make_source()),
+                Sampling::Fraction { fraction, seed },
+            );
+            for i in 0..1000 {
+                notifier.notify(&[MatchingInterval::new(i, i)], &[0]);
+            }
+            assert_eq!(notifier.total(), 1000);
+            let mut forwarded = Vec::new();
+            while let Some(result) = sink.pop() {
+                forwarded.push(result.intervals[0].start);
+            }
+            assert_eq!(forwarded.len(), notifier.forwarded());
+            forwarded
+        };
+
+        assert!(sample(0.0, 1).is_empty());
+        assert_eq!(sample(1.0, 1).len(), 1000);
+        let forwarded = sample(0.1, 42);
+        assert!((50..150).contains(&forwarded.len()));
+        // The same seed forwards the same matches
+        assert_eq!(forwarded, sample(0.1, 42));
+    }
+
+    #[test]
+    fn test_buffered_result_notifier_shared_among_clones() {
+        let buffer = SharedBuffer::new();
+        let mut sink = buffer.make_sink();
+        let mut notifier =
+            BufferedResultNotifier::new(SharedBufferResultNotifier::new(buffer.make_source()), 3);
+        let mut cloned = notifier.clone();
+        notifier.notify(&[MatchingInterval::new(0, 1)], &[0]);
+        cloned.notify(&[MatchingInterval::new(1, 2)], &[0]);
+        assert!(sink.pop().is_none());
+        // The third match of all the clones flushes the buffer in the order of the notifications
+        cloned.notify(&[MatchingInterval::new(2, 3)], &[0]);
+        for i in 0..3 {
+            assert_eq!(
+                sink.pop().unwrap().intervals,
+                vec![MatchingInterval::new(i, i + 1)]
+            );
+        }
+
+        notifier.notify(&[MatchingInterval::new(3, 4)], &[0]);
+        drop(notifier);
+        // The buffer is flushed only when the last clone is dropped
+        assert!(sink.pop().is_none());
+        drop(cloned);
+        assert_eq!(
+            sink.pop().unwrap().intervals,
+            vec![MatchingInterval::new(3, 4)]
+        );
+
+        // A huge limit is not allocated up front
+        let mut notifier = BufferedResultNotifier::new(
+            SharedBufferResultNotifier::new(buffer.make_source()),
+            usize::MAX,
+        );
+        notifier.notify(&[MatchingInterval::new(4, 5)], &[0]);
+        drop(notifier);
+        assert_eq!(
+            sink.pop().unwrap().intervals,
+            vec![MatchingInterval::new(4, 5)]
+        );
+    }
+
+    #[test]
+    fn test_buffered_file_result_notifier() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        {
+            let notifier = FileResultNotifier::new(temp_file.path().to_str().unwrap())?;
+            let mut notifier = BufferedResultNotifier::new(notifier, 100);
+            notifier.notify(
+                &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 4)],
+                &[0, 1],
+            );
+            notifier.notify(
+                &[MatchingInterval::new(5, 6), MatchingInterval::new(7, 8)],
+                &[1, 0],
+            );
+        }
+        let content = std::fs::read_to_string(temp_file.path())?;
+        assert_eq!(content, "0: (1, 2), 1: (3, 4)\n1: (5, 6), 0: (7, 8)\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_flat_bounds() {
+        let result = MatchingResult::new(
+            vec![MatchingInterval::new(1, 3), MatchingInterval::new(0, 2)],
+            vec![1, 0],
+        );
+        assert_eq!(result.flat_bounds(), vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn test_matching_result_order() {
+        // All the results with two variables over small ids and intervals
+        let intervals = (0..2)
+            .flat_map(|start| (start..3).map(move |end| MatchingInterval::new(start, end)))
+            .collect::<Vec<_>>();
+        let mut results = Vec::new();
+        for ids in [vec![0, 0], vec![0, 1], vec![1, 0]] {
+            for first in &intervals {
+                for second in &intervals {
+                    results.push(MatchingResult::new(
+                        vec![first.clone(), second.clone()],
+                        ids.clone(),
+                    ));
+                }
+            }
+        }
+
+        // The order is total and consistent with the equality
+        for a in &results {
+            for b in &results {
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+                for c in &results {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{:?} <= {:?} <= {:?}", a, b, c);
+                    }
+                }
+            }
+        }
+
+        // The ids take precedence over the intervals
+        let later_interval = MatchingResult::new(
+            vec![MatchingInterval::new(0, 0), MatchingInterval::new(1, 1)],
+            vec![0, 1],
+        );
+        let later_ids = MatchingResult::new(
+            vec![MatchingInterval::new(0, 0), MatchingInterval::new(0, 0)],
+            vec![1, 0],
+        );
+        assert!(later_interval < later_ids);
+
+        // Sorting makes the duplicates adjacent
+        let mut duplicated = results
+            .iter()
+            .chain(results.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        duplicated.reverse();
+        duplicated.sort();
+        duplicated.dedup();
+        assert_eq!(duplicated, results);
     }
 }
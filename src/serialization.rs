@@ -1,3 +1,4 @@
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
@@ -7,101 +8,183 @@ use std::{
 use typed_arena::Arena;
 
 // Import your NFA types from automata.rs
-use crate::automata::{Automata, State, Transition, ValidLabel};
+use crate::automata::{
+    Automata, ClassNFAH, LabelMatch, NFAHState, NFAHTransition, State, Transition, ValidLabel, NFAH,
+};
+use crate::dfa::DFA;
 
-#[derive(Serialize, Deserialize)]
-struct SerializedAutomata<L> {
-    dimensions: usize,
-    states: Vec<SerializedState>,
-    transitions: Vec<SerializedTransition<L>>,
+/// A plain representation of an automaton that owns its states and transitions.
+///
+/// Unlike the arena-backed [`Automata`], this representation can derive `Serialize` and
+/// `Deserialize`. The states are identified by their index in `states`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedAutomata<L> {
+    pub dimensions: usize,
+    pub states: Vec<OwnedState>,
+    pub transitions: Vec<OwnedTransition<L>>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct SerializedState {
-    id: usize,
-    is_initial: bool,
-    is_final: bool,
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedState {
+    pub id: usize,
+    pub is_initial: bool,
+    pub is_final: bool,
 }
 
-#[derive(Serialize, Deserialize)]
-struct SerializedTransition<L> {
-    from: usize,
-    to: usize,
-    label: L,
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedTransition<L> {
+    pub from: usize,
+    pub to: usize,
+    pub label: L,
 }
 
-/// Serializes the given NFA into a JSON string.
-///
-/// The serialization traverses the automata (starting from the initial states),
-/// assigns an ID to each reachable state, and then outputs the states and transitions.
-pub fn serialize_nfa<'a, L: Serialize + Clone>(automata: &Automata<'a, L>) -> String {
-    // Mapping from state pointer to (state reference, assigned id)
-    let mut state_ids: HashMap<*const State<'a, L>, (&State<'a, L>, usize)> = HashMap::new();
-    let mut queue: VecDeque<&State<'a, L>> = VecDeque::new();
+/// The owned representation of an NFAH.
+pub type OwnedNfah = OwnedAutomata<(String, usize)>;
 
-    // Start from each initial state.
-    for state in &automata.initial_states {
-        queue.push_back(state);
-    }
+impl<L: Clone> OwnedAutomata<L> {
+    /// Builds the owned representation of the given automaton.
+    ///
+    /// The conversion traverses the automaton (starting from the initial states),
+    /// assigns an ID to each reachable state, and then collects the states and transitions.
+    /// The IDs and the order of the transitions only depend on the order of the transitions
+    /// in the automaton.
+    fn from_automata<'a>(automata: &Automata<'a, L>) -> Self {
+        // Mapping from state pointer to the assigned id
+        let mut state_ids: HashMap<*const State<'a, L>, usize> = HashMap::new();
+        let mut states_by_id: Vec<&State<'a, L>> = Vec::new();
+        let mut queue: VecDeque<&State<'a, L>> = VecDeque::new();
 
-    let mut next_id = 0;
-    while let Some(state) = queue.pop_front() {
-        let ptr = state as *const State<'a, L>;
-        if state_ids.contains_key(&ptr) {
-            continue;
+        // Start from each initial state.
+        for state in &automata.initial_states {
+            queue.push_back(state);
         }
-        state_ids.insert(ptr, (state, next_id));
-        next_id += 1;
-        // Queue all adjacent states.
-        for t in state.transitions.borrow().iter() {
-            queue.push_back(t.next_state);
+
+        while let Some(state) = queue.pop_front() {
+            let ptr = state as *const State<'a, L>;
+            if state_ids.contains_key(&ptr) {
+                continue;
+            }
+            state_ids.insert(ptr, states_by_id.len());
+            states_by_id.push(state);
+            // Queue all adjacent states.
+            for t in state.transitions.borrow().iter() {
+                queue.push_back(t.next_state);
+            }
         }
-    }
 
-    // Create a set of initial state pointers for easy lookup.
-    let initial_ptrs: HashSet<*const State<'a, L>> = automata
-        .initial_states
-        .iter()
-        .map(|s| *s as *const State<'a, L>)
-        .collect();
-
-    // Build the vector of serialized states.
-    let mut states_vec = Vec::new();
-    // Sorting states by their assigned id for a deterministic order.
-    let mut states_by_id: Vec<(&State<'a, L>, usize)> = state_ids.values().cloned().collect();
-    states_by_id.sort_by_key(|&(_, id)| id);
-    for (state, id) in states_by_id {
-        states_vec.push(SerializedState {
-            id,
-            is_initial: initial_ptrs.contains(&(state as *const State<'a, L>)),
-            is_final: state.is_final,
-        });
+        // Create a set of initial state pointers for easy lookup.
+        let initial_ptrs: HashSet<*const State<'a, L>> = automata
+            .initial_states
+            .iter()
+            .map(|s| *s as *const State<'a, L>)
+            .collect();
+
+        // Build the vector of owned states.
+        let states = states_by_id
+            .iter()
+            .enumerate()
+            .map(|(id, &state)| OwnedState {
+                id,
+                is_initial: initial_ptrs.contains(&(state as *const State<'a, L>)),
+                is_final: state.is_final(),
+            })
+            .collect();
+
+        // Build the vector of owned transitions.
+        let mut transitions = Vec::new();
+        for (id, state) in states_by_id.iter().enumerate() {
+            for t in state.transitions.borrow().iter() {
+                transitions.push(OwnedTransition {
+                    from: id,
+                    to: state_ids[&(t.next_state as *const State<'a, L>)],
+                    label: t.label.clone(),
+                });
+            }
+        }
+
+        Self {
+            dimensions: automata.dimensions,
+            states,
+            transitions,
+        }
     }
+}
 
-    // Build the vector of serialized transitions.
-    let mut transitions_vec = Vec::new();
-    for (&_ptr, &(state, id)) in &state_ids {
-        for t in state.transitions.borrow().iter() {
-            let target_ptr = t.next_state as *const State<'a, L>;
-            let target_id = state_ids
-                .get(&target_ptr)
-                .expect("Target state not found in state_ids")
-                .1;
-            transitions_vec.push(SerializedTransition::<L> {
-                from: id,
-                to: target_id,
-                label: t.label.clone(),
-            });
+impl<L: Eq + Hash + Clone + ValidLabel> OwnedAutomata<L> {
+    /// Builds the arena-backed automaton from the owned representation.
+    ///
+    /// # Arguments
+    /// * `state_arena` - An arena for allocating `State` objects.
+    /// * `trans_arena` - An arena for allocating `Transition` objects.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a state id or a transition refers to an invalid state.
+    fn into_automata<'a>(
+        self,
+        state_arena: &'a Arena<State<'a, L>>,
+        trans_arena: &'a Arena<Transition<'a, L>>,
+    ) -> Automata<'a, L> {
+        let mut automata = Automata::new(state_arena, trans_arena, self.dimensions);
+        let num_states = self.states.len();
+        let mut id_to_state: Vec<Option<&'a State<'a, L>>> = vec![None; num_states];
+
+        // Create states in the automata.
+        for s in self.states {
+            if s.id >= num_states {
+                panic!("State id {} out of range", s.id);
+            }
+            let state = automata.add_state(s.is_initial, s.is_final);
+            id_to_state[s.id] = Some(state);
+        }
+
+        // Add transitions using the id-to-state mapping.
+        for t in self.transitions {
+            let from_state = id_to_state
+                .get(t.from)
+                .copied()
+                .flatten()
+                .unwrap_or_else(|| panic!("Invalid 'from' state id: {}", t.from));
+            let to_state = id_to_state
+                .get(t.to)
+                .copied()
+                .flatten()
+                .unwrap_or_else(|| panic!("Invalid 'to' state id: {}", t.to));
+            automata.add_transition(from_state, t.label, to_state);
         }
+
+        automata
     }
+}
 
-    let serialized = SerializedAutomata {
-        dimensions: automata.dimensions,
-        states: states_vec,
-        transitions: transitions_vec,
-    };
+impl OwnedNfah {
+    /// Builds the owned representation of the given NFAH.
+    pub fn from_nfah(nfah: &NFAH) -> Self {
+        Self::from_automata(nfah)
+    }
 
-    serde_json::to_string_pretty(&serialized).expect("Serialization failed")
+    /// Builds an NFAH in the given arenas from the owned representation, consuming it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transition refers to an invalid state.
+    // Named after `from_nfah`, but takes `self` to move the labels without cloning.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_nfah<'a>(
+        self,
+        state_arena: &'a Arena<NFAHState<'a>>,
+        trans_arena: &'a Arena<NFAHTransition<'a>>,
+    ) -> NFAH<'a> {
+        self.into_automata(state_arena, trans_arena)
+    }
+}
+
+/// Serializes the given NFA into a JSON string.
+///
+/// The states are numbered in the BFS order from the initial states (see [`OwnedAutomata`]).
+pub fn serialize_nfa<L: Serialize + Clone>(automata: &Automata<'_, L>) -> String {
+    serde_json::to_string_pretty(&OwnedAutomata::from_automata(automata))
+        .expect("Serialization failed")
 }
 
 /// Deserializes a JSON string into an NFA.
@@ -111,40 +194,218 @@ pub fn serialize_nfa<'a, L: Serialize + Clone>(automata: &Automata<'a, L>) -> St
 /// * `state_arena` - An arena for allocating `State` objects.
 /// * `trans_arena` - An arena for allocating `Transition` objects.
 ///
-/// # Panics
+/// # Errors
 ///
-/// Panics if JSON parsing fails or if a transition refers to an invalid state.
+/// Returns an error message if JSON parsing fails or if the automaton is invalid
+/// (see [`validate_automaton`]).
 pub fn deserialize_nfa<'a, L: Deserialize<'a> + Eq + Hash + Clone + ValidLabel>(
     input: &'a str,
     state_arena: &'a Arena<State<'a, L>>,
     trans_arena: &'a Arena<Transition<'a, L>>,
-) -> Automata<'a, L> {
-    let ser: SerializedAutomata<L> =
-        serde_json::from_str(input).expect("Failed to deserialize NFA from JSON");
+) -> Result<Automata<'a, L>, String> {
+    deserialize_nfa_with_dimensions(input, None, state_arena, trans_arena)
+}
 
-    let mut automata = Automata::new(state_arena, trans_arena, ser.dimensions);
-    let num_states = ser.states.len();
-    let mut id_to_state: Vec<Option<&'a State<'a, L>>> = vec![None; num_states];
+/// Deserializes a JSON string into an NFA, replacing its dimensions with `dimensions` if given.
+///
+/// This is for the automata whose `dimensions` field is authored incorrectly. The labels are
+/// validated against the replaced dimensions.
+///
+/// # Errors
+///
+/// Returns an error message if JSON parsing fails or if the automaton is invalid with the
+/// replaced dimensions, e.g., a label reads a variable not less than `dimensions`.
+pub fn deserialize_nfa_with_dimensions<'a, L: Deserialize<'a> + Eq + Hash + Clone + ValidLabel>(
+    input: &'a str,
+    dimensions: Option<usize>,
+    state_arena: &'a Arena<State<'a, L>>,
+    trans_arena: &'a Arena<Transition<'a, L>>,
+) -> Result<Automata<'a, L>, String> {
+    let mut owned: OwnedAutomata<L> = serde_json::from_str(input)
+        .map_err(|e| format!("Failed to deserialize NFA from JSON: {}", e))?;
+    if let Some(dimensions) = dimensions {
+        owned.dimensions = dimensions;
+    }
+    validate_automaton(&owned)?;
+    Ok(owned.into_automata(state_arena, trans_arena))
+}
 
-    // Create states in the automata.
-    for s in ser.states {
-        if s.id >= num_states {
-            panic!("State id {} out of range", s.id);
+/// The letter of a label in the JSON form of an NFAH, a list of letters matching any of them, or
+/// a negated class `{"not": [...]}` matching any letter except them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonLetters {
+    One(String),
+    Any(Vec<String>),
+    Not { not: Vec<String> },
+}
+
+/// Deserializes the JSON form of an NFAH, where the letters of each label are converted to the
+/// letters of the transitions by `letters`, and replaces its dimensions with `dimensions` if given.
+///
+/// The error of `letters` is reported with the transition.
+fn deserialize_json_letters<L>(
+    input: &str,
+    dimensions: Option<usize>,
+    mut letters: impl FnMut(JsonLetters) -> Result<Vec<L>, String>,
+) -> Result<OwnedAutomata<(L, usize)>, String>
+where
+    (L, usize): ValidLabel,
+{
+    let parsed: OwnedAutomata<(JsonLetters, usize)> = serde_json::from_str(input)
+        .map_err(|e| format!("Failed to deserialize NFA from JSON: {}", e))?;
+    let mut transitions = Vec::with_capacity(parsed.transitions.len());
+    for (i, t) in parsed.transitions.into_iter().enumerate() {
+        let (json_letters, var) = t.label;
+        let letters = letters(json_letters)
+            .map_err(|e| format!("Invalid transition {} ({} -> {}): {}", i, t.from, t.to, e))?;
+        transitions.extend(letters.into_iter().map(|letter| OwnedTransition {
+            from: t.from,
+            to: t.to,
+            label: (letter, var),
+        }));
+    }
+    let owned = OwnedAutomata {
+        dimensions: dimensions.unwrap_or(parsed.dimensions),
+        states: parsed.states,
+        transitions,
+    };
+    validate_automaton(&owned)?;
+    Ok(owned)
+}
+
+/// Returns the distinct letters of a list of letters, which must not be empty.
+fn listed_letters(letters: Vec<String>) -> Result<Vec<String>, String> {
+    if letters.is_empty() {
+        return Err("the list of letters is empty".to_string());
+    }
+    Ok(letters.into_iter().unique().collect())
+}
+
+/// Deserializes a JSON string into an NFAH, replacing its dimensions with `dimensions` if given.
+///
+/// This is the same as [`deserialize_nfa_with_dimensions`], but the letter of a label may be a
+/// list of letters, e.g., `[["a", "c"], 0]` for `a` or `c` on the variable 0. Such a label is
+/// expanded into one transition for each letter, so the matching does not need to handle it.
+///
+/// # Errors
+///
+/// Returns an error message if JSON parsing fails, if a list of letters is empty, if a label is a
+/// negated class (see [`deserialize_class_nfah_with_dimensions`]), or if the expanded automaton
+/// is invalid (see [`validate_automaton`]).
+pub fn deserialize_nfah_with_dimensions<'a>(
+    input: &str,
+    dimensions: Option<usize>,
+    state_arena: &'a Arena<NFAHState<'a>>,
+    trans_arena: &'a Arena<NFAHTransition<'a>>,
+) -> Result<NFAH<'a>, String> {
+    let owned = deserialize_json_letters(input, dimensions, |letters| match letters {
+        JsonLetters::One(letter) => Ok(vec![letter]),
+        JsonLetters::Any(letters) => listed_letters(letters),
+        JsonLetters::Not { .. } => {
+            Err("a negated class of letters is read only with --letter-classes".to_string())
         }
-        let state = automata.add_state(s.is_initial, s.is_final);
-        id_to_state[s.id] = Some(state);
+    })?;
+    Ok(owned.into_automata(state_arena, trans_arena))
+}
+
+/// Returns the matcher of a letter of a [`ClassNFAH`] in JSON, where `"*"` is the wildcard
+/// matching any letter and `"\\*"` is the letter `*` itself.
+fn class_letter(letter: String) -> LabelMatch {
+    match letter.as_str() {
+        "*" => LabelMatch::Any,
+        "\\*" => LabelMatch::Exact("*".to_string()),
+        _ => LabelMatch::Exact(letter),
     }
+}
+
+/// Deserializes a JSON string into a [`ClassNFAH`], replacing its dimensions with `dimensions` if
+/// given.
+///
+/// In addition to the letters of [`deserialize_nfah_with_dimensions`], the letter of a label may
+/// be a negated class, e.g., `[{"not": ["a", "b"]}, 0]` for any letter except `a` and `b` on the
+/// variable 0, or the wildcard `"*"` for any letter (see [`class_letter`]). A list of letters is
+/// expanded into one transition for each letter.
+///
+/// # Errors
+///
+/// Returns an error message if JSON parsing fails, if a list of letters is empty, or if the
+/// expanded automaton is invalid (see [`validate_automaton`]).
+pub fn deserialize_class_nfah_with_dimensions<'a>(
+    input: &str,
+    dimensions: Option<usize>,
+    state_arena: &'a Arena<State<'a, (LabelMatch, usize)>>,
+    trans_arena: &'a Arena<Transition<'a, (LabelMatch, usize)>>,
+) -> Result<ClassNFAH<'a>, String> {
+    let owned = deserialize_json_letters(input, dimensions, |letters| match letters {
+        JsonLetters::One(letter) => Ok(vec![class_letter(letter)]),
+        JsonLetters::Any(letters) => Ok(listed_letters(letters)?
+            .into_iter()
+            .map(class_letter)
+            .collect()),
+        JsonLetters::Not { not } => Ok(vec![LabelMatch::NotIn(not.into_iter().collect())]),
+    })?;
+    Ok(owned.into_automata(state_arena, trans_arena))
+}
+
+/// Serializes the given NFAH into the compact binary format of `bincode`.
+///
+/// The binary form encodes the same [`OwnedNfah`] as the JSON form, but it is smaller and much
+/// faster to load for large automata.
+pub fn save_nfah_bincode(nfah: &NFAH) -> Vec<u8> {
+    bincode::serialize(&OwnedNfah::from_nfah(nfah)).expect("Serialization failed")
+}
 
-    // Add transitions using the id-to-state mapping.
-    for t in ser.transitions {
-        let from_state =
-            id_to_state[t.from].unwrap_or_else(|| panic!("Invalid 'from' state id: {}", t.from));
-        let to_state =
-            id_to_state[t.to].unwrap_or_else(|| panic!("Invalid 'to' state id: {}", t.to));
-        automata.add_transition(from_state, t.label, to_state);
+/// Deserializes an NFAH from the binary format written by [`save_nfah_bincode`], replacing its
+/// dimensions with `dimensions` if given.
+///
+/// # Errors
+///
+/// Returns an error message if decoding fails or if the automaton is invalid
+/// (see [`validate_automaton`]).
+pub fn load_nfah_bincode<'a>(
+    input: &[u8],
+    dimensions: Option<usize>,
+    state_arena: &'a Arena<NFAHState<'a>>,
+    trans_arena: &'a Arena<NFAHTransition<'a>>,
+) -> Result<NFAH<'a>, String> {
+    let mut owned: OwnedNfah = bincode::deserialize(input)
+        .map_err(|e| format!("Failed to deserialize NFA from bincode: {}", e))?;
+    if let Some(dimensions) = dimensions {
+        owned.dimensions = dimensions;
     }
+    validate_automaton(&owned)?;
+    Ok(owned.into_automata(state_arena, trans_arena))
+}
 
-    automata
+/// Checks that the given automaton can be built, i.e., the state ids are distinct and in range,
+/// and each transition connects valid states with a valid label.
+///
+/// Since the labels are validated here, the matching does not need to check them for each transition.
+pub fn validate_automaton<L: ValidLabel>(automaton: &OwnedAutomata<L>) -> Result<(), String> {
+    let num_states = automaton.states.len();
+    let mut seen = vec![false; num_states];
+    for s in &automaton.states {
+        if s.id >= num_states {
+            return Err(format!("State id {} out of range", s.id));
+        }
+        if seen[s.id] {
+            return Err(format!("Duplicated state id: {}", s.id));
+        }
+        seen[s.id] = true;
+    }
+    for (i, t) in automaton.transitions.iter().enumerate() {
+        if t.from >= num_states {
+            return Err(format!("Invalid 'from' state id: {}", t.from));
+        }
+        if t.to >= num_states {
+            return Err(format!("Invalid 'to' state id: {}", t.to));
+        }
+        t.label
+            .validate(automaton.dimensions)
+            .map_err(|e| format!("Invalid transition {} ({} -> {}): {}", i, t.from, t.to, e))?;
+    }
+    Ok(())
 }
 
 /// Generates a DOT representation of the given NFA suitable for Graphviz.
@@ -153,10 +414,91 @@ pub fn deserialize_nfa<'a, L: Deserialize<'a> + Eq + Hash + Clone + ValidLabel>(
 /// Final states are drawn with a `doublecircle` shape, while non-final states use a `circle`.
 /// An invisible __start__ node points to all initial states.
 pub fn automaton_to_dot<'a, L: Debug>(automata: &Automata<'a, L>) -> String {
+    let (state_ids, id_to_state) = number_reachable_states(automata);
+
+    // Create edges from the invisible __start__ to all initial states.
+    let initial_ids = automata
+        .initial_states
+        .iter()
+        .map(|&state| state_ids[&(state as *const State<'a, L>)])
+        .collect_vec();
+    let mut dot = dot_header("NFA", &initial_ids);
+
+    // Define nodes.
+    for (id, state) in id_to_state.iter().enumerate() {
+        dot.push_str(&dot_state_node(id, state.is_final()));
+    }
+
+    // Define edges for transitions.
+    for (id, state) in id_to_state.iter().enumerate() {
+        for t in state.transitions.borrow().iter() {
+            let target_id = state_ids[&(t.next_state as *const State<'a, L>)];
+            dot.push_str(&dot_edge(id, target_id, &t.label));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Generates a DOT representation of the given DFA in the same style as [`automaton_to_dot`].
+///
+/// The states are numbered in the BFS order from the initial state, where the transitions of a
+/// state are visited in the order of the `Debug` representation of their symbols. The states not
+/// reachable from the initial state are omitted.
+pub fn dfa_to_dot<S, A>(dfa: &DFA<S, A>) -> String
+where
+    S: Eq + Hash + Clone,
+    A: Eq + Hash + Clone + Debug,
+{
+    let alphabet = dfa
+        .alphabet
+        .iter()
+        .sorted_by_cached_key(|symbol| format!("{:?}", symbol))
+        .collect_vec();
+    let mut state_ids: HashMap<&S, usize> = HashMap::new();
+    let mut id_to_state: Vec<&S> = Vec::new();
+    let mut queue: VecDeque<&S> = VecDeque::new();
+    state_ids.insert(&dfa.initial, 0);
+    id_to_state.push(&dfa.initial);
+    queue.push_back(&dfa.initial);
+    while let Some(state) = queue.pop_front() {
+        for &symbol in &alphabet {
+            if let Some(next_state) = dfa.transitions.get(&(state.clone(), symbol.clone())) {
+                if let std::collections::hash_map::Entry::Vacant(e) = state_ids.entry(next_state) {
+                    e.insert(id_to_state.len());
+                    id_to_state.push(next_state);
+                    queue.push_back(next_state);
+                }
+            }
+        }
+    }
+
+    let mut dot = dot_header("DFA", &[0]);
+    for (id, state) in id_to_state.iter().enumerate() {
+        dot.push_str(&dot_state_node(id, dfa.finals.contains(state)));
+    }
+    for (id, &state) in id_to_state.iter().enumerate() {
+        for &symbol in &alphabet {
+            if let Some(next_state) = dfa.transitions.get(&(state.clone(), symbol.clone())) {
+                dot.push_str(&dot_edge(id, state_ids[next_state], symbol));
+            }
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// The ids of the states reachable from the initial states, and the states indexed by their ids.
+type StateNumbering<'a, L> = (HashMap<*const State<'a, L>, usize>, Vec<&'a State<'a, L>>);
+
+/// Numbers the states reachable from the initial states of `automata` in the BFS order.
+fn number_reachable_states<'a, L>(automata: &Automata<'a, L>) -> StateNumbering<'a, L> {
     // Map each state's pointer to a unique id and store the state pointers.
     let mut state_ids: HashMap<*const State<'a, L>, usize> = HashMap::new();
-    let mut id_to_state: Vec<&State<'a, L>> = Vec::new();
-    let mut queue: VecDeque<&State<'a, L>> = VecDeque::new();
+    let mut id_to_state: Vec<&'a State<'a, L>> = Vec::new();
+    let mut queue: VecDeque<&'a State<'a, L>> = VecDeque::new();
 
     // Enqueue initial states.
     for &state in &automata.initial_states {
@@ -181,54 +523,330 @@ pub fn automaton_to_dot<'a, L: Debug>(automata: &Automata<'a, L>) -> String {
         }
     }
 
-    let mut dot = String::new();
-    dot.push_str("digraph NFA {\n");
-    dot.push_str("  rankdir=LR;\n");
-    dot.push_str("  node [shape=circle];\n");
-    dot.push_str("  __start__ [shape=point];\n");
+    (state_ids, id_to_state)
+}
 
-    // Create edges from the invisible __start__ to all initial states.
-    for &state in &automata.initial_states {
-        let id = state_ids[&(state as *const State<'a, L>)];
-        dot.push_str(&format!("  __start__ -> state{};\n", id));
-    }
+/// Generates a GraphML representation of the given NFAH.
+///
+/// The states are numbered as in [`automaton_to_dot`]. Each node has the boolean data keys
+/// `initial` and `final`, and each edge has the data keys `letter` and `var` of its label.
+pub fn automaton_to_graphml(automaton: &NFAH) -> String {
+    let (state_ids, id_to_state) = number_reachable_states(automaton);
+
+    let mut graphml = String::new();
+    graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    graphml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    graphml.push_str(
+        "  <key id=\"initial\" for=\"node\" attr.name=\"initial\" attr.type=\"boolean\"/>\n",
+    );
+    graphml
+        .push_str("  <key id=\"final\" for=\"node\" attr.name=\"final\" attr.type=\"boolean\"/>\n");
+    graphml.push_str(
+        "  <key id=\"letter\" for=\"edge\" attr.name=\"letter\" attr.type=\"string\"/>\n",
+    );
+    graphml.push_str("  <key id=\"var\" for=\"edge\" attr.name=\"var\" attr.type=\"int\"/>\n");
+    graphml.push_str("  <graph id=\"NFAH\" edgedefault=\"directed\">\n");
 
-    // Define nodes.
     for (id, state) in id_to_state.iter().enumerate() {
-        // Use doublecircle for final states.
-        let shape = if state.is_final {
-            "doublecircle"
-        } else {
-            "circle"
-        };
-        dot.push_str(&format!(
-            "  state{} [label=\"State {}\", shape={}];\n",
-            id, id, shape
+        let is_initial = automaton
+            .initial_states
+            .iter()
+            .any(|&initial| std::ptr::eq(initial, *state));
+        graphml.push_str(&format!("    <node id=\"state{}\">\n", id));
+        graphml.push_str(&format!(
+            "      <data key=\"initial\">{}</data>\n",
+            is_initial
         ));
+        graphml.push_str(&format!(
+            "      <data key=\"final\">{}</data>\n",
+            state.is_final()
+        ));
+        graphml.push_str("    </node>\n");
     }
 
-    // Define edges for transitions.
     for (id, state) in id_to_state.iter().enumerate() {
         for t in state.transitions.borrow().iter() {
-            let target_id = state_ids[&(t.next_state as *const State<'a, L>)];
-            dot.push_str(&format!(
-                "  state{} -> state{} [label=\"{:?}\"];\n",
-                id, target_id, t.label
+            let target_id = state_ids[&(t.next_state as *const NFAHState)];
+            graphml.push_str(&format!(
+                "    <edge source=\"state{}\" target=\"state{}\">\n",
+                id, target_id
+            ));
+            graphml.push_str(&format!(
+                "      <data key=\"letter\">{}</data>\n",
+                xml_escape(t.letter())
             ));
+            graphml.push_str(&format!(
+                "      <data key=\"var\">{}</data>\n",
+                t.variable()
+            ));
+            graphml.push_str("    </edge>\n");
         }
     }
 
-    dot.push_str("}\n");
+    graphml.push_str("  </graph>\n");
+    graphml.push_str("</graphml>\n");
+    graphml
+}
+
+/// Escapes the characters of `text` with a special meaning in XML.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Returns the beginning of a DOT graph `name`, with an invisible `__start__` node pointing to
+/// the states of `initial_ids`.
+fn dot_header(name: &str, initial_ids: &[usize]) -> String {
+    let mut dot = String::new();
+    dot.push_str(&format!("digraph {} {{\n", name));
+    dot.push_str("  rankdir=LR;\n");
+    dot.push_str("  node [shape=circle];\n");
+    dot.push_str("  __start__ [shape=point];\n");
+    for id in initial_ids {
+        dot.push_str(&format!("  __start__ -> state{};\n", id));
+    }
     dot
 }
 
+/// Returns the DOT node of the state `id`, drawn with a `doublecircle` if it is final.
+fn dot_state_node(id: usize, is_final: bool) -> String {
+    let shape = if is_final { "doublecircle" } else { "circle" };
+    format!("  state{} [label=\"State {}\", shape={}];\n", id, id, shape)
+}
+
+/// Returns the DOT edge from the state `from` to `to` labelled with `label`.
+fn dot_edge<L: Debug>(from: usize, to: usize, label: &L) -> String {
+    format!("  state{} -> state{} [label=\"{:?}\"];\n", from, to, label)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::automata::{Automata, NFAHState, NFAHTransition};
+    use crate::tests::utils::create_small_automaton;
     use std::collections::{HashSet, VecDeque};
     use typed_arena::Arena;
 
+    #[test]
+    fn test_deserialize_list_of_letters() {
+        let json = r#"{
+            "dimensions": 2,
+            "states": [
+                { "id": 0, "is_initial": true, "is_final": false },
+                { "id": 1, "is_initial": false, "is_final": true }
+            ],
+            "transitions": [
+                { "from": 0, "to": 1, "label": [["a", "c"], 0] },
+                { "from": 1, "to": 1, "label": ["b", 1] }
+            ]
+        }"#;
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let nfah = deserialize_nfah_with_dimensions(json, None, &state_arena, &trans_arena)
+            .expect("Failed to deserialize");
+        assert_eq!(nfah.dimensions, 2);
+
+        // Both letters in the list fire the edge on the variable 0
+        let initial = nfah.initial_states[0];
+        let labels: HashSet<(String, usize)> = initial
+            .get_transitions()
+            .iter()
+            .map(|t| t.label.clone())
+            .collect();
+        assert_eq!(
+            labels,
+            HashSet::from([("a".to_string(), 0), ("c".to_string(), 0)])
+        );
+        assert!(initial
+            .get_transitions()
+            .iter()
+            .all(|t| t.next_state.is_final()));
+
+        // An empty list matches nothing, which is likely a mistake
+        let empty = json.replace(r#"["a", "c"]"#, "[]");
+        let error =
+            deserialize_nfah_with_dimensions(&empty, None, &state_arena, &trans_arena).unwrap_err();
+        assert!(error.contains("the list of letters is empty"), "{}", error);
+    }
+
+    #[test]
+    fn test_deserialize_negated_class() {
+        let json = r#"{
+            "dimensions": 1,
+            "states": [
+                { "id": 0, "is_initial": true, "is_final": false },
+                { "id": 1, "is_initial": false, "is_final": true }
+            ],
+            "transitions": [
+                { "from": 0, "to": 1, "label": [{ "not": ["a", "b"] }, 0] },
+                { "from": 1, "to": 1, "label": [["a", "c"], 0] }
+            ]
+        }"#;
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let nfah = deserialize_class_nfah_with_dimensions(json, None, &state_arena, &trans_arena)
+            .expect("Failed to deserialize");
+        let labels: Vec<(LabelMatch, usize)> = nfah.initial_states[0]
+            .get_transitions()
+            .iter()
+            .map(|t| t.label.clone())
+            .collect();
+        assert_eq!(
+            labels,
+            vec![(
+                LabelMatch::NotIn(["a".to_string(), "b".to_string()].into_iter().collect()),
+                0
+            )]
+        );
+        // The list of letters is expanded as in the NFAH
+        let final_state = nfah.initial_states[0].get_transitions()[0].next_state;
+        assert_eq!(final_state.get_transitions().len(), 2);
+
+        // The class cannot be expanded into the letters of an NFAH
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let error =
+            deserialize_nfah_with_dimensions(json, None, &state_arena, &trans_arena).unwrap_err();
+        assert!(error.contains("Invalid transition 0 (0 -> 1)"), "{}", error);
+    }
+
+    #[test]
+    fn test_deserialize_wildcard() {
+        let json = r#"{
+            "dimensions": 1,
+            "states": [
+                { "id": 0, "is_initial": true, "is_final": false },
+                { "id": 1, "is_initial": false, "is_final": true }
+            ],
+            "transitions": [
+                { "from": 0, "to": 1, "label": ["*", 0] },
+                { "from": 0, "to": 1, "label": ["\\*", 0] },
+                { "from": 0, "to": 1, "label": [["a", "*"], 0] }
+            ]
+        }"#;
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let nfah = deserialize_class_nfah_with_dimensions(json, None, &state_arena, &trans_arena)
+            .expect("Failed to deserialize");
+        let labels: Vec<(LabelMatch, usize)> = nfah.initial_states[0]
+            .get_transitions()
+            .iter()
+            .map(|t| t.label.clone())
+            .collect();
+        // The escaped asterisk is the letter "*" itself
+        assert_eq!(
+            labels,
+            vec![
+                (LabelMatch::Any, 0),
+                (LabelMatch::Exact("*".to_string()), 0),
+                (LabelMatch::Exact("a".to_string()), 0),
+                (LabelMatch::Any, 0),
+            ]
+        );
+        let symbol_matcher = crate::symbol_matcher::ExactSymbolMatcher;
+        assert!(LabelMatch::Any.matches_with("b", &symbol_matcher));
+        assert!(!labels[1].0.matches_with("b", &symbol_matcher));
+        assert!(labels[1].0.matches_with("*", &symbol_matcher));
+
+        // Without the classes, the asterisk is an ordinary letter
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let nfah = deserialize_nfah_with_dimensions(json, None, &state_arena, &trans_arena)
+            .expect("Failed to deserialize");
+        assert_eq!(nfah.initial_states[0].get_transitions()[0].label.0, "*");
+    }
+
+    #[test]
+    fn test_deserialize_out_of_range_variable() {
+        let json = r#"{
+            "dimensions": 2,
+            "states": [
+                { "id": 0, "is_initial": true, "is_final": false },
+                { "id": 1, "is_initial": false, "is_final": true }
+            ],
+            "transitions": [
+                { "from": 0, "to": 1, "label": ["a", 0] },
+                { "from": 1, "to": 1, "label": ["b", 2] }
+            ]
+        }"#;
+        let owned: OwnedNfah = serde_json::from_str(json).unwrap();
+        let error = validate_automaton(&owned).unwrap_err();
+        assert!(
+            error.contains("Variable index 2 out of bounds"),
+            "{}",
+            error
+        );
+
+        // The automaton is rejected at load time instead of panicking during the matching
+        let state_arena: Arena<NFAHState> = Arena::new();
+        let trans_arena: Arena<NFAHTransition> = Arena::new();
+        let result = deserialize_nfa(json, &state_arena, &trans_arena);
+        assert_eq!(result.err(), Some(error));
+    }
+
+    #[test]
+    fn test_deserialize_with_dimensions() {
+        let json = r#"{
+            "dimensions": 2,
+            "states": [
+                { "id": 0, "is_initial": true, "is_final": false },
+                { "id": 1, "is_initial": false, "is_final": true }
+            ],
+            "transitions": [
+                { "from": 0, "to": 1, "label": ["a", 0] },
+                { "from": 1, "to": 1, "label": ["b", 1] }
+            ]
+        }"#;
+        let state_arena: Arena<NFAHState> = Arena::new();
+        let trans_arena: Arena<NFAHTransition> = Arena::new();
+
+        // Overriding upward is harmless
+        let automaton =
+            deserialize_nfa_with_dimensions(json, Some(3), &state_arena, &trans_arena).unwrap();
+        assert_eq!(automaton.dimensions, 3);
+        assert_eq!(automaton.states.len(), 2);
+
+        // Overriding downward is rejected if a label reads a dropped variable
+        let error =
+            deserialize_nfa_with_dimensions(json, Some(1), &state_arena, &trans_arena).unwrap_err();
+        assert!(
+            error.contains("Variable index 1 out of bounds for 1 dimensions"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_validate_automaton_invalid_state() {
+        let mut owned = OwnedNfah {
+            dimensions: 1,
+            states: vec![OwnedState {
+                id: 0,
+                is_initial: true,
+                is_final: true,
+            }],
+            transitions: vec![OwnedTransition {
+                from: 0,
+                to: 0,
+                label: ("a".to_string(), 0),
+            }],
+        };
+        assert_eq!(validate_automaton(&owned), Ok(()));
+
+        owned.transitions[0].to = 1;
+        assert!(validate_automaton(&owned).is_err());
+        owned.transitions[0].to = 0;
+        owned.states.push(OwnedState {
+            id: 0,
+            is_initial: false,
+            is_final: false,
+        });
+        assert!(validate_automaton(&owned).is_err());
+    }
+
     #[test]
     fn test_serialize_deserialize() {
         // Create arenas for the original automata.
@@ -260,7 +878,8 @@ mod tests {
         // Create new arenas for the deserialized automata.
         let new_state_arena = Arena::new();
         let new_trans_arena = Arena::new();
-        let deserialized = deserialize_nfa(&serialized, &new_state_arena, &new_trans_arena);
+        let deserialized =
+            deserialize_nfa(&serialized, &new_state_arena, &new_trans_arena).unwrap();
 
         // Check that the dimensions match.
         assert_eq!(automata.dimensions, deserialized.dimensions);
@@ -336,4 +955,171 @@ mod tests {
         assert!(dot.contains("state1 -> state2 [label=\"(\"b\", 0)\"];"));
         assert!(dot.contains("state2 -> state0 [label=\"(\"c\", 0)\"];"));
     }
+
+    #[test]
+    fn test_dfa_to_dot_same_style() {
+        let state_arena = Arena::new();
+        let transition_arena = Arena::new();
+        let mut automata = Automata::new(&state_arena, &transition_arena, 1);
+        let s0 = automata.add_state(true, false);
+        let s1 = automata.add_state(false, true);
+        let s2 = automata.add_state(false, false);
+        automata.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automata.add_nfah_transition(s1, "b".to_string(), 0, s2);
+        automata.add_nfah_transition(s2, "c".to_string(), 0, s0);
+
+        let nfa_dot = automaton_to_dot(&automata);
+        let dfa_dot = dfa_to_dot(&automata.determinize());
+        assert!(dfa_dot.contains("digraph DFA {"));
+
+        // The automaton is already deterministic, so only the graph names differ
+        let final_nodes = |dot: &str| {
+            dot.lines()
+                .filter(|line| line.contains("doublecircle"))
+                .map(str::to_string)
+                .collect_vec()
+        };
+        assert_eq!(
+            final_nodes(&nfa_dot),
+            vec!["  state1 [label=\"State 1\", shape=doublecircle];"]
+        );
+        assert_eq!(final_nodes(&nfa_dot), final_nodes(&dfa_dot));
+        assert_eq!(nfa_dot.replace("digraph NFA", "digraph DFA"), dfa_dot);
+    }
+
+    #[test]
+    fn test_automaton_to_graphml() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automaton = create_small_automaton(&state_arena, &trans_arena);
+
+        let graphml = automaton_to_graphml(&automaton);
+        assert!(graphml.starts_with("<?xml"));
+        assert_eq!(graphml.matches("<node ").count(), 5);
+        assert_eq!(graphml.matches("<edge ").count(), 6);
+        assert!(graphml.contains("<key id=\"final\" for=\"node\""));
+        // Only the last state in the BFS order is final
+        assert_eq!(
+            graphml.matches("<data key=\"final\">true</data>").count(),
+            1
+        );
+        assert!(graphml.contains(
+            "<node id=\"state4\">\n      <data key=\"initial\">false</data>\n      <data key=\"final\">true</data>"
+        ));
+        assert!(graphml.contains(
+            "<node id=\"state0\">\n      <data key=\"initial\">true</data>\n      <data key=\"final\">false</data>"
+        ));
+        assert!(graphml.contains("<data key=\"letter\">d</data>\n      <data key=\"var\">1</data>"));
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape("a<b>&\"'"), "a&lt;b&gt;&amp;&quot;&apos;");
+        assert_eq!(xml_escape("abc"), "abc");
+    }
+
+    /// Builds a 2-dimensional NFAH with a loop, a dead end, and two initial states
+    fn build_nfah<'a>(
+        state_arena: &'a Arena<NFAHState<'a>>,
+        trans_arena: &'a Arena<NFAHTransition<'a>>,
+    ) -> NFAH<'a> {
+        let mut automata = Automata::new(state_arena, trans_arena, 2);
+        let s0 = automata.add_state(true, false);
+        let s1 = automata.add_state(false, true);
+        let s2 = automata.add_state(false, false);
+        let s3 = automata.add_state(true, false);
+        automata.add_nfah_transition(s0, "a".to_string(), 0, s1);
+        automata.add_nfah_transition(s1, "b".to_string(), 1, s0);
+        automata.add_nfah_transition(s1, "c".to_string(), 0, s2);
+        automata.add_nfah_transition(s3, "b".to_string(), 1, s1);
+        automata
+    }
+
+    /// Checks that the two NFAHs accept the same words up to the given length
+    fn assert_same_language(left: &NFAH, right: &NFAH, max_length: usize) {
+        let left_dfa = left.determinize();
+        let right_dfa = right.determinize();
+        let alphabet = left_dfa
+            .alphabet
+            .union(&right_dfa.alphabet)
+            .cloned()
+            .collect::<Vec<_>>();
+        for length in 0..=max_length {
+            for word in itertools::Itertools::multi_cartesian_product(
+                (0..length).map(|_| alphabet.iter().cloned()),
+            ) {
+                assert_eq!(
+                    left_dfa.accepts(&word),
+                    right_dfa.accepts(&word),
+                    "The languages differ on {:?}",
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_owned_nfah_json_round_trip() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automata = build_nfah(&state_arena, &trans_arena);
+
+        let owned = OwnedNfah::from_nfah(&automata);
+        assert_eq!(owned.dimensions, 2);
+        assert_eq!(owned.states.len(), 4);
+        assert_eq!(owned.transitions.len(), 4);
+
+        let json = serde_json::to_string(&owned).unwrap();
+        let restored: OwnedNfah = serde_json::from_str(&json).unwrap();
+        assert_eq!(owned, restored);
+    }
+
+    #[test]
+    fn test_owned_nfah_arena_round_trip() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automata = build_nfah(&state_arena, &trans_arena);
+
+        let owned = OwnedNfah::from_nfah(&automata);
+        let new_state_arena = Arena::new();
+        let new_trans_arena = Arena::new();
+        let restored = owned.clone().to_nfah(&new_state_arena, &new_trans_arena);
+
+        assert_eq!(restored.dimensions, automata.dimensions);
+        assert_eq!(restored.initial_states.len(), 2);
+        assert_same_language(&automata, &restored, 5);
+        // The owned representation is stable under the round trip
+        assert_eq!(OwnedNfah::from_nfah(&restored), owned);
+    }
+
+    #[test]
+    fn test_nfah_bincode_round_trip() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automata = build_nfah(&state_arena, &trans_arena);
+
+        let binary = save_nfah_bincode(&automata);
+        let new_state_arena = Arena::new();
+        let new_trans_arena = Arena::new();
+        let restored = load_nfah_bincode(&binary, None, &new_state_arena, &new_trans_arena)
+            .expect("Failed to load the bincode form");
+
+        assert_eq!(restored.dimensions, automata.dimensions);
+        assert_same_language(&automata, &restored, 5);
+        assert!(binary.len() < serialize_nfa(&automata).len());
+    }
+
+    #[test]
+    fn test_load_nfah_bincode_invalid() {
+        let state_arena = Arena::new();
+        let trans_arena = Arena::new();
+        let automata = build_nfah(&state_arena, &trans_arena);
+        let binary = save_nfah_bincode(&automata);
+
+        let new_state_arena = Arena::new();
+        let new_trans_arena = Arena::new();
+        assert!(load_nfah_bincode(&binary[..3], None, &new_state_arena, &new_trans_arena).is_err());
+        // The labels read the variable 1, which is out of range with one dimension
+        assert!(load_nfah_bincode(&binary, Some(1), &new_state_arena, &new_trans_arena).is_err());
+    }
 }
@@ -2,12 +2,19 @@ use std::collections::VecDeque;
 use std::io::{self, BufRead};
 use std::sync::{Arc, Mutex};
 
+/// The items shared by a `SharedBuffer` and its sources and sinks.
+struct SharedItems<T> {
+    queue: VecDeque<T>,
+    /// The number of the resets so far, so that the sinks notice a reset and read from the start.
+    generation: usize,
+}
+
 /// A dynamic buffer that supports concurrent reads and writes.
 ///
 /// This structure is designed for real-time data streams where multiple producers
 /// and consumers can interact with the buffer safely.
 pub struct SharedBuffer<T> {
-    buffer: Arc<Mutex<VecDeque<T>>>,
+    buffer: Arc<Mutex<SharedItems<T>>>,
     internal_buf: Vec<u8>, // Persistent internal buffer for `fill_buf`
 }
 
@@ -15,7 +22,10 @@ impl<T: Clone> SharedBuffer<T> {
     /// Creates a new shared buffer.
     pub fn new() -> Self {
         Self {
-            buffer: Arc::new(Mutex::new(VecDeque::new())),
+            buffer: Arc::new(Mutex::new(SharedItems {
+                queue: VecDeque::new(),
+                generation: 0,
+            })),
             internal_buf: Vec::new(),
         }
     }
@@ -26,7 +36,18 @@ impl<T: Clone> SharedBuffer<T> {
     /// - `line`: The data to be added.
     pub fn push(&self, line: T) {
         let mut buffer = self.buffer.lock().unwrap();
-        buffer.push_back(line);
+        buffer.queue.push_back(line);
+    }
+
+    /// Removes all the queued data so that the buffer can be reused, e.g., in the next phase of a
+    /// test.
+    ///
+    /// The existing sources and sinks remain valid: the sinks read the data pushed after the
+    /// reset from the start.
+    pub fn reset(&self) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.queue.clear();
+        buffer.generation += 1;
     }
 
     /// Creates a source for the shared buffer.
@@ -59,7 +80,7 @@ impl io::Read for SharedBuffer<&str> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let mut buffer = self.buffer.lock().unwrap();
 
-        if let Some(line) = buffer.pop_front() {
+        if let Some(line) = buffer.queue.pop_front() {
             let bytes = line.as_bytes();
             let len = bytes.len().min(buf.len());
             buf[..len].copy_from_slice(&bytes[..len]);
@@ -73,7 +94,7 @@ impl io::Read for SharedBuffer<&str> {
 impl BufRead for SharedBuffer<&str> {
     fn fill_buf(&mut self) -> io::Result<&[u8]> {
         if self.internal_buf.is_empty() {
-            if let Some(line) = self.buffer.lock().unwrap().pop_front() {
+            if let Some(line) = self.buffer.lock().unwrap().queue.pop_front() {
                 self.internal_buf.extend_from_slice(line.as_bytes());
                 // We need to add a line feed to the internal buffer
                 self.internal_buf.push(b'\n');
@@ -94,7 +115,7 @@ impl BufRead for SharedBuffer<&str> {
 /// A producer for the `SharedBuffer`, allowing data to be added.
 #[derive(Clone)]
 pub struct SharedBufferSource<T> {
-    buffer: Arc<Mutex<VecDeque<T>>>,
+    buffer: Arc<Mutex<SharedItems<T>>>,
 }
 
 impl<T> SharedBufferSource<T> {
@@ -102,7 +123,7 @@ impl<T> SharedBufferSource<T> {
     ///
     /// # Arguments
     /// - `buffer`: The shared buffer to which this source will write data.
-    pub fn new(buffer: Arc<Mutex<VecDeque<T>>>) -> Self {
+    fn new(buffer: Arc<Mutex<SharedItems<T>>>) -> Self {
         Self { buffer }
     }
 
@@ -112,14 +133,15 @@ impl<T> SharedBufferSource<T> {
     /// - `line`: The data to be added to the buffer.
     pub fn push(&self, line: T) {
         let mut buffer = self.buffer.lock().unwrap();
-        buffer.push_back(line);
+        buffer.queue.push_back(line);
     }
 }
 
 /// A consumer for the `SharedBuffer`, allowing data to be read in sequence.
 pub struct SharedBufferSink<T> {
-    buffer: Arc<Mutex<VecDeque<T>>>,
-    start: usize, // Start index of the readable range
+    buffer: Arc<Mutex<SharedItems<T>>>,
+    start: usize,      // Start index of the readable range
+    generation: usize, // The generation of the buffer that `start` refers to
 }
 
 impl<T: Clone> SharedBufferSink<T> {
@@ -127,8 +149,13 @@ impl<T: Clone> SharedBufferSink<T> {
     ///
     /// # Arguments
     /// - `buffer`: The shared buffer from which this sink will read data.
-    pub fn new(buffer: Arc<Mutex<VecDeque<T>>>) -> Self {
-        Self { buffer, start: 0 }
+    fn new(buffer: Arc<Mutex<SharedItems<T>>>) -> Self {
+        let generation = buffer.lock().unwrap().generation;
+        Self {
+            buffer,
+            start: 0,
+            generation,
+        }
     }
 
     /// Reads the next line of data from the buffer.
@@ -138,9 +165,14 @@ impl<T: Clone> SharedBufferSink<T> {
     /// - `None`: If no more data is available.
     pub fn pop(&mut self) -> Option<T> {
         let buffer = self.buffer.lock().unwrap();
-        if buffer.len() > self.start {
+        if buffer.generation != self.generation {
+            // The buffer is reset after the last read
+            self.start = 0;
+            self.generation = buffer.generation;
+        }
+        if buffer.queue.len() > self.start {
             self.start += 1;
-            Some(buffer[self.start - 1].clone())
+            Some(buffer.queue[self.start - 1].clone())
         } else {
             None
         }
@@ -212,4 +244,28 @@ mod tests {
 
         assert_eq!(buffer.fill_buf().unwrap(), b"");
     }
+
+    #[test]
+    fn test_reset() {
+        let buffer = SharedBuffer::new();
+        let source = buffer.make_source();
+        let mut sink = buffer.make_sink();
+
+        // The first phase
+        source.push("first1");
+        source.push("first2");
+        assert_eq!(sink.pop(), Some("first1"));
+        assert_eq!(sink.pop(), Some("first2"));
+        assert_eq!(sink.pop(), None);
+
+        // The second phase starts empty, reusing the source and the sink
+        buffer.reset();
+        let mut new_sink = buffer.make_sink();
+        assert_eq!(sink.pop(), None);
+        assert_eq!(new_sink.pop(), None);
+        source.push("second");
+        assert_eq!(sink.pop(), Some("second"));
+        assert_eq!(new_sink.pop(), Some("second"));
+        assert_eq!(sink.pop(), None);
+    }
 }
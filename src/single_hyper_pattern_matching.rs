@@ -9,6 +9,8 @@ use crate::{
     hyper_pattern_matching::PatternMatchingAutomataRunner,
     naive_hyper_pattern_matching::StartPosition,
     result_notifier::{MatchingInterval, ResultNotifier},
+    skip_value_cache::SkipValueCache,
+    symbol_matcher::SharedSymbolMatcher,
 };
 
 /// Trait of the algorithms for hyper pattern matching, where the word assignment is already fixed.
@@ -20,17 +22,50 @@ pub trait SingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
         ids: Vec<usize>,
     ) -> Self;
 
+    /// Creates a new matching taking the tables depending only on `automaton`, e.g., the skip
+    /// values, from `cache`, so that the matchings of all the id-assignments share them.
+    ///
+    /// The default implementation ignores `cache` and calls [`SingleHyperPatternMatching::new`].
+    fn with_cache(
+        automaton: &'a NFAH<'a>,
+        notifier: Notifier,
+        input_streams: Vec<ReadableView<String>>,
+        ids: Vec<usize>,
+        _cache: &SkipValueCache<'a>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(automaton, notifier, input_streams, ids)
+    }
+
     /// The number of variables in hyper pattern matching.
     fn dimensions(&self) -> usize;
 
     /// Returns the word id of the given variable
     fn get_id(&self, variable: usize) -> Option<usize>;
 
+    /// Returns the number of matches notified so far, or 0 if the algorithm does not count them.
+    fn matches_found(&self) -> usize {
+        0
+    }
+
     /// Consumes elements from the input stream, conduct single hyper pattern matching, and notify the detected matching.
     fn consume_input(&mut self);
 
     fn get_input_stream(&self, variable: usize) -> &ReadableView<String>;
 
+    /// Drops the matching trials consuming more than `max_window` actions in a dimension.
+    ///
+    /// Returns `false` if the algorithm does not support it, where the setting is ignored.
+    fn set_max_window(&mut self, max_window: usize) -> bool;
+
+    /// Compares the input symbols and the letters of the automaton by `symbol_matcher`.
+    ///
+    /// Returns `false` if the algorithm does not support it, where the letters are compared
+    /// exactly.
+    fn set_symbol_matcher(&mut self, symbol_matcher: SharedSymbolMatcher) -> bool;
+
     /// Check if the given start position is within the range of the input streams.
     fn in_range(&self, start_position: &StartPosition) -> bool {
         for i in 0..start_position.start_indices.len() {
@@ -50,6 +85,8 @@ pub struct NaiveSingleHyperPatternMatching<'a, Notifier: ResultNotifier> {
     input_streams: Vec<ReadableView<String>>,
     ids: Vec<usize>,
     waiting_queue: BTreeSet<StartPosition>,
+    /// The number of matches notified so far
+    matches_found: usize,
 }
 
 impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
@@ -74,6 +111,7 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
             input_streams,
             ids,
             waiting_queue,
+            matches_found: 0,
         }
     }
 
@@ -85,6 +123,10 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
         self.ids.get(variable).copied()
     }
 
+    fn matches_found(&self) -> usize {
+        self.matches_found
+    }
+
     fn consume_input(&mut self) {
         self.automata_runner.consume();
         let final_configurations = self.automata_runner.get_final_configurations();
@@ -97,6 +139,7 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
                 intervals.push(MatchingInterval::new(begin, end));
             }
             self.notifier.notify(&intervals, &c.ids);
+            self.matches_found += 1;
         });
         self.automata_runner.remove_non_waiting_configurations();
         while self.automata_runner.is_empty() {
@@ -133,11 +176,22 @@ impl<'a, Notifier: ResultNotifier> SingleHyperPatternMatching<'a, Notifier>
                     intervals.push(MatchingInterval::new(begin, end));
                 }
                 self.notifier.notify(&intervals, &c.ids);
+                self.matches_found += 1;
             });
             self.automata_runner.remove_non_waiting_configurations();
         }
     }
 
+    fn set_max_window(&mut self, max_window: usize) -> bool {
+        self.automata_runner.set_max_window(max_window);
+        true
+    }
+
+    fn set_symbol_matcher(&mut self, symbol_matcher: SharedSymbolMatcher) -> bool {
+        self.automata_runner.set_symbol_matcher(symbol_matcher);
+        true
+    }
+
     fn get_input_stream(&self, variable: usize) -> &ReadableView<String> {
         if let Some(stream) = self.input_streams.get(variable) {
             stream
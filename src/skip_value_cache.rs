@@ -0,0 +1,62 @@
+use crate::automata::NFAH;
+use crate::kmp_skip_values::KMPSkipValues;
+use crate::quick_search_skip_values::QuickSearchSkipValues;
+use log::debug;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A cache of the skip value tables shared among matcher instances.
+///
+/// The tables only depend on the automaton, so matchers for the same automaton, e.g., for many
+/// short logs, can reuse them instead of recomputing them. The tables are keyed by the address of
+/// the automaton.
+pub struct SkipValueCache<'a> {
+    tables: RefCell<HashMap<*const NFAH<'a>, SkipValueTables<'a>>>,
+    /// The number of times the tables are computed
+    computations: Cell<usize>,
+}
+
+/// The skip value tables for an automaton.
+#[derive(Clone)]
+pub struct SkipValueTables<'a> {
+    pub quick_search_skip_values: Rc<QuickSearchSkipValues>,
+    pub kmp_skip_values: Rc<KMPSkipValues<'a>>,
+}
+
+impl<'a> SkipValueCache<'a> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self {
+            tables: RefCell::new(HashMap::new()),
+            computations: Cell::new(0),
+        }
+    }
+
+    /// Returns the skip value tables for `automaton`, computing them only if they are not cached.
+    pub fn get(&self, automaton: &'a NFAH<'a>) -> SkipValueTables<'a> {
+        let key = automaton as *const NFAH<'a>;
+        if let Some(tables) = self.tables.borrow().get(&key) {
+            debug!("Reuse the cached skip value tables");
+            return tables.clone();
+        }
+        let tables = SkipValueTables {
+            quick_search_skip_values: Rc::new(QuickSearchSkipValues::new(automaton)),
+            kmp_skip_values: Rc::new(KMPSkipValues::new(automaton)),
+        };
+        self.computations.set(self.computations.get() + 1);
+        self.tables.borrow_mut().insert(key, tables.clone());
+        tables
+    }
+
+    /// Returns the number of times the tables are computed, i.e., the number of cache misses.
+    pub fn computations(&self) -> usize {
+        self.computations.get()
+    }
+}
+
+impl Default for SkipValueCache<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,269 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::{debug, warn};
+
+use crate::result_notifier::{json_line, MatchingInterval, MatchingResult, Report, ResultNotifier};
+
+/// The response header starting an event stream.
+const SSE_RESPONSE_HEADER: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+
+/// The time to wait for the request of a client or for a client to receive the events.
+const SSE_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The number of the batches of events queued for a client before it is dropped as too slow.
+const SSE_CLIENT_QUEUE: usize = 256;
+
+/// A `ResultNotifier` streaming the matching results as Server-Sent Events over HTTP, e.g., to a
+/// live dashboard.
+///
+/// A tiny HTTP server accepts the clients in a background thread, and every request is answered
+/// with an event stream regardless of its path. Each matching result is sent to all the connected
+/// clients as a `data:` line with the same JSON as [`crate::result_notifier::JsonResultNotifier`].
+/// The clients are shared among the clones.
+///
+/// Each client is served by its own thread, so the matching never waits for the network: the
+/// events are queued for the thread, and a client is dropped when it falls
+/// [`SSE_CLIENT_QUEUE`] batches behind, disconnects, or does not send its request or receive the
+/// events within [`SSE_CLIENT_TIMEOUT`].
+#[derive(Clone)]
+pub struct SseResultNotifier {
+    /// The queues of the events to the threads of the clients
+    subscribers: Arc<Mutex<Vec<SyncSender<Arc<str>>>>>,
+    local_addr: SocketAddr,
+}
+
+impl SseResultNotifier {
+    /// Starts the server listening on `addr`, e.g., `("127.0.0.1", 8080)`.
+    ///
+    /// The port 0 binds an arbitrary free port, which is obtained by
+    /// [`SseResultNotifier::local_addr`].
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&subscribers);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let subscribers = Arc::clone(&accepted);
+                        thread::spawn(move || Self::serve(stream, &subscribers));
+                    }
+                    Err(e) => warn!("Failed to accept an SSE client: {}", e),
+                }
+            }
+        });
+        Ok(Self {
+            subscribers,
+            local_addr,
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Serves a client in its own thread until it is dropped.
+    fn serve(stream: TcpStream, subscribers: &Mutex<Vec<SyncSender<Arc<str>>>>) {
+        let (sender, receiver) = mpsc::sync_channel(SSE_CLIENT_QUEUE);
+        let result = Self::subscribe(&stream, subscribers, sender).and_then(|peer| {
+            debug!("SSE client connected: {}", peer);
+            Self::send_events(stream, receiver)
+        });
+        if let Err(e) = result {
+            warn!("Dropped an SSE client: {}", e);
+        }
+    }
+
+    /// Reads the request of a client, adds `sender` to the subscribers, and starts its event
+    /// stream.
+    ///
+    /// The client is subscribed before the header is written, so that the client receives all the
+    /// events after the header. The events queued meanwhile are sent after the header.
+    fn subscribe(
+        mut stream: &TcpStream,
+        subscribers: &Mutex<Vec<SyncSender<Arc<str>>>>,
+        sender: SyncSender<Arc<str>>,
+    ) -> io::Result<SocketAddr> {
+        let peer = stream.peer_addr()?;
+        stream.set_read_timeout(Some(SSE_CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(SSE_CLIENT_TIMEOUT))?;
+        // Skip the request line and the headers until the empty line
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && line.trim_end() != "" {
+            line.clear();
+        }
+
+        subscribers.lock().unwrap().push(sender);
+        stream.write_all(SSE_RESPONSE_HEADER.as_bytes())?;
+        stream.flush()?;
+        Ok(peer)
+    }
+
+    /// Writes the events queued for a client until it is dropped.
+    fn send_events(mut stream: TcpStream, receiver: Receiver<Arc<str>>) -> io::Result<()> {
+        // The queue is closed when the client is dropped from the subscribers
+        for events in receiver {
+            stream.write_all(events.as_bytes())?;
+            stream.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Queues `events` for all the subscribers, dropping the ones disconnected or too slow.
+    fn broadcast(&self, events: &str) {
+        let events: Arc<str> = Arc::from(events);
+        self.subscribers.lock().unwrap().retain(|sender| {
+            match sender.try_send(Arc::clone(&events)) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    warn!("Dropped an SSE client too slow to receive the events");
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
+
+/// Formats a matching result as an SSE event, with the field `"ts"` of `timestamp` if any.
+fn sse_event(
+    intervals: &[MatchingInterval],
+    ids: &[usize],
+    timestamp: Option<SystemTime>,
+) -> String {
+    format!(
+        "data: {}\n\n",
+        json_line(intervals, ids, None, Report::Interval, timestamp)
+    )
+}
+
+impl ResultNotifier for SseResultNotifier {
+    fn notify(&mut self, intervals: &[MatchingInterval], ids: &[usize]) {
+        self.broadcast(&sse_event(intervals, ids, None));
+    }
+
+    fn notify_with_timestamp(
+        &mut self,
+        intervals: &[MatchingInterval],
+        ids: &[usize],
+        timestamp: SystemTime,
+    ) {
+        self.broadcast(&sse_event(intervals, ids, Some(timestamp)));
+    }
+
+    fn notify_all(&mut self, results: &[MatchingResult]) {
+        let events: String = results
+            .iter()
+            .map(|result| sse_event(&result.intervals, &result.ids, None))
+            .collect();
+        self.broadcast(&events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_sse_result_notifier() {
+        let mut notifier = SseResultNotifier::bind(("127.0.0.1", 0)).unwrap();
+        let mut client = TcpStream::connect(notifier.local_addr()).unwrap();
+        client
+            .write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+
+        // The header is sent once the client is subscribed
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut header = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            header.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        assert!(header.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(header.contains("Content-Type: text/event-stream\r\n"));
+
+        notifier.notify(
+            &[MatchingInterval::new(1, 2), MatchingInterval::new(3, 3)],
+            &[0, 1],
+        );
+        let mut event = String::new();
+        reader.read_line(&mut event).unwrap();
+        assert_eq!(
+            event,
+            "data: {\"intervals\":[{\"end\":2,\"id\":0,\"start\":1,\"variable\":0},{\"end\":3,\"id\":1,\"start\":3,\"variable\":1}]}\n"
+        );
+        let mut separator = String::new();
+        reader.read_line(&mut separator).unwrap();
+        assert_eq!(separator, "\n");
+
+        // A disconnected client is dropped
+        drop(reader);
+        client.shutdown(std::net::Shutdown::Both).unwrap();
+        drop(client);
+        // The failure to write is noticed after the peer resets the connection
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !notifier.subscribers.lock().unwrap().is_empty() && Instant::now() < deadline {
+            notifier.notify(&[MatchingInterval::new(0, 0)], &[0]);
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(notifier.subscribers.lock().unwrap().is_empty());
+
+        // A client not sending its request does not block the other clients
+        let _idle = TcpStream::connect(notifier.local_addr()).unwrap();
+
+        // The results are sent to every client connected
+        let mut clients: Vec<_> = (0..2)
+            .map(|_| {
+                let mut client = TcpStream::connect(notifier.local_addr()).unwrap();
+                client.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+                let mut header = [0; SSE_RESPONSE_HEADER.len()];
+                client.read_exact(&mut header).unwrap();
+                client
+            })
+            .collect();
+        notifier.notify_all(&[MatchingResult::new(
+            vec![MatchingInterval::new(4, 5)],
+            vec![2],
+        )]);
+        let expected =
+            "data: {\"intervals\":[{\"end\":5,\"id\":2,\"start\":4,\"variable\":0}]}\n\n";
+        for client in clients.iter_mut() {
+            let mut event = vec![0; expected.len()];
+            client.read_exact(&mut event).unwrap();
+            assert_eq!(String::from_utf8(event).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_sse_result_notifier_drops_slow_client() {
+        let mut notifier = SseResultNotifier::bind(("127.0.0.1", 0)).unwrap();
+        // A client never receiving the events, with the room for one batch
+        let (sender, receiver) = mpsc::sync_channel(1);
+        notifier.subscribers.lock().unwrap().push(sender);
+
+        // The first batch fills the queue of the client
+        notifier.notify(&[MatchingInterval::new(0, 1)], &[0]);
+        assert_eq!(notifier.subscribers.lock().unwrap().len(), 1);
+
+        // The notification does not wait for the full queue, and the client is dropped
+        notifier.notify(&[MatchingInterval::new(2, 3)], &[0]);
+        assert!(notifier.subscribers.lock().unwrap().is_empty());
+        let queued = receiver.try_recv().unwrap();
+        assert!(queued.contains("\"start\":0"));
+        assert_eq!(receiver.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+    }
+}
@@ -0,0 +1,67 @@
+use std::rc::Rc;
+
+/// Decides whether a symbol of the input matches a letter of the automaton.
+///
+/// The matchers use it instead of `==` when they check if a transition is applicable, e.g., to
+/// treat the symbols equivalent up to some normalization as the same letter. Only the naive and
+/// online matchers support it: the skip values of the FJS matchers and the filters of the filtered
+/// matchers are computed from the exact letters.
+pub trait SymbolMatcher {
+    /// Returns `true` if the input `symbol` matches the automaton `letter`.
+    fn matches(&self, letter: &str, symbol: &str) -> bool;
+}
+
+/// The default `SymbolMatcher`, where a symbol matches only the same letter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactSymbolMatcher;
+
+impl SymbolMatcher for ExactSymbolMatcher {
+    fn matches(&self, letter: &str, symbol: &str) -> bool {
+        letter == symbol
+    }
+}
+
+/// A `SymbolMatcher` ignoring a prefix of the input symbols, e.g., `"evt:a"` matches `"a"` if the
+/// prefix is `"evt:"`.
+///
+/// The symbols without the prefix are compared as they are.
+#[derive(Debug, Clone)]
+pub struct PrefixStrippingSymbolMatcher {
+    prefix: String,
+}
+
+impl PrefixStrippingSymbolMatcher {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl SymbolMatcher for PrefixStrippingSymbolMatcher {
+    fn matches(&self, letter: &str, symbol: &str) -> bool {
+        symbol.strip_prefix(self.prefix.as_str()).unwrap_or(symbol) == letter
+    }
+}
+
+/// A `SymbolMatcher` shared among the runners, e.g., of the single matchings of an adapter.
+pub type SharedSymbolMatcher = Rc<dyn SymbolMatcher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_matchers() {
+        assert!(ExactSymbolMatcher.matches("a", "a"));
+        assert!(!ExactSymbolMatcher.matches("a", "evt:a"));
+
+        let matcher = PrefixStrippingSymbolMatcher::new("evt:");
+        assert!(matcher.matches("a", "evt:a"));
+        assert!(matcher.matches("a", "a"));
+        assert!(!matcher.matches("a", "evt:b"));
+
+        let shared: SharedSymbolMatcher = Rc::new(matcher);
+        assert!(shared.matches("a", "evt:a"));
+    }
+}
@@ -0,0 +1,1237 @@
+use crate::error::HyppauError;
+use crate::result_notifier::MatchingInterval;
+use crate::tests::utils::install_recording_logger;
+use crate::{run, Args};
+use clap::Parser;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn example(name: &str) -> String {
+    format!("{}/examples/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn run_with(args: &[&str]) -> Result<(), HyppauError> {
+    let args = Args::try_parse_from(std::iter::once("hyppau").chain(args.iter().copied()))
+        .expect("Failed to parse the arguments");
+    run(args)
+}
+
+#[test]
+fn test_missing_automaton_file() {
+    let result = run_with(&["-f", "/nonexistent/automaton.json"]);
+    assert!(matches!(
+        result,
+        Err(HyppauError::AutomatonFile { ref path, .. }) if path == "/nonexistent/automaton.json"
+    ));
+    assert_eq!(result.unwrap_err().exit_code(), 1);
+}
+
+#[test]
+fn test_missing_input_file() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "-i",
+        "/nonexistent/input.txt",
+    ]);
+    assert!(matches!(
+        result,
+        Err(HyppauError::InputFile { ref path, .. }) if path == "/nonexistent/input.txt"
+    ));
+}
+
+#[test]
+fn test_malformed_automaton() {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    write!(file, "{{\"states\": [").expect("Failed to write temp file");
+    let path = file.path().to_str().unwrap().to_string();
+    let result = run_with(&["-f", &path]);
+    assert!(matches!(result, Err(HyppauError::InvalidAutomaton { .. })));
+}
+
+#[test]
+fn test_unwritable_output() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+    let output = "/nonexistent/output.txt";
+    let result = run_with(&["-f", &automaton, "-i", &input, "-i", &input, "-o", output]);
+    assert!(matches!(
+        result,
+        Err(HyppauError::Output { ref path, .. }) if path == output
+    ));
+
+    let result = run_with(&["-f", &automaton, "-g", "-o", output]);
+    assert!(matches!(result, Err(HyppauError::Output { .. })));
+}
+
+#[test]
+fn test_invalid_share_stream() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+    let result = run_with(&["-f", &automaton, "-i", &input, "--share-stream", "1=2"]);
+    let err = result.unwrap_err();
+    assert!(matches!(err, HyppauError::InvalidArgument(_)));
+    assert_eq!(err.exit_code(), 2);
+}
+
+#[test]
+fn test_unknown_action() {
+    let automaton = example("small.json");
+    let mut input = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(input, "a\ntypo\nc").expect("Failed to write temp file");
+    let path = input.path().to_str().unwrap().to_string();
+
+    let result = run_with(&["-f", &automaton, "-i", &path, "-i", &path, "-q"]);
+    assert!(result.is_ok());
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &path,
+        "-i",
+        &path,
+        "--check-alphabet",
+        "warn",
+    ]);
+    assert!(result.is_ok());
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &path,
+        "-i",
+        &path,
+        "--check-alphabet",
+        "error",
+    ]);
+    assert!(matches!(
+        result,
+        Err(HyppauError::UnknownAction { track: 0, ref action }) if action == "typo"
+    ));
+}
+
+#[test]
+fn test_strip_prefix() {
+    let automaton = example("small.json");
+    let second = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    // examples/small1.txt with the prefix "evt:" on each action
+    let prefixed = dir
+        .path()
+        .join("prefixed.log")
+        .to_str()
+        .unwrap()
+        .to_string();
+    std::fs::write(&prefixed, "evt:a\nevt:a\nevt:c\n").unwrap();
+    let output = dir.path().join("output.txt").to_str().unwrap().to_string();
+
+    let run_to_lines = |first: &str, options: &[&str]| {
+        let mut args = vec!["-f", &automaton, "-i", first, "-i", &second, "-o", &output];
+        args.extend_from_slice(options);
+        run_with(&args).expect("Failed to run");
+        let mut lines = std::fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        lines
+    };
+
+    let expected = run_to_lines(&example("small1.txt"), &[]);
+    assert!(!expected.is_empty());
+    assert!(run_to_lines(&prefixed, &[]).is_empty());
+    for mode in ["naive", "online"] {
+        assert_eq!(
+            run_to_lines(&prefixed, &["-m", mode, "--strip-prefix", "evt:"]),
+            expected
+        );
+    }
+    // The alphabet check compares the actions without the prefix, too
+    assert_eq!(
+        run_to_lines(
+            &prefixed,
+            &["--strip-prefix", "evt:", "--check-alphabet", "error"]
+        ),
+        expected
+    );
+    // The FJS skip values are computed from the exact letters, so the prefix is not stripped
+    assert!(run_to_lines(&prefixed, &["-m", "fjs", "--strip-prefix", "evt:"]).is_empty());
+}
+
+#[test]
+fn test_glob_input() {
+    let automaton = example("small.json");
+    let second = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    // examples/small1.txt split into two rotated logs; the first one lacks the last newline
+    std::fs::write(dir.path().join("app-1.log"), "a\na").unwrap();
+    std::fs::write(dir.path().join("app-2.log"), "c\n").unwrap();
+    let pattern = dir.path().join("app-*.log").to_str().unwrap().to_string();
+    let output = dir.path().join("output.txt").to_str().unwrap().to_string();
+
+    let run_to_lines = |first: &str| {
+        run_with(&["-f", &automaton, "-i", first, "-i", &second, "-o", &output])
+            .expect("Failed to run");
+        let mut lines = std::fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        lines
+    };
+
+    let lines = run_to_lines(&pattern);
+    // The matches span the boundary of the files
+    assert!(lines.contains(&"0: (0, 2), 1: (1, 1)".to_string()));
+    assert!(lines.contains(&"0: (2, 2), 1: (2, 2)".to_string()));
+    assert_eq!(lines, run_to_lines(&example("small1.txt")));
+
+    let missing = dir
+        .path()
+        .join("missing-*.log")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let result = run_with(&["-f", &automaton, "-i", &missing, "-i", &second]);
+    assert!(matches!(
+        result,
+        Err(HyppauError::InputFile { ref path, .. }) if path == &missing
+    ));
+}
+
+#[test]
+fn test_sorted_output() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run_sorted = |mode: &str, name: &str| {
+        let output = dir.path().join(name);
+        let output = output.to_str().unwrap();
+        run_with(&[
+            "-f", &automaton, "-i", &input1, "-i", &input2, "-m", mode, "--sorted", "-o", output,
+        ])
+        .expect("Failed to run");
+        std::fs::read(output).expect("Failed to read the output")
+    };
+
+    let first = run_sorted("naive", "first.txt");
+    let second = run_sorted("naive", "second.txt");
+    assert!(!first.is_empty());
+    // Two runs produce byte-identical output, also across the modes
+    assert_eq!(first, second);
+    assert_eq!(first, run_sorted("fjs", "fjs.txt"));
+    assert_eq!(first, run_sorted("online-filtered", "filtered.txt"));
+}
+
+#[test]
+fn test_json_var_names() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let text_output = dir.path().join("text.txt");
+    let text_output = text_output.to_str().unwrap();
+    let json_output = dir.path().join("json.txt");
+    let json_output = json_output.to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--sorted",
+        "-o",
+        text_output,
+    ])
+    .expect("Failed to run");
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--sorted",
+        "--json",
+        "--var-names",
+        "x,y",
+        "-o",
+        json_output,
+    ])
+    .expect("Failed to run");
+
+    // Each line of JSON labels the same intervals as the text output with the variable names
+    let text = std::fs::read_to_string(text_output).expect("Failed to read the output");
+    let json = std::fs::read_to_string(json_output).expect("Failed to read the output");
+    assert!(!json.is_empty());
+    let from_json: Vec<String> = json
+        .lines()
+        .map(|line| {
+            let value: serde_json::Value = serde_json::from_str(line).expect("Invalid JSON");
+            let intervals = value["intervals"].as_array().unwrap();
+            assert_eq!(intervals.len(), 2);
+            assert_eq!(intervals[0]["variable"], "x");
+            assert_eq!(intervals[1]["variable"], "y");
+            intervals
+                .iter()
+                .map(|interval| {
+                    format!(
+                        "{}: ({}, {})",
+                        interval["id"], interval["start"], interval["end"]
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .collect();
+    assert_eq!(from_json, text.lines().collect::<Vec<_>>());
+
+    // The number of the names must match the dimensions
+    assert!(run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--json",
+        "--var-names",
+        "x",
+    ])
+    .is_err());
+    assert!(run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--var-names",
+        "x,y",
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_report_end() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run_report = |report: &str| {
+        let output = dir.path().join(format!("{}.txt", report));
+        let output = output.to_str().unwrap();
+        run_with(&[
+            "-f", &automaton, "-i", &input1, "-i", &input2, "--sorted", "--report", report, "-o",
+            output,
+        ])
+        .expect("Failed to run");
+        std::fs::read_to_string(output).expect("Failed to read the output")
+    };
+
+    // Each line reports only the end positions of the intervals of the same match
+    let intervals = run_report("interval");
+    let ends = run_report("end");
+    assert!(!ends.is_empty());
+    let expected: Vec<String> = intervals
+        .lines()
+        .map(|line| {
+            line.split("), ")
+                .map(|interval| {
+                    let (id, positions) = interval.split_once(": (").unwrap();
+                    let end = positions.trim_end_matches(')').split(", ").nth(1).unwrap();
+                    format!("{}: {}", id, end)
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .collect();
+    assert_eq!(ends.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_dry_run() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let output = dir.path().join("counts.txt");
+    let output = output.to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--dry-run",
+        "-o",
+        output,
+    ])
+    .expect("Failed to run");
+    // Nothing is output without --stats
+    assert!(!std::path::Path::new(output).exists());
+
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--dry-run",
+        "--stats",
+        "-o",
+        output,
+    ])
+    .expect("Failed to run");
+    let counts = std::fs::read_to_string(output).expect("Failed to read the output");
+    assert_eq!(counts, "0: 3\n1: 3\n");
+
+    // --stats is only for the dry run
+    let args = ["hyppau", "-f", &automaton, "-i", &input1, "--stats"];
+    assert!(Args::try_parse_from(args).is_err());
+}
+
+#[test]
+fn test_count() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run = |mode: &str, count: bool| {
+        let output = dir.path().join(format!("{}-{}.txt", mode, count));
+        let output = output.to_str().unwrap();
+        let mut args = vec![
+            "-f", &automaton, "-i", &input1, "-i", &input2, "-m", mode, "-o", output,
+        ];
+        if count {
+            args.push("--count");
+        }
+        run_with(&args).expect("Failed to run");
+        std::fs::read_to_string(output).expect("Failed to read the output")
+    };
+
+    // The number of the lines output without --count, including the matches some modes notify
+    // more than once
+    assert_eq!(run("naive", true), "8\n");
+    for mode in ["naive", "online", "fjs", "naive-filtered"] {
+        let lines = run(mode, false).lines().count();
+        assert_eq!(run(mode, true), format!("{}\n", lines), "{}", mode);
+    }
+}
+
+#[test]
+fn test_grouped_json() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run = |mode: &str| {
+        let output = dir.path().join(format!("{}.json", mode));
+        let output = output.to_str().unwrap();
+        run_with(&[
+            "-f",
+            &automaton,
+            "-i",
+            &input1,
+            "-i",
+            &input2,
+            "-m",
+            mode,
+            "--grouped-json",
+            "-o",
+            output,
+        ])
+        .expect("Failed to run");
+        let document = std::fs::read_to_string(output).expect("Failed to read the output");
+        serde_json::from_str::<serde_json::Value>(&document).expect("Failed to parse the output")
+    };
+
+    // The distinct matches of small.expected, grouped by the ids
+    let document = run("naive");
+    let groups = document.as_object().unwrap();
+    assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["[0,1]"]);
+    assert_eq!(groups["[0,1]"].as_array().unwrap().len(), 6);
+    assert_eq!(
+        groups["[0,1]"][0],
+        serde_json::json!([{ "start": 0, "end": 2 }, { "start": 1, "end": 1 }])
+    );
+    // The document does not depend on the order or the duplicates of the matches
+    for mode in ["online", "fjs", "naive-filtered"] {
+        assert_eq!(run(mode), document, "{}", mode);
+    }
+
+    let result = run_with(&["-f", &automaton, "-i", &input1, "--grouped-json", "--tally"]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_profile() {
+    let logger = install_recording_logger();
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let output = NamedTempFile::new().expect("Failed to create temp file");
+    let output_path = output.path().to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "-m",
+        "fjs",
+        "--profile",
+        "-o",
+        output_path,
+    ])
+    .expect("Failed to run");
+
+    let profile = logger
+        .records()
+        .into_iter()
+        .filter(|record| record.contains("Profile: "))
+        .collect::<Vec<_>>();
+    for phase in ["loading", "matcher construction", "feeding", "draining"] {
+        assert!(
+            profile
+                .iter()
+                .any(|record| record.contains(&format!("Profile: {}: ", phase))),
+            "No time for {} in {:?}",
+            phase,
+            profile
+        );
+    }
+}
+
+#[test]
+fn test_json_field() {
+    let automaton = example("small.json");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    // The JSON events of the lines of small1.txt and small2.txt
+    let input1 = dir.path().join("small1.jsonl");
+    let input2 = dir.path().join("small2.jsonl");
+    let to_events = |path: &str| {
+        std::fs::read_to_string(path)
+            .expect("Failed to read the input")
+            .lines()
+            .enumerate()
+            .map(|(i, line)| format!("{{\"id\":{},\"evt\":\"{}\"}}\n", i, line))
+            .collect::<String>()
+    };
+    std::fs::write(&input1, to_events(&example("small1.txt"))).unwrap();
+    std::fs::write(&input2, to_events(&example("small2.txt"))).unwrap();
+    let run_inputs = |inputs: [&str; 2], extra: &[&str], name: &str| {
+        let output = dir.path().join(name);
+        let output = output.to_str().unwrap();
+        let mut args = vec!["-f", &automaton, "-i", inputs[0], "-i", inputs[1]];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["--sorted", "-o", output]);
+        run_with(&args).expect("Failed to run");
+        std::fs::read_to_string(output).expect("Failed to read the output")
+    };
+
+    let expected = run_inputs(
+        [&example("small1.txt"), &example("small2.txt")],
+        &[],
+        "plain.txt",
+    );
+    assert!(!expected.is_empty());
+    assert_eq!(
+        run_inputs(
+            [input1.to_str().unwrap(), input2.to_str().unwrap()],
+            &["--json-field", "/evt"],
+            "json.txt"
+        ),
+        expected
+    );
+}
+
+#[test]
+fn test_capture_and_replay() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let capture = dir.path().join("capture.jsonl");
+    let capture = capture.to_str().unwrap();
+    let captured_output = dir.path().join("captured.txt");
+    let captured_output = captured_output.to_str().unwrap();
+    let replayed_output = dir.path().join("replayed.txt");
+    let replayed_output = replayed_output.to_str().unwrap();
+
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        // More input files than the dimensions of the automaton
+        "-i",
+        &input1,
+        "--capture",
+        capture,
+        "--sorted",
+        "-o",
+        captured_output,
+    ])
+    .expect("Failed to run");
+    // The replay needs no input files
+    run_with(&[
+        "-f",
+        &automaton,
+        "--replay",
+        capture,
+        "--sorted",
+        "-o",
+        replayed_output,
+    ])
+    .expect("Failed to replay");
+
+    let captured = std::fs::read_to_string(captured_output).expect("Failed to read the output");
+    assert!(!captured.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(replayed_output).expect("Failed to read the output"),
+        captured
+    );
+
+    let result = run_with(&["-f", &automaton, "-i", &input1, "--replay", capture]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+
+    // A track out of the captured streams is an error
+    let edited = dir.path().join("edited.jsonl");
+    std::fs::write(
+        &edited,
+        "{\"streams\":2}\n{\"event\":\"feed\",\"track\":7,\"action\":\"a\"}\n",
+    )
+    .unwrap();
+    let result = run_with(&["-f", &automaton, "--replay", edited.to_str().unwrap()]);
+    assert!(matches!(result, Err(HyppauError::InputFile { .. })));
+}
+
+#[test]
+fn test_generate_random_without_transitions() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let automaton = dir.path().join("no_transitions.json");
+    std::fs::write(
+        &automaton,
+        r#"{"dimensions": 2, "states": [{ "id": 0, "is_initial": true, "is_final": true }], "transitions": []}"#,
+    )
+    .unwrap();
+    let result = run_with(&["-f", automaton.to_str().unwrap(), "--generate-random", "3"]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_graphml() {
+    let automaton = example("small.json");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let output = dir.path().join("small.graphml");
+    let output = output.to_str().unwrap();
+    run_with(&["-f", &automaton, "--graphml", "-o", output]).expect("Failed to run");
+    let graphml = std::fs::read_to_string(output).expect("Failed to read the output");
+    assert!(graphml.starts_with("<?xml"));
+    assert!(graphml.contains("<graphml"));
+    assert!(graphml.contains("<edge "));
+}
+
+#[test]
+fn test_sample_fraction() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run_sample_fraction = |fraction: &str| {
+        let output = dir.path().join(format!("{}.txt", fraction));
+        let output = output.to_str().unwrap();
+        run_with(&[
+            "-f",
+            &automaton,
+            "-i",
+            &input1,
+            "-i",
+            &input2,
+            "--sample-fraction",
+            fraction,
+            "--sorted",
+            "-o",
+            output,
+        ])
+        .expect("Failed to run");
+        std::fs::read_to_string(output).expect("Failed to read the output")
+    };
+
+    assert_eq!(run_sample_fraction("0"), "");
+    assert_eq!(run_sample_fraction("1").lines().count(), 6);
+
+    for args in [
+        &["--sample-fraction", "1.5"][..],
+        &["--sample-fraction", "0.5", "--sample-every", "2"],
+    ] {
+        let mut all_args = vec!["-f", &automaton, "-i", &input1, "-i", &input2];
+        all_args.extend_from_slice(args);
+        let result = run_with(&all_args);
+        assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+    }
+}
+
+#[test]
+fn test_timestamps() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let output = dir.path().join("output.txt");
+    let output = output.to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--flush-every",
+        "100",
+        "--timestamps",
+        "-o",
+        output,
+    ])
+    .expect("Failed to run");
+
+    // Each match is followed by the time of the detection, never decreasing
+    let output = std::fs::read_to_string(output).expect("Failed to read the output");
+    let timestamps: Vec<f64> = output
+        .lines()
+        .map(|line| {
+            let (_, timestamp) = line.split_once(" @ ").expect("No timestamp");
+            timestamp.parse().expect("Invalid timestamp")
+        })
+        .collect();
+    assert!(!timestamps.is_empty());
+    assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "--timestamps",
+        "--sorted",
+    ]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_min_len() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let run_min_len = |len_in: &str| {
+        let output = dir.path().join(format!("{}.txt", len_in));
+        let output = output.to_str().unwrap();
+        run_with(&[
+            "-f",
+            &automaton,
+            "-i",
+            &input1,
+            "-i",
+            &input2,
+            "--min-len",
+            "2",
+            "--len-in",
+            len_in,
+            "--sorted",
+            "-o",
+            output,
+        ])
+        .expect("Failed to run");
+        std::fs::read_to_string(output).expect("Failed to read the output")
+    };
+
+    // The intervals in the second input are of length 1
+    assert_eq!(run_min_len("all"), "");
+    // Only the matches of length 1 in both inputs are excluded
+    assert_eq!(
+        run_min_len("any"),
+        "0: (0, 2), 1: (1, 1)\n0: (0, 2), 1: (2, 2)\n0: (1, 2), 1: (1, 1)\n0: (1, 2), 1: (2, 2)\n"
+    );
+}
+
+#[test]
+fn test_min_len_larger_than_max_len() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "-i",
+        &input,
+        "--min-len",
+        "3",
+        "--max-len",
+        "2",
+    ]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_dimensions_override() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+
+    // The third input is unused by the automaton
+    run_with(&[
+        "-q",
+        "-f",
+        &automaton,
+        "--dimensions",
+        "3",
+        "-i",
+        &input,
+        "-i",
+        &input,
+        "-i",
+        &input,
+    ])
+    .expect("Failed to run with more dimensions");
+
+    let result = run_with(&["-f", &automaton, "--dimensions", "1", "-i", &input]);
+    assert!(matches!(
+        result,
+        Err(HyppauError::InvalidAutomaton { ref message, .. }) if message.contains("--dimensions 1")
+    ));
+}
+
+#[test]
+fn test_match_callback_byte_spans() {
+    use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
+    use crate::serialization::deserialize_nfa;
+    use crate::{run_with_match_callback, Mode};
+    use std::io::Cursor;
+    use typed_arena::Arena;
+
+    let contents = std::fs::read_to_string(example("small.json")).unwrap();
+    let state_arena = Arena::new();
+    let trans_arena = Arena::new();
+    let automaton = deserialize_nfa(&contents, &state_arena, &trans_arena).unwrap();
+
+    // Multi-byte actions and trailing whitespace make the byte spans differ from the positions
+    let texts = ["é\na  \na\nc\n", "a\nd\nd\n"];
+    let reader = MultiStreamReader::new(
+        texts
+            .iter()
+            .map(|text| Box::new(Cursor::new(text.as_bytes().to_vec())) as Box<dyn StreamSource>)
+            .collect(),
+    );
+
+    let mut calls = Vec::new();
+    run_with_match_callback(
+        &Mode::Naive,
+        &automaton,
+        reader,
+        &[0, 1],
+        Default::default(),
+        |result, spans| {
+            calls.push((result.clone(), spans.to_vec()));
+        },
+    )
+    .expect("Failed to run");
+
+    assert!(!calls.is_empty());
+    for (result, spans) in &calls {
+        assert_eq!(spans.len(), result.intervals.len());
+        for ((interval, &track), &(begin, end)) in
+            result.intervals.iter().zip(&result.ids).zip(spans)
+        {
+            // The span covers exactly the matched lines
+            let lines: Vec<&str> = texts[track].lines().collect();
+            let text = &texts[track][begin..end];
+            let expected: Vec<&str> = lines[interval.start..=interval.end]
+                .iter()
+                .map(|line| line.trim_end())
+                .collect();
+            let actual: Vec<&str> = text.split('\n').map(|line| line.trim_end()).collect();
+            assert_eq!(actual, expected, "{:?}", result);
+            assert!(!text.ends_with(char::is_whitespace));
+        }
+    }
+    // The match of "a", "c" on the first input and "d" on the second one, e.g., (0: 2, 3), (1: 1, 1)
+    let known = calls
+        .iter()
+        .find(|(result, _)| {
+            result.ids == [0, 1]
+                && result.intervals == [MatchingInterval::new(2, 3), MatchingInterval::new(1, 1)]
+        })
+        .expect("The known match is not reported");
+    assert_eq!(known.1, vec![(7, 10), (2, 3)]);
+}
+
+#[test]
+fn test_byte_spans() {
+    let automaton = example("small.json");
+    // Multi-byte actions make the byte spans differ from the positions
+    let mut input = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(input, "é\na\nc").expect("Failed to write temp file");
+    let input = input.path().to_str().unwrap().to_string();
+    let second = example("small2.txt");
+    let output = NamedTempFile::new().expect("Failed to create temp file");
+    let output = output.path().to_str().unwrap();
+
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "-i",
+        &second,
+        "--json",
+        "--byte-spans",
+        "-o",
+        output,
+    ]);
+    assert!(result.is_ok());
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // "a", "c" on the first input and "d" on the second one
+    let known = serde_json::json!({"intervals": [
+        {"bytes": [3, 6], "end": 2, "id": 0, "start": 1, "variable": 0},
+        {"bytes": [2, 3], "end": 1, "id": 1, "start": 1, "variable": 1},
+    ]});
+    assert!(lines.contains(&known), "{:?}", lines);
+    assert!(lines
+        .iter()
+        .all(|line| line["intervals"][0]["bytes"].is_array()));
+
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "-i",
+        &second,
+        "--json",
+        "--byte-spans",
+        "--sorted",
+    ]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+    assert!(
+        Args::try_parse_from(["hyppau", "-f", &automaton, "-i", &input, "--byte-spans"]).is_err()
+    );
+}
+
+#[test]
+fn test_delimiter() {
+    let automaton = example("small.json");
+    let mut first = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(first, "a,a\nc").expect("Failed to write temp file");
+    let first = first.path().to_str().unwrap().to_string();
+    let mut second = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(second, "a,d,d").expect("Failed to write temp file");
+    let second = second.path().to_str().unwrap().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let split = dir.path().join("split.txt");
+    let split = split.to_str().unwrap();
+    let plain = dir.path().join("plain.txt");
+    let plain = plain.to_str().unwrap();
+
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &first,
+        "-i",
+        &second,
+        "--delimiter",
+        ",",
+        "--sorted",
+        "-o",
+        split,
+    ])
+    .expect("Failed to run with --delimiter");
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &example("small1.txt"),
+        "-i",
+        &example("small2.txt"),
+        "--sorted",
+        "-o",
+        plain,
+    ])
+    .expect("Failed to run without --delimiter");
+
+    // The same actions are fed as from the files of an action per line
+    let expected = std::fs::read_to_string(plain).unwrap();
+    assert!(!expected.is_empty());
+    assert_eq!(std::fs::read_to_string(split).unwrap(), expected);
+}
+
+#[test]
+fn test_bincode_automaton_format() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+    let second = example("small2.txt");
+    let binary = NamedTempFile::new().expect("Failed to create temp file");
+    let binary_path = binary.path().to_str().unwrap();
+    run_with(&["-f", &automaton, "--save-bincode", binary_path])
+        .expect("Failed to save the bincode automaton");
+
+    let json_output = NamedTempFile::new().expect("Failed to create temp file");
+    let json_output_path = json_output.path().to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "-i",
+        &second,
+        "-o",
+        json_output_path,
+    ])
+    .expect("Failed to run with the JSON automaton");
+    let bincode_output = NamedTempFile::new().expect("Failed to create temp file");
+    let bincode_output_path = bincode_output.path().to_str().unwrap();
+    run_with(&[
+        "-f",
+        binary_path,
+        "--automaton-format",
+        "bincode",
+        "-i",
+        &input,
+        "-i",
+        &second,
+        "-o",
+        bincode_output_path,
+    ])
+    .expect("Failed to run with the bincode automaton");
+
+    let expected = std::fs::read_to_string(json_output_path).unwrap();
+    assert!(!expected.is_empty());
+    assert_eq!(
+        expected,
+        std::fs::read_to_string(bincode_output_path).unwrap()
+    );
+
+    // A JSON file is not a valid bincode automaton
+    let result = run_with(&["-f", &automaton, "--automaton-format", "bincode"]);
+    assert!(matches!(result, Err(HyppauError::InvalidAutomaton { .. })));
+}
+
+#[test]
+fn test_interleaved_input() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    // examples/small1.txt and examples/small2.txt interleaved, with a malformed and a blank line
+    let interleaved = dir.path().join("interleaved.txt");
+    let interleaved = interleaved.to_str().unwrap();
+    std::fs::write(interleaved, "0:a\n1:a\n0:a\nmalformed\n1:d\n\n0:c\n1:d\n")
+        .expect("Failed to write the interleaved input");
+
+    let separate = dir.path().join("separate.txt");
+    let separate = separate.to_str().unwrap();
+    run_with(&[
+        "-f", &automaton, "-i", &input1, "-i", &input2, "--sorted", "-o", separate,
+    ])
+    .expect("Failed to run with the separate inputs");
+    let demultiplexed = dir.path().join("demultiplexed.txt");
+    let demultiplexed = demultiplexed.to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "--interleaved",
+        interleaved,
+        "--sorted",
+        "-o",
+        demultiplexed,
+    ])
+    .expect("Failed to run with the interleaved input");
+
+    let expected = std::fs::read_to_string(separate).unwrap();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, std::fs::read_to_string(demultiplexed).unwrap());
+
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "--interleaved",
+        interleaved,
+        "-i",
+        &input1,
+    ]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_explain() {
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    let dir = tempfile::tempdir().unwrap();
+    let explained = dir.path().join("explained.txt");
+    let explained = explained.to_str().unwrap();
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input1,
+        "-i",
+        &input2,
+        "-m",
+        "fjs",
+        "--sorted",
+        "-o",
+        explained,
+        "--explain",
+    ])
+    .expect("Failed to run with --explain");
+    let plain = dir.path().join("plain.txt");
+    let plain = plain.to_str().unwrap();
+    run_with(&[
+        "-f", &automaton, "-i", &input1, "-i", &input2, "-m", "fjs", "--sorted", "-o", plain,
+    ])
+    .expect("Failed to run without --explain");
+
+    // Explaining the skips does not change the matches
+    let expected = std::fs::read_to_string(plain).unwrap();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, std::fs::read_to_string(explained).unwrap());
+
+    let result = run_with(&["-f", &automaton, "-i", &input1, "-m", "naive", "--explain"]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_explain_inputs_other_than_dimensions() {
+    // The automaton has two variables
+    let automaton = example("small.json");
+    let input1 = example("small1.txt");
+    let input2 = example("small2.txt");
+    // A single input matching on both variables, i.e., "c" and then "d"
+    let mut single = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(single, "a\nc\nd").expect("Failed to write temp file");
+    let single = single.path().to_str().unwrap().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    for inputs in [vec![&single], vec![&input1, &input2, &input1]] {
+        let mut args = vec!["-f", automaton.as_str(), "-m", "fjs", "--sorted"];
+        for input in &inputs {
+            args.extend(["-i", input.as_str()]);
+        }
+        let explained = dir.path().join("explained.txt");
+        let explained = explained.to_str().unwrap();
+        let plain = dir.path().join("plain.txt");
+        let plain = plain.to_str().unwrap();
+        run_with(&[args.as_slice(), &["-o", explained, "--explain"]].concat())
+            .expect("Failed to run with --explain");
+        run_with(&[args.as_slice(), &["-o", plain]].concat())
+            .expect("Failed to run without --explain");
+
+        // The matches have an interval for each variable with or without --explain
+        let expected = std::fs::read_to_string(plain).unwrap();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, std::fs::read_to_string(explained).unwrap());
+    }
+}
+
+#[test]
+fn test_follow() {
+    let automaton = example("small.json");
+    let input = example("small1.txt");
+    // The results of --sorted are never output while following
+    let result = run_with(&["-f", &automaton, "-i", &input, "--follow", "--sorted"]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+
+    let result = run_with(&["-f", &automaton, "-i", "no_such_file.txt", "--follow"]);
+    assert!(matches!(result, Err(HyppauError::InputFile { .. })));
+}
+
+#[test]
+fn test_letter_classes() {
+    // "a" and then any letter except "a"
+    let mut automaton = NamedTempFile::new().expect("Failed to create temp file");
+    write!(
+        automaton,
+        r#"{{
+            "dimensions": 1,
+            "states": [
+                {{ "id": 0, "is_initial": true, "is_final": false }},
+                {{ "id": 1, "is_initial": false, "is_final": false }},
+                {{ "id": 2, "is_initial": false, "is_final": true }}
+            ],
+            "transitions": [
+                {{ "from": 0, "to": 1, "label": ["a", 0] }},
+                {{ "from": 1, "to": 2, "label": [{{ "not": ["a"] }}, 0] }}
+            ]
+        }}"#
+    )
+    .expect("Failed to write temp file");
+    let automaton = automaton.path().to_str().unwrap().to_string();
+    let mut input = NamedTempFile::new().expect("Failed to create temp file");
+    writeln!(input, "a\nb\na\na\na\nc").expect("Failed to write temp file");
+    let input = input.path().to_str().unwrap().to_string();
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("output.txt");
+    let output = output.to_str().unwrap();
+
+    run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "--letter-classes",
+        "-o",
+        output,
+    ])
+    .expect("Failed to run with --letter-classes");
+    let mut lines: Vec<String> = std::fs::read_to_string(output)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    lines.sort();
+    lines.dedup();
+    // "a a" is not matched by the class
+    assert_eq!(lines, vec!["0: (0, 1)", "0: (4, 5)"]);
+
+    // The class is rejected without --letter-classes
+    let result = run_with(&["-f", &automaton, "-i", &input]);
+    assert!(matches!(result, Err(HyppauError::InvalidAutomaton { .. })));
+    // Only the naive matcher supports the classes
+    let result = run_with(&[
+        "-f",
+        &automaton,
+        "-i",
+        &input,
+        "--letter-classes",
+        "-m",
+        "online",
+    ]);
+    assert!(matches!(result, Err(HyppauError::InvalidArgument(_))));
+    assert!(
+        Args::try_parse_from(["hyppau", "-f", &automaton, "--letter-classes", "--count"]).is_err()
+    );
+}
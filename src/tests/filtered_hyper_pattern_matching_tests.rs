@@ -6,6 +6,7 @@ use crate::multi_stream_reader::{MultiStreamReader, StreamSource};
 use crate::reading_scheduler::ReadingScheduler;
 use crate::result_notifier::{MatchingResult, SharedBufferResultNotifier};
 use crate::shared_buffer::SharedBuffer;
+use std::collections::BTreeSet;
 use typed_arena::Arena;
 
 use super::utils::{create_small_automaton, verify_ids, verify_intervals};
@@ -340,3 +341,45 @@ fn test_small_with_abcd_10() {
 
     verify_ids(&results, &expected_ids);
 }
+
+/// A track sharing the input of another track gives the same results as duplicating the input
+#[test]
+fn test_shared_stream() {
+    let state_arena = Arena::new();
+    let transition_arena = Arena::new();
+    let automaton = create_small_automaton(&state_arena, &transition_arena);
+    let input = ["a", "b", "a", "c", "d", "d"];
+
+    let run = |sources: &[usize]| {
+        let result_buffer = SharedBuffer::new();
+        let notifier = SharedBufferResultNotifier::new(result_buffer.make_source());
+        let mut result_sink = result_buffer.make_sink();
+        let mut matching = FilteredHyperPatternMatching::<
+            NaiveFilteredSingleHyperPatternMatching<SharedBufferResultNotifier>,
+            SharedBufferResultNotifier,
+        >::with_stream_sources(&automaton, notifier, sources);
+        // Feed only the tracks with their own input
+        let own_tracks = (0..sources.len())
+            .filter(|&t| sources[t] == t)
+            .collect::<Vec<_>>();
+        for action in input {
+            for &track in &own_tracks {
+                matching.feed(action, track);
+            }
+        }
+        for &track in &own_tracks {
+            matching.set_eof(track);
+        }
+        matching.consume_remaining();
+
+        let mut results = BTreeSet::new();
+        while let Some(result) = result_sink.pop() {
+            results.insert(result);
+        }
+        results
+    };
+
+    let shared = run(&[0, 0]);
+    assert!(!shared.is_empty());
+    assert_eq!(shared, run(&[0, 1]));
+}
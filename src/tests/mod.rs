@@ -1,3 +1,4 @@
 // Import test modules
+pub mod cli_tests;
 pub mod filtered_hyper_pattern_matching_tests;
 pub mod utils;
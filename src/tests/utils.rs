@@ -1,8 +1,15 @@
+use std::cell::RefCell;
+
+use log::{LevelFilter, Log, Metadata, Record};
 use typed_arena::Arena;
 
 use crate::{
     automata::{NFAHState, NFAHTransition, NFAH},
+    hyper_pattern_matching::HyperPatternMatching,
+    multi_stream_reader::{MultiStreamReader, StreamSource},
+    reading_scheduler::ReadingScheduler,
     result_notifier::{MatchingInterval, MatchingResult},
+    shared_buffer::SharedBuffer,
 };
 
 /// Helper function to create a standard test automaton with 2 dimensions
@@ -30,6 +37,68 @@ pub fn create_small_automaton<'a>(
     automaton
 }
 
+/// Helper function to create an automaton with 2 dimensions accepting a non-empty sequence of "a"
+/// for each variable
+pub fn create_repeated_a_automaton<'a>(
+    state_arena: &'a Arena<NFAHState<'a>>,
+    transition_arena: &'a Arena<NFAHTransition<'a>>,
+) -> NFAH<'a> {
+    let mut automaton = NFAH::new(state_arena, transition_arena, 2);
+
+    let s1 = automaton.add_state(true, false);
+    let s2 = automaton.add_state(false, false);
+    let s3 = automaton.add_state(false, true);
+
+    automaton.add_nfah_transition(s1, "a".to_string(), 0, s2);
+    automaton.add_nfah_transition(s2, "a".to_string(), 0, s2);
+    automaton.add_nfah_transition(s2, "a".to_string(), 1, s3);
+    automaton.add_nfah_transition(s3, "a".to_string(), 1, s3);
+
+    automaton
+}
+
+/// Helper function to run `matching` of the automaton by [`create_repeated_a_automaton`] in the
+/// first-only mode, and to verify that it notifies to `result_buffer` exactly the earliest match
+/// for each id-assignment
+pub fn verify_first_only(
+    matching: impl HyperPatternMatching,
+    result_buffer: &SharedBuffer<MatchingResult>,
+) {
+    let input_buffers = vec![SharedBuffer::new(), SharedBuffer::new()];
+    let reader = MultiStreamReader::new(
+        input_buffers
+            .clone()
+            .into_iter()
+            .map(|buf| Box::new(buf) as Box<dyn StreamSource>)
+            .collect(),
+    );
+    let mut result_sink = result_buffer.make_sink();
+    let mut scheduler = ReadingScheduler::new(matching, reader);
+
+    // Each id-assignment has nine matches in the exhaustive mode
+    input_buffers[0].push("a");
+    input_buffers[1].push("a");
+    input_buffers[0].push("a");
+    input_buffers[1].push("a");
+
+    scheduler.run();
+
+    let mut results = vec![];
+    while let Some(result) = result_sink.pop() {
+        results.push(result);
+    }
+    results.sort();
+    // Exactly one match per id-assignment
+    assert_eq!(
+        results.iter().map(|r| r.ids.clone()).collect::<Vec<_>>(),
+        vec![vec![0, 0], vec![0, 1], vec![1, 0], vec![1, 1]]
+    );
+    for result in results {
+        assert_eq!(result.intervals[0], MatchingInterval::new(0, 0));
+        assert_eq!(result.intervals[1], MatchingInterval::new(0, 0));
+    }
+}
+
 /// Helper function to verify matching results against expected intervals
 pub fn verify_intervals(results: &[MatchingResult], expected_intervals: &[Vec<usize>]) {
     assert_eq!(
@@ -38,18 +107,12 @@ pub fn verify_intervals(results: &[MatchingResult], expected_intervals: &[Vec<us
         "Number of results doesn't match expected"
     );
 
-    for (i, result) in results.iter().enumerate() {
+    for (i, (result, expected)) in results.iter().zip(expected_intervals).enumerate() {
         assert_eq!(result.intervals.len(), 2, "Result should have 2 intervals");
         assert_eq!(
-            result.intervals[0],
-            MatchingInterval::new(expected_intervals[i][0], expected_intervals[i][1]),
-            "First interval mismatch at result {}",
-            i
-        );
-        assert_eq!(
-            result.intervals[1],
-            MatchingInterval::new(expected_intervals[i][2], expected_intervals[i][3]),
-            "Second interval mismatch at result {}",
+            &result.flat_bounds(),
+            expected,
+            "Interval mismatch at result {}",
             i
         );
     }
@@ -77,3 +140,49 @@ pub fn verify_ids(results: &[MatchingResult], expected_ids: &[Vec<usize>]) {
         );
     }
 }
+
+/// A logger recording all the log messages so that tests can inspect them
+///
+/// The records are kept per thread, so each test sees only the messages logged by its own thread.
+pub struct RecordingLogger;
+
+static RECORDING_LOGGER: RecordingLogger = RecordingLogger;
+
+thread_local! {
+    /// The messages logged by the current thread
+    static RECORDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+impl RecordingLogger {
+    /// Returns the messages recorded by the current thread since the logger is installed,
+    /// formatted as `LEVEL target: message`
+    pub fn records(&self) -> Vec<String> {
+        RECORDS.with(|records| records.borrow().clone())
+    }
+}
+
+impl Log for RecordingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = format!("{} {}: {}", record.level(), record.target(), record.args());
+        RECORDS.with(|records| records.borrow_mut().push(message));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Helper function to install the recording logger with the most verbose level
+///
+/// The logger is global, but the records are kept per thread and the ones of the current thread
+/// are cleared here, so the records of the tests running concurrently or before on the same thread
+/// are not mixed.
+pub fn install_recording_logger() -> &'static RecordingLogger {
+    // The logger may already be installed by another test
+    let _ = log::set_logger(&RECORDING_LOGGER);
+    log::set_max_level(LevelFilter::Trace);
+    RECORDS.with(|records| records.borrow_mut().clear());
+    &RECORDING_LOGGER
+}